@@ -0,0 +1,49 @@
+//! Audio Metadata
+//! Drop 音声の再生時間・ビットレート・サンプルレートの抽出
+
+use std::path::{Path, PathBuf};
+
+use lofty::file::AudioFile;
+use lofty::probe::Probe;
+
+/// 音声ファイルから読み取ったメタデータ（読み取れない項目は None）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioMetadata {
+    pub duration_ms: Option<i64>,
+    pub bitrate_kbps: Option<i64>,
+    pub sample_rate_hz: Option<i64>,
+}
+
+/// メタデータを読み取る（形式は拡張子ではなくヘッダから判定）
+/// 同期処理なので spawn_blocking 内で呼ぶこと
+pub fn read_metadata(path: &Path) -> anyhow::Result<AudioMetadata> {
+    let tagged = Probe::open(path)?.guess_file_type()?.read()?;
+    let properties = tagged.properties();
+    let duration_ms = properties.duration().as_millis() as i64;
+    Ok(AudioMetadata {
+        // 0 は長さを取得できなかった扱い
+        duration_ms: (duration_ms > 0).then_some(duration_ms),
+        bitrate_kbps: properties.audio_bitrate().map(i64::from),
+        sample_rate_hz: properties.sample_rate().map(i64::from),
+    })
+}
+
+/// 保存済みの音声ファイルからメタデータを抽出（ベストエフォート、解析できない場合は全て None）
+pub async fn extract_metadata(path: PathBuf) -> AudioMetadata {
+    let result = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || read_metadata(&path)
+    })
+    .await;
+    match result {
+        Ok(Ok(metadata)) => metadata,
+        Ok(Err(e)) => {
+            tracing::warn!("Audio metadata could not be read: {:?} ({})", path, e);
+            AudioMetadata::default()
+        }
+        Err(e) => {
+            tracing::warn!("Audio metadata task failed: {:?} ({})", path, e);
+            AudioMetadata::default()
+        }
+    }
+}
@@ -1,10 +1,16 @@
 //! Database Module
 //! SQLite を使用した vendors/listings/receipts/artists の管理
 
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    ConnectOptions, Pool, Sqlite,
+};
 use anyhow::Result;
+use serde::Serialize;
 use sha2::{Sha256, Digest};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::info;
@@ -12,26 +18,77 @@ use tracing::info;
 /// データベース接続プール
 pub type DbPool = Pool<Sqlite>;
 
+/// 接続プールの設定
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// この時間以上かかったクエリを warn ログに出す（None で無効）
+    pub slow_query_threshold: Option<Duration>,
+}
+
 /// データベースを初期化
-pub async fn init_db(db_path: &str) -> Result<DbPool> {
+pub async fn init_db(db_path: &str, settings: PoolSettings) -> Result<DbPool> {
     // SQLite接続文字列
     let db_url = format!("sqlite:{}?mode=rwc", db_path);
 
-    info!("Initializing database: {}", db_path);
+    info!(
+        "Initializing database: {} (max_connections={}, min_connections={})",
+        db_path, settings.max_connections, settings.min_connections
+    );
 
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
+    // 通常のクエリは debug、閾値を超えたものだけ warn
+    let mut options = SqliteConnectOptions::from_str(&db_url)?.log_statements(log::LevelFilter::Debug);
+    options = match settings.slow_query_threshold {
+        Some(threshold) => options.log_slow_statements(log::LevelFilter::Warn, threshold),
+        None => options.log_slow_statements(log::LevelFilter::Off, Duration::default()),
+    };
+
+    // スキーマ作成 + マイグレーション適用（1接続のみで行う）
+    // 複数接続で適用すると、ALTER TABLE 前のスキーマを保持したままの接続が残り、
+    // その接続で準備した SELECT * の列数が実際の行と食い違う
+    let migration_pool = SqlitePoolOptions::new()
+        .max_connections(1)
         .connect(&db_url)
         .await?;
+    create_schema(&migration_pool).await?;
+    run_migrations(&migration_pool).await?;
+    migration_pool.close().await;
 
-    // スキーマ作成
-    create_schema(&pool).await?;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections.min(settings.max_connections))
+        .connect_with(options)
+        .await?;
 
     info!("Database initialized successfully");
     Ok(pool)
 }
 
-/// スキーマ作成
+/// 接続プールの使用状況（ヘルスチェック用）
+#[derive(Debug, Serialize)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub in_use: usize,
+    pub max_connections: u32,
+}
+
+impl PoolStats {
+    pub fn of(pool: &DbPool) -> Self {
+        let size = pool.size();
+        let idle = pool.num_idle();
+        Self {
+            size,
+            idle,
+            in_use: (size as usize).saturating_sub(idle),
+            max_connections: pool.options().get_max_connections(),
+        }
+    }
+}
+
+/// 基本スキーマ作成（テーブル・インデックスの初期形）
+/// 既存テーブルへのカラム追加などの変更は MIGRATIONS に追加する
 async fn create_schema(pool: &DbPool) -> Result<()> {
     // runs テーブル（世代管理）
     sqlx::query(r#"
@@ -69,14 +126,6 @@ async fn create_schema(pool: &DbPool) -> Result<()> {
     .execute(pool)
     .await?;
 
-    // backend カラム追加（既存DBのマイグレーション用）
-    sqlx::query("ALTER TABLE vendors ADD COLUMN backend INTEGER NOT NULL DEFAULT 0")
-        .execute(pool).await.ok();  // 既に存在する場合は無視
-
-    // vendors の peer_id インデックス
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_vendors_peer_id ON vendors(peer_id)")
-        .execute(pool).await.ok();  // 既存テーブルでは失敗してもOK
-
     // artists テーブル（peer_id 対応）
     sqlx::query(r#"
         CREATE TABLE IF NOT EXISTS artists (
@@ -155,18 +204,6 @@ async fn create_schema(pool: &DbPool) -> Result<()> {
     .execute(pool)
     .await?;
 
-    // listings カラム追加（既存DBのマイグレーション用）
-    sqlx::query("ALTER TABLE listings ADD COLUMN inventory_id TEXT")
-        .execute(pool).await.ok();
-    sqlx::query("ALTER TABLE listings ADD COLUMN manifest_id TEXT")
-        .execute(pool).await.ok();
-    sqlx::query("ALTER TABLE listings ADD COLUMN title TEXT")
-        .execute(pool).await.ok();
-    sqlx::query("ALTER TABLE listings ADD COLUMN artist TEXT")
-        .execute(pool).await.ok();
-    sqlx::query("ALTER TABLE listings ADD COLUMN cover_url TEXT")
-        .execute(pool).await.ok();
-
     // receipts テーブル
     sqlx::query(r#"
         CREATE TABLE IF NOT EXISTS receipts (
@@ -381,6 +418,356 @@ async fn create_schema(pool: &DbPool) -> Result<()> {
     Ok(())
 }
 
+// ========================================
+// マイグレーション
+// ========================================
+
+/// マイグレーションの1ステップ
+enum MigrationStep {
+    /// 任意の SQL
+    Sql(&'static str),
+    /// カラム追加（既に存在する場合はスキップ。ランナー導入前に手動/旧コードで追加済みのDB向け）
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+/// バージョン付きマイグレーション
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [MigrationStep],
+}
+
+/// 適用順のマイグレーション一覧（version は単調増加、適用済みのものは変更しないこと）
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "vendors: peer_id, backend",
+        steps: &[
+            MigrationStep::AddColumn { table: "vendors", column: "peer_id", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "vendors", column: "backend", definition: "INTEGER NOT NULL DEFAULT 0" },
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_vendors_peer_id ON vendors(peer_id)"),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "listings: on-chain reference and metadata columns",
+        steps: &[
+            MigrationStep::AddColumn { table: "listings", column: "inventory_id", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "listings", column: "manifest_id", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "listings", column: "title", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "listings", column: "artist", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "listings", column: "cover_url", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "drops: idempotency_key",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "idempotency_key", definition: "TEXT" },
+            MigrationStep::Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_drops_idempotency ON drops(vendor_stable_id, idempotency_key)"),
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "drop_claims: tx_digest",
+        steps: &[
+            MigrationStep::AddColumn { table: "drop_claims", column: "tx_digest", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "drops/drop_claims: per-claim download limit",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "max_downloads_per_claim", definition: "INTEGER" },
+            MigrationStep::AddColumn { table: "drop_claims", column: "download_count", definition: "INTEGER NOT NULL DEFAULT 0" },
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "drops: preview_object_key",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "preview_object_key", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "vendor_profile_history",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS vendor_profile_history (
+                    stable_id TEXT NOT NULL,
+                    profile_seq INTEGER NOT NULL,
+                    sha256 TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    created_at_ms INTEGER NOT NULL,
+                    PRIMARY KEY (stable_id, profile_seq),
+                    FOREIGN KEY (stable_id) REFERENCES vendors(stable_id)
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "file_downloads (legacy file access log)",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS file_downloads (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    file_type TEXT NOT NULL,
+                    album_id TEXT NOT NULL,
+                    filename TEXT NOT NULL,
+                    bytes_sent INTEGER NOT NULL,
+                    ip_hash TEXT,
+                    downloaded_at_ms INTEGER NOT NULL
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_file_downloads_album ON file_downloads(album_id, downloaded_at_ms)"),
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "vendors: max_active_drops",
+        steps: &[
+            MigrationStep::AddColumn { table: "vendors", column: "max_active_drops", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "listings: sold-out webhook",
+        steps: &[
+            MigrationStep::AddColumn { table: "listings", column: "webhook_url", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "listings", column: "sold_out_notified_at_ms", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "audio_blobs (content-addressed drop audio)",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS audio_blobs (
+                    sha256 TEXT PRIMARY KEY,
+                    object_key TEXT NOT NULL UNIQUE,
+                    size_bytes INTEGER NOT NULL,
+                    ref_count INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "vendors.owner index (by-owner lookup)",
+        steps: &[
+            // owner は大文字小文字を区別せず比較するため NOCASE で索引
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_vendors_owner ON vendors(owner COLLATE NOCASE, created_at_ms)"),
+        ],
+    },
+    Migration {
+        version: 13,
+        description: "drops: cover derivatives (webp, blurhash)",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "cover_webp_object_key", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "drops", column: "cover_blurhash", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 14,
+        description: "drop_claims: email (plain or sha256)",
+        steps: &[
+            MigrationStep::AddColumn { table: "drop_claims", column: "email", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 15,
+        description: "drops: audio metadata (duration, bitrate, sample rate)",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "audio_duration_ms", definition: "INTEGER" },
+            MigrationStep::AddColumn { table: "drops", column: "audio_bitrate_kbps", definition: "INTEGER" },
+            MigrationStep::AddColumn { table: "drops", column: "audio_sample_rate_hz", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 16,
+        description: "listings: featured flag and rank",
+        steps: &[
+            MigrationStep::AddColumn { table: "listings", column: "featured", definition: "INTEGER NOT NULL DEFAULT 0" },
+            MigrationStep::AddColumn { table: "listings", column: "featured_rank", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 17,
+        description: "drops: claim passcode hash (private drops)",
+        steps: &[
+            MigrationStep::AddColumn { table: "drops", column: "claim_passcode_hash", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 18,
+        description: "drop_claims: revoked_at (leaked download links)",
+        steps: &[
+            MigrationStep::AddColumn { table: "drop_claims", column: "revoked_at", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 19,
+        description: "vendors: max_drop_bytes (drop storage quota)",
+        steps: &[
+            MigrationStep::AddColumn { table: "vendors", column: "max_drop_bytes", definition: "INTEGER" },
+        ],
+    },
+    // v20-v24: 以前は create_schema で作成していたテーブル・インデックス（IF NOT EXISTS のため作成済みの DB でもそのまま記録される）
+    Migration {
+        version: 20,
+        description: "drop_downloads table (drop download history)",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS drop_downloads (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    claim_id TEXT NOT NULL,
+                    drop_id TEXT NOT NULL,
+                    downloaded_at INTEGER NOT NULL,
+                    bytes_sent INTEGER NOT NULL,
+                    ip_hash TEXT,
+                    FOREIGN KEY (drop_id) REFERENCES drops(drop_id)
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_drop_downloads_drop ON drop_downloads(drop_id)"),
+        ],
+    },
+    Migration {
+        version: 21,
+        description: "reservations table (listing stock holds)",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS reservations (
+                    reservation_id TEXT PRIMARY KEY,
+                    listing_id TEXT NOT NULL,
+                    buyer TEXT,
+                    qty INTEGER NOT NULL,
+                    status INTEGER NOT NULL DEFAULT 0,
+                    created_at_ms INTEGER NOT NULL,
+                    expires_at_ms INTEGER NOT NULL,
+                    consumed_at_ms INTEGER,
+                    released_at_ms INTEGER,
+                    FOREIGN KEY (listing_id) REFERENCES listings(listing_id)
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_reservations_status_expires ON reservations(status, expires_at_ms)"),
+        ],
+    },
+    Migration {
+        version: 22,
+        description: "tombstones: observed_dead_at_ms index",
+        steps: &[
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_tombstones_observed ON tombstones(observed_dead_at_ms)"),
+        ],
+    },
+    Migration {
+        version: 23,
+        description: "listing_price_history table",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS listing_price_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    listing_id TEXT NOT NULL,
+                    old_price INTEGER NOT NULL,
+                    new_price INTEGER NOT NULL,
+                    changed_at_ms INTEGER NOT NULL,
+                    FOREIGN KEY (listing_id) REFERENCES listings(listing_id)
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_listing_price_history_listing ON listing_price_history(listing_id, changed_at_ms)"),
+        ],
+    },
+    Migration {
+        version: 24,
+        description: "upload_sessions table (resumable uploads)",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS upload_sessions (
+                    upload_id TEXT PRIMARY KEY,
+                    total_size INTEGER NOT NULL,
+                    offset_bytes INTEGER NOT NULL DEFAULT 0,
+                    filename TEXT,
+                    content_type TEXT,
+                    sha256 TEXT,
+                    status INTEGER NOT NULL DEFAULT 0,
+                    created_at_ms INTEGER NOT NULL,
+                    updated_at_ms INTEGER NOT NULL,
+                    expires_at_ms INTEGER NOT NULL
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_upload_sessions_expires ON upload_sessions(status, expires_at_ms)"),
+        ],
+    },
+];
+
+/// 未適用のマイグレーションを version 順に適用（1マイグレーション = 1トランザクション）
+/// 適用済み version は schema_migrations に記録し、2回目以降はスキップする
+async fn run_migrations(pool: &DbPool) -> Result<usize> {
+    sqlx::query(r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at_ms INTEGER NOT NULL
+        )
+    "#)
+    .execute(pool)
+    .await?;
+
+    let applied: Vec<(i64,)> = sqlx::query_as("SELECT version FROM schema_migrations")
+        .fetch_all(pool)
+        .await?;
+    let applied: std::collections::HashSet<i64> = applied.into_iter().map(|(v,)| v).collect();
+
+    let mut count = 0;
+    for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+        let mut tx = pool.begin().await?;
+
+        for step in migration.steps {
+            match step {
+                MigrationStep::Sql(sql) => {
+                    sqlx::query(sql).execute(&mut *tx).await?;
+                }
+                MigrationStep::AddColumn { table, column, definition } => {
+                    let exists: Option<(i32,)> = sqlx::query_as(
+                        "SELECT 1 FROM pragma_table_info(?) WHERE name = ?"
+                    )
+                    .bind(table)
+                    .bind(column)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    if exists.is_none() {
+                        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                            .execute(&mut *tx)
+                            .await?;
+                    }
+                }
+            }
+        }
+
+        sqlx::query("INSERT INTO schema_migrations (version, description, applied_at_ms) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(chrono::Utc::now().timestamp_millis())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Migration applied: v{} ({})", migration.version, migration.description);
+        count += 1;
+    }
+
+    if count == 0 {
+        info!("Database schema is up to date (v{})", MIGRATIONS.last().map_or(0, |m| m.version));
+    }
+    Ok(count)
+}
+
 /// 公式ショップの stable_id
 const OFFICIAL_VENDOR_STABLE_ID: &str = "VENDOR_9189MZWY";
 
@@ -452,3 +839,124 @@ pub async fn seed_official_vendors(pool: &DbPool, base_dir: &str, base_url: &str
     info!("Official vendor seeded: {} (profile: {})", OFFICIAL_VENDOR_STABLE_ID, manifest_url);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    async fn applied_versions(pool: &DbPool) -> Vec<i64> {
+        let rows: Vec<(i64,)> = sqlx::query_as("SELECT version FROM schema_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        rows.into_iter().map(|(v,)| v).collect()
+    }
+
+    fn settings() -> PoolSettings {
+        PoolSettings { max_connections: 4, min_connections: 1, slow_query_threshold: None }
+    }
+
+    #[test]
+    fn migration_versions_are_strictly_increasing() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+    }
+
+    #[tokio::test]
+    async fn migrations_are_applied_exactly_once() {
+        let dir = TempDir::new();
+        let db_path = dir.path().join("test.db").to_string_lossy().into_owned();
+        let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+
+        let pool = init_db(&db_path, settings()).await.unwrap();
+        assert_eq!(applied_versions(&pool).await, expected);
+        pool.close().await;
+
+        // 再起動しても再適用されない
+        let pool = init_db(&db_path, settings()).await.unwrap();
+        assert_eq!(applied_versions(&pool).await, expected);
+        assert_eq!(run_migrations(&pool).await.unwrap(), 0);
+
+        // マイグレーション後のカラムで読み書きできる
+        let vendors: Vec<crate::models::Vendor> = sqlx::query_as("SELECT * FROM vendors").fetch_all(&pool).await.unwrap();
+        assert!(vendors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn add_column_steps_tolerate_existing_columns() {
+        let dir = TempDir::new();
+        let db_path = dir.path().join("test.db").to_string_lossy().into_owned();
+        let pool = init_db(&db_path, settings()).await.unwrap();
+
+        // 記録が無くてもカラムが既にあれば ALTER TABLE をスキップする（手動適用済みの環境）
+        sqlx::query("DELETE FROM schema_migrations").execute(&pool).await.unwrap();
+        assert_eq!(run_migrations(&pool).await.unwrap(), MIGRATIONS.len());
+        assert_eq!(applied_versions(&pool).await.len(), MIGRATIONS.len());
+    }
+
+    #[tokio::test]
+    async fn tables_added_after_baseline_come_from_migrations() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let objects = [
+            "drop_downloads",
+            "reservations",
+            "listing_price_history",
+            "upload_sessions",
+            "idx_tombstones_observed",
+        ];
+        let exists = |name: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_as::<_, (String,)>("SELECT name FROM sqlite_master WHERE name = ?")
+                    .bind(name)
+                    .fetch_optional(&pool)
+                    .await
+                    .unwrap()
+                    .is_some()
+            }
+        };
+
+        // 基本スキーマには含まれない
+        create_schema(&pool).await.unwrap();
+        for name in objects {
+            assert!(!exists(name).await, "{} created by create_schema", name);
+        }
+
+        run_migrations(&pool).await.unwrap();
+        for name in objects {
+            assert!(exists(name).await, "{} missing after migrations", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn pool_size_is_capped_at_max_connections() {
+        let dir = TempDir::new();
+        let db_path = dir.path().join("test.db").to_string_lossy().into_owned();
+        let settings = PoolSettings { max_connections: 2, min_connections: 1, slow_query_threshold: None };
+        let pool = init_db(&db_path, settings).await.unwrap();
+
+        let first = pool.acquire().await.unwrap();
+        let second = pool.acquire().await.unwrap();
+        let stats = PoolStats::of(&pool);
+        assert_eq!((stats.size, stats.in_use, stats.idle, stats.max_connections), (2, 2, 0, 2));
+
+        // 上限に達していれば3本目は空くまで待たされる
+        let third = tokio::time::timeout(Duration::from_millis(200), pool.acquire()).await;
+        assert!(third.is_err());
+
+        // 返却されれば取得できる
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_secs(5), pool.acquire()).await;
+        assert!(third.unwrap().is_ok());
+        assert_eq!(PoolStats::of(&pool).size, 2);
+        drop(second);
+    }
+
+    #[tokio::test]
+    async fn health_reports_configured_pool() {
+        let app = crate::test_support::TestApp::with_config(|c| c.db_max_connections = 3).await;
+        let body = app.get("/api/health").await.json();
+        assert_eq!(body["db_pool"]["max_connections"], 3);
+        assert!(body["db_pool"]["size"].as_u64().unwrap() <= 3);
+    }
+}
@@ -0,0 +1,212 @@
+//! API Error
+//! 全ハンドラ共通のエラー型。`{success:false, error, fields?}` 形式の JSON で返す
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use tracing::{error, warn};
+use utoipa::ToSchema;
+
+/// エラーレスポンスの JSON
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: String,
+    /// 入力検証エラー時の対象フィールド（それ以外のエラーでは省略）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+/// ハンドラのエラー
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PayloadTooLarge(String),
+    Unprocessable(String),
+    /// 400 + 対象フィールド
+    InvalidFields { message: String, fields: Vec<String> },
+    /// 上記以外のステータス（410・429・503 等）
+    Status(StatusCode, String),
+    /// 500（DB・I/O 等の内部エラー）
+    Internal(anyhow::Error),
+}
+
+impl ApiError {
+    /// ステータスとメッセージから生成（対応する variant があればそれを使う）
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            StatusCode::BAD_REQUEST => Self::BadRequest(message),
+            StatusCode::UNAUTHORIZED => Self::Unauthorized(message),
+            StatusCode::FORBIDDEN => Self::Forbidden(message),
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            StatusCode::CONFLICT => Self::Conflict(message),
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge(message),
+            StatusCode::UNPROCESSABLE_ENTITY => Self::Unprocessable(message),
+            StatusCode::INTERNAL_SERVER_ERROR => Self::internal(message),
+            status => Self::Status(status, message),
+        }
+    }
+
+    /// メッセージのみの 500
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::Internal(anyhow::anyhow!(message.into()))
+    }
+
+    /// 必須フィールド不足をまとめて返す（400 + fields）
+    pub fn missing_fields(fields: &[&str]) -> Self {
+        Self::invalid_fields(format!("Missing required fields: {}", fields.join(", ")), fields)
+    }
+
+    /// 入力検証エラー（400 + 対象フィールド）
+    pub fn invalid_fields(message: impl Into<String>, fields: &[&str]) -> Self {
+        Self::InvalidFields {
+            message: message.into(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) | Self::InvalidFields { .. } => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Status(status, _) => *status,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadRequest(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::PayloadTooLarge(message)
+            | Self::Unprocessable(message)
+            | Self::InvalidFields { message, .. }
+            | Self::Status(_, message) => f.write_str(message),
+            // context も含めて "DB error: ..." の形で出す
+            Self::Internal(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let message = self.to_string();
+        if status.is_server_error() {
+            error!("API Error ({}): {}", status, message);
+        } else {
+            warn!("API Error ({}): {}", status, message);
+        }
+        let fields = match self {
+            Self::InvalidFields { fields, .. } => fields,
+            _ => Vec::new(),
+        };
+        (
+            status,
+            Json(ErrorResponse {
+                success: false,
+                error: message,
+                fields,
+            }),
+        )
+            .into_response()
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Internal(anyhow::Error::new(e).context("DB error"))
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Internal(anyhow::Error::new(e).context("I/O error"))
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// レスポンスのステータスと JSON ボディ
+    async fn render(error: ApiError) -> (StatusCode, serde_json::Value) {
+        let res = error.into_response();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn each_variant_maps_to_status_and_json_shape() {
+        let cases = [
+            (ApiError::BadRequest("bad".into()), StatusCode::BAD_REQUEST),
+            (ApiError::Unauthorized("bad".into()), StatusCode::UNAUTHORIZED),
+            (ApiError::Forbidden("bad".into()), StatusCode::FORBIDDEN),
+            (ApiError::NotFound("bad".into()), StatusCode::NOT_FOUND),
+            (ApiError::Conflict("bad".into()), StatusCode::CONFLICT),
+            (ApiError::PayloadTooLarge("bad".into()), StatusCode::PAYLOAD_TOO_LARGE),
+            (ApiError::Unprocessable("bad".into()), StatusCode::UNPROCESSABLE_ENTITY),
+            (ApiError::Status(StatusCode::GONE, "bad".into()), StatusCode::GONE),
+            (ApiError::internal("bad"), StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+        for (error, expected) in cases {
+            let (status, body) = render(error).await;
+            assert_eq!(status, expected);
+            assert_eq!(body, serde_json::json!({ "success": false, "error": "bad" }));
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_fields_include_field_names() {
+        let (status, body) = render(ApiError::missing_fields(&["title", "end_at"])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"], serde_json::json!(["title", "end_at"]));
+        assert_eq!(body["error"], "Missing required fields: title, end_at");
+    }
+
+    #[test]
+    fn new_picks_matching_variant() {
+        assert!(matches!(ApiError::new(StatusCode::NOT_FOUND, "x"), ApiError::NotFound(_)));
+        assert!(matches!(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "x"), ApiError::Internal(_)));
+        assert!(matches!(
+            ApiError::new(StatusCode::TOO_MANY_REQUESTS, "x"),
+            ApiError::Status(StatusCode::TOO_MANY_REQUESTS, _)
+        ));
+    }
+
+    #[tokio::test]
+    async fn io_and_db_errors_become_500_with_context() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let (status, body) = render(ApiError::from(io)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "I/O error: denied");
+
+        let (status, body) = render(ApiError::from(sqlx::Error::RowNotFound)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error"].as_str().unwrap().starts_with("DB error: "));
+    }
+}
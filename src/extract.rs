@@ -0,0 +1,236 @@
+//! Request Extractors
+//! axum 標準の extractor を包み、エラーを crate 共通の `{success:false,error}` 形式で返す
+
+use axum::{
+    async_trait,
+    extract::{multipart::Field, rejection::JsonRejection, FromRequest, Request},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use base64::Engine;
+use md5::Md5;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+use crate::error::ApiError;
+
+/// JSON ボディ extractor（`axum::Json` の代替）
+///
+/// - Content-Type が application/json でない場合は 415
+/// - JSON として壊れている（途中で切れている等）場合は 400（パースエラーの詳細付き）
+/// - 型が合わない場合は 422、サイズ超過は 413（axum と同じステータス）
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => {
+                let status = match &rejection {
+                    JsonRejection::MissingJsonContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    JsonRejection::JsonSyntaxError(_) => StatusCode::BAD_REQUEST,
+                    _ => rejection.status(),
+                };
+                Err(ApiError::new(status, rejection.body_text()))
+            }
+        }
+    }
+}
+
+/// Multipart テキストフィールド1つあたりの上限（バイト）
+pub const MAX_TEXT_FIELD_BYTES: usize = 16 * 1024;
+/// 1リクエスト内のテキストフィールド合計の上限（バイト）
+pub const MAX_TEXT_FIELDS_TOTAL_BYTES: usize = 64 * 1024;
+
+/// Multipart のテキストフィールドを上限付きで読むための予算
+/// `field.text()` は全体をバッファするため、チャンクごとに上限を確認して超過時点で 400 を返す
+/// （ファイルフィールドはルートごとのボディ上限に従うのでここでは扱わない）
+pub struct TextFieldBudget {
+    remaining: usize,
+}
+
+impl TextFieldBudget {
+    pub fn new() -> Self {
+        Self { remaining: MAX_TEXT_FIELDS_TOTAL_BYTES }
+    }
+
+    /// テキストフィールドを読み込み、UTF-8 文字列として返す
+    pub async fn read(&mut self, mut field: Field<'_>) -> Result<String, ApiError> {
+        let name = field.name().unwrap_or("").to_string();
+        let mut buf = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| ApiError::new(e.status(), format!("{} read error: {}", name, e)))?
+        {
+            buf.extend_from_slice(&chunk);
+            if buf.len() > MAX_TEXT_FIELD_BYTES {
+                return Err(ApiError::BadRequest(format!(
+                    "Field '{}' exceeds {} bytes",
+                    name, MAX_TEXT_FIELD_BYTES
+                )));
+            }
+            if buf.len() > self.remaining {
+                return Err(ApiError::BadRequest(format!(
+                    "Text fields exceed {} bytes in total",
+                    MAX_TEXT_FIELDS_TOTAL_BYTES
+                )));
+            }
+        }
+        self.remaining -= buf.len();
+        String::from_utf8(buf)
+            .map_err(|_| ApiError::BadRequest(format!("Field '{}' is not valid UTF-8", name)))
+    }
+}
+
+impl Default for TextFieldBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// クライアントが送ったファイルのハッシュ（受信したバイト列との照合用）
+/// `Digest: sha-256=<base64>`（RFC 3230、md5 も可）または `Content-MD5: <base64>` で指定する
+pub enum ExpectedDigest {
+    Sha256(Vec<u8>),
+    Md5(Vec<u8>),
+}
+
+impl ExpectedDigest {
+    /// ヘッダから読み取る（どちらも無い場合は None、Digest と Content-MD5 の両方がある場合は Digest を優先）
+    /// 形式不正・対応アルゴリズムなしは 400
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, ApiError> {
+        if let Some(value) = headers.get("digest") {
+            let value = value
+                .to_str()
+                .map_err(|_| ApiError::BadRequest("Invalid Digest header".to_string()))?;
+            let mut md5 = None;
+            for part in value.split(',') {
+                let Some((algorithm, encoded)) = part.trim().split_once('=') else {
+                    continue;
+                };
+                match algorithm.to_ascii_lowercase().as_str() {
+                    "sha-256" => return decode_digest("Digest", encoded, 32).map(|d| Some(Self::Sha256(d))),
+                    "md5" => md5 = Some(encoded.to_string()),
+                    _ => {}
+                }
+            }
+            return match md5 {
+                Some(encoded) => decode_digest("Digest", &encoded, 16).map(|d| Some(Self::Md5(d))),
+                None => Err(ApiError::BadRequest(
+                    "Unsupported Digest algorithm (use sha-256 or md5)".to_string(),
+                )),
+            };
+        }
+        if let Some(value) = headers.get("content-md5") {
+            let value = value
+                .to_str()
+                .map_err(|_| ApiError::BadRequest("Invalid Content-MD5 header".to_string()))?;
+            return decode_digest("Content-MD5", value, 16).map(|d| Some(Self::Md5(d)));
+        }
+        Ok(None)
+    }
+
+    /// 受信したファイルと照合（不一致は 422）
+    pub fn verify(&self, data: &[u8]) -> Result<(), ApiError> {
+        let (algorithm, matches) = match self {
+            Self::Sha256(expected) => ("sha-256", Sha256::digest(data).as_slice() == expected.as_slice()),
+            Self::Md5(expected) => ("md5", Md5::digest(data).as_slice() == expected.as_slice()),
+        };
+        Self::check(algorithm, matches)
+    }
+
+    /// ディスク上のファイルと照合（大容量のためブロッキングスレッドで読み込み、不一致は 422）
+    pub async fn verify_file(&self, path: PathBuf) -> Result<(), ApiError> {
+        fn hash_file<D: Digest + std::io::Write>(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+            let mut hasher = D::new();
+            std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+            Ok(hasher.finalize().to_vec())
+        }
+
+        let (algorithm, expected) = match self {
+            Self::Sha256(expected) => ("sha-256", expected.clone()),
+            Self::Md5(expected) => ("md5", expected.clone()),
+        };
+        let actual = tokio::task::spawn_blocking(move || match algorithm {
+            "sha-256" => hash_file::<Sha256>(&path),
+            _ => hash_file::<Md5>(&path),
+        })
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to hash file: {}", e)))?
+        .map_err(|e| ApiError::internal(format!("Failed to hash file: {}", e)))?;
+        Self::check(algorithm, actual == expected)
+    }
+
+    fn check(algorithm: &str, matches: bool) -> Result<(), ApiError> {
+        if matches {
+            Ok(())
+        } else {
+            Err(ApiError::Unprocessable(format!(
+                "File {} digest does not match the received bytes",
+                algorithm
+            )))
+        }
+    }
+}
+
+/// base64 のハッシュ値をデコードし、長さも確認する
+fn decode_digest(header: &str, encoded: &str, len: usize) -> Result<Vec<u8>, ApiError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .filter(|d| d.len() == len)
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid {} header value", header)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Method;
+
+    use crate::test_support::{TestApp, TestResponse};
+
+    async fn post_raw(app: &TestApp, content_type: &str, body: &str) -> TestResponse {
+        let req = TestApp::request(Method::POST, "/api/vendors")
+            .header("content-type", content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        app.send(req).await
+    }
+
+    #[tokio::test]
+    async fn form_encoded_body_is_415_with_error_response() {
+        let app = TestApp::new().await;
+        let res = post_raw(&app, "application/x-www-form-urlencoded", "peer_id=abc&name=shop").await;
+        assert_eq!(res.status, 415);
+        let body = res.json();
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("application/json"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn truncated_json_is_400_with_parse_detail() {
+        let app = TestApp::new().await;
+        let res = post_raw(&app, "application/json", r#"{"peer_id": "abc", "profile": {"name": "Sh"#).await;
+        assert_eq!(res.status, 400);
+        let body = res.json();
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("EOF"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn mistyped_json_is_422_with_error_response() {
+        let app = TestApp::new().await;
+        let res = post_raw(&app, "application/json", r#"{"peer_id": 42, "profile": {"name": "Shop"}}"#).await;
+        assert_eq!(res.status, 422);
+        assert!(res.json()["error"].as_str().unwrap().contains("peer_id"));
+    }
+}
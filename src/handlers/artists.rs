@@ -2,11 +2,11 @@
 //! /api/account/artists エンドポイント
 
 use axum::{
-    extract::{Path, State, Multipart},
-    http::StatusCode,
+    extract::{Path, Query, State, Multipart},
     response::Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs;
@@ -15,41 +15,53 @@ use tracing::{info, warn};
 use sha2::{Sha256, Digest};
 use base32;
 
+use crate::error::{ApiError, ErrorResponse};
+use crate::response::{ApiResponse, ListMeta};
 use crate::models::{
     CreateArtistRequest, UpdateArtistRequest, Artist, ArtistProfile, ArtistP2P,
     ArtistResponse, ArtistCreateResponse, AddDiscographyRequest, DiscographyEntry,
     DiscographyJson, DiscographyAlbum, TrackPreview,
-    AddFollowerRequest, FollowerResponse, FollowerListResponse, CountResponse,
+    AddFollowerRequest, FollowerResponse, FollowerListResponse, CountResponse, tombstone_kind, ms_to_iso,
 };
+use crate::handlers::tombstones;
+use crate::images;
+use crate::signed_url;
+use crate::storage;
+use crate::extract::ApiJson;
 use crate::AppState;
 
 // ========================================
 // Response Types
 // ========================================
 
-#[derive(Serialize)]
-pub struct ArtistListResponse {
-    pub success: bool,
-    pub artists: Vec<ArtistResponse>,
-    pub total: usize,
-}
+/// 一覧レスポンス（`data` + `meta`、移行期間中は旧フィールド `artists` / `total` も返す）
+pub type ArtistListResponse = ApiResponse<Vec<ArtistResponse>>;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ArtistDetailResponse {
     pub success: bool,
     pub artist: Option<ArtistResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DiscographyResponse {
     pub success: bool,
     pub discography: DiscographyJson,
+    /// アルバムのページング情報（GET で limit / offset を指定した場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ListMeta>,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub success: bool,
-    pub error: String,
+/// 1アルバムあたり discography.json に載せる track_preview の上限（全曲数は track_count）
+const MAX_TRACK_PREVIEW: usize = 20;
+
+/// ディスコグラフィ取得のページング（どちらも未指定の場合は全アルバム）
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiscographyQuery {
+    /// 取得するアルバム数（1〜200、offset のみ指定時は 50）
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 // ========================================
@@ -57,15 +69,24 @@ pub struct ErrorResponse {
 // ========================================
 
 /// GET /api/account/artists - Artist一覧取得
+#[utoipa::path(
+    get,
+    path = "/api/account/artists",
+    tag = "artists",
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<ArtistResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_artists(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ArtistListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ArtistListResponse>, ApiError> {
     let artists: Vec<Artist> = sqlx::query_as(
         "SELECT * FROM artists WHERE is_alive = 1 ORDER BY created_at_ms DESC"
     )
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let mut responses = Vec::new();
     for a in &artists {
@@ -73,26 +94,34 @@ pub async fn list_artists(
         responses.push(artist_to_response(a, profile));
     }
 
-    let total = responses.len();
-    Ok(Json(ArtistListResponse {
-        success: true,
-        artists: responses,
-        total,
-    }))
+    let meta = ListMeta::total(responses.len());
+    Ok(Json(
+        ApiResponse::list(responses, meta).with_legacy_fields(state.config.legacy_list_fields, "artists"),
+    ))
 }
 
 /// GET /api/account/artists/:stable_id - Artist詳細取得
+#[utoipa::path(
+    get,
+    path = "/api/account/artists/{stable_id}",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ArtistDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_artist(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<ArtistDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ArtistDetailResponse>, ApiError> {
     let artist: Option<Artist> = sqlx::query_as(
         "SELECT * FROM artists WHERE stable_id = ?"
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     match artist {
         Some(a) => {
@@ -102,22 +131,32 @@ pub async fn get_artist(
                 artist: Some(artist_to_response(&a, profile)),
             }))
         }
-        None => Err(error_response(StatusCode::NOT_FOUND, "Artist not found".to_string())),
+        None => Err(ApiError::NotFound("Artist not found".to_string())),
     }
 }
 
 /// GET /api/account/artists/by-peer/:peer_id - peer_idでArtist取得
+#[utoipa::path(
+    get,
+    path = "/api/account/artists/by-peer/{peer_id}",
+    tag = "artists",
+    params(("peer_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ArtistDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_artist_by_peer(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
-) -> Result<Json<ArtistDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ArtistDetailResponse>, ApiError> {
     let artist: Option<Artist> = sqlx::query_as(
         "SELECT * FROM artists WHERE peer_id = ?"
     )
     .bind(&peer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     match artist {
         Some(a) => {
@@ -127,15 +166,26 @@ pub async fn get_artist_by_peer(
                 artist: Some(artist_to_response(&a, profile)),
             }))
         }
-        None => Err(error_response(StatusCode::NOT_FOUND, "Artist not found for this peer_id".to_string())),
+        None => Err(ApiError::NotFound("Artist not found for this peer_id".to_string())),
     }
 }
 
 /// POST /api/account/artists - Artist作成
+#[utoipa::path(
+    post,
+    path = "/api/account/artists",
+    tag = "artists",
+    request_body = CreateArtistRequest,
+    responses(
+        (status = 200, description = "成功", body = ArtistCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn create_artist(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateArtistRequest>,
-) -> Result<Json<ArtistCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<CreateArtistRequest>,
+) -> Result<Json<ArtistCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // peer_id の重複チェック
@@ -144,8 +194,7 @@ pub async fn create_artist(
     )
     .bind(&req.peer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     if let Some(a) = existing {
         // 既存を返す（冪等性）
@@ -154,10 +203,10 @@ pub async fn create_artist(
             success: true,
             stable_id: a.stable_id.clone(),
             peer_id: a.peer_id.clone(),
-            profile_url: a.profile_url.unwrap_or_default(),
-            profile_sha256: a.profile_sha256.unwrap_or_default(),
-            discography_url: a.discography_url.unwrap_or_default(),
-            discography_sha256: a.discography_sha256.unwrap_or_default(),
+            profile_url: signed_url::sign_opt(&state.config, a.profile_url),
+            profile_sha256: a.profile_sha256,
+            discography_url: signed_url::sign_opt(&state.config, a.discography_url),
+            discography_sha256: a.discography_sha256,
             icon_url: None,
             updated_at_ms: a.updated_at_ms.unwrap_or(now_ms),
         }));
@@ -175,7 +224,7 @@ pub async fn create_artist(
         .join("artists")
         .join(&stable_id);
     fs::create_dir_all(&artist_dir).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+        ApiError::internal(format!("Failed to create dir: {}", e))
     })?;
 
     // profile.json 保存
@@ -185,6 +234,7 @@ pub async fn create_artist(
         name: req.name.clone(),
         bio: req.bio.clone(),
         icon_url: None,
+        icon_thumb_url: None,
         links: vec![],
         p2p: Some(ArtistP2P {
             peer_id: req.peer_id.clone(),
@@ -198,7 +248,7 @@ pub async fn create_artist(
         &stable_id,
         &profile,
     ).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save profile: {}", e))
+        ApiError::internal(format!("Failed to save profile: {}", e))
     })?;
 
     // discography.json 初期生成（空）
@@ -214,7 +264,7 @@ pub async fn create_artist(
         &stable_id,
         &discography,
     ).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save discography: {}", e))
+        ApiError::internal(format!("Failed to save discography: {}", e))
     })?;
 
     // DBに挿入
@@ -237,8 +287,7 @@ pub async fn create_artist(
     .bind(now_ms)
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     info!("Artist created: stable_id={}, peer_id={}", stable_id, req.peer_id);
 
@@ -246,21 +295,33 @@ pub async fn create_artist(
         success: true,
         stable_id,
         peer_id: req.peer_id,
-        profile_url,
-        profile_sha256,
-        discography_url,
-        discography_sha256,
+        profile_url: Some(signed_url::sign(&state.config, &profile_url)),
+        profile_sha256: Some(profile_sha256),
+        discography_url: Some(signed_url::sign(&state.config, &discography_url)),
+        discography_sha256: Some(discography_sha256),
         icon_url: None,
         updated_at_ms: now_ms,
     }))
 }
 
 /// PUT /api/account/artists/:stable_id - Artist更新
+#[utoipa::path(
+    put,
+    path = "/api/account/artists/{stable_id}",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    request_body = UpdateArtistRequest,
+    responses(
+        (status = 200, description = "成功", body = ArtistCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn update_artist(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-    Json(req): Json<UpdateArtistRequest>,
-) -> Result<Json<ArtistCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<UpdateArtistRequest>,
+) -> Result<Json<ArtistCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // 既存チェック
@@ -269,11 +330,10 @@ pub async fn update_artist(
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let artist = existing.ok_or_else(|| {
-        error_response(StatusCode::NOT_FOUND, "Artist not found".to_string())
+        ApiError::NotFound("Artist not found".to_string())
     })?;
 
     // profile.json 更新
@@ -285,6 +345,7 @@ pub async fn update_artist(
             name: "Unknown".to_string(),
             bio: None,
             icon_url: None,
+            icon_thumb_url: None,
             links: vec![],
             p2p: Some(ArtistP2P {
                 peer_id: artist.peer_id.clone(),
@@ -307,7 +368,7 @@ pub async fn update_artist(
         &stable_id,
         &profile,
     ).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save profile: {}", e))
+        ApiError::internal(format!("Failed to save profile: {}", e))
     })?;
 
     // DB更新
@@ -330,8 +391,7 @@ pub async fn update_artist(
     .bind(now_ms)
     .bind(&stable_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     info!("Artist updated: stable_id={}", stable_id);
 
@@ -339,31 +399,209 @@ pub async fn update_artist(
         success: true,
         stable_id,
         peer_id: artist.peer_id,
-        profile_url,
-        profile_sha256,
-        discography_url: artist.discography_url.unwrap_or_default(),
-        discography_sha256: artist.discography_sha256.unwrap_or_default(),
-        icon_url: profile.icon_url,
+        profile_url: Some(signed_url::sign(&state.config, &profile_url)),
+        profile_sha256: Some(profile_sha256),
+        discography_url: signed_url::sign_opt(&state.config, artist.discography_url),
+        discography_sha256: artist.discography_sha256,
+        icon_url: signed_url::sign_opt(&state.config, profile.icon_url),
         updated_at_ms: now_ms,
     }))
 }
 
+/// DELETE /api/account/artists/:stable_id - Artist削除（論理削除）
+/// 詳細取得は引き続き可能（監査用）、一覧からは除外される
+#[utoipa::path(
+    delete,
+    path = "/api/account/artists/{stable_id}",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn delist_artist(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let artist: Artist = sqlx::query_as("SELECT * FROM artists WHERE stable_id = ?")
+        .bind(&stable_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Artist not found".to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+
+    // is_alive を 0 に設定（論理削除）
+    sqlx::query("UPDATE artists SET is_alive = 0, updated_at_ms = ? WHERE stable_id = ?")
+        .bind(now_ms)
+        .bind(&stable_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // 初回の delist のみ Tombstone を記録（object_id 未登録時は stable_id）
+    if artist.is_alive == 1 {
+        tombstones::record_tombstone(
+            &mut *tx,
+            tombstone_kind::ARTIST,
+            Some(&stable_id),
+            artist.latest_object_id.as_deref().unwrap_or(&stable_id),
+            &artist.env,
+            now_ms,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    info!("Artist delisted: stable_id={}, peer_id={}", stable_id, artist.peer_id);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "stable_id": stable_id,
+        "message": "Artist delisted successfully"
+    })))
+}
+
+/// POST /api/account/artists/:stable_id/regenerate - profile.json / discography.json を再生成
+/// ディスク上のファイルが外部で編集・破損した場合の修復用。
+/// discography は DB から再構築し、profile は読み込めない場合のみ DB とアイコンファイルから最小限で再構築する
+#[utoipa::path(
+    post,
+    path = "/api/account/artists/{stable_id}/regenerate",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn regenerate_artist(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let artist: Artist = sqlx::query_as("SELECT * FROM artists WHERE stable_id = ?")
+        .bind(&stable_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Artist not found".to_string()))?;
+
+    // P2P 情報は DB を正とする
+    let p2p = ArtistP2P {
+        peer_id: artist.peer_id.clone(),
+        peer_id_sha256: artist.peer_id_sha256.clone(),
+    };
+    let (profile, profile_source) = match load_artist_profile(&state.base_data_dir, &stable_id).await {
+        Ok(mut profile) => {
+            profile.stable_id = stable_id.clone();
+            profile.p2p = Some(p2p);
+            profile.updated_at_ms = now_ms;
+            (profile, "file")
+        }
+        Err(e) => {
+            warn!("Artist profile unreadable, rebuilding from DB: stable_id={}, error={}", stable_id, e);
+            let dir = PathBuf::from(&state.base_data_dir)
+                .join("account")
+                .join("artists")
+                .join(&stable_id);
+            let (icon, thumb) = storage::find_icon_files(&dir).await;
+            let icon_url_for = |name: String| {
+                format!("{}/account/artists/{}/{}", state.vps_base_url, stable_id, name)
+            };
+            let profile = ArtistProfile {
+                version: "1.0".to_string(),
+                stable_id: stable_id.clone(),
+                name: stable_id.clone(),
+                bio: None,
+                icon_url: icon.map(icon_url_for),
+                icon_thumb_url: thumb.map(icon_url_for),
+                links: vec![],
+                p2p: Some(p2p),
+                updated_at_ms: now_ms,
+            };
+            (profile, "db")
+        }
+    };
+
+    let (profile_url, profile_sha256) = save_artist_profile(
+        &state.base_data_dir,
+        &state.vps_base_url,
+        &stable_id,
+        &profile,
+    ).await.map_err(|e| {
+        ApiError::internal(format!("Failed to save profile: {}", e))
+    })?;
+
+    sqlx::query(r#"
+        UPDATE artists SET
+            profile_url = ?,
+            profile_sha256 = ?,
+            profile_seq = profile_seq + 1,
+            updated_at_ms = ?
+        WHERE stable_id = ?
+    "#)
+    .bind(&profile_url)
+    .bind(&profile_sha256)
+    .bind(now_ms)
+    .bind(&stable_id)
+    .execute(&state.db)
+    .await?;
+
+    // discography.json は DB から再生成（URL/SHA256 も更新される）
+    regenerate_discography(&state, &stable_id, now_ms).await?;
+
+    let artist: Artist = sqlx::query_as("SELECT * FROM artists WHERE stable_id = ?")
+        .bind(&stable_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    info!("Artist files regenerated: stable_id={}, profile_source={}", stable_id, profile_source);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "stable_id": stable_id,
+        "profile_url": signed_url::sign(&state.config, &profile_url),
+        "profile_sha256": profile_sha256,
+        "discography_url": signed_url::sign_opt(&state.config, artist.discography_url),
+        "discography_sha256": artist.discography_sha256,
+        "profile_source": profile_source
+    })))
+}
+
 /// POST /api/account/artists/:stable_id/icon - アイコンアップロード
+#[utoipa::path(
+    post,
+    path = "/api/account/artists/{stable_id}/icon",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    request_body(content_type = "multipart/form-data", description = "file（または icon）: 画像ファイル"),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn upload_artist_icon(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
     mut multipart: Multipart,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error_response(StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
         if name == "file" || name == "icon" {
             let filename = field.file_name().unwrap_or("icon.webp").to_string();
-            let ext = filename.split('.').last().unwrap_or("webp");
+            let ext = filename.split('.').next_back().unwrap_or("webp");
 
             let data = field.bytes().await.map_err(|e| {
-                error_response(StatusCode::BAD_REQUEST, format!("File read error: {}", e))
+                ApiError::new(e.status(), format!("File read error: {}", e))
             })?;
 
             // 保存先ディレクトリ
@@ -372,32 +610,23 @@ pub async fn upload_artist_icon(
                 .join("artists")
                 .join(&stable_id);
             fs::create_dir_all(&dir).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+                ApiError::internal(format!("Failed to create dir: {}", e))
             })?;
 
             // オリジナルファイル保存
             let icon_filename = format!("icon.{}", ext);
             let path = dir.join(&icon_filename);
             let mut file = fs::File::create(&path).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create file: {}", e))
+                ApiError::internal(format!("Failed to create file: {}", e))
             })?;
             file.write_all(&data).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write file: {}", e))
+                ApiError::internal(format!("Failed to write file: {}", e))
             })?;
 
-            // サムネイル生成（200x200、PFP用の正方形）
-            let thumb_filename = format!("icon_thumb.{}", ext);
-            let thumb_path = dir.join(&thumb_filename);
-            let data_clone = data.to_vec();
-            let thumb_path_clone = thumb_path.clone();
-            let _ = tokio::task::spawn_blocking(move || {
-                if let Ok(img) = image::load_from_memory(&data_clone) {
-                    // Lanczos3で高品質リサイズ、200x200正方形
-                    let thumb = img.resize_to_fill(200, 200, image::imageops::FilterType::Lanczos3);
-                    let _ = thumb.save(&thumb_path_clone);
-                    info!("Icon thumbnail generated: {:?}", thumb_path_clone);
-                }
-            }).await;
+            // サムネイル生成（正方形 WebP、失敗してもオリジナルは保存済み）
+            let thumb_filename = "icon_thumb.webp";
+            let thumb_generated =
+                images::generate_icon_thumbnail(data.to_vec(), dir.join(thumb_filename)).await;
 
             // icon_url を profile.json に更新（サムネイルURLも含む）
             let icon_url = format!(
@@ -406,45 +635,92 @@ pub async fn upload_artist_icon(
                 stable_id,
                 icon_filename
             );
-            let icon_thumb_url = format!(
-                "{}/account/artists/{}/{}",
-                state.vps_base_url,
-                stable_id,
-                thumb_filename
-            );
-
-            // profile.json を更新
+            let icon_thumb_url = thumb_generated.then(|| {
+                format!(
+                    "{}/account/artists/{}/{}",
+                    state.vps_base_url,
+                    stable_id,
+                    thumb_filename
+                )
+            });
+
+            // profile.json を更新し、DB の URL/SHA256・profile_seq・updated_at_ms も合わせる
+            // （profile.json が無い・読めない場合も updated_at_ms は進める）
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let mut profile_sha256: Option<String> = None;
             if let Ok(mut profile) = load_artist_profile(&state.base_data_dir, &stable_id).await {
                 profile.icon_url = Some(icon_url.clone());
-                profile.updated_at_ms = chrono::Utc::now().timestamp_millis();
-                let _ = save_artist_profile(
+                profile.icon_thumb_url = icon_thumb_url.clone();
+                profile.updated_at_ms = now_ms;
+                let (profile_url, sha256) = save_artist_profile(
                     &state.base_data_dir,
                     &state.vps_base_url,
                     &stable_id,
                     &profile,
-                ).await;
+                ).await.map_err(|e| {
+                    ApiError::internal(format!("Failed to save profile: {}", e))
+                })?;
+
+                sqlx::query(r#"
+                    UPDATE artists SET
+                        profile_url = ?,
+                        profile_sha256 = ?,
+                        profile_seq = profile_seq + 1,
+                        updated_at_ms = ?
+                    WHERE stable_id = ?
+                "#)
+                .bind(&profile_url)
+                .bind(&sha256)
+                .bind(now_ms)
+                .bind(&stable_id)
+                .execute(&state.db)
+                .await?;
+                profile_sha256 = Some(sha256);
+            } else {
+                sqlx::query("UPDATE artists SET updated_at_ms = ? WHERE stable_id = ?")
+                    .bind(now_ms)
+                    .bind(&stable_id)
+                    .execute(&state.db)
+                    .await?;
             }
 
-            info!("Icon uploaded: {} (thumb: {})", icon_url, icon_thumb_url);
+            info!("Icon uploaded: {} (thumb: {:?})", icon_url, icon_thumb_url);
+
+            // 所有権を変更（UPLOAD_CHOWN、ベストエフォート）
+            storage::apply_upload_owner(state.config.upload_chown.as_deref(), &dir, true).await;
 
             return Ok(Json(serde_json::json!({
                 "success": true,
-                "icon_url": icon_url,
-                "icon_thumb_url": icon_thumb_url,
+                "icon_url": signed_url::sign(&state.config, &icon_url),
+                "icon_thumb_url": signed_url::sign_opt(&state.config, icon_thumb_url),
+                "profile_sha256": profile_sha256,
+                "updated_at_ms": now_ms,
                 "path": path.to_string_lossy()
             })));
         }
     }
 
-    Err(error_response(StatusCode::BAD_REQUEST, "No file provided".to_string()))
+    Err(ApiError::BadRequest("No file provided".to_string()))
 }
 
 /// POST /api/account/artists/:stable_id/discography - ディスコグラフィ追加
+#[utoipa::path(
+    post,
+    path = "/api/account/artists/{stable_id}/discography",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    request_body = AddDiscographyRequest,
+    responses(
+        (status = 200, description = "成功", body = DiscographyResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn add_discography(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-    Json(req): Json<AddDiscographyRequest>,
-) -> Result<Json<DiscographyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(mut req): ApiJson<AddDiscographyRequest>,
+) -> Result<Json<DiscographyResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // Artist 存在チェック
@@ -453,14 +729,14 @@ pub async fn add_discography(
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     if artist.is_none() {
-        return Err(error_response(StatusCode::NOT_FOUND, "Artist not found".to_string()));
+        return Err(ApiError::NotFound("Artist not found".to_string()));
     }
 
-    // track_preview を JSON 文字列に変換
+    // track_preview は先頭 MAX_TRACK_PREVIEW 曲のみ保存し、JSON 文字列に変換
+    req.track_preview.truncate(MAX_TRACK_PREVIEW);
     let track_preview_json = serde_json::to_string(&req.track_preview).unwrap_or("[]".to_string());
 
     // DB に UPSERT
@@ -489,8 +765,7 @@ pub async fn add_discography(
     .bind(req.deployed_at_ms.unwrap_or(now_ms))
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     // discography.json を再生成
     let discography = regenerate_discography(&state, &stable_id, now_ms).await?;
@@ -500,21 +775,94 @@ pub async fn add_discography(
     Ok(Json(DiscographyResponse {
         success: true,
         discography,
+        meta: None,
+    }))
+}
+
+/// DELETE /api/account/artists/:stable_id/discography/:album_id - ディスコグラフィ削除
+#[utoipa::path(
+    delete,
+    path = "/api/account/artists/{stable_id}/discography/{album_id}",
+    tag = "artists",
+    params(("stable_id" = String, Path), ("album_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = DiscographyResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn remove_discography(
+    State(state): State<Arc<AppState>>,
+    Path((stable_id, album_id)): Path<(String, String)>,
+) -> Result<Json<DiscographyResponse>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let result = sqlx::query("DELETE FROM discography WHERE artist_stable_id = ? AND album_id = ?")
+        .bind(&stable_id)
+        .bind(&album_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Album not found in discography".to_string()));
+    }
+
+    // discography.json を再生成
+    let discography = regenerate_discography(&state, &stable_id, now_ms).await?;
+
+    info!("Discography removed: artist={}, album={}", stable_id, album_id);
+
+    Ok(Json(DiscographyResponse {
+        success: true,
+        discography,
+        meta: None,
     }))
 }
 
 /// GET /api/account/artists/:stable_id/discography - ディスコグラフィ取得
+/// limit / offset 指定時は albums をその範囲に絞り、meta に全アルバム数を返す
+#[utoipa::path(
+    get,
+    path = "/api/account/artists/{stable_id}/discography",
+    tag = "artists",
+    params(("stable_id" = String, Path), DiscographyQuery),
+    responses(
+        (status = 200, description = "成功", body = DiscographyResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_discography(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<DiscographyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<DiscographyQuery>,
+) -> Result<Json<DiscographyResponse>, ApiError> {
     // discography.json を読み込み
-    let discography = load_discography_json(&state.base_data_dir, &stable_id).await
-        .map_err(|_| error_response(StatusCode::NOT_FOUND, "Discography not found".to_string()))?;
+    let mut discography = load_discography_json(&state.base_data_dir, &stable_id).await
+        .map_err(|_| ApiError::NotFound("Discography not found".to_string()))?;
+
+    let meta = if query.limit.is_some() || query.offset.is_some() {
+        let limit = query.limit.unwrap_or(50).clamp(1, 200);
+        let offset = query.offset.unwrap_or(0).max(0);
+        let total = discography.albums.len();
+        discography.albums = discography.albums
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        Some(ListMeta {
+            limit: Some(limit),
+            offset: Some(offset),
+            ..ListMeta::total(total)
+        })
+    } else {
+        None
+    };
 
     Ok(Json(DiscographyResponse {
         success: true,
         discography,
+        meta,
     }))
 }
 
@@ -523,11 +871,23 @@ pub async fn get_discography(
 // ========================================
 
 /// POST /api/account/artists/:stable_id/followers - フォロワー登録
+#[utoipa::path(
+    post,
+    path = "/api/account/artists/{stable_id}/followers",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    request_body = AddFollowerRequest,
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn add_follower(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-    Json(req): Json<AddFollowerRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<AddFollowerRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // peer_profiles を UPSERT（初回は display_name=NULL のまま登録）
@@ -539,8 +899,7 @@ pub async fn add_follower(
     .bind(&req.peer_id)
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     // artist_followers に UPSERT
     sqlx::query(r#"
@@ -552,8 +911,7 @@ pub async fn add_follower(
     .bind(&req.peer_id)
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     info!("Follower added: artist={}, peer={}", stable_id, &req.peer_id[..20.min(req.peer_id.len())]);
 
@@ -561,16 +919,26 @@ pub async fn add_follower(
 }
 
 /// DELETE /api/account/artists/:stable_id/followers/:peer_id - フォロワー削除
+#[utoipa::path(
+    delete,
+    path = "/api/account/artists/{stable_id}/followers/{peer_id}",
+    tag = "artists",
+    params(("stable_id" = String, Path), ("peer_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn remove_follower(
     State(state): State<Arc<AppState>>,
     Path((stable_id, peer_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     sqlx::query("DELETE FROM artist_followers WHERE artist_stable_id = ? AND peer_id = ?")
         .bind(&stable_id)
         .bind(&peer_id)
         .execute(&state.db)
-        .await
-        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
     info!("Follower removed: artist={}, peer={}", stable_id, &peer_id[..20.min(peer_id.len())]);
 
@@ -578,10 +946,21 @@ pub async fn remove_follower(
 }
 
 /// GET /api/account/artists/:stable_id/followers - フォロワー一覧（peer_id 非公開）
+#[utoipa::path(
+    get,
+    path = "/api/account/artists/{stable_id}/followers",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = FollowerListResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_followers(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<FollowerListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<FollowerListResponse>, ApiError> {
     let rows: Vec<(Option<String>, Option<String>, i64)> = sqlx::query_as(
         r#"
         SELECT pp.display_name, pp.pfp_url, af.followed_at_ms
@@ -593,8 +972,7 @@ pub async fn list_followers(
     )
     .bind(&stable_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let followers: Vec<FollowerResponse> = rows.into_iter().map(|(name, pfp, ts)| {
         FollowerResponse { display_name: name, pfp_url: pfp, followed_at_ms: ts }
@@ -604,17 +982,27 @@ pub async fn list_followers(
 }
 
 /// GET /api/account/artists/:stable_id/follower-count - フォロワー数（パブリック）
+#[utoipa::path(
+    get,
+    path = "/api/account/artists/{stable_id}/follower-count",
+    tag = "artists",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = CountResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_follower_count(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<CountResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<CountResponse>, ApiError> {
     let (count,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM artist_followers WHERE artist_stable_id = ?"
     )
     .bind(&stable_id)
     .fetch_one(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     Ok(Json(CountResponse { success: true, count }))
 }
@@ -731,24 +1119,24 @@ async fn load_discography_json(base_dir: &str, stable_id: &str) -> anyhow::Resul
 }
 
 /// DB から discography を読み直して JSON を再生成
-async fn regenerate_discography(
+pub(crate) async fn regenerate_discography(
     state: &Arc<AppState>,
     stable_id: &str,
     now_ms: i64,
-) -> Result<DiscographyJson, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<DiscographyJson, ApiError> {
     let entries: Vec<DiscographyEntry> = sqlx::query_as(
         "SELECT * FROM discography WHERE artist_stable_id = ? ORDER BY deployed_at_ms DESC"
     )
     .bind(stable_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let albums: Vec<DiscographyAlbum> = entries.iter().map(|e| {
-        let track_preview: Vec<TrackPreview> = e.track_preview
+        let mut track_preview: Vec<TrackPreview> = e.track_preview
             .as_ref()
             .and_then(|s| serde_json::from_str(s).ok())
             .unwrap_or_default();
+        track_preview.truncate(MAX_TRACK_PREVIEW);
 
         DiscographyAlbum {
             album_id: e.album_id.clone(),
@@ -776,7 +1164,7 @@ async fn regenerate_discography(
         stable_id,
         &discography,
     ).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save discography: {}", e))
+        ApiError::internal(format!("Failed to save discography: {}", e))
     })?;
 
     // DB の discography_url/sha256 を更新
@@ -792,8 +1180,7 @@ async fn regenerate_discography(
     .bind(now_ms)
     .bind(stable_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     Ok(discography)
 }
@@ -814,12 +1201,221 @@ fn artist_to_response(a: &Artist, profile: Option<ArtistProfile>) -> ArtistRespo
         status: a.status,
         created_at_ms: a.created_at_ms,
         updated_at_ms: a.updated_at_ms,
+        created_at_iso: a.created_at_ms.and_then(ms_to_iso),
+        updated_at_iso: a.updated_at_ms.and_then(ms_to_iso),
         is_alive: a.is_alive == 1,
     }
 }
 
-/// エラーレスポンス生成
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    warn!("API Error: {}", message);
-    (status, Json(ErrorResponse { success: false, error: message }))
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+    use sha2::Digest;
+
+    use crate::test_support::{artist_body, png_bytes, MultipartForm, TestApp};
+
+    async fn add_album(app: &TestApp, artist: &str, album_id: &str, deployed_at_ms: i64) {
+        let res = app
+            .post_json(
+                &format!("/api/account/artists/{}/discography", artist),
+                json!({ "album_id": album_id, "title": album_id, "deployed_at_ms": deployed_at_ms }),
+            )
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    fn album_ids(discography: &serde_json::Value) -> Vec<String> {
+        discography["albums"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["album_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn removing_album_updates_json_and_db() {
+        let app = TestApp::new().await;
+        let artist = app.create_artist(None).await;
+        add_album(&app, &artist, "ALBUM_A", 1_000).await;
+        add_album(&app, &artist, "ALBUM_B", 2_000).await;
+
+        let uri = format!("/api/account/artists/{}/discography/ALBUM_A", artist);
+        let res = app.send_json(Method::DELETE, &uri, None, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(album_ids(&res.json()["discography"]), vec!["ALBUM_B"]);
+
+        let path = app.data_path(format!("account/artists/{}/discography.json", artist));
+        let file: serde_json::Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        assert_eq!(album_ids(&file), vec!["ALBUM_B"]);
+
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT album_id FROM discography WHERE artist_stable_id = ?")
+            .bind(&artist)
+            .fetch_all(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![("ALBUM_B".to_string(),)]);
+
+        // 削除済み・未登録のアルバムは 404
+        assert_eq!(app.send_json(Method::DELETE, &uri, None, &[]).await.status, 404);
+    }
+
+    fn artist_ids(body: &serde_json::Value) -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["stable_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn delisted_artist_is_hidden_from_list_but_still_readable() {
+        let app = TestApp::new().await;
+        let kept = app.create_artist(None).await;
+        let gone = app.create_artist(None).await;
+
+        let uri = format!("/api/account/artists/{}", gone);
+        let res = app.send_json(Method::DELETE, &uri, None, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        assert_eq!(artist_ids(&app.get("/api/account/artists").await.json()), vec![kept]);
+
+        let res = app.get(&uri).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.json()["artist"]["is_alive"], false);
+
+        let tombstones = app.get("/api/tombstones?kind=artist").await.json();
+        assert_eq!(tombstones["data"][0]["stable_id"], gone.as_str());
+
+        let missing = app.send_json(Method::DELETE, "/api/account/artists/ARTIST_MISSING", None, &[]).await;
+        assert_eq!(missing.status, 404);
+    }
+
+    fn file_sha256(path: &std::path::Path) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(std::fs::read(path).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn regenerate_repairs_corrupted_artist_files() {
+        let app = TestApp::new().await;
+        let artist = app.create_artist(None).await;
+        add_album(&app, &artist, "ALBUM_A", 1_000).await;
+        let dir = app.data_path(format!("account/artists/{}", artist));
+        std::fs::write(dir.join("profile.json"), b"{ corrupted").unwrap();
+        std::fs::write(dir.join("discography.json"), b"[]").unwrap();
+
+        let res = app.post_json(&format!("/api/account/artists/{}/regenerate", artist), json!({})).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["profile_source"], "db");
+
+        let (profile_sha256, discography_sha256): (String, String) =
+            sqlx::query_as("SELECT profile_sha256, discography_sha256 FROM artists WHERE stable_id = ?")
+                .bind(&artist)
+                .fetch_one(&app.state.db)
+                .await
+                .unwrap();
+        assert_eq!(file_sha256(&dir.join("profile.json")), profile_sha256);
+        assert_eq!(file_sha256(&dir.join("discography.json")), discography_sha256);
+        assert_eq!(body["profile_sha256"], profile_sha256.as_str());
+
+        // discography は DB から再構築される
+        let discography = app.get(&format!("/api/account/artists/{}/discography", artist)).await.json();
+        assert_eq!(album_ids(&discography["discography"]), vec!["ALBUM_A"]);
+    }
+
+    async fn icon_state(app: &TestApp, stable_id: &str) -> (i64, i64, String, String) {
+        sqlx::query_as("SELECT updated_at_ms, profile_seq, profile_sha256, profile_url FROM artists WHERE stable_id = ?")
+            .bind(stable_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn icon_upload_bumps_updated_at_seq_and_sha256() {
+        let app = TestApp::new().await;
+        let stable_id = app.create_artist(None).await;
+        let (updated_before, seq_before, sha_before, _) = icon_state(&app, &stable_id).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(8, 8));
+        let res = app.send_form(Method::POST, &format!("/api/account/artists/{}/icon", stable_id), form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+
+        let (updated_after, seq_after, sha_after, url) = icon_state(&app, &stable_id).await;
+        assert!(updated_after > updated_before);
+        assert_eq!(body["updated_at_ms"], updated_after);
+        assert_eq!(seq_after, seq_before + 1);
+        assert_ne!(sha_after, sha_before);
+        assert_eq!(body["profile_sha256"], sha_after.as_str());
+
+        // 保存された JSON のハッシュと一致し、icon_url が反映されている
+        let relative = url.strip_prefix(&format!("{}/", app.state.vps_base_url)).unwrap();
+        let data = std::fs::read(app.data_path(relative)).unwrap();
+        assert_eq!(hex::encode(sha2::Sha256::digest(&data)), sha_after);
+        let profile: Value = serde_json::from_slice(&data).unwrap();
+        assert!(profile["icon_url"].as_str().unwrap().ends_with("/icon.png"));
+    }
+
+    #[tokio::test]
+    async fn recreating_artist_returns_null_for_unset_discography() {
+        let app = TestApp::new().await;
+        let body = artist_body(None);
+        let res = app.post_json("/api/account/artists", body.clone()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let stable_id = res.json()["stable_id"].as_str().unwrap().to_string();
+        sqlx::query("UPDATE artists SET discography_url = NULL, discography_sha256 = NULL WHERE stable_id = ?")
+            .bind(&stable_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+
+        // 同じ peer_id での作成は既存を返す
+        let res = app.post_json("/api/account/artists", body).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let again = res.json();
+        assert_eq!(again["stable_id"], stable_id.as_str());
+        assert!(again["discography_url"].is_null());
+        assert!(again["discography_sha256"].is_null());
+        assert!(again["profile_url"].is_string());
+    }
+
+    #[tokio::test]
+    async fn discography_caps_track_preview_and_pages_albums() {
+        let app = TestApp::new().await;
+        let artist = app.create_artist(None).await;
+        let tracks: Vec<Value> = (1..=30).map(|i| json!({ "i": i, "title": format!("Track {}", i) })).collect();
+        for n in 1..=5 {
+            let body = json!({
+                "album_id": format!("al{}", n),
+                "track_count": 30,
+                "track_preview": tracks,
+                "deployed_at_ms": n * 1_000,
+            });
+            let res = app.post_json(&format!("/api/account/artists/{}/discography", artist), body).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+        }
+
+        let uri = format!("/api/account/artists/{}/discography", artist);
+        let body = app.get(&uri).await.json();
+        assert!(body.get("meta").is_none());
+        let albums = body["discography"]["albums"].as_array().unwrap();
+        assert_eq!(albums.len(), 5);
+        for album in albums {
+            assert_eq!(album["track_count"], 30);
+            assert_eq!(album["track_preview"].as_array().unwrap().len(), 20);
+        }
+        let all = album_ids(&body["discography"]);
+
+        let body = app.get(&format!("{}?limit=2&offset=1", uri)).await.json();
+        assert_eq!(album_ids(&body["discography"]), all[1..3]);
+        assert_eq!(body["meta"], json!({ "total": 5, "limit": 2, "offset": 1 }));
+        assert_eq!(album_ids(&app.get(&format!("{}?offset=4", uri)).await.json()["discography"]), all[4..]);
+        assert!(album_ids(&app.get(&format!("{}?offset=9", uri)).await.json()["discography"]).is_empty());
+    }
 }
@@ -0,0 +1,126 @@
+//! Assets API Handlers
+//! /api/assets エンドポイント - 署名付きアセット URL の検証（CDN/エッジ向け）
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::error::ApiError;
+use crate::signed_url;
+use crate::AppState;
+
+// ========================================
+// Response Types
+// ========================================
+
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    pub success: bool,
+    pub path: String,
+    pub expires: i64,
+}
+
+// ========================================
+// Query Parameters
+// ========================================
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyQuery {
+    /// 署名 URL のパス部分（例: /account/vendors/VENDOR_XXX/profile.json）
+    pub path: String,
+    pub expires: i64,
+    pub sig: String,
+}
+
+// ========================================
+// Handlers
+// ========================================
+
+/// GET /api/assets/verify?path=...&expires=...&sig=... - 署名付きアセット URL の検証
+/// 有効なら 200、期限切れ・改ざんは 403（署名が無効化されている場合も 403）
+pub async fn verify_asset(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VerifyQuery>,
+) -> Result<Json<VerifyResponse>, ApiError> {
+    let secret = state.config.asset_signing_secret.as_deref().ok_or_else(|| {
+        ApiError::Forbidden("Asset signing is disabled".to_string())
+    })?;
+
+    let now = chrono::Utc::now().timestamp();
+    signed_url::verify(secret, &query.path, query.expires, &query.sig, now)
+        .map_err(|e| ApiError::Forbidden(e.to_string()))?;
+
+    Ok(Json(VerifyResponse {
+        success: true,
+        path: query.path,
+        expires: query.expires,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{vendor_body, TestApp, TestResponse};
+
+    /// Vendor を作成し、レスポンスの manifest_url を返す
+    async fn create_vendor_manifest_url(app: &TestApp) -> reqwest::Url {
+        let res = app.post_json("/api/vendors", vendor_body(None)).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        reqwest::Url::parse(res.json()["manifest_url"].as_str().unwrap()).unwrap()
+    }
+
+    fn query_param(url: &reqwest::Url, name: &str) -> String {
+        url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned()).unwrap()
+    }
+
+    async fn verify(app: &TestApp, path: &str, url: &reqwest::Url) -> TestResponse {
+        app.get(&format!(
+            "/api/assets/verify?path={}&expires={}&sig={}",
+            path,
+            query_param(url, "expires"),
+            query_param(url, "sig")
+        ))
+        .await
+    }
+
+    #[tokio::test]
+    async fn signed_url_verifies_and_rejects_tampered_path() {
+        let app = TestApp::with_config(|c| c.asset_signing_secret = Some("signing-secret".to_string())).await;
+        let url = create_vendor_manifest_url(&app).await;
+
+        let res = verify(&app, url.path(), &url).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["path"], url.path());
+
+        let tampered = url.path().replace("VENDOR_", "VENDOR_X");
+        let res = verify(&app, &tampered, &url).await;
+        assert_eq!(res.status, 403);
+        assert!(res.json()["error"].as_str().unwrap().contains("Invalid signature"));
+    }
+
+    #[tokio::test]
+    async fn expired_signature_is_rejected() {
+        let app = TestApp::with_config(|c| {
+            c.asset_signing_secret = Some("signing-secret".to_string());
+            c.asset_url_ttl_secs = -10;
+        })
+        .await;
+        let url = create_vendor_manifest_url(&app).await;
+
+        let res = verify(&app, url.path(), &url).await;
+        assert_eq!(res.status, 403);
+        assert!(res.json()["error"].as_str().unwrap().contains("expired"));
+    }
+
+    #[tokio::test]
+    async fn urls_are_unsigned_by_default() {
+        let app = TestApp::new().await;
+        let url = create_vendor_manifest_url(&app).await;
+        assert_eq!(url.query(), None);
+
+        let res = app.get("/api/assets/verify?path=/x&expires=1&sig=00").await;
+        assert_eq!(res.status, 403);
+    }
+}
@@ -1,17 +1,106 @@
 use axum::{
-    extract::{Multipart, State},
-    http::{header, StatusCode},
-    response::{Html, IntoResponse},
+    extract::{Multipart, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
 };
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::{info, warn};
 
+use crate::error::ApiError;
 use crate::AppState;
 
-const CAMERA_TEMP_DIR: &str = "/data/camera_temp";
-const LATEST_FILE: &str = "/data/camera_temp/latest";
+/// 旧形式（トークン導入前）の単一ファイル名
+const LATEST_FILE_NAME: &str = "latest";
+/// トークン長（sha256 hex の先頭）
+const CAPTURE_TOKEN_LEN: usize = 16;
+/// インデックスに保持する直近キャプチャ数
+const MAX_RECENT_CAPTURES: usize = 20;
+
+// ========================================
+// Capture Index
+// ========================================
+
+/// 受信済みキャプチャ（新しい順に AppState で保持）
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraCapture {
+    pub token: String,
+    pub sha256: String,
+    pub size: u64,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CameraUploadResponse {
+    pub success: bool,
+    pub token: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CaptureQuery {
+    /// 指定時はそのキャプチャ、未指定時は最新
+    pub token: Option<String>,
+}
+
+/// トークンの形式検証（パス・トラバーサル防止）
+fn is_valid_capture_token(token: &str) -> bool {
+    token.len() == CAPTURE_TOKEN_LEN && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn capture_path(state: &AppState, token: &str) -> String {
+    format!("{}/{}", state.config.camera_temp_dir, token)
+}
+
+/// 取得対象のファイルパスを解決
+/// token 指定時はそのキャプチャ、未指定時は最新キャプチャ
+/// （インデックスはメモリ上のみなので、空なら再起動前のファイルから更新日時が最新のもの）
+async fn resolve_capture_path(
+    state: &AppState,
+    token: Option<&str>,
+) -> Result<String, ApiError> {
+    match token {
+        Some(token) => {
+            if !is_valid_capture_token(token) {
+                return Err(ApiError::BadRequest(format!("Invalid capture token: {}", token)));
+            }
+            Ok(capture_path(state, token))
+        }
+        None => {
+            let newest = state.camera_captures.read().await.front().map(|c| c.token.clone());
+            let name = match newest {
+                Some(token) => token,
+                None => newest_capture_on_disk(state)
+                    .await
+                    .unwrap_or_else(|| LATEST_FILE_NAME.to_string()),
+            };
+            Ok(capture_path(state, &name))
+        }
+    }
+}
+
+/// camera_temp_dir 内のキャプチャ（トークン名と旧形式の latest）のうち更新日時が最新のファイル名
+async fn newest_capture_on_disk(state: &AppState) -> Option<String> {
+    let mut entries = fs::read_dir(&state.config.camera_temp_dir).await.ok()?;
+    let mut newest: Option<(std::time::SystemTime, String)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_valid_capture_token(&name) && name != LATEST_FILE_NAME {
+            continue;
+        }
+        let Some(modified) = entry.metadata().await.ok().filter(|m| m.is_file()).and_then(|m| m.modified().ok()) else {
+            continue;
+        };
+        if newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+            newest = Some((modified, name));
+        }
+    }
+    newest.map(|(_, name)| name)
+}
 
 /// GET /camera — モバイル向けカメラ撮影ページ
 pub async fn camera_page() -> Html<&'static str> {
@@ -75,12 +164,13 @@ fileInput.addEventListener('change',async(e)=>{
     form.append('image',file);
     const res=await fetch('/api/camera/upload',{method:'POST',body:form});
     if(res.ok){
+      const data=await res.json();
       status.className='success';
-      status.textContent='アップロード完了！アプリで取得してください。';
+      status.textContent='アップロード完了！アプリで取得してください。（'+data.token+'）';
     }else{
-      const text=await res.text();
+      const data=await res.json().catch(()=>null);
       status.className='error';
-      status.textContent='エラー: '+text;
+      status.textContent='エラー: '+((data&&data.error)||res.status);
     }
   }catch(err){
     status.className='error';
@@ -94,47 +184,124 @@ fileInput.addEventListener('change',async(e)=>{
 }
 
 /// POST /api/camera/upload — モバイルから画像受信
+/// 受信しながら sha256 を計算し、`camera_temp/<token>` に保存してトークンを返す
 pub async fn upload_image(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     mut multipart: Multipart,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
     // camera_temp ディレクトリ作成
-    fs::create_dir_all(CAMERA_TEMP_DIR).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+    fs::create_dir_all(&state.config.camera_temp_dir).await.map_err(|e| {
+        ApiError::internal(format!("Failed to create dir: {}", e))
     })?;
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        (StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
-        if name == "image" {
-            let bytes = field.bytes().await.map_err(|e| {
-                (StatusCode::BAD_REQUEST, format!("Read error: {}", e))
-            })?;
+        if name != "image" {
+            continue;
+        }
 
-            info!("Camera upload received: {} bytes", bytes.len());
+        // 同時アップロードで衝突しない一時ファイルに書き込みつつハッシュ計算
+        let part_path = format!("{}/upload-{}.part", state.config.camera_temp_dir, uuid::Uuid::new_v4());
+        let mut file = fs::File::create(&part_path).await.map_err(|e| {
+            ApiError::internal(format!("File create error: {}", e))
+        })?;
+        let mut hasher = Sha256::new();
+        let mut size: u64 = 0;
 
-            let mut file = fs::File::create(LATEST_FILE).await.map_err(|e| {
-                (StatusCode::INTERNAL_SERVER_ERROR, format!("File create error: {}", e))
-            })?;
+        let written: Result<(), ApiError> = async {
+            while let Some(chunk) = field.chunk().await.map_err(|e| {
+                ApiError::new(e.status(), format!("Read error: {}", e))
+            })? {
+                hasher.update(&chunk);
+                size += chunk.len() as u64;
+                file.write_all(&chunk).await.map_err(|e| {
+                    ApiError::internal(format!("Write error: {}", e))
+                })?;
+            }
+            file.sync_all().await.map_err(|e| {
+                ApiError::internal(format!("Write error: {}", e))
+            })
+        }
+        .await;
+        if let Err(e) = written {
+            let _ = fs::remove_file(&part_path).await;
+            return Err(e);
+        }
 
-            file.write_all(&bytes).await.map_err(|e| {
-                (StatusCode::INTERNAL_SERVER_ERROR, format!("Write error: {}", e))
-            })?;
+        let sha256 = hex::encode(hasher.finalize());
+        let token = sha256[..CAPTURE_TOKEN_LEN].to_string();
+        if let Err(e) = fs::rename(&part_path, capture_path(&state, &token)).await {
+            let _ = fs::remove_file(&part_path).await;
+            return Err(ApiError::internal(format!("Rename error: {}", e)));
+        }
 
-            info!("Camera image saved to {}", LATEST_FILE);
-            return Ok((StatusCode::OK, "OK"));
+        // インデックス更新（同一内容は先頭へ移動）
+        {
+            let mut captures = state.camera_captures.write().await;
+            captures.retain(|c| c.token != token);
+            captures.push_front(CameraCapture {
+                token: token.clone(),
+                sha256: sha256.clone(),
+                size,
+                created_at_ms: chrono::Utc::now().timestamp_millis(),
+            });
+            captures.truncate(MAX_RECENT_CAPTURES);
         }
+
+        info!("Camera image saved: token={} ({} bytes)", token, size);
+        return Ok(Json(CameraUploadResponse {
+            success: true,
+            token,
+            sha256,
+            size,
+        }));
     }
 
-    Err((StatusCode::BAD_REQUEST, "No image field found".to_string()))
+    Err(ApiError::missing_fields(&["image"]))
 }
 
-/// GET /api/camera/latest — 最新画像を返す
+/// GET /api/camera/latest?token=... — 画像を返す（token 未指定時は最新）
+/// ETag / Last-Modified を付与し、If-None-Match / If-Modified-Since が一致すれば 304
 pub async fn get_latest(
-    State(_state): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let bytes = fs::read(LATEST_FILE).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CaptureQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let path = resolve_capture_path(&state, query.token.as_deref()).await?;
+    let metadata = fs::metadata(&path).await.map_err(|_| capture_not_found())?;
+    let modified: chrono::DateTime<chrono::Utc> = metadata.modified()?.into();
+    let last_modified = modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+    // キャプチャはファイル名が内容ハッシュなので読まずに ETag を決められる
+    let file_name = std::path::Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut bytes = None;
+    let etag_hash = if is_valid_capture_token(&file_name) {
+        file_name
+    } else {
+        let data = fs::read(&path).await.map_err(|_| capture_not_found())?;
+        let hash = hex::encode(Sha256::digest(&data))[..CAPTURE_TOKEN_LEN].to_string();
+        bytes = Some(data);
+        hash
+    };
+    let etag = format!("\"{}\"", etag_hash);
+
+    if is_not_modified(&headers, &etag, modified) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::LAST_MODIFIED, last_modified)],
+        )
+            .into_response());
+    }
+
+    let bytes = match bytes {
+        Some(b) => b,
+        None => fs::read(&path).await.map_err(|_| capture_not_found())?,
+    };
 
     // Content-Type を推定 (JPEG/PNG)
     let content_type = if bytes.len() >= 4 && bytes[0..4] == [0x89, 0x50, 0x4E, 0x47] {
@@ -143,21 +310,287 @@ pub async fn get_latest(
         "image/jpeg"
     };
 
-    Ok(([(header::CONTENT_TYPE, content_type)], bytes))
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::ETAG, etag),
+            (header::LAST_MODIFIED, last_modified),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+fn capture_not_found() -> ApiError {
+    ApiError::NotFound("Camera capture not found".to_string())
+}
+
+/// 条件付きリクエストの判定（If-None-Match を優先し、無い場合のみ If-Modified-Since を見る）
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: chrono::DateTime<chrono::Utc>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match
+            .split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == "*" || t == etag);
+    }
+
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok())
+        .is_some_and(|since| modified.timestamp() <= since.timestamp())
 }
 
-/// DELETE /api/camera/latest — 画像削除（クリーンアップ）
+/// GET /api/camera/captures — 直近キャプチャ一覧（新しい順）
+pub async fn list_captures(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<CameraCapture>> {
+    let captures = state.camera_captures.read().await;
+    Json(captures.iter().cloned().collect())
+}
+
+/// DELETE /api/camera/latest?token=... — 画像削除（クリーンアップ、token 未指定時は最新）
 pub async fn delete_latest(
-    State(_state): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    match fs::remove_file(LATEST_FILE).await {
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CaptureQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let path = resolve_capture_path(&state, query.token.as_deref()).await?;
+    if let Some(token) = path.strip_prefix(&format!("{}/", state.config.camera_temp_dir)) {
+        state.camera_captures.write().await.retain(|c| c.token != token);
+    }
+
+    match fs::remove_file(&path).await {
         Ok(_) => {
-            info!("Camera temp file deleted");
+            info!("Camera temp file deleted: {}", path);
             Ok((StatusCode::OK, "Deleted"))
         }
         Err(_) => {
-            warn!("Camera temp file not found for deletion");
+            warn!("Camera temp file not found for deletion: {}", path);
             Ok((StatusCode::OK, "Not found (already clean)"))
         }
     }
 }
+
+// ========================================
+// Background Job
+// ========================================
+
+/// 保持期間を過ぎたカメラ一時ファイルを削除（バックグラウンドジョブ用）
+/// 削除したキャプチャはインデックスからも外す
+pub async fn sweep_camera_temp(state: &Arc<AppState>) -> anyhow::Result<usize> {
+    let ttl = std::time::Duration::from_secs(state.config.camera_temp_ttl_secs);
+    let now = std::time::SystemTime::now();
+
+    let mut entries = match fs::read_dir(&state.config.camera_temp_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut removed_tokens = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = match entry.metadata().await {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age <= ttl {
+            continue;
+        }
+
+        match fs::remove_file(entry.path()).await {
+            Ok(_) => removed_tokens.push(entry.file_name().to_string_lossy().to_string()),
+            Err(e) => warn!("Failed to remove camera temp file {:?}: {}", entry.path(), e),
+        }
+    }
+
+    if !removed_tokens.is_empty() {
+        state
+            .camera_captures
+            .write()
+            .await
+            .retain(|c| !removed_tokens.contains(&c.token));
+    }
+
+    Ok(removed_tokens.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use axum::http::Method;
+
+    use super::sweep_camera_temp;
+    use crate::test_support::{png_bytes, MultipartForm, TestApp};
+
+    async fn upload(app: &TestApp, data: &[u8]) -> String {
+        let form = MultipartForm::new().file("image", "capture.png", "image/png", data);
+        let res = app.send_form(Method::POST, "/api/camera/upload", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        res.json()["token"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn concurrent_uploads_are_retrievable_by_token() {
+        let app = TestApp::new().await;
+        let first = png_bytes(8, 8);
+        let second = png_bytes(16, 16);
+
+        let (token_a, token_b) = tokio::join!(upload(&app, &first), upload(&app, &second));
+        assert_ne!(token_a, token_b);
+
+        for (token, data) in [(&token_a, &first), (&token_b, &second)] {
+            let res = app.get(&format!("/api/camera/latest?token={}", token)).await;
+            assert_eq!(res.status, 200);
+            assert_eq!(res.header("content-type"), Some("image/png"));
+            assert_eq!(res.body.as_ref(), data.as_slice());
+        }
+
+        // token 未指定は最新キャプチャ
+        let captures = app.get("/api/camera/captures").await.json();
+        let newest = captures[0]["token"].as_str().unwrap();
+        assert_eq!(captures.as_array().unwrap().len(), 2);
+        let latest = app.get("/api/camera/latest").await;
+        let expected = if newest == token_a { &first } else { &second };
+        assert_eq!(latest.body.as_ref(), expected.as_slice());
+    }
+
+    #[tokio::test]
+    async fn latest_falls_back_to_legacy_file_and_rejects_bad_tokens() {
+        let app = TestApp::new().await;
+        assert_eq!(app.get("/api/camera/latest").await.status, 404);
+
+        let dir = &app.state.config.camera_temp_dir;
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(format!("{}/latest", dir), b"legacy jpeg").unwrap();
+        let res = app.get("/api/camera/latest").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_ref(), b"legacy jpeg");
+
+        assert_eq!(app.get("/api/camera/latest?token=..%2Ftest.db").await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn errors_use_the_json_error_shape() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/camera/latest").await;
+        assert_eq!(res.status, 404);
+        assert_eq!(res.json()["success"], false);
+        assert_eq!(res.json()["error"], "Camera capture not found");
+
+        let res = app.get("/api/camera/latest?token=..%2Ftest.db").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["success"], false);
+
+        let res = app.send_json(Method::DELETE, "/api/camera/latest?token=nope", None, &[]).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["success"], false);
+
+        let form = MultipartForm::new().text("note", "no image");
+        let res = app.send_form(Method::POST, "/api/camera/upload", form, &[]).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["success"], false);
+        assert_eq!(res.json()["fields"], serde_json::json!(["image"]));
+    }
+
+    #[tokio::test]
+    async fn latest_after_restart_serves_the_newest_file_on_disk() {
+        let app = TestApp::new().await;
+        let older = upload(&app, &png_bytes(8, 8)).await;
+        let newer = png_bytes(16, 16);
+        let newer_token = upload(&app, &newer).await;
+        let dir = std::path::PathBuf::from(&app.state.config.camera_temp_dir);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join(&older))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+        std::fs::write(dir.join("upload-in-progress.part"), b"partial").unwrap();
+
+        // 再起動でインデックスは空になる
+        app.state.camera_captures.write().await.clear();
+        let res = app.get("/api/camera/latest").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_ref(), newer.as_slice());
+        assert_eq!(res.header("etag"), Some(format!("\"{}\"", newer_token).as_str()));
+
+        let res = app.send_json(Method::DELETE, "/api/camera/latest", None, &[]).await;
+        assert_eq!(res.status, 200);
+        assert!(!dir.join(&newer_token).exists());
+        assert!(dir.join(&older).exists());
+    }
+
+    #[tokio::test]
+    async fn sweeper_removes_only_stale_files() {
+        let app = TestApp::with_config(|c| c.camera_temp_ttl_secs = 3600).await;
+        let fresh = upload(&app, &png_bytes(8, 8)).await;
+        let stale = upload(&app, &png_bytes(16, 16)).await;
+
+        let dir = std::path::PathBuf::from(&app.state.config.camera_temp_dir);
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(7200);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join(&stale))
+            .unwrap()
+            .set_modified(two_hours_ago)
+            .unwrap();
+
+        assert_eq!(sweep_camera_temp(&app.state).await.unwrap(), 1);
+        assert!(dir.join(&fresh).exists());
+        assert!(!dir.join(&stale).exists());
+
+        // インデックスからも外れる
+        let captures = app.get("/api/camera/captures").await.json();
+        let tokens: Vec<&str> = captures.as_array().unwrap().iter().map(|c| c["token"].as_str().unwrap()).collect();
+        assert_eq!(tokens, vec![fresh.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn sweeper_tolerates_missing_directory() {
+        let app = TestApp::new().await;
+        assert_eq!(sweep_camera_temp(&app.state).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn repeated_poll_with_validators_returns_304() {
+        let app = TestApp::new().await;
+        let token = upload(&app, &png_bytes(8, 8)).await;
+
+        let res = app.get("/api/camera/latest").await;
+        assert_eq!(res.status, 200);
+        let etag = res.header("etag").unwrap().to_string();
+        let last_modified = res.header("last-modified").unwrap().to_string();
+        assert_eq!(etag, format!("\"{}\"", token));
+
+        let res = app.get_with("/api/camera/latest", &[("if-modified-since", &last_modified)]).await;
+        assert_eq!(res.status, 304);
+        assert!(res.body.is_empty());
+        assert_eq!(res.header("etag"), Some(etag.as_str()));
+
+        let res = app.get_with("/api/camera/latest", &[("if-none-match", &etag)]).await;
+        assert_eq!(res.status, 304);
+
+        // 新しいキャプチャが届けば ETag が変わり 200
+        upload(&app, &png_bytes(16, 16)).await;
+        let res = app.get_with("/api/camera/latest", &[("if-none-match", &etag)]).await;
+        assert_eq!(res.status, 200);
+        assert_ne!(res.header("etag"), Some(etag.as_str()));
+    }
+
+    #[tokio::test]
+    async fn stale_if_modified_since_returns_full_image() {
+        let app = TestApp::new().await;
+        upload(&app, &png_bytes(8, 8)).await;
+        let res = app
+            .get_with("/api/camera/latest", &[("if-modified-since", "Mon, 01 Jan 2001 00:00:00 GMT")])
+            .await;
+        assert_eq!(res.status, 200);
+        assert!(!res.body.is_empty());
+    }
+}
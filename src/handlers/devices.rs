@@ -5,7 +5,7 @@
 
 use axum::{
     extract::{Path, State},
-    http::{HeaderMap, StatusCode},
+    http::HeaderMap,
     response::Json,
 };
 use base64::Engine;
@@ -13,40 +13,26 @@ use ed25519_dalek::{Verifier, VerifyingKey, Signature};
 use rand::RngCore;
 use serde::Serialize;
 use std::sync::Arc;
-use tracing::{info, warn};
+use tracing::info;
 
+use crate::error::ApiError;
 use crate::models::{
     Device, RegisterDeviceRequest, DeviceResponse, DeviceListResponse, RegisterDeviceResponse,
-    DeviceChallengeResponse, DeviceVerifyRequest, DeviceVerifyResponse,
+    DeviceChallengeResponse, DeviceVerifyRequest, DeviceVerifyResponse, ms_to_iso,
 };
+use crate::extract::ApiJson;
+use crate::redact;
 use crate::AppState;
 
 // ========================================
 // Response Types
 // ========================================
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub success: bool,
-    pub error: String,
-}
-
 #[derive(Serialize)]
 pub struct SuccessResponse {
     pub success: bool,
 }
 
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    warn!("Device API Error: {}", message);
-    (
-        status,
-        Json(ErrorResponse {
-            success: false,
-            error: message,
-        }),
-    )
-}
-
 fn device_to_response(d: &Device) -> DeviceResponse {
     DeviceResponse {
         device_id: d.device_id.clone(),
@@ -56,6 +42,8 @@ fn device_to_response(d: &Device) -> DeviceResponse {
         platform: d.platform.clone(),
         registered_at_ms: d.registered_at_ms,
         last_seen_at_ms: d.last_seen_at_ms,
+        registered_at_iso: ms_to_iso(d.registered_at_ms),
+        last_seen_at_iso: ms_to_iso(d.last_seen_at_ms),
     }
 }
 
@@ -89,24 +77,24 @@ fn derive_peer_id_from_pubkey(public_key: &[u8; 32]) -> String {
 async fn extract_auth_peer_id(
     state: &Arc<AppState>,
     headers: &HeaderMap,
-) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<String, ApiError> {
     let auth_header = headers
         .get("authorization")
         .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "Authorization header required".to_string()))?;
+        .ok_or_else(|| ApiError::Unauthorized("Authorization header required".to_string()))?;
 
     let token = auth_header
         .strip_prefix("Bearer ")
-        .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "Invalid authorization format".to_string()))?;
+        .ok_or_else(|| ApiError::Unauthorized("Invalid authorization format".to_string()))?;
 
     let tokens = state.tokens.read().await;
     let (peer_id, expires_at_ms) = tokens.get(token).ok_or_else(|| {
-        error_response(StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string())
+        ApiError::Unauthorized("Invalid or expired token".to_string())
     })?;
 
     let now_ms = chrono::Utc::now().timestamp_millis();
     if *expires_at_ms < now_ms {
-        return Err(error_response(StatusCode::UNAUTHORIZED, "Token expired".to_string()));
+        return Err(ApiError::Unauthorized("Token expired".to_string()));
     }
 
     Ok(peer_id.clone())
@@ -149,20 +137,20 @@ pub async fn get_challenge(
 /// 5. トークン発行（1時間有効）
 pub async fn verify_challenge(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<DeviceVerifyRequest>,
-) -> Result<Json<DeviceVerifyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<DeviceVerifyRequest>,
+) -> Result<Json<DeviceVerifyResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // 1. Challenge確認
     {
         let mut challenges = state.challenges.write().await;
         let entry = challenges.get(&req.challenge).ok_or_else(|| {
-            error_response(StatusCode::BAD_REQUEST, "Unknown or expired challenge".to_string())
+            ApiError::BadRequest("Unknown or expired challenge".to_string())
         })?;
 
         if entry.1 < now_ms {
             challenges.remove(&req.challenge);
-            return Err(error_response(StatusCode::BAD_REQUEST, "Challenge expired".to_string()));
+            return Err(ApiError::BadRequest("Challenge expired".to_string()));
         }
 
         // 使用済みchallengeを削除（再利用防止）
@@ -172,49 +160,46 @@ pub async fn verify_challenge(
     // 2. 公開鍵デコード
     let pubkey_bytes = base64::engine::general_purpose::STANDARD
         .decode(&req.pubkey)
-        .map_err(|e| error_response(StatusCode::BAD_REQUEST, format!("Invalid pubkey base64: {}", e)))?;
+        .map_err(|e| ApiError::BadRequest(format!("Invalid pubkey base64: {}", e)))?;
 
     if pubkey_bytes.len() != 32 {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             format!("Public key must be 32 bytes, got {}", pubkey_bytes.len()),
         ));
     }
 
     let pubkey_array: [u8; 32] = pubkey_bytes
         .try_into()
-        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "Invalid pubkey length".to_string()))?;
+        .map_err(|_| ApiError::BadRequest("Invalid pubkey length".to_string()))?;
 
     let verifying_key = VerifyingKey::from_bytes(&pubkey_array)
-        .map_err(|e| error_response(StatusCode::BAD_REQUEST, format!("Invalid public key: {}", e)))?;
+        .map_err(|e| ApiError::BadRequest(format!("Invalid public key: {}", e)))?;
 
     // 3. 署名デコード・検証
     let sig_bytes = base64::engine::general_purpose::STANDARD
         .decode(&req.sig)
-        .map_err(|e| error_response(StatusCode::BAD_REQUEST, format!("Invalid sig base64: {}", e)))?;
+        .map_err(|e| ApiError::BadRequest(format!("Invalid sig base64: {}", e)))?;
 
     if sig_bytes.len() != 64 {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             format!("Signature must be 64 bytes, got {}", sig_bytes.len()),
         ));
     }
 
     let sig_array: [u8; 64] = sig_bytes
         .try_into()
-        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "Invalid sig length".to_string()))?;
+        .map_err(|_| ApiError::BadRequest("Invalid sig length".to_string()))?;
 
     let signature = Signature::from_bytes(&sig_array);
 
     verifying_key
         .verify(req.challenge.as_bytes(), &signature)
-        .map_err(|_| error_response(StatusCode::UNAUTHORIZED, "Signature verification failed".to_string()))?;
+        .map_err(|_| ApiError::Unauthorized("Signature verification failed".to_string()))?;
 
     // 4. 公開鍵からpeer_id導出 → リクエストと一致確認
     let derived_peer_id = derive_peer_id_from_pubkey(&pubkey_array);
     if derived_peer_id != req.peer_id {
-        return Err(error_response(
-            StatusCode::UNAUTHORIZED,
+        return Err(ApiError::Unauthorized(
             "peer_id does not match public key".to_string(),
         ));
     }
@@ -280,30 +265,27 @@ pub async fn cleanup_expired_auth(state: &Arc<AppState>) {
 pub async fn register_device(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(req): Json<RegisterDeviceRequest>,
-) -> Result<Json<RegisterDeviceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<RegisterDeviceRequest>,
+) -> Result<Json<RegisterDeviceResponse>, ApiError> {
     // 認証
     let auth_peer_id = extract_auth_peer_id(&state, &headers).await?;
 
     // リクエストのpeer_idとトークンのpeer_idが一致するか確認
     if auth_peer_id != req.peer_id {
-        return Err(error_response(
-            StatusCode::FORBIDDEN,
+        return Err(ApiError::Forbidden(
             "Token peer_id does not match request peer_id".to_string(),
         ));
     }
 
     // バリデーション
     if req.device_type != "pc" && req.device_type != "mobile" {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             "device_type must be 'pc' or 'mobile'".to_string(),
         ));
     }
 
     if req.peer_id.is_empty() || req.device_id.is_empty() {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             "peer_id and device_id are required".to_string(),
         ));
     }
@@ -317,8 +299,7 @@ pub async fn register_device(
     .bind(&req.peer_id)
     .bind(&req.device_type)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     if let Some(existing_device) = existing {
         if existing_device.device_id == req.device_id {
@@ -328,14 +309,12 @@ pub async fn register_device(
                 .bind(&req.device_name)
                 .bind(&req.device_id)
                 .execute(&state.db)
-                .await
-                .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+                .await?;
 
-            info!("[Device] Heartbeat: {} ({})", req.device_id, req.device_type);
+            info!("[Device] Heartbeat: {} ({})", redact::value(&req.device_id), req.device_type);
         } else {
             // 別のデバイス → スロット使用中、拒否
-            return Err(error_response(
-                StatusCode::FORBIDDEN,
+            return Err(ApiError::Forbidden(
                 format!(
                     "Device slot '{}' is already in use by '{}'. Unregister it first.",
                     req.device_type, existing_device.device_name
@@ -356,8 +335,7 @@ pub async fn register_device(
         .bind(now_ms)
         .bind(now_ms)
         .execute(&state.db)
-        .await
-        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
         info!(
             "[Device] Registered: {} ({}) for peer {}",
@@ -372,8 +350,7 @@ pub async fn register_device(
     let device: Device = sqlx::query_as("SELECT * FROM devices WHERE device_id = ?")
         .bind(&req.device_id)
         .fetch_one(&state.db)
-        .await
-        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
     Ok(Json(RegisterDeviceResponse {
         success: true,
@@ -388,13 +365,12 @@ pub async fn list_devices(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Path(peer_id): Path<String>,
-) -> Result<Json<DeviceListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<DeviceListResponse>, ApiError> {
     // 認証
     let auth_peer_id = extract_auth_peer_id(&state, &headers).await?;
 
     if auth_peer_id != peer_id {
-        return Err(error_response(
-            StatusCode::FORBIDDEN,
+        return Err(ApiError::Forbidden(
             "Token peer_id does not match requested peer_id".to_string(),
         ));
     }
@@ -404,8 +380,7 @@ pub async fn list_devices(
     )
     .bind(&peer_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let pc_slot = devices.iter().any(|d| d.device_type == "pc");
     let mobile_slot = devices.iter().any(|d| d.device_type == "mobile");
@@ -425,20 +400,18 @@ pub async fn unregister_device(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Path((peer_id, device_type)): Path<(String, String)>,
-) -> Result<Json<SuccessResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<SuccessResponse>, ApiError> {
     // 認証
     let auth_peer_id = extract_auth_peer_id(&state, &headers).await?;
 
     if auth_peer_id != peer_id {
-        return Err(error_response(
-            StatusCode::FORBIDDEN,
+        return Err(ApiError::Forbidden(
             "Token peer_id does not match requested peer_id".to_string(),
         ));
     }
 
     if device_type != "pc" && device_type != "mobile" {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             "device_type must be 'pc' or 'mobile'".to_string(),
         ));
     }
@@ -449,12 +422,10 @@ pub async fn unregister_device(
     .bind(&peer_id)
     .bind(&device_type)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     if result.rows_affected() == 0 {
-        return Err(error_response(
-            StatusCode::NOT_FOUND,
+        return Err(ApiError::NotFound(
             format!("No active {} device found for this peer", device_type),
         ));
     }
@@ -2,68 +2,174 @@
 //! /api/drops エンドポイント - 期限付きファイル配信
 
 use axum::{
-    extract::{Path, Query, State, Multipart},
-    http::StatusCode,
-    response::Json,
+    extract::{ConnectInfo, Path, Query, State, Multipart},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
     body::Body,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::{info, warn};
+use tokio::task::JoinSet;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
 use sha2::{Sha256, Digest};
 use base32;
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
 use rand::Rng;
 use uuid::Uuid;
 
+use crate::error::{ApiError, ErrorResponse};
+use crate::response::{exists_response, ApiResponse, ListMeta};
 use crate::models::{
-    Drop, DropResponse, DropClaim, ClaimDropRequest, ClaimDropResponse,
-    BatchDropRequest, BatchDropResponse, drop_status,
+    Drop, DropResponse, DropClaim, ClaimDropRequest, ClaimDropResponse, ClaimReceipt, ClaimReceiptResponse,
+    BatchDropRequest, BatchDropResponse, ExtendDropRequest, UploadSession, drop_status, tombstone_kind,
 };
+use crate::handlers::{admin, tombstones, uploads, vendors};
+use crate::audio::{self, AudioMetadata};
+use crate::images;
+use crate::transfer::{LoggedStream, TransferTimer, VerifiedStream};
+use crate::mailer;
+use crate::middleware;
+use crate::storage;
+use crate::extract::{ApiJson, ExpectedDigest, TextFieldBudget};
 use crate::AppState;
 
+/// プレビュークリップの長さ（秒）・ビットレート・ファイル名
+const PREVIEW_SECONDS: &str = "30";
+const PREVIEW_BITRATE: &str = "96k";
+const PREVIEW_FILE_NAME: &str = "preview.mp3";
+/// カバーの WebP 派生のファイル名（オリジナルが WebP でも衝突しない名前）
+const COVER_WEBP_FILE: &str = "cover_display.webp";
+/// POST /api/drops/availability で一度に問い合わせできる drop_id の上限
+const MAX_AVAILABILITY_BATCH: usize = 100;
+
 // ========================================
 // Response Types
 // ========================================
 
-#[derive(Serialize)]
-pub struct DropListResponse {
-    pub success: bool,
-    pub drops: Vec<DropResponse>,
-    pub total: usize,
-}
+/// 一覧レスポンス（`data` + `meta`、移行期間中は旧フィールド `drops` / `total` も返す）
+pub type DropListResponse = ApiResponse<Vec<DropResponse>>;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DropDetailResponse {
     pub success: bool,
     pub drop: Option<DropResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DropCreateResponse {
     pub success: bool,
     pub drop: DropResponse,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
+/// 全Vendor横断フィードの1件（残り時間付き）
+#[derive(Serialize, ToSchema)]
+pub struct DropFeedItem {
+    #[serde(flatten)]
+    pub drop: DropResponse,
+    /// end_at までの残り秒数（終了済みは 0）
+    pub remaining_seconds: i64,
+}
+
+/// フィードレスポンス（`meta` に total / limit / offset、移行期間中は旧フィールド `drops` 等も返す）
+pub type DropFeedResponse = ApiResponse<Vec<DropFeedItem>>;
+
+#[derive(Serialize, ToSchema)]
+pub struct ClaimStatusResponse {
+    pub success: bool,
+    pub claim: DropClaim,
+}
+
+/// Claim 一覧（`data` + `meta`、移行期間中は旧フィールド `claims` / `drop_id` / `total` も返す）
+pub type ClaimListResponse = ApiResponse<Vec<DropClaim>>;
+/// Claim 可否の事前確認結果
+#[derive(Serialize, ToSchema)]
+pub struct ClaimableResponse {
+    pub success: bool,
+    pub drop_id: String,
+    pub claimable: bool,
+    /// ok / not_started / expired / sold_out / already_claimed
+    pub reason: String,
+    pub remaining_claims: i64,
+    pub start_at: i64,
+    pub end_at: i64,
+}
+
+/// Drop 一覧画面向けの在庫・残り時間
+#[derive(Serialize, ToSchema)]
+pub struct DropAvailability {
+    pub remaining_claims: i64,
+    /// end_at までの残り秒数（終了済みは 0）
+    pub seconds_left: i64,
+    /// 現在時刻で判定したステータス（期限切れジョブ前の ACTIVE も ENDED として返す）
+    pub status: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DropAvailabilityResponse {
+    pub success: bool,
+    pub drops: HashMap<String, DropAvailability>,
+    /// 存在しない drop_id
+    pub missing: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DropStatsResponse {
     pub success: bool,
-    pub error: String,
+    pub drop_id: String,
+    pub claim_count: i64,
+    pub download_count: i64,
+    pub unique_downloaders: i64,
+    pub total_bytes_sent: i64,
 }
 
 // ========================================
 // Query Parameters
 // ========================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListDropsQuery {
     pub status: Option<i32>,
+    /// 作成日時の下限（Unix秒、含む）
+    pub created_after: Option<i64>,
+    /// 作成日時の上限（Unix秒、含む）
+    pub created_before: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DropFeedQuery {
+    /// 未指定時は開催中（start_at <= 現在 < end_at）のみ
+    pub status: Option<i32>,
+    /// 環境（devnet / mainnet 等）。未指定時は DEFAULT_ENV、"all" で全環境
+    pub env: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListClaimsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClaimableQuery {
+    /// 指定時のみ受け取り済みかを確認する
+    pub user_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct DownloadQuery {
     pub token: Option<String>,
 }
@@ -73,11 +179,30 @@ pub struct DownloadQuery {
 // ========================================
 
 /// GET /api/vendors/:vendor_stable_id/drops - Vendor別Drop一覧
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{vendor_stable_id}/drops",
+    tag = "drops",
+    params(("vendor_stable_id" = String, Path), ListDropsQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<DropResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_drops(
     State(state): State<Arc<AppState>>,
     Path(vendor_stable_id): Path<String>,
     Query(query): Query<ListDropsQuery>,
-) -> Result<Json<DropListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<DropListResponse>, ApiError> {
+    if let (Some(after), Some(before)) = (query.created_after, query.created_before) {
+        if after > before {
+            return Err(ApiError::BadRequest(
+                "created_after must be <= created_before".to_string(),
+            ));
+        }
+    }
+
     let now = chrono::Utc::now().timestamp();
 
     // 期限切れのDropをENDEDに更新（クエリ時に自動処理）
@@ -92,69 +217,212 @@ pub async fn list_drops(
     .execute(&state.db)
     .await;
 
-    let drops: Vec<Drop> = if let Some(status) = query.status {
-        sqlx::query_as(
-            "SELECT * FROM drops WHERE vendor_stable_id = ? AND status = ? ORDER BY created_at DESC"
-        )
-        .bind(&vendor_stable_id)
-        .bind(status)
-        .fetch_all(&state.db)
-        .await
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM drops WHERE vendor_stable_id = ");
+    builder.push_bind(&vendor_stable_id);
+    if let Some(status) = query.status {
+        builder.push(" AND status = ").push_bind(status);
     } else {
-        sqlx::query_as(
-            "SELECT * FROM drops WHERE vendor_stable_id = ? AND status != ? ORDER BY created_at DESC"
-        )
-        .bind(&vendor_stable_id)
-        .bind(drop_status::PURGED)
-        .fetch_all(&state.db)
-        .await
+        builder.push(" AND status != ").push_bind(drop_status::PURGED);
     }
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    if let Some(after) = query.created_after {
+        builder.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = query.created_before {
+        builder.push(" AND created_at <= ").push_bind(before);
+    }
+    builder.push(" ORDER BY created_at DESC");
+
+    let drops: Vec<Drop> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await?;
 
     let responses: Vec<DropResponse> = drops
         .iter()
         .map(|d| DropResponse::from_drop(d, &state.vps_base_url))
         .collect();
 
-    let total = responses.len();
-    Ok(Json(DropListResponse {
-        success: true,
-        drops: responses,
+    let meta = ListMeta::total(responses.len());
+    Ok(Json(
+        ApiResponse::list(responses, meta).with_legacy_fields(state.config.legacy_list_fields, "drops"),
+    ))
+}
+
+/// GET /api/drops - 全Vendor横断のDropフィード（終了が近い順、PURGEDは除外）
+#[utoipa::path(
+    get,
+    path = "/api/drops",
+    tag = "drops",
+    params(DropFeedQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<DropFeedItem>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn list_drop_feed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DropFeedQuery>,
+) -> Result<Json<DropFeedResponse>, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    // 期限切れのDropをENDEDに更新（クエリ時に自動処理）
+    expire_drops(&state).await?;
+
+    // 一覧と件数で同じ条件を使う
+    let push_filters = |builder: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>| {
+        builder.push(" WHERE status != ").push_bind(drop_status::PURGED);
+        match query.status {
+            Some(status) => {
+                builder.push(" AND status = ").push_bind(status);
+            }
+            None => {
+                builder
+                    .push(" AND status IN (")
+                    .push_bind(drop_status::SCHEDULED)
+                    .push(", ")
+                    .push_bind(drop_status::ACTIVE)
+                    .push(") AND start_at <= ")
+                    .push_bind(now)
+                    .push(" AND end_at > ")
+                    .push_bind(now);
+            }
+        }
+        if let Some(env) = state.config.env_filter(query.env.as_deref()) {
+            builder.push(" AND env = ").push_bind(env.to_string());
+        }
+    };
+
+    let mut count_builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT COUNT(*) FROM drops");
+    push_filters(&mut count_builder);
+    let (total,): (i64,) = count_builder
+        .build_query_as()
+        .fetch_one(&state.db)
+        .await?;
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM drops");
+    push_filters(&mut builder);
+    builder
+        .push(" ORDER BY end_at ASC, drop_id ASC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let drops: Vec<Drop> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await?;
+
+    let items = drops
+        .iter()
+        .map(|d| DropFeedItem {
+            drop: DropResponse::from_drop(d, &state.vps_base_url),
+            remaining_seconds: (d.end_at - now).max(0),
+        })
+        .collect();
+
+    let meta = ListMeta {
         total,
-    }))
+        limit: Some(limit),
+        offset: Some(offset),
+        ..ListMeta::default()
+    };
+    Ok(Json(
+        ApiResponse::list(items, meta).with_legacy_fields(state.config.legacy_list_fields, "drops"),
+    ))
 }
 
 /// GET /api/drops/:drop_id - Drop詳細
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = DropDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_drop(
     State(state): State<Arc<AppState>>,
     Path(drop_id): Path<String>,
-) -> Result<Json<DropDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<DropDetailResponse>, ApiError> {
     let drop: Option<Drop> = sqlx::query_as(
         "SELECT * FROM drops WHERE drop_id = ?"
     )
     .bind(&drop_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     match drop {
         Some(d) => Ok(Json(DropDetailResponse {
             success: true,
             drop: Some(DropResponse::from_drop(&d, &state.vps_base_url)),
         })),
-        None => Err(error_response(StatusCode::NOT_FOUND, "Drop not found".to_string())),
+        None => Err(ApiError::NotFound("Drop not found".to_string())),
     }
 }
 
+/// HEAD /api/drops/:drop_id - Drop の存在確認（200 / 404、ボディなし）
+#[utoipa::path(
+    head,
+    path = "/api/drops/{drop_id}",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    responses(
+        (status = 200, description = "存在する（ETag / Last-Modified 付き）"),
+        (status = 404, description = "存在しない"),
+    )
+)]
+pub async fn head_drop(
+    State(state): State<Arc<AppState>>,
+    Path(drop_id): Path<String>,
+) -> Result<axum::response::Response, ApiError> {
+    let updated_at: Option<(i64,)> = sqlx::query_as("SELECT updated_at FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+    Ok(match updated_at {
+        Some((updated_at,)) => exists_response(&drop_id, Some(updated_at * 1000)),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
+
+/// Drop 作成のオプション
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateDropQuery {
+    /// テキストフィールドをすべて audio / cover より前に送る場合に true。
+    /// 最初のファイルフィールドの時点で必須項目が不足していれば、ファイルを受信せずに 400 を返す
+    #[serde(default)]
+    pub fields_first: bool,
+}
+
 /// POST /api/drops - Drop作成（Multipart）
+/// `?fields_first=true` 指定時は必須テキストフィールドの不足をファイル受信前に検出する（未指定時はフィールド順を問わない）
+#[utoipa::path(
+    post,
+    path = "/api/drops",
+    tag = "drops",
+    params(CreateDropQuery),
+    request_body(content_type = "multipart/form-data", description = "vendor_stable_id, artist_name, title, end_at, max_claims, audio（必須）/ artist_stable_id, description, start_at, max_downloads_per_claim, claim_passcode, env, idempotency_key, upload_id, cover（任意）"),
+    responses(
+        (status = 200, description = "成功", body = DropCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn create_drop(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<CreateDropQuery>,
     mut multipart: Multipart,
-) -> Result<Json<DropCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<DropCreateResponse>, ApiError> {
+    let timer = TransferTimer::start("create_drop");
     let now = chrono::Utc::now().timestamp();
     let drop_id = generate_drop_id();
 
@@ -167,156 +435,248 @@ pub async fn create_drop(
     let mut start_at: Option<i64> = None;
     let mut end_at: Option<i64> = None;
     let mut max_claims: Option<i64> = None;
+    let mut max_downloads_per_claim: Option<i64> = None;
+    let mut claim_passcode: Option<String> = None;
     let mut env = "devnet".to_string();
+    let mut idempotency_key: Option<String> = None;
+    let mut upload_id: Option<String> = None;
 
     let mut audio_data: Option<Vec<u8>> = None;
     let mut audio_filename: Option<String> = None;
     let mut audio_mime: Option<String> = None;
     let mut cover_data: Option<Vec<u8>> = None;
     let mut cover_filename: Option<String> = None;
+    let mut text_budget = TextFieldBudget::new();
+    let mut file_seen = false;
 
     while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error_response(StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
 
+        // fields_first 指定時は最初のファイルの時点で必須項目が揃っているはずなので、
+        // 不足していればファイル本体（最大 MAX_AUDIO_BYTES）を読まずに 400 を返す
+        // 未指定時はファイルの後にテキストフィールドが来てもよいため、全体を読んでから検証する
+        if query.fields_first && !file_seen && matches!(name.as_str(), "audio" | "cover") {
+            let missing = missing_drop_text_fields(&vendor_stable_id, &artist_name, &title, end_at, max_claims);
+            if !missing.is_empty() {
+                return Err(ApiError::missing_fields(&missing));
+            }
+            file_seen = true;
+        }
+
         match name.as_str() {
             "vendor_stable_id" => {
-                vendor_stable_id = Some(field.text().await.unwrap_or_default());
+                vendor_stable_id = Some(text_budget.read(field).await?);
             }
             "artist_stable_id" => {
-                let val = field.text().await.unwrap_or_default();
+                let val = text_budget.read(field).await?;
                 if !val.is_empty() {
                     artist_stable_id = Some(val);
                 }
             }
             "artist_name" => {
-                artist_name = Some(field.text().await.unwrap_or_default());
+                artist_name = Some(text_budget.read(field).await?);
             }
             "title" => {
-                title = Some(field.text().await.unwrap_or_default());
+                title = Some(text_budget.read(field).await?);
             }
             "description" => {
-                let val = field.text().await.unwrap_or_default();
+                let val = text_budget.read(field).await?;
                 if !val.is_empty() {
                     description = Some(val);
                 }
             }
             "start_at" => {
-                if let Ok(val) = field.text().await.unwrap_or_default().parse::<i64>() {
+                if let Ok(val) = text_budget.read(field).await?.parse::<i64>() {
                     start_at = Some(val);
                 }
             }
             "end_at" => {
-                if let Ok(val) = field.text().await.unwrap_or_default().parse::<i64>() {
+                if let Ok(val) = text_budget.read(field).await?.parse::<i64>() {
                     end_at = Some(val);
                 }
             }
             "max_claims" => {
-                if let Ok(val) = field.text().await.unwrap_or_default().parse::<i64>() {
+                if let Ok(val) = text_budget.read(field).await?.parse::<i64>() {
                     max_claims = Some(val);
                 }
             }
+            "max_downloads_per_claim" => {
+                if let Ok(val) = text_budget.read(field).await?.parse::<i64>() {
+                    max_downloads_per_claim = Some(val);
+                }
+            }
+            "claim_passcode" => {
+                let val = text_budget.read(field).await?;
+                if !val.is_empty() {
+                    claim_passcode = Some(val);
+                }
+            }
             "env" => {
-                env = field.text().await.unwrap_or_default();
+                env = text_budget.read(field).await?;
+            }
+            "idempotency_key" => {
+                let val = text_budget.read(field).await?;
+                if !val.is_empty() {
+                    idempotency_key = Some(val);
+                }
+            }
+            "upload_id" => {
+                let val = text_budget.read(field).await?;
+                if !val.is_empty() {
+                    upload_id = Some(val);
+                }
             }
             "audio" => {
                 audio_filename = field.file_name().map(|s| s.to_string());
                 audio_mime = field.content_type().map(|s| s.to_string());
                 audio_data = Some(field.bytes().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("Audio read error: {}", e))
+                    ApiError::new(e.status(), format!("Audio read error: {}", e))
                 })?.to_vec());
             }
             "cover" => {
                 cover_filename = field.file_name().map(|s| s.to_string());
                 cover_data = Some(field.bytes().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("Cover read error: {}", e))
+                    ApiError::new(e.status(), format!("Cover read error: {}", e))
                 })?.to_vec());
             }
             _ => {}
         }
     }
 
-    // 必須フィールドチェック
-    let vendor_stable_id = vendor_stable_id.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "vendor_stable_id is required".to_string())
-    })?;
-    let artist_name = artist_name.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "artist_name is required".to_string())
-    })?;
-    let title = title.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "title is required".to_string())
-    })?;
-    let end_at = end_at.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "end_at is required".to_string())
-    })?;
-    let max_claims = max_claims.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "max_claims is required".to_string())
-    })?;
-    let audio_data = audio_data.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "audio file is required".to_string())
-    })?;
+    // 再開可能アップロード済みの音声を使用（audio が直接送られた場合はそちらを優先）
+    // ファイルは読み込まず、保存時に Drop ディレクトリへ移す
+    let audio = match (audio_data, upload_id) {
+        (Some(data), _) => Some(AudioSource::Inline(data)),
+        (None, Some(id)) => {
+            let upload = uploads::fetch_completed_upload(&state, &id)
+                .await?;
+            audio_filename = upload.filename.clone();
+            audio_mime = upload.content_type.clone();
+            Some(AudioSource::Upload(upload))
+        }
+        (None, None) => None,
+    };
+
+    // 必須フィールドチェック（不足分はまとめて返す）
+    let mut missing = missing_drop_text_fields(&vendor_stable_id, &artist_name, &title, end_at, max_claims);
+    if audio.is_none() {
+        missing.push("audio");
+    }
+    let (Some(vendor_stable_id), Some(artist_name), Some(title), Some(end_at), Some(max_claims), Some(audio)) =
+        (vendor_stable_id, artist_name, title, end_at, max_claims, audio)
+    else {
+        return Err(ApiError::missing_fields(&missing));
+    };
+
+    // Digest / Content-MD5 ヘッダ指定時は音声のハッシュを照合（保存前に 422）
+    if let Some(expected) = ExpectedDigest::from_headers(&headers)? {
+        match &audio {
+            AudioSource::Inline(data) => expected.verify(data)?,
+            AudioSource::Upload(upload) => {
+                expected
+                    .verify_file(uploads::upload_file_path(&state.base_data_dir, &upload.upload_id))
+                    .await?
+            }
+        }
+    }
+    if max_downloads_per_claim.is_some_and(|n| n < 1) {
+        return Err(ApiError::BadRequest(
+            "max_downloads_per_claim must be at least 1".to_string(),
+        ));
+    }
+
+    validate_drop_window(start_at.unwrap_or(now), end_at, now, state.config.max_drop_window_secs)
+        .map_err(|(message, fields)| ApiError::invalid_fields(message, fields))?;
+
+    if let Some(passcode) = &claim_passcode {
+        validate_claim_passcode(passcode)
+            .map_err(|message| ApiError::invalid_fields(message, &["claim_passcode"]))?;
+    }
+    let claim_passcode_hash = claim_passcode.map(|p| hash_claim_passcode(&drop_id, &p));
+
+    // カバー画像サイズチェック（リクエスト全体は音声の上限で制限済み）
+    if cover_data.as_ref().is_some_and(|c| c.len() > state.config.max_cover_bytes) {
+        return Err(ApiError::PayloadTooLarge(
+            format!("Cover too large: at most {} bytes", state.config.max_cover_bytes),
+        ));
+    }
 
     // Vendor存在チェック
-    let vendor_exists: Option<(i32,)> = sqlx::query_as(
-        "SELECT 1 FROM vendors WHERE stable_id = ? AND is_alive = 1"
+    let vendor_owner: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT owner FROM vendors WHERE stable_id = ? AND is_alive = 1"
     )
     .bind(&vendor_stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
-    if vendor_exists.is_none() {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+    let Some((vendor_owner,)) = vendor_owner else {
+        return Err(ApiError::BadRequest(
             format!("Vendor not found: {}", vendor_stable_id),
         ));
+    };
+
+    // オーナー検証（設定で有効な場合のみ）
+    vendors::verify_vendor_owner(&state, &headers, &vendor_stable_id).await?;
+
+    // Artist 存在チェック（指定時のみ）
+    if let Some(artist_stable_id) = &artist_stable_id {
+        check_drop_artist(&state, artist_stable_id, &vendor_stable_id, vendor_owner.as_deref()).await?;
+    }
+
+    // 冪等性チェック（同じキーで作成済みなら既存のDropを返す）
+    if let Some(key) = &idempotency_key {
+        if let Some(existing) = find_drop_by_idempotency_key(&state, &vendor_stable_id, key).await? {
+            info!("Drop already created for idempotency_key: {} -> {}", key, existing.drop_id);
+            return Ok(Json(DropCreateResponse {
+                success: true,
+                drop: DropResponse::from_drop(&existing, &state.vps_base_url),
+            }));
+        }
     }
 
+    // Vendor ごとの Drop 数・音声容量の上限（ファイル保存前の早期判定、確定は挿入直前にロック下で行う）
+    check_active_drop_quota(&state, &vendor_stable_id).await?;
+    check_drop_storage_quota(&state, &vendor_stable_id, audio.size_bytes(), 0).await?;
+
     // ディレクトリ作成
     let dir = PathBuf::from(&state.base_data_dir)
         .join("drops")
         .join(&drop_id);
     fs::create_dir_all(&dir).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+        ApiError::internal(format!("Failed to create dir: {}", e))
     })?;
 
     // 音声ファイル保存
     let audio_ext = audio_filename
         .as_ref()
-        .and_then(|f| f.split('.').last())
+        .and_then(|f| f.split('.').next_back())
         .unwrap_or("mp3");
-    let audio_object_key = format!("{}/audio.{}", drop_id, audio_ext);
+    // いったん Drop ディレクトリに書き、DB 挿入直前に blob へ移す
     let audio_path = dir.join(format!("audio.{}", audio_ext));
-    let mut file = fs::File::create(&audio_path).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create audio file: {}", e))
-    })?;
-    file.write_all(&audio_data).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write audio: {}", e))
-    })?;
+    let (audio_sha256, audio_size_bytes) = match audio {
+        AudioSource::Inline(data) => {
+            storage::write_atomic(&audio_path, &data).await.map_err(|e| {
+                ApiError::internal(format!("Failed to write audio: {}", e))
+            })?;
+            (compute_sha256(&data), data.len() as i64)
+        }
+        // アップロードを確保（使用済みにする）してからファイルを移す
+        AudioSource::Upload(upload) => uploads::take_completed_upload(&state, &upload.upload_id, &audio_path)
+            .await?,
+    };
+    let audio_mime = audio_mime.unwrap_or_else(|| guess_audio_mime(audio_ext).to_string());
 
-    // SHA256計算
-    let audio_sha256 = compute_sha256(&audio_data);
-    let audio_size_bytes = audio_data.len() as i64;
-    let audio_mime = audio_mime.unwrap_or_else(|| {
-        // 拡張子からMIMEタイプを推測
-        match audio_ext {
-            "flac" => "audio/flac".to_string(),
-            "wav" => "audio/wav".to_string(),
-            "ogg" => "audio/ogg".to_string(),
-            "aac" => "audio/aac".to_string(),
-            "m4a" => "audio/mp4".to_string(),
-            _ => "audio/mpeg".to_string(),
-        }
-    });
-
-    // カバー画像保存（任意）+ サムネイル生成
-    let cover_object_key = if let Some(cover) = cover_data {
+    // 試聴用プレビュー生成・メタデータ抽出（失敗してもDrop作成は継続）
+    let preview_object_key = generate_preview(&state, &drop_id, &audio_path).await;
+    let audio_metadata = audio::extract_metadata(audio_path.clone()).await;
+
+    // カバー画像保存（任意）+ サムネイル・派生（WebP + blurhash）生成
+    let (cover_object_key, cover_webp_object_key, cover_blurhash) = if let Some(cover) = cover_data {
         let cover_ext = cover_filename
             .as_ref()
-            .and_then(|f| f.split('.').last())
+            .and_then(|f| f.split('.').next_back())
             .unwrap_or("jpg")
             .to_lowercase();
         let key = format!("{}/cover.{}", drop_id, cover_ext);
@@ -324,11 +684,8 @@ pub async fn create_drop(
         let thumb_path = dir.join(format!("cover_thumb.{}", cover_ext));
 
         // オリジナル保存
-        let mut file = fs::File::create(&cover_path).await.map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create cover file: {}", e))
-        })?;
-        file.write_all(&cover).await.map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write cover: {}", e))
+        storage::write_atomic(&cover_path, &cover).await.map_err(|e| {
+            ApiError::internal(format!("Failed to write cover: {}", e))
         })?;
 
         // サムネイル生成（400x400、高DPI対応、非同期でブロッキング処理）
@@ -343,24 +700,53 @@ pub async fn create_drop(
             }
         }).await;
 
-        Some(key)
+        // 派生はベストエフォート（失敗してもオリジナルのみで Drop 作成は継続）
+        let derivatives = images::generate_cover_derivatives(cover, dir.join(COVER_WEBP_FILE)).await;
+        let webp_key = derivatives.webp.then(|| format!("{}/{}", drop_id, COVER_WEBP_FILE));
+
+        (Some(key), webp_key, derivatives.blurhash)
     } else {
-        None
+        (None, None, None)
     };
 
     // start_at デフォルト設定
     let start_at = start_at.unwrap_or(now);
     let status = if now >= start_at { drop_status::ACTIVE } else { drop_status::SCHEDULED };
 
+    // 音声を blob に配置し、Drop 挿入と参照カウント加算を1トランザクションで行う
+    // （blob_lock でパージによる blob 削除と直列化）
+    let blob_guard = state.blob_lock.lock().await;
+
+    // 上限は挿入直前に数え直して確定する（blob_lock 下なので同時作成でも超えない）
+    let quota_result = match check_active_drop_quota(&state, &vendor_stable_id).await {
+        Ok(()) => check_drop_storage_quota(&state, &vendor_stable_id, audio_size_bytes, 0).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = quota_result {
+        std::mem::drop(blob_guard);
+        let _ = fs::remove_dir_all(&dir).await;
+        return Err(e);
+    }
+
+    let (audio_object_key, blob_created) = place_audio_blob(&state, &audio_path, &audio_sha256, audio_ext)
+        .await
+        .map_err(|e| {
+            ApiError::internal(format!("Failed to store audio: {}", e))
+        })?;
+
+    let mut tx = state.db.begin().await?;
+
     // DB挿入
-    sqlx::query(r#"
+    let insert_result = sqlx::query(r#"
         INSERT INTO drops (
             drop_id, vendor_stable_id, artist_stable_id, artist_name,
             title, description, cover_object_key, audio_object_key,
             audio_mime, audio_size_bytes, audio_sha256,
             start_at, end_at, max_claims, claimed_count,
-            status, env, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?)
+            status, env, created_at, updated_at, idempotency_key, max_downloads_per_claim,
+            preview_object_key, cover_webp_object_key, cover_blurhash,
+            audio_duration_ms, audio_bitrate_kbps, audio_sample_rate_hz, claim_passcode_hash
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#)
     .bind(&drop_id)
     .bind(&vendor_stable_id)
@@ -380,22 +766,60 @@ pub async fn create_drop(
     .bind(&env)
     .bind(now)
     .bind(now)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .bind(&idempotency_key)
+    .bind(max_downloads_per_claim)
+    .bind(&preview_object_key)
+    .bind(&cover_webp_object_key)
+    .bind(&cover_blurhash)
+    .bind(audio_metadata.duration_ms)
+    .bind(audio_metadata.bitrate_kbps)
+    .bind(audio_metadata.sample_rate_hz)
+    .bind(&claim_passcode_hash)
+    .execute(&mut *tx)
+    .await;
+
+    let insert_result = match insert_result {
+        Ok(_) => acquire_blob_ref(&mut *tx, &audio_sha256, &audio_object_key, audio_size_bytes, now).await,
+        Err(e) => Err(e),
+    };
+    let insert_result = match insert_result {
+        Ok(()) => tx.commit().await,
+        Err(e) => Err(e),
+    };
+
+    if insert_result.is_err() && blob_created {
+        // 配置した blob は参照されないまま残るので削除
+        remove_blob_file(&state, &audio_object_key).await;
+    }
+    std::mem::drop(blob_guard);
+
+    if let Err(e) = insert_result {
+        // 同じ idempotency_key の同時リクエストに負けた場合は、保存したファイルを消して既存を返す
+        if let (sqlx::Error::Database(db_err), Some(key)) = (&e, &idempotency_key) {
+            if db_err.is_unique_violation() {
+                let _ = fs::remove_dir_all(&dir).await;
+                if let Some(existing) = find_drop_by_idempotency_key(&state, &vendor_stable_id, key).await? {
+                    return Ok(Json(DropCreateResponse {
+                        success: true,
+                        drop: DropResponse::from_drop(&existing, &state.vps_base_url),
+                    }));
+                }
+            }
+        }
+        return Err(e.into());
+    }
 
     info!("Drop created: drop_id={}, vendor={}, title={}", drop_id, vendor_stable_id, title);
+    timer.finish(&drop_id, audio_size_bytes as u64);
+
+    // 所有権を変更（UPLOAD_CHOWN、ベストエフォート）
+    apply_drop_owner(&state, &dir, &audio_object_key, blob_created).await;
 
     // レスポンス用にDropを取得
     let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
         .bind(&drop_id)
         .fetch_one(&state.db)
-        .await
-        .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-        })?;
+        .await?;
 
     Ok(Json(DropCreateResponse {
         success: true,
@@ -403,350 +827,3334 @@ pub async fn create_drop(
     }))
 }
 
-/// POST /api/drops/:drop_id/claim - Drop受け取り
-pub async fn claim_drop(
+/// PUT /api/drops/:drop_id/audio - 開始前Dropの音声差し替え（Multipart）
+/// SCHEDULED かつ start_at 前のみ許可。開始済み・終了済みは 409
+/// 管理者トークンまたは Vendor オーナー（X-Owner-* の署名）が必須（ENFORCE_VENDOR_OWNER に関わらず）
+#[utoipa::path(
+    put,
+    path = "/api/drops/{drop_id}/audio",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    request_body(content_type = "multipart/form-data", description = "audio: 音声ファイル"),
+    responses(
+        (status = 200, description = "成功", body = DropDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn replace_drop_audio(
     State(state): State<Arc<AppState>>,
     Path(drop_id): Path<String>,
-    Json(req): Json<ClaimDropRequest>,
-) -> Result<Json<ClaimDropResponse>, (StatusCode, Json<ErrorResponse>)> {
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<DropDetailResponse>, ApiError> {
     let now = chrono::Utc::now().timestamp();
 
-    // Drop取得
-    let drop: Option<Drop> = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+    let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
         .bind(&drop_id)
         .fetch_optional(&state.db)
-        .await
-        .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-        })?;
-
-    let drop = drop.ok_or_else(|| {
-        error_response(StatusCode::NOT_FOUND, "Drop not found".to_string())
-    })?;
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?;
 
-    // ステータスチェック
-    if drop.status == drop_status::ENDED || drop.status == drop_status::PURGED {
-        return Err(error_response(StatusCode::BAD_REQUEST, "Drop has ended".to_string()));
+    // 認可: 管理者トークン、または Vendor オーナー（他人の Drop の音声を差し替えられないよう常に検証）
+    if !admin::is_admin(&state, &headers) {
+        vendors::check_vendor_owner(&state, &headers, &drop.vendor_stable_id)
+            .await?;
     }
 
-    // 期限チェック
-    if now < drop.start_at {
-        return Err(error_response(StatusCode::BAD_REQUEST, "Drop has not started yet".to_string()));
-    }
-    if now >= drop.end_at {
-        return Err(error_response(StatusCode::BAD_REQUEST, "Drop has expired".to_string()));
+    if drop.status != drop_status::SCHEDULED || now >= drop.start_at {
+        return Err(ApiError::Conflict(
+            "Audio can only be replaced before the drop starts".to_string(),
+        ));
     }
 
-    // 在庫チェック
-    if drop.claimed_count >= drop.max_claims {
-        return Err(error_response(StatusCode::BAD_REQUEST, "No more claims available".to_string()));
+    let mut audio_data: Option<Vec<u8>> = None;
+    let mut audio_filename: Option<String> = None;
+    let mut audio_mime: Option<String> = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
+    })? {
+        if field.name() == Some("audio") {
+            audio_filename = field.file_name().map(|s| s.to_string());
+            audio_mime = field.content_type().map(|s| s.to_string());
+            audio_data = Some(field.bytes().await.map_err(|e| {
+                ApiError::new(e.status(), format!("Audio read error: {}", e))
+            })?.to_vec());
+        }
     }
 
-    // 重複チェック
-    let existing_claim: Option<DropClaim> = sqlx::query_as(
-        "SELECT * FROM drop_claims WHERE drop_id = ? AND user_id = ?"
-    )
-    .bind(&drop_id)
-    .bind(&req.user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
+    let audio_data = audio_data.ok_or_else(|| {
+        ApiError::BadRequest("audio file is required".to_string())
     })?;
 
-    if existing_claim.is_some() {
-        return Err(error_response(StatusCode::BAD_REQUEST, "Already claimed".to_string()));
-    }
+    // 差し替え後の音声容量が Vendor の上限に収まるか（旧音声の分は解放される、確定は blob 配置前にロック下で行う）
+    check_drop_storage_quota(&state, &drop.vendor_stable_id, audio_data.len() as i64, drop.audio_size_bytes).await?;
 
-    // Claim作成
-    let claim_id = Uuid::new_v4().to_string();
-    sqlx::query(
-        "INSERT INTO drop_claims (claim_id, drop_id, user_id, device_id_hash, claimed_at) VALUES (?, ?, ?, ?, ?)"
-    )
-    .bind(&claim_id)
-    .bind(&drop_id)
-    .bind(&req.user_id)
-    .bind(&req.device_id_hash)
-    .bind(now)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    // 音声ファイル保存（Drop ディレクトリに書いてから blob へ移す）
+    let dir = PathBuf::from(&state.base_data_dir).join("drops").join(&drop_id);
+    let audio_ext = audio_filename
+        .as_ref()
+        .and_then(|f| f.split('.').next_back())
+        .unwrap_or("mp3");
+    let audio_path = dir.join(format!("audio.{}", audio_ext));
+    storage::write_atomic(&audio_path, &audio_data)
+        .await
+        .map_err(|e| {
+            ApiError::internal(format!("Failed to write audio: {}", e))
+        })?;
 
-    // claimed_count更新
-    sqlx::query("UPDATE drops SET claimed_count = claimed_count + 1, updated_at = ? WHERE drop_id = ?")
-        .bind(now)
-        .bind(&drop_id)
-        .execute(&state.db)
+    let audio_sha256 = compute_sha256(&audio_data);
+    let audio_size_bytes = audio_data.len() as i64;
+    let audio_mime = audio_mime.unwrap_or_else(|| guess_audio_mime(audio_ext).to_string());
+
+    // プレビューも新しい音声から作り直す
+    let preview_object_key = generate_preview(&state, &drop_id, &audio_path).await;
+    let audio_metadata = audio::extract_metadata(audio_path.clone()).await;
+
+    // 新しい blob の参照を取り、旧 blob の参照を外す（blob_lock でパージと直列化）
+    let blob_guard = state.blob_lock.lock().await;
+    if let Err(e) = check_drop_storage_quota(&state, &drop.vendor_stable_id, audio_size_bytes, drop.audio_size_bytes).await {
+        std::mem::drop(blob_guard);
+        // 旧形式の音声は同じパスに上書き済みのため残す
+        if is_blob_key(&drop.audio_object_key) {
+            let _ = fs::remove_file(&audio_path).await;
+        }
+        return Err(e);
+    }
+    let (audio_object_key, blob_created) = place_audio_blob(&state, &audio_path, &audio_sha256, audio_ext)
         .await
         .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
+            ApiError::internal(format!("Failed to store audio: {}", e))
         })?;
 
-    info!("Drop claimed: drop_id={}, user_id={}, claim_id={}", drop_id, req.user_id, claim_id);
+    let replaced = replace_audio_in_tx(&state, &drop, &audio_object_key, &audio_mime, audio_size_bytes, &audio_sha256, &preview_object_key, &audio_metadata, now).await;
 
-    // ダウンロードURL生成（簡易トークン）
-    let download_url = format!(
-        "{}/api/drops/{}/download?token={}",
-        state.vps_base_url.replace("/nft", ""),
-        drop_id,
-        claim_id
+    match &replaced {
+        Ok(Some(old_blob_unreferenced)) => {
+            if *old_blob_unreferenced {
+                remove_blob_file(&state, &drop.audio_object_key).await;
+            } else if !is_blob_key(&drop.audio_object_key) && drop.audio_object_key != audio_object_key {
+                // 旧形式（Drop ディレクトリ内）の音声は参照カウントなしで削除
+                let old_path = PathBuf::from(&state.base_data_dir).join("drops").join(&drop.audio_object_key);
+                if let Err(e) = fs::remove_file(&old_path).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        warn!("Failed to remove old audio {:?}: {}", old_path, e);
+                    }
+                }
+            }
+        }
+        Ok(None) | Err(_) => {
+            if blob_created {
+                remove_blob_file(&state, &audio_object_key).await;
+            }
+        }
+    }
+    std::mem::drop(blob_guard);
+
+    match replaced {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Err(ApiError::Conflict(
+                "Audio can only be replaced before the drop starts".to_string(),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    info!(
+        "Drop audio replaced: drop_id={}, size={}, sha256={}",
+        drop_id, audio_size_bytes, &audio_sha256[..16]
     );
 
-    Ok(Json(ClaimDropResponse {
+    apply_drop_owner(&state, &dir, &audio_object_key, blob_created).await;
+
+    let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    Ok(Json(DropDetailResponse {
         success: true,
-        claim_id,
-        drop_id,
-        download_url,
-        expires_at: drop.end_at,
-        audio_sha256: drop.audio_sha256,
-        audio_size_bytes: drop.audio_size_bytes,
+        drop: Some(DropResponse::from_drop(&drop, &state.vps_base_url)),
     }))
 }
 
-/// GET /api/drops/:drop_id/download - Dropダウンロード
-pub async fn download_drop(
-    State(state): State<Arc<AppState>>,
-    Path(drop_id): Path<String>,
-    Query(query): Query<DownloadQuery>,
-) -> Result<axum::response::Response<Body>, (StatusCode, Json<ErrorResponse>)> {
-    let token = query.token.ok_or_else(|| {
-        error_response(StatusCode::UNAUTHORIZED, "Token required".to_string())
-    })?;
+/// 音声差し替えの DB 更新（開始前であることを条件に更新し、blob の参照を付け替える）
+/// 開始済みで更新できなかった場合は None、成功時は旧 blob が参照されなくなったか
+#[allow(clippy::too_many_arguments)]
+async fn replace_audio_in_tx(
+    state: &AppState,
+    drop: &Drop,
+    audio_object_key: &str,
+    audio_mime: &str,
+    audio_size_bytes: i64,
+    audio_sha256: &str,
+    preview_object_key: &Option<String>,
+    audio_metadata: &AudioMetadata,
+    now: i64,
+) -> Result<Option<bool>, sqlx::Error> {
+    let mut tx = state.db.begin().await?;
 
-    // Claim検証
-    let claim: Option<DropClaim> = sqlx::query_as(
-        "SELECT * FROM drop_claims WHERE claim_id = ? AND drop_id = ?"
-    )
-    .bind(&token)
-    .bind(&drop_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    // 開始前であることを条件に更新（アップロード中に開始した場合は 409）
+    let result = sqlx::query(r#"
+        UPDATE drops SET
+            audio_object_key = ?, audio_mime = ?, audio_size_bytes = ?, audio_sha256 = ?,
+            preview_object_key = ?, audio_duration_ms = ?, audio_bitrate_kbps = ?, audio_sample_rate_hz = ?,
+            updated_at = ?
+        WHERE drop_id = ? AND status = ? AND start_at > ?
+    "#)
+    .bind(audio_object_key)
+    .bind(audio_mime)
+    .bind(audio_size_bytes)
+    .bind(audio_sha256)
+    .bind(preview_object_key)
+    .bind(audio_metadata.duration_ms)
+    .bind(audio_metadata.bitrate_kbps)
+    .bind(audio_metadata.sample_rate_hz)
+    .bind(now)
+    .bind(&drop.drop_id)
+    .bind(drop_status::SCHEDULED)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
 
-    if claim.is_none() {
-        return Err(error_response(StatusCode::UNAUTHORIZED, "Invalid token".to_string()));
+    if result.rows_affected() == 0 {
+        return Ok(None);
     }
 
-    // Drop取得
+    acquire_blob_ref(&mut *tx, audio_sha256, audio_object_key, audio_size_bytes, now).await?;
+    let old_unreferenced = release_blob_ref(&mut tx, &drop.audio_object_key).await?;
+    tx.commit().await?;
+    Ok(Some(old_unreferenced))
+}
+
+/// POST /api/drops/:drop_id/extend - Drop の終了時刻を延長
+/// SCHEDULED / ACTIVE のみ。期限切れで自動終了した Drop は DROP_REACTIVATE_GRACE_SECS 以内なら再開する
+/// 管理者トークンまたは Vendor オーナー（X-Owner-Address）が必須（ENFORCE_VENDOR_OWNER に関わらず）
+#[utoipa::path(
+    post,
+    path = "/api/drops/{drop_id}/extend",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    request_body = ExtendDropRequest,
+    responses(
+        (status = 200, description = "成功", body = DropDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn extend_drop(
+    State(state): State<Arc<AppState>>,
+    Path(drop_id): Path<String>,
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<ExtendDropRequest>,
+) -> Result<Json<DropDetailResponse>, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+
     let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
         .bind(&drop_id)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-        })?;
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?;
 
-    // 期限チェック
-    let now = chrono::Utc::now().timestamp();
-    if now >= drop.end_at {
-        return Err(error_response(StatusCode::BAD_REQUEST, "Drop has expired".to_string()));
+    // 認可: 管理者トークン、または Vendor オーナー（他人の Drop を延長・再開できないよう常に検証）
+    if !admin::is_admin(&state, &headers) {
+        vendors::check_vendor_owner(&state, &headers, &drop.vendor_stable_id).await?;
     }
 
-    // ファイル読み込み
-    let audio_path = PathBuf::from(&state.base_data_dir)
-        .join("drops")
-        .join(&drop.audio_object_key);
+    // 再開するか（ENDED は自動終了かつ猶予内のみ）
+    let reactivate = match drop.status {
+        drop_status::SCHEDULED | drop_status::ACTIVE => false,
+        drop_status::ENDED if can_reactivate(&drop, now, state.config.drop_reactivate_grace_secs) => true,
+        _ => {
+            return Err(ApiError::Conflict(
+                "Only scheduled or active drops can be extended".to_string(),
+            ));
+        }
+    };
 
-    let audio_data = fs::read(&audio_path).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("File read error: {}", e))
-    })?;
+    if req.end_at <= drop.end_at {
+        return Err(ApiError::invalid_fields(
+            format!("end_at must be after the current end_at ({}) (got {})", drop.end_at, req.end_at),
+            &["end_at"],
+        ));
+    }
+    validate_drop_window(drop.start_at, req.end_at, now, state.config.max_drop_window_secs)
+        .map_err(|(message, fields)| ApiError::invalid_fields(message, fields))?;
 
-    // レスポンス構築
-    let response = axum::response::Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", &drop.audio_mime)
-        .header("Content-Length", audio_data.len())
-        .header("Content-Disposition", format!("attachment; filename=\"{}\"", drop.title))
-        .body(Body::from(audio_data))
-        .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Response build error: {}", e))
-        })?;
+    // 読み取り後に状態・end_at が変わっていないことを条件に更新
+    let new_status = if reactivate { drop_status::ACTIVE } else { drop.status };
+    let result = sqlx::query(
+        "UPDATE drops SET end_at = ?, status = ?, ended_at = CASE WHEN ? THEN NULL ELSE ended_at END, updated_at = ? WHERE drop_id = ? AND status = ? AND end_at = ?"
+    )
+    .bind(req.end_at)
+    .bind(new_status)
+    .bind(reactivate)
+    .bind(now)
+    .bind(&drop_id)
+    .bind(drop.status)
+    .bind(drop.end_at)
+    .execute(&state.db)
+    .await?;
 
-    Ok(response)
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict(
+            "Drop was modified concurrently; retry".to_string(),
+        ));
+    }
+
+    info!(
+        "Drop extended: drop_id={}, end_at={} -> {}, reactivated={}",
+        drop_id, drop.end_at, req.end_at, reactivate
+    );
+
+    let updated: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    Ok(Json(DropDetailResponse {
+        success: true,
+        drop: Some(DropResponse::from_drop(&updated, &state.vps_base_url)),
+    }))
 }
 
-/// POST /api/vendors/:vendor_stable_id/drops/batch_end - 一括終了
-pub async fn batch_end_drops(
-    State(state): State<Arc<AppState>>,
-    Path(vendor_stable_id): Path<String>,
-    Json(req): Json<BatchDropRequest>,
-) -> Result<Json<BatchDropResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let now = chrono::Utc::now().timestamp();
-    let mut results = HashMap::new();
+/// 終了済み Drop を延長で再開できるか
+/// 期限切れジョブによる終了（ended_at >= end_at）のみ対象で、手動終了（batch_end 等）は再開しない
+fn can_reactivate(drop: &Drop, now: i64, grace_secs: i64) -> bool {
+    let Some(ended_at) = drop.ended_at else {
+        return false;
+    };
+    grace_secs > 0 && ended_at >= drop.end_at && now - ended_at <= grace_secs
+}
 
-    for drop_id in &req.drop_ids {
-        let result = sqlx::query(
-            "UPDATE drops SET status = ?, ended_at = ?, updated_at = ? WHERE drop_id = ? AND vendor_stable_id = ? AND status IN (?, ?)"
-        )
-        .bind(drop_status::ENDED)
-        .bind(now)
-        .bind(now)
-        .bind(drop_id)
-        .bind(&vendor_stable_id)
-        .bind(drop_status::SCHEDULED)
-        .bind(drop_status::ACTIVE)
-        .execute(&state.db)
-        .await;
+/// GET /api/drops/:drop_id/preview - 30秒の試聴クリップ（Claim不要）
+/// プレビューが生成されていない場合は 404
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/preview",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    responses(
+        (status = 200, description = "音声データ", content_type = "audio/mpeg"),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_drop_preview(
+    State(state): State<Arc<AppState>>,
+    Path(drop_id): Path<String>,
+) -> Result<axum::response::Response<Body>, ApiError> {
+    let preview_key: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT preview_object_key FROM drops WHERE drop_id = ?"
+    )
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?;
 
-        results.insert(drop_id.clone(), result.map(|r| r.rows_affected() > 0).unwrap_or(false));
-    }
+    let preview_key = preview_key
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?
+        .0
+        .ok_or_else(|| ApiError::NotFound("Preview not available".to_string()))?;
 
-    info!("Batch end drops: vendor={}, count={}", vendor_stable_id, req.drop_ids.len());
+    let preview_path = PathBuf::from(&state.base_data_dir).join("drops").join(&preview_key);
+    let data = match fs::read(&preview_path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ApiError::NotFound("Preview not available".to_string()));
+        }
+        Err(e) => {
+            return Err(ApiError::internal(format!("File read error: {}", e)));
+        }
+    };
 
-    Ok(Json(BatchDropResponse {
-        success: true,
-        results,
-    }))
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "audio/mpeg")
+        .header("Content-Length", data.len())
+        .header("Content-Disposition", "inline; filename=\"preview.mp3\"")
+        .body(Body::from(data))
+        .map_err(|e| {
+            ApiError::internal(format!("Response build error: {}", e))
+        })
 }
 
-/// POST /api/vendors/:vendor_stable_id/drops/batch_purge - 一括削除
-pub async fn batch_purge_drops(
+/// POST /api/drops/:drop_id/claim - Drop受け取り
+#[utoipa::path(
+    post,
+    path = "/api/drops/{drop_id}/claim",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    request_body = ClaimDropRequest,
+    responses(
+        (status = 200, description = "成功", body = ClaimDropResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn claim_drop(
     State(state): State<Arc<AppState>>,
-    Path(vendor_stable_id): Path<String>,
-    Json(req): Json<BatchDropRequest>,
-) -> Result<Json<BatchDropResponse>, (StatusCode, Json<ErrorResponse>)> {
+    Path(drop_id): Path<String>,
+    ApiJson(req): ApiJson<ClaimDropRequest>,
+) -> Result<Json<ClaimDropResponse>, ApiError> {
     let now = chrono::Utc::now().timestamp();
-    let mut results = HashMap::new();
 
-    for drop_id in &req.drop_ids {
-        // まずENDEDに（まだの場合）
-        let _ = sqlx::query(
-            "UPDATE drops SET status = ?, ended_at = COALESCE(ended_at, ?), updated_at = ? WHERE drop_id = ? AND vendor_stable_id = ? AND status IN (?, ?)"
-        )
-        .bind(drop_status::ENDED)
-        .bind(now)
-        .bind(now)
-        .bind(drop_id)
-        .bind(&vendor_stable_id)
-        .bind(drop_status::SCHEDULED)
-        .bind(drop_status::ACTIVE)
-        .execute(&state.db)
-        .await;
+    // tx_digest 形式チェック（Base58 の 32 バイト）
+    if let Some(tx_digest) = &req.tx_digest {
+        if !is_valid_tx_digest(tx_digest) {
+            return Err(ApiError::BadRequest("Invalid tx_digest format".to_string()));
+        }
+    }
 
-        // Drop取得
-        let drop: Option<Drop> = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ? AND vendor_stable_id = ?")
-            .bind(drop_id)
-            .bind(&vendor_stable_id)
-            .fetch_optional(&state.db)
-            .await
-            .ok()
-            .flatten();
+    // email 形式チェック（空文字は未指定扱い）
+    let email = req.email.as_deref().map(str::trim).filter(|e| !e.is_empty());
+    if let Some(email) = email {
+        mailer::validate_email(email)
+            .map_err(|message| ApiError::invalid_fields(message, &["email"]))?;
+    }
 
-        if let Some(_d) = drop {
-            // ファイル削除
-            let dir = PathBuf::from(&state.base_data_dir).join("drops").join(drop_id);
-            let _ = fs::remove_dir_all(&dir).await;
+    // Drop取得
+    let drop: Option<Drop> = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?;
 
-            // PURGED更新
-            let result = sqlx::query(
-                "UPDATE drops SET status = ?, purged_at = ?, updated_at = ? WHERE drop_id = ?"
-            )
-            .bind(drop_status::PURGED)
-            .bind(now)
-            .bind(now)
-            .bind(drop_id)
-            .execute(&state.db)
-            .await;
+    let drop = drop.ok_or_else(|| {
+        ApiError::NotFound("Drop not found".to_string())
+    })?;
 
-            results.insert(drop_id.clone(), result.map(|r| r.rows_affected() > 0).unwrap_or(false));
-            info!("Drop purged: drop_id={}", drop_id);
-        } else {
-            results.insert(drop_id.clone(), false);
+    // パスコード付き Drop は一致する場合のみ（状態を漏らさないよう他のチェックより先に行う）
+    if let Some(expected) = &drop.claim_passcode_hash {
+        let passcode = req.passcode.as_deref().ok_or_else(|| {
+            ApiError::Forbidden("Passcode required".to_string())
+        })?;
+        if !constant_time_eq(hash_claim_passcode(&drop_id, passcode).as_bytes(), expected.as_bytes()) {
+            warn!("Claim passcode mismatch: drop_id={}, user_id={}", drop_id, req.user_id);
+            return Err(ApiError::Forbidden("Invalid passcode".to_string()));
         }
     }
 
-    Ok(Json(BatchDropResponse {
-        success: true,
-        results,
-    }))
-}
+    // ステータス・期限・在庫チェック
+    if let Some(blocked) = ClaimBlock::check(&drop, now) {
+        return Err(ApiError::BadRequest(blocked.message().to_string()));
+    }
 
-// ========================================
-// Background Job (期限切れ自動処理)
-// ========================================
+    // 重複チェック
+    if has_claimed(&state, &drop_id, &req.user_id).await? {
+        return Err(ApiError::BadRequest(ClaimBlock::AlreadyClaimed.message().to_string()));
+    }
 
-/// 期限切れDropsを終了させる（定期実行用）
-pub async fn expire_drops(state: &Arc<AppState>) -> anyhow::Result<usize> {
-    let now = chrono::Utc::now().timestamp();
+    // Claim作成（在庫の条件付き加算と Claim 挿入を1トランザクションで行う）
+    let claim_id = Uuid::new_v4().to_string();
+    let mut tx = state.db.begin().await?;
 
+    // claimed_count更新（max_claims 未満の場合のみ）
     let result = sqlx::query(
-        "UPDATE drops SET status = ?, ended_at = ?, updated_at = ? WHERE end_at <= ? AND status IN (?, ?)"
+        "UPDATE drops SET claimed_count = claimed_count + 1, updated_at = ? WHERE drop_id = ? AND claimed_count < max_claims"
     )
-    .bind(drop_status::ENDED)
-    .bind(now)
     .bind(now)
-    .bind(now)
-    .bind(drop_status::SCHEDULED)
-    .bind(drop_status::ACTIVE)
-    .execute(&state.db)
+    .bind(&drop_id)
+    .execute(&mut *tx)
     .await?;
 
-    let count = result.rows_affected() as usize;
-    if count > 0 {
-        info!("Expired {} drops", count);
+    if result.rows_affected() == 0 {
+        return Err(ApiError::BadRequest("No more claims available".to_string()));
     }
-    Ok(count)
-}
-
-/// 終了済みDropsを削除（定期実行用）
-pub async fn purge_ended_drops(state: &Arc<AppState>, grace_seconds: i64) -> anyhow::Result<usize> {
-    let now = chrono::Utc::now().timestamp();
-    let cutoff = now - grace_seconds;
 
-    // 削除対象取得
-    let drops: Vec<Drop> = sqlx::query_as(
-        "SELECT * FROM drops WHERE status = ? AND ended_at IS NOT NULL AND ended_at <= ?"
+    sqlx::query(
+        "INSERT INTO drop_claims (claim_id, drop_id, user_id, device_id_hash, claimed_at, tx_digest, email) VALUES (?, ?, ?, ?, ?, ?, ?)"
     )
-    .bind(drop_status::ENDED)
-    .bind(cutoff)
-    .fetch_all(&state.db)
-    .await?;
-
-    let mut count = 0;
-    for drop in drops {
-        // ファイル削除
-        let dir = PathBuf::from(&state.base_data_dir).join("drops").join(&drop.drop_id);
-        let _ = fs::remove_dir_all(&dir).await;
+    .bind(&claim_id)
+    .bind(&drop_id)
+    .bind(&req.user_id)
+    .bind(&req.device_id_hash)
+    .bind(now)
+    .bind(&req.tx_digest)
+    .bind(email.map(|e| mailer::stored_email(&state.config, e)))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // 同時リクエストによる重複（UNIQUE(drop_id, user_id)）
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            ApiError::BadRequest("Already claimed".to_string())
+        }
+        e => e.into(),
+    })?;
 
-        // PURGED更新
-        sqlx::query(
-            "UPDATE drops SET status = ?, purged_at = ?, updated_at = ? WHERE drop_id = ?"
-        )
-        .bind(drop_status::PURGED)
-        .bind(now)
-        .bind(now)
-        .bind(&drop.drop_id)
-        .execute(&state.db)
-        .await?;
+    tx.commit().await?;
 
-        info!("Purged drop: drop_id={}", drop.drop_id);
-        count += 1;
-    }
+    info!("Drop claimed: drop_id={}, user_id={}, claim_id={}", drop_id, req.user_id, claim_id);
 
-    Ok(count)
-}
+    // ダウンロードURL生成（簡易トークン）
+    let download_url = format!(
+        "{}/api/drops/{}/download?token={}",
+        state.config.asset_base_url,
+        drop_id,
+        claim_id
+    );
 
-// ========================================
-// Helper Functions
-// ========================================
+    // メール通知（SMTP 未設定時はレスポンスのリンクのみ）
+    let email_queued = match email {
+        Some(to) => mailer::spawn_claim_email(&state.config, mailer::ClaimEmail {
+            claim_id: claim_id.clone(),
+            to: to.to_string(),
+            drop_title: drop.title.clone(),
+            artist_name: drop.artist_name.clone(),
+            download_url: download_url.clone(),
+            expires_at: drop.end_at,
+        }),
+        None => false,
+    };
 
-fn generate_drop_id() -> String {
-    let random_bytes: [u8; 5] = rand::thread_rng().gen();
-    let encoded = base32::encode(base32::Alphabet::Crockford, &random_bytes);
-    format!("DROP_{}", &encoded[..8])
+    Ok(Json(ClaimDropResponse {
+        success: true,
+        claim_id,
+        drop_id,
+        download_url,
+        expires_at: drop.end_at,
+        audio_sha256: drop.audio_sha256,
+        audio_size_bytes: drop.audio_size_bytes,
+        tx_digest: req.tx_digest,
+        email_queued,
+    }))
 }
 
-fn compute_sha256(data: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
-}
+/// GET /api/drops/:drop_id/claimable?user_id=... - Claim できるかの事前確認（状態は変更しない）
+/// 判定順は POST /api/drops/:drop_id/claim と同じ
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/claimable",
+    tag = "drops",
+    params(("drop_id" = String, Path), ClaimableQuery),
+    responses(
+        (status = 200, description = "成功", body = ClaimableResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_drop_claimable(
+    State(state): State<Arc<AppState>>,
+    Path(drop_id): Path<String>,
+    Query(query): Query<ClaimableQuery>,
+) -> Result<Json<ClaimableResponse>, ApiError> {
+    let now = chrono::Utc::now().timestamp();
 
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    warn!("API Error: {}", message);
-    (status, Json(ErrorResponse { success: false, error: message }))
+    let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?;
+
+    let mut blocked = ClaimBlock::check(&drop, now);
+    if blocked.is_none() {
+        if let Some(user_id) = query.user_id.as_deref().filter(|u| !u.is_empty()) {
+            if has_claimed(&state, &drop_id, user_id).await? {
+                blocked = Some(ClaimBlock::AlreadyClaimed);
+            }
+        }
+    }
+
+    Ok(Json(ClaimableResponse {
+        success: true,
+        drop_id,
+        claimable: blocked.is_none(),
+        reason: blocked.map_or("ok", |b| b.reason()).to_string(),
+        remaining_claims: (drop.max_claims - drop.claimed_count).max(0),
+        start_at: drop.start_at,
+        end_at: drop.end_at,
+    }))
+}
+
+/// POST /api/drops/availability - 複数 Drop の在庫・残り時間をまとめて取得（最大 100 件）
+#[utoipa::path(
+    post,
+    path = "/api/drops/availability",
+    tag = "drops",
+    request_body = BatchDropRequest,
+    responses(
+        (status = 200, description = "成功", body = DropAvailabilityResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_drops_availability(
+    State(state): State<Arc<AppState>>,
+    ApiJson(req): ApiJson<BatchDropRequest>,
+) -> Result<Json<DropAvailabilityResponse>, ApiError> {
+    let mut drop_ids = req.drop_ids;
+    drop_ids.sort();
+    drop_ids.dedup();
+    if drop_ids.len() > MAX_AVAILABILITY_BATCH {
+        return Err(ApiError::BadRequest(format!(
+            "Too many drop_ids: at most {} per request",
+            MAX_AVAILABILITY_BATCH
+        )));
+    }
+    if drop_ids.is_empty() {
+        return Ok(Json(DropAvailabilityResponse {
+            success: true,
+            drops: HashMap::new(),
+            missing: Vec::new(),
+        }));
+    }
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "SELECT drop_id, status, start_at, end_at, max_claims, claimed_count FROM drops WHERE drop_id IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for drop_id in &drop_ids {
+        separated.push_bind(drop_id);
+    }
+    separated.push_unseparated(")");
+    let rows: Vec<(String, i32, i64, i64, i64, i64)> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await?;
+
+    let now = chrono::Utc::now().timestamp();
+    let drops: HashMap<String, DropAvailability> = rows
+        .into_iter()
+        .map(|(drop_id, status, start_at, end_at, max_claims, claimed_count)| {
+            let availability = DropAvailability {
+                remaining_claims: (max_claims - claimed_count).max(0),
+                seconds_left: (end_at - now).max(0),
+                status: effective_status(status, start_at, end_at, now),
+            };
+            (drop_id, availability)
+        })
+        .collect();
+    let missing = drop_ids.into_iter().filter(|id| !drops.contains_key(id)).collect();
+
+    Ok(Json(DropAvailabilityResponse {
+        success: true,
+        drops,
+        missing,
+    }))
+}
+
+/// GET /api/drops/:drop_id/claims - Claim一覧（Vendor オーナーまたは管理者のみ）
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/claims",
+    tag = "drops",
+    params(("drop_id" = String, Path), ListClaimsQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<DropClaim>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn list_drop_claims(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(drop_id): Path<String>,
+    Query(query): Query<ListClaimsQuery>,
+) -> Result<Json<ClaimListResponse>, ApiError> {
+    let vendor: Option<(String,)> = sqlx::query_as("SELECT vendor_stable_id FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+    let (vendor_stable_id,) = vendor.ok_or_else(|| {
+        ApiError::NotFound("Drop not found".to_string())
+    })?;
+
+    // 認可: 管理者トークン、または Vendor オーナー
+    if !admin::is_admin(&state, &headers) {
+        vendors::check_vendor_owner(&state, &headers, &vendor_stable_id).await?;
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drop_claims WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    let claims: Vec<DropClaim> = sqlx::query_as(
+        "SELECT * FROM drop_claims WHERE drop_id = ? ORDER BY claimed_at DESC, claim_id ASC LIMIT ? OFFSET ?"
+    )
+    .bind(&drop_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let meta = ListMeta {
+        total,
+        limit: Some(limit),
+        offset: Some(offset),
+        ..ListMeta::default()
+    };
+    let legacy = state.config.legacy_list_fields;
+    Ok(Json(
+        ApiResponse::list(claims, meta)
+            .with_legacy_fields(legacy, "claims")
+            .with_legacy_value(legacy, "drop_id", drop_id),
+    ))
+}
+
+/// GET /api/drops/:drop_id/claims/:claim_id - Claim状態取得
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/claims/{claim_id}",
+    tag = "drops",
+    params(("drop_id" = String, Path), ("claim_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ClaimStatusResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_claim_status(
+    State(state): State<Arc<AppState>>,
+    Path((drop_id, claim_id)): Path<(String, String)>,
+) -> Result<Json<ClaimStatusResponse>, ApiError> {
+    let claim: Option<DropClaim> = sqlx::query_as(
+        "SELECT * FROM drop_claims WHERE claim_id = ? AND drop_id = ?"
+    )
+    .bind(&claim_id)
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let claim = claim.ok_or_else(|| {
+        ApiError::NotFound("Claim not found".to_string())
+    })?;
+
+    Ok(Json(ClaimStatusResponse {
+        success: true,
+        claim,
+    }))
+}
+
+/// POST /api/drops/:drop_id/claims/:claim_id/revoke - Claim の失効（漏えいしたダウンロードリンクの無効化）
+/// Claim の枠は消費したまま（claimed_count は戻さない）。失効済みの場合はそのまま返す
+/// 管理者トークンまたは Vendor オーナー（X-Owner-Address）が必須（ENFORCE_VENDOR_OWNER に関わらず）
+#[utoipa::path(
+    post,
+    path = "/api/drops/{drop_id}/claims/{claim_id}/revoke",
+    tag = "drops",
+    params(("drop_id" = String, Path), ("claim_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ClaimStatusResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn revoke_claim(
+    State(state): State<Arc<AppState>>,
+    Path((drop_id, claim_id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Json<ClaimStatusResponse>, ApiError> {
+    let (vendor_stable_id,): (String,) = sqlx::query_as("SELECT vendor_stable_id FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?;
+
+    // 認可: 管理者トークン、または Vendor オーナー（他の購入者の Claim を失効できないよう常に検証）
+    if !admin::is_admin(&state, &headers) {
+        vendors::check_vendor_owner(&state, &headers, &vendor_stable_id).await?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE drop_claims SET revoked_at = ? WHERE claim_id = ? AND drop_id = ? AND revoked_at IS NULL"
+    )
+    .bind(now)
+    .bind(&claim_id)
+    .bind(&drop_id)
+    .execute(&state.db)
+    .await?;
+
+    let claim: DropClaim = sqlx::query_as(
+        "SELECT * FROM drop_claims WHERE claim_id = ? AND drop_id = ?"
+    )
+    .bind(&claim_id)
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Claim not found".to_string()))?;
+
+    if result.rows_affected() > 0 {
+        info!("Claim revoked: drop_id={}, claim_id={}", drop_id, claim_id);
+    }
+
+    Ok(Json(ClaimStatusResponse {
+        success: true,
+        claim,
+    }))
+}
+
+/// GET /api/drops/:drop_id/claims/:claim_id/receipt?token=... - 署名付き Claim レシート
+/// token は claim_id（ダウンロードと同じ bearer トークン）
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/claims/{claim_id}/receipt",
+    tag = "drops",
+    params(("drop_id" = String, Path), ("claim_id" = String, Path), DownloadQuery),
+    responses(
+        (status = 200, description = "成功", body = ClaimReceiptResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_claim_receipt(
+    State(state): State<Arc<AppState>>,
+    Path((drop_id, claim_id)): Path<(String, String)>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Json<ClaimReceiptResponse>, ApiError> {
+    let signing_key = receipt_signing_key(&state)?;
+
+    let token = query.token.ok_or_else(|| {
+        ApiError::Unauthorized("Token required".to_string())
+    })?;
+    if token != claim_id {
+        return Err(ApiError::Unauthorized("Invalid token".to_string()));
+    }
+
+    let claim: DropClaim = sqlx::query_as(
+        "SELECT * FROM drop_claims WHERE claim_id = ? AND drop_id = ?"
+    )
+    .bind(&claim_id)
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Claim not found".to_string()))?;
+
+    let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    let receipt = ClaimReceipt {
+        receipt_version: 1,
+        claim_id: claim.claim_id,
+        drop_id: drop.drop_id,
+        vendor_stable_id: drop.vendor_stable_id,
+        artist_name: drop.artist_name,
+        title: drop.title,
+        user_id: claim.user_id,
+        claimed_at: claim.claimed_at,
+        audio_sha256: drop.audio_sha256,
+        audio_size_bytes: drop.audio_size_bytes,
+        expires_at: drop.end_at,
+        tx_digest: claim.tx_digest,
+        issued_at: chrono::Utc::now().timestamp(),
+    };
+
+    let payload = serde_json::to_string(&receipt).map_err(|e| {
+        ApiError::internal(format!("Serialize error: {}", e))
+    })?;
+    let signature = signing_key.sign(payload.as_bytes());
+
+    Ok(Json(ClaimReceiptResponse {
+        success: true,
+        receipt,
+        payload,
+        signature: base64::engine::general_purpose::STANDARD.encode(signature.to_bytes()),
+        public_key: base64::engine::general_purpose::STANDARD.encode(signing_key.verifying_key().to_bytes()),
+    }))
+}
+
+/// GET /api/receipts/public-key - Claim レシート検証用の Ed25519 公開鍵
+#[utoipa::path(
+    get,
+    path = "/api/receipts/public-key",
+    tag = "drops",
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_receipt_public_key(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let signing_key = receipt_signing_key(&state)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "algorithm": "ed25519",
+        "public_key": base64::engine::general_purpose::STANDARD.encode(signing_key.verifying_key().to_bytes()),
+    })))
+}
+
+/// GET /api/drops/:drop_id/download - Dropダウンロード
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/download",
+    tag = "drops",
+    params(("drop_id" = String, Path), DownloadQuery),
+    responses(
+        (status = 200, description = "音声データ", content_type = "application/octet-stream"),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn download_drop(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(drop_id): Path<String>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<axum::response::Response<Body>, ApiError> {
+    let timer = TransferTimer::start("download_drop");
+    let token = query.token.ok_or_else(|| {
+        ApiError::Unauthorized("Token required".to_string())
+    })?;
+
+    // Drop取得（パージ時に drop_claims も削除されるため、Claim 検証より先に確認する）
+    let drop: Drop = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+        .bind(&drop_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Drop not found".to_string()))?;
+
+    // パージ済み（音声は削除済み）
+    if drop.status == drop_status::PURGED {
+        return Err(ApiError::new(StatusCode::GONE, "Drop has been purged"));
+    }
+
+    // Claim検証
+    let claim: Option<DropClaim> = sqlx::query_as(
+        "SELECT * FROM drop_claims WHERE claim_id = ? AND drop_id = ?"
+    )
+    .bind(&token)
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let claim = claim.ok_or_else(|| {
+        ApiError::Unauthorized("Invalid token".to_string())
+    })?;
+    if claim.revoked_at.is_some() {
+        return Err(ApiError::Forbidden("Claim has been revoked".to_string()));
+    }
+
+    // 期限チェック
+    let now = chrono::Utc::now().timestamp();
+    if now >= drop.end_at {
+        return Err(ApiError::BadRequest("Drop has expired".to_string()));
+    }
+
+    // Claimごとのダウンロード回数を加算（上限到達時は 429）
+    let counted = sqlx::query(
+        "UPDATE drop_claims SET download_count = download_count + 1 WHERE claim_id = ? AND (? IS NULL OR download_count < ?)"
+    )
+    .bind(&token)
+    .bind(drop.max_downloads_per_claim)
+    .bind(drop.max_downloads_per_claim)
+    .execute(&state.db)
+    .await?;
+
+    if counted.rows_affected() == 0 {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Download limit reached for this claim".to_string(),
+        ));
+    }
+
+    // ファイルを開く（全体をメモリに読み込まず、レスポンスにストリーミングする）
+    let audio_path = PathBuf::from(&state.base_data_dir)
+        .join("drops")
+        .join(&drop.audio_object_key);
+
+    let (audio_file, audio_len) = match open_with_len(&audio_path).await {
+        Ok(opened) => opened,
+        Err(e) => {
+            // 読み込み失敗分はカウントを戻す
+            refund_download_count(&state, &token).await;
+            return Err(audio_read_error(&drop_id, &audio_path, e));
+        }
+    };
+
+    // ダウンロード実績を記録（失敗してもダウンロードは継続）
+    let bytes_sent = audio_len as i64;
+    let ip_hash = compute_sha256(middleware::client_ip(&state.config, &headers, &remote_addr).as_bytes());
+    if let Err(e) = sqlx::query(
+        "INSERT INTO drop_downloads (claim_id, drop_id, downloaded_at, bytes_sent, ip_hash) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&token)
+    .bind(&drop_id)
+    .bind(now)
+    .bind(bytes_sent)
+    .bind(&ip_hash)
+    .execute(&state.db)
+    .await
+    {
+        warn!("Failed to record drop download: drop_id={}, error={}", drop_id, e);
+    }
+
+    // レスポンス構築（設定で有効な場合は送りながらハッシュし、終端で audio_sha256 と照合する）
+    let stream = ReaderStream::new(audio_file);
+    let body = if state.config.verify_download_sha256 {
+        let verified = VerifiedStream::new(stream, drop.audio_sha256.clone(), drop_id.clone());
+        Body::from_stream(LoggedStream::new(verified, timer, drop_id.clone(), audio_len))
+    } else {
+        Body::from_stream(LoggedStream::new(stream, timer, drop_id.clone(), audio_len))
+    };
+    let response = axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", &drop.audio_mime)
+        .header("Content-Length", audio_len)
+        .header("Content-Disposition", format!("attachment; filename=\"{}\"", drop.title))
+        .body(body)
+        .map_err(|e| {
+            ApiError::internal(format!("Response build error: {}", e))
+        })?;
+
+    Ok(response)
+}
+
+/// 音声ファイル読み込みエラーの変換（ファイルが無い場合は 404、それ以外は 500）
+/// パージ前にファイルだけ消えている状態なので、404 でも運用側で気付けるようにログを残す
+fn audio_read_error(drop_id: &str, audio_path: &std::path::Path, e: std::io::Error) -> ApiError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        error!("Drop audio file is missing: drop_id={}, path={}", drop_id, audio_path.display());
+        ApiError::NotFound("Audio no longer available".to_string())
+    } else {
+        ApiError::internal(format!("File read error: {}", e))
+    }
+}
+
+/// GET /api/drops/:drop_id/stats - Drop配信統計
+#[utoipa::path(
+    get,
+    path = "/api/drops/{drop_id}/stats",
+    tag = "drops",
+    params(("drop_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = DropStatsResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_drop_stats(
+    State(state): State<Arc<AppState>>,
+    Path(drop_id): Path<String>,
+) -> Result<Json<DropStatsResponse>, ApiError> {
+    let claim_count: Option<(i64,)> = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM drop_claims WHERE drop_id = d.drop_id) FROM drops d WHERE d.drop_id = ?"
+    )
+    .bind(&drop_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let (claim_count,) = claim_count.ok_or_else(|| {
+        ApiError::NotFound("Drop not found".to_string())
+    })?;
+
+    let (download_count, unique_downloaders, total_bytes_sent): (i64, i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(DISTINCT claim_id), COALESCE(SUM(bytes_sent), 0) FROM drop_downloads WHERE drop_id = ?"
+    )
+    .bind(&drop_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(DropStatsResponse {
+        success: true,
+        drop_id,
+        claim_count,
+        download_count,
+        unique_downloaders,
+        total_bytes_sent,
+    }))
+}
+
+/// POST /api/vendors/:vendor_stable_id/drops/batch_end - 一括終了
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{vendor_stable_id}/drops/batch_end",
+    tag = "drops",
+    params(("vendor_stable_id" = String, Path)),
+    request_body = BatchDropRequest,
+    responses(
+        (status = 200, description = "成功", body = BatchDropResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn batch_end_drops(
+    State(state): State<Arc<AppState>>,
+    Path(vendor_stable_id): Path<String>,
+    ApiJson(req): ApiJson<BatchDropRequest>,
+) -> Result<Json<BatchDropResponse>, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let mut results = HashMap::new();
+
+    for drop_id in &req.drop_ids {
+        let result = sqlx::query(
+            "UPDATE drops SET status = ?, ended_at = ?, updated_at = ? WHERE drop_id = ? AND vendor_stable_id = ? AND status IN (?, ?)"
+        )
+        .bind(drop_status::ENDED)
+        .bind(now)
+        .bind(now)
+        .bind(drop_id)
+        .bind(&vendor_stable_id)
+        .bind(drop_status::SCHEDULED)
+        .bind(drop_status::ACTIVE)
+        .execute(&state.db)
+        .await;
+
+        results.insert(drop_id.clone(), result.map(|r| r.rows_affected() > 0).unwrap_or(false));
+    }
+
+    info!("Batch end drops: vendor={}, count={}", vendor_stable_id, req.drop_ids.len());
+
+    Ok(Json(BatchDropResponse {
+        success: true,
+        results,
+    }))
+}
+
+/// POST /api/vendors/:vendor_stable_id/drops/batch_purge - 一括削除
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{vendor_stable_id}/drops/batch_purge",
+    tag = "drops",
+    params(("vendor_stable_id" = String, Path)),
+    request_body = BatchDropRequest,
+    responses(
+        (status = 200, description = "成功", body = BatchDropResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn batch_purge_drops(
+    State(state): State<Arc<AppState>>,
+    Path(vendor_stable_id): Path<String>,
+    ApiJson(req): ApiJson<BatchDropRequest>,
+) -> Result<Json<BatchDropResponse>, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let mut results = HashMap::new();
+
+    for drop_id in &req.drop_ids {
+        // まずENDEDに（まだの場合）
+        let _ = sqlx::query(
+            "UPDATE drops SET status = ?, ended_at = COALESCE(ended_at, ?), updated_at = ? WHERE drop_id = ? AND vendor_stable_id = ? AND status IN (?, ?)"
+        )
+        .bind(drop_status::ENDED)
+        .bind(now)
+        .bind(now)
+        .bind(drop_id)
+        .bind(&vendor_stable_id)
+        .bind(drop_status::SCHEDULED)
+        .bind(drop_status::ACTIVE)
+        .execute(&state.db)
+        .await;
+
+        // Drop取得
+        let drop: Option<Drop> = sqlx::query_as("SELECT * FROM drops WHERE drop_id = ? AND vendor_stable_id = ?")
+            .bind(drop_id)
+            .bind(&vendor_stable_id)
+            .fetch_optional(&state.db)
+            .await
+            .ok()
+            .flatten();
+
+        if let Some(d) = drop {
+            let purged = match purge_drop(&state, &d, now).await {
+                Ok(purged) => purged,
+                Err(e) => {
+                    warn!("Purge drop error: drop_id={}, error={:?}", drop_id, e);
+                    false
+                }
+            };
+            results.insert(drop_id.clone(), purged);
+        } else {
+            results.insert(drop_id.clone(), false);
+        }
+    }
+
+    Ok(Json(BatchDropResponse {
+        success: true,
+        results,
+    }))
+}
+
+// ========================================
+// Background Job (期限切れ自動処理)
+// ========================================
+
+/// 期限切れDropsを終了させる（定期実行用）
+pub async fn expire_drops(state: &Arc<AppState>) -> anyhow::Result<usize> {
+    let now = chrono::Utc::now().timestamp();
+
+    let result = sqlx::query(
+        "UPDATE drops SET status = ?, ended_at = ?, updated_at = ? WHERE end_at <= ? AND status IN (?, ?)"
+    )
+    .bind(drop_status::ENDED)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .bind(drop_status::SCHEDULED)
+    .bind(drop_status::ACTIVE)
+    .execute(&state.db)
+    .await?;
+
+    let count = result.rows_affected() as usize;
+    if count > 0 {
+        info!("Expired {} drops", count);
+    }
+    Ok(count)
+}
+
+/// 終了済みDropsを削除（定期実行用）
+/// PURGE_DRY_RUN 有効時は対象をログに出すだけで何も削除しない（戻り値は 0）
+pub async fn purge_ended_drops(state: &Arc<AppState>, grace_seconds: i64) -> anyhow::Result<usize> {
+    let now = chrono::Utc::now().timestamp();
+    let cutoff = now - grace_seconds;
+
+    // 削除対象取得（1回あたりの上限件数まで。残りは次回のジョブで処理）
+    let drops: Vec<Drop> = sqlx::query_as(
+        "SELECT * FROM drops WHERE status = ? AND ended_at IS NOT NULL AND ended_at <= ? ORDER BY ended_at ASC LIMIT ?"
+    )
+    .bind(drop_status::ENDED)
+    .bind(cutoff)
+    .bind(state.config.purge_max_per_tick)
+    .fetch_all(&state.db)
+    .await?;
+
+    // dry-run: 対象をログに出すだけで削除・ステータス更新はしない
+    if state.config.purge_dry_run {
+        for drop in &drops {
+            info!(
+                "[Purge dry-run] would purge drop_id={}, ended_at={:?}, dir={}, audio={}",
+                drop.drop_id,
+                drop.ended_at,
+                PathBuf::from(&state.base_data_dir).join("drops").join(&drop.drop_id).display(),
+                drop.audio_object_key
+            );
+        }
+        info!("[Purge dry-run] {} drop(s) would be purged (grace={}s)", drops.len(), grace_seconds);
+        return Ok(0);
+    }
+
+    // 同時実行数を制限して並列にパージ
+    let mut tasks = JoinSet::new();
+    let mut count = 0;
+    for drop in drops {
+        if tasks.len() >= state.config.purge_concurrency {
+            count += collect_purge_result(tasks.join_next().await);
+        }
+        let state = state.clone();
+        tasks.spawn(async move { purge_drop(&state, &drop, now).await });
+    }
+    while let Some(result) = tasks.join_next().await {
+        count += collect_purge_result(Some(result));
+    }
+
+    Ok(count)
+}
+
+/// 1件の Drop をパージ（ENDED のままの場合のみ PURGED に更新）
+/// ステータス更新と drop_claims 削除・音声 blob の参照解除を1トランザクションで行い、
+/// 更新できた（この呼び出しが確保した）場合のみコミット後にファイルを削除する
+async fn purge_drop(state: &Arc<AppState>, drop: &Drop, now: i64) -> anyhow::Result<bool> {
+    let blob_guard = state.blob_lock.lock().await;
+    let mut tx = state.db.begin().await?;
+
+    // PURGED更新（条件付き UPDATE で確保。並行パージや再開で状態が変わっていれば何もしない）
+    let result = sqlx::query(
+        "UPDATE drops SET status = ?, purged_at = ?, updated_at = ? WHERE drop_id = ? AND status = ?"
+    )
+    .bind(drop_status::PURGED)
+    .bind(now)
+    .bind(now)
+    .bind(&drop.drop_id)
+    .bind(drop_status::ENDED)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    // Claim削除
+    let claims = sqlx::query("DELETE FROM drop_claims WHERE drop_id = ?")
+        .bind(&drop.drop_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tombstones::record_tombstone(
+        &mut *tx,
+        tombstone_kind::DROP,
+        Some(&drop.vendor_stable_id),
+        &drop.drop_id,
+        &drop.env,
+        now * 1000,
+    )
+    .await?;
+
+    // 他の Drop と共有していなければ blob も削除
+    let blob_unreferenced = release_blob_ref(&mut tx, &drop.audio_object_key).await?;
+
+    tx.commit().await?;
+
+    if blob_unreferenced {
+        remove_blob_file(state, &drop.audio_object_key).await;
+    }
+    std::mem::drop(blob_guard);
+
+    // ファイル削除（ディレクトリが既に無い場合は成功扱い）
+    let dir = PathBuf::from(&state.base_data_dir).join("drops").join(&drop.drop_id);
+    let removed_files = match remove_drop_dir(&dir).await {
+        Ok(files) => files,
+        Err(e) => {
+            warn!("Failed to remove purged drop dir: drop_id={}, dir={}, error={}", drop.drop_id, dir.display(), e);
+            0
+        }
+    };
+    // audio（旧形式のみ、blob は Drop ディレクトリ外）+ (cover + cover_thumb) + cover WebP 派生
+    let audio_files = if is_blob_key(&drop.audio_object_key) { 0 } else { 1 };
+    let expected_files = audio_files
+        + if drop.cover_object_key.is_some() { 2 } else { 0 }
+        + if drop.cover_webp_object_key.is_some() { 1 } else { 0 };
+    if removed_files != expected_files {
+        warn!(
+            "Purge file count mismatch: drop_id={}, removed={}, expected={}",
+            drop.drop_id, removed_files, expected_files
+        );
+    }
+
+    info!(
+        "Purged drop: drop_id={}, files_removed={}/{}, claims_deleted={}",
+        drop.drop_id, removed_files, expected_files, claims.rows_affected()
+    );
+    Ok(true)
+}
+
+/// Drop ディレクトリを削除し、削除したファイル数を返す（存在しない場合は 0）
+async fn remove_drop_dir(dir: &std::path::Path) -> std::io::Result<usize> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut files = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await.is_ok_and(|t| t.is_file()) {
+            files += 1;
+        }
+    }
+
+    match fs::remove_dir_all(dir).await {
+        Ok(()) => Ok(files),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(files),
+        Err(e) => Err(e),
+    }
+}
+
+/// パージタスクの結果を集計（失敗はログのみ）
+fn collect_purge_result(
+    result: Option<Result<anyhow::Result<bool>, tokio::task::JoinError>>,
+) -> usize {
+    match result {
+        Some(Ok(Ok(true))) => 1,
+        Some(Ok(Ok(false))) | None => 0,
+        Some(Ok(Err(e))) => {
+            warn!("Purge drop error: {:?}", e);
+            0
+        }
+        Some(Err(e)) => {
+            warn!("Purge task panicked: {:?}", e);
+            0
+        }
+    }
+}
+
+// ========================================
+// Helper Functions
+// ========================================
+
+/// 開催中・開催予定の Drop 数が上限に達していれば 409
+/// 上限は vendors.max_active_drops → MAX_ACTIVE_DROPS_PER_VENDOR の順（0 は無制限）
+/// 確定判定は blob_lock を保持したまま挿入の直前に行うこと
+async fn check_active_drop_quota(
+    state: &AppState,
+    vendor_stable_id: &str,
+) -> Result<(), ApiError> {
+    let (quota, active): (i64, i64) = sqlx::query_as(r#"
+        SELECT
+            COALESCE((SELECT max_active_drops FROM vendors WHERE stable_id = ?), ?),
+            (SELECT COUNT(*) FROM drops WHERE vendor_stable_id = ? AND status IN (?, ?))
+    "#)
+    .bind(vendor_stable_id)
+    .bind(state.config.max_active_drops_per_vendor)
+    .bind(vendor_stable_id)
+    .bind(drop_status::SCHEDULED)
+    .bind(drop_status::ACTIVE)
+    .fetch_one(&state.db)
+    .await?;
+
+    if quota > 0 && active >= quota {
+        return Err(ApiError::Conflict(
+            format!(
+                "Active drop quota exceeded: vendor {} already has {} scheduled/active drops (max {})",
+                vendor_stable_id, active, quota
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// パージ前の Drop の音声合計に adding_bytes を加える（released_bytes は差し替えで解放される分）と上限を超える場合は 413
+/// 上限は vendors.max_drop_bytes → MAX_DROP_BYTES_PER_VENDOR の順（0 は無制限）。パージされた Drop は合計から外れる
+/// 確定判定は blob_lock を保持したまま書き込みの直前に行うこと
+async fn check_drop_storage_quota(
+    state: &AppState,
+    vendor_stable_id: &str,
+    adding_bytes: i64,
+    released_bytes: i64,
+) -> Result<(), ApiError> {
+    let (quota, used): (i64, i64) = sqlx::query_as(r#"
+        SELECT
+            COALESCE((SELECT max_drop_bytes FROM vendors WHERE stable_id = ?), ?),
+            (SELECT COALESCE(SUM(audio_size_bytes), 0) FROM drops WHERE vendor_stable_id = ? AND status != ?)
+    "#)
+    .bind(vendor_stable_id)
+    .bind(state.config.max_drop_bytes_per_vendor)
+    .bind(vendor_stable_id)
+    .bind(drop_status::PURGED)
+    .fetch_one(&state.db)
+    .await?;
+
+    let used = (used - released_bytes).max(0);
+    if quota > 0 && used + adding_bytes > quota {
+        return Err(ApiError::PayloadTooLarge(
+            format!(
+                "Drop storage quota exceeded: vendor {} has {} bytes remaining (used {} of {}), audio is {} bytes",
+                vendor_stable_id,
+                (quota - used).max(0),
+                used,
+                quota,
+                adding_bytes
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Drop に紐付ける Artist を検証（存在しない・削除済みは 400）
+/// ENFORCE_ARTIST_OWNER 有効時は Artist と Vendor の owner の一致も確認する（どちらかが未設定なら確認しない）
+async fn check_drop_artist(
+    state: &AppState,
+    artist_stable_id: &str,
+    vendor_stable_id: &str,
+    vendor_owner: Option<&str>,
+) -> Result<(), ApiError> {
+    let artist_owner: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT owner FROM artists WHERE stable_id = ? AND is_alive = 1"
+    )
+    .bind(artist_stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some((artist_owner,)) = artist_owner else {
+        return Err(ApiError::invalid_fields(
+            format!("Artist not found: {}", artist_stable_id),
+            &["artist_stable_id"],
+        ));
+    };
+
+    if state.config.enforce_artist_owner {
+        if let (Some(artist_owner), Some(vendor_owner)) = (artist_owner.as_deref(), vendor_owner) {
+            if !artist_owner.eq_ignore_ascii_case(vendor_owner) {
+                return Err(ApiError::Forbidden(format!(
+                    "Artist {} is not owned by the owner of vendor {}",
+                    artist_stable_id, vendor_stable_id
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// idempotency_key で作成済みのDropを検索
+async fn find_drop_by_idempotency_key(
+    state: &Arc<AppState>,
+    vendor_stable_id: &str,
+    key: &str,
+) -> Result<Option<Drop>, ApiError> {
+    Ok(sqlx::query_as("SELECT * FROM drops WHERE vendor_stable_id = ? AND idempotency_key = ?")
+        .bind(vendor_stable_id)
+        .bind(key)
+        .fetch_optional(&state.db)
+        .await?)
+}
+
+/// ffmpeg で先頭30秒・低ビットレートの MP3 プレビューを生成し、object key を返す
+/// ffmpeg が無い・変換に失敗した場合は None（プレビュー無しとして扱う）
+async fn generate_preview(state: &Arc<AppState>, drop_id: &str, audio_path: &std::path::Path) -> Option<String> {
+    let preview_path = audio_path.with_file_name(PREVIEW_FILE_NAME);
+    let output = tokio::process::Command::new(&state.config.ffmpeg_path)
+        .args(["-y", "-v", "error", "-i"])
+        .arg(audio_path)
+        .args(["-t", PREVIEW_SECONDS, "-vn", "-ac", "2", "-b:a", PREVIEW_BITRATE, "-f", "mp3"])
+        .arg(&preview_path)
+        .output()
+        .await;
+
+    match output {
+        Ok(out) if out.status.success() => {
+            info!("Preview generated: drop_id={}", drop_id);
+            Some(format!("{}/{}", drop_id, PREVIEW_FILE_NAME))
+        }
+        Ok(out) => {
+            warn!(
+                "Preview generation failed: drop_id={}, stderr={}",
+                drop_id,
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+            let _ = fs::remove_file(&preview_path).await;
+            None
+        }
+        Err(e) => {
+            warn!("Preview generation skipped (ffmpeg unavailable): drop_id={}, error={}", drop_id, e);
+            let _ = fs::remove_file(&preview_path).await;
+            None
+        }
+    }
+}
+
+fn generate_drop_id() -> String {
+    let random_bytes: [u8; 5] = rand::thread_rng().gen();
+    let encoded = base32::encode(base32::Alphabet::Crockford, &random_bytes);
+    format!("DROP_{}", &encoded[..8])
+}
+
+/// 現在時刻での Drop ステータス（SCHEDULED / ACTIVE は期間から判定し直す）
+fn effective_status(status: i32, start_at: i64, end_at: i64, now: i64) -> i32 {
+    match status {
+        drop_status::SCHEDULED | drop_status::ACTIVE if now >= end_at => drop_status::ENDED,
+        drop_status::SCHEDULED | drop_status::ACTIVE if now < start_at => drop_status::SCHEDULED,
+        drop_status::SCHEDULED | drop_status::ACTIVE => drop_status::ACTIVE,
+        other => other,
+    }
+}
+
+/// Claim できない理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClaimBlock {
+    /// ENDED / PURGED（reason は expired）
+    Ended,
+    NotStarted,
+    Expired,
+    SoldOut,
+    AlreadyClaimed,
+}
+
+impl ClaimBlock {
+    /// ユーザーに依存しない判定（ステータス → 期限 → 在庫の順）
+    fn check(drop: &Drop, now: i64) -> Option<Self> {
+        if drop.status == drop_status::ENDED || drop.status == drop_status::PURGED {
+            Some(Self::Ended)
+        } else if now < drop.start_at {
+            Some(Self::NotStarted)
+        } else if now >= drop.end_at {
+            Some(Self::Expired)
+        } else if drop.claimed_count >= drop.max_claims {
+            Some(Self::SoldOut)
+        } else {
+            None
+        }
+    }
+
+    /// GET /claimable の reason
+    fn reason(self) -> &'static str {
+        match self {
+            Self::Ended | Self::Expired => "expired",
+            Self::NotStarted => "not_started",
+            Self::SoldOut => "sold_out",
+            Self::AlreadyClaimed => "already_claimed",
+        }
+    }
+
+    /// POST /claim のエラーメッセージ
+    fn message(self) -> &'static str {
+        match self {
+            Self::Ended => "Drop has ended",
+            Self::NotStarted => "Drop has not started yet",
+            Self::Expired => "Drop has expired",
+            Self::SoldOut => "No more claims available",
+            Self::AlreadyClaimed => "Already claimed",
+        }
+    }
+}
+
+/// ユーザーがこの Drop を受け取り済みか
+async fn has_claimed(state: &AppState, drop_id: &str, user_id: &str) -> Result<bool, ApiError> {
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT claim_id FROM drop_claims WHERE drop_id = ? AND user_id = ?"
+    )
+    .bind(drop_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
+    Ok(existing.is_some())
+}
+
+/// tx_digest の形式チェック（Base58 エンコードされた 32 バイトのダイジェスト）
+fn is_valid_tx_digest(tx_digest: &str) -> bool {
+    bs58::decode(tx_digest)
+        .into_vec()
+        .is_ok_and(|bytes| bytes.len() == 32)
+}
+
+/// 拡張子から音声の MIME タイプを推測
+fn guess_audio_mime(ext: &str) -> &'static str {
+    match ext {
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "aac" => "audio/aac",
+        "m4a" => "audio/mp4",
+        _ => "audio/mpeg",
+    }
+}
+
+/// Drop 作成時の音声の受け取り元
+enum AudioSource {
+    /// multipart の audio フィールド
+    Inline(Vec<u8>),
+    /// 完了済みの再開可能アップロード（upload_id）
+    Upload(UploadSession),
+}
+
+impl AudioSource {
+    fn size_bytes(&self) -> i64 {
+        match self {
+            Self::Inline(data) => data.len() as i64,
+            Self::Upload(upload) => upload.total_size,
+        }
+    }
+}
+
+/// 音声 blob を置くディレクトリ（drops/ 配下）
+const BLOB_DIR: &str = "_blobs";
+
+/// Drop ディレクトリに書いた音声を content-addressed blob（drops/_blobs/<sha256>.<ext>）に移す
+/// 同じ内容の blob が既にあれば書き込まずに一時ファイルを削除する
+/// 戻り値は (object_key, 新規に配置したか)。呼び出し側は state.blob_lock を保持すること
+async fn place_audio_blob(
+    state: &AppState,
+    staged_path: &std::path::Path,
+    sha256: &str,
+    ext: &str,
+) -> std::io::Result<(String, bool)> {
+    let drops_dir = PathBuf::from(&state.base_data_dir).join("drops");
+
+    let existing: Option<(String,)> = sqlx::query_as("SELECT object_key FROM audio_blobs WHERE sha256 = ?")
+        .bind(sha256)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(std::io::Error::other)?;
+    let object_key = existing.map_or_else(|| format!("{}/{}.{}", BLOB_DIR, sha256, ext), |(key,)| key);
+    let blob_path = drops_dir.join(&object_key);
+
+    if fs::try_exists(&blob_path).await? {
+        let _ = fs::remove_file(staged_path).await;
+        return Ok((object_key, false));
+    }
+
+    fs::create_dir_all(drops_dir.join(BLOB_DIR)).await?;
+    fs::rename(staged_path, &blob_path).await?;
+    Ok((object_key, true))
+}
+
+/// Drop 作成の必須テキストフィールドのうち未指定のもの
+fn missing_drop_text_fields(
+    vendor_stable_id: &Option<String>,
+    artist_name: &Option<String>,
+    title: &Option<String>,
+    end_at: Option<i64>,
+    max_claims: Option<i64>,
+) -> Vec<&'static str> {
+    [
+        ("vendor_stable_id", vendor_stable_id.is_none()),
+        ("artist_name", artist_name.is_none()),
+        ("title", title.is_none()),
+        ("end_at", end_at.is_none()),
+        ("max_claims", max_claims.is_none()),
+    ]
+    .into_iter()
+    .filter_map(|(name, is_missing)| is_missing.then_some(name))
+    .collect()
+}
+
+/// Claim パスコードの長さ上限（文字数）
+const MAX_CLAIM_PASSCODE_CHARS: usize = 128;
+
+/// Claim パスコードの検証（前後の空白は不可、128文字以内）
+fn validate_claim_passcode(passcode: &str) -> Result<(), String> {
+    if passcode.trim() != passcode {
+        return Err("claim_passcode must not have leading or trailing whitespace".to_string());
+    }
+    if passcode.chars().count() > MAX_CLAIM_PASSCODE_CHARS {
+        return Err(format!("claim_passcode must be at most {} characters", MAX_CLAIM_PASSCODE_CHARS));
+    }
+    Ok(())
+}
+
+/// 保存用のパスコードハッシュ（drop_id を混ぜて Drop 間で同じ値にならないようにする）
+fn hash_claim_passcode(drop_id: &str, passcode: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}:{}", drop_id, passcode).as_bytes()))
+}
+
+/// 内容によらず全バイトを比較する（一致位置による時間差を出さない）
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 開催期間の検証（end_at は未来かつ start_at より後、期間は max_window_secs 以内。0 は無制限）
+fn validate_drop_window(
+    start_at: i64,
+    end_at: i64,
+    now: i64,
+    max_window_secs: i64,
+) -> Result<(), (String, &'static [&'static str])> {
+    if end_at <= now {
+        return Err((format!("end_at must be in the future (end_at={}, now={})", end_at, now), &["end_at"]));
+    }
+    if end_at <= start_at {
+        return Err((
+            format!("end_at must be after start_at (start_at={}, end_at={})", start_at, end_at),
+            &["start_at", "end_at"],
+        ));
+    }
+    if max_window_secs > 0 && end_at - start_at > max_window_secs {
+        return Err((
+            format!(
+                "Drop window too long: {} seconds (at most {})",
+                end_at - start_at,
+                max_window_secs
+            ),
+            &["start_at", "end_at"],
+        ));
+    }
+    Ok(())
+}
+
+/// Drop ディレクトリと新規配置した blob の所有者を変更（UPLOAD_CHOWN）
+async fn apply_drop_owner(state: &AppState, dir: &std::path::Path, audio_object_key: &str, blob_created: bool) {
+    let owner = state.config.upload_chown.as_deref();
+    storage::apply_upload_owner(owner, dir, true).await;
+    if blob_created {
+        let blob_path = PathBuf::from(&state.base_data_dir).join("drops").join(audio_object_key);
+        storage::apply_upload_owner(owner, &blob_path, false).await;
+    }
+}
+
+/// blob の参照カウントを1増やす（初回は行を作成）
+async fn acquire_blob_ref<'e, E>(
+    executor: E,
+    sha256: &str,
+    object_key: &str,
+    size_bytes: i64,
+    now: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(r#"
+        INSERT INTO audio_blobs (sha256, object_key, size_bytes, ref_count, created_at)
+        VALUES (?, ?, ?, 1, ?)
+        ON CONFLICT(sha256) DO UPDATE SET ref_count = ref_count + 1
+    "#)
+    .bind(sha256)
+    .bind(object_key)
+    .bind(size_bytes)
+    .bind(now)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// blob の参照カウントを1減らし、0 になったら行を削除して true を返す（ファイル削除は commit 後に呼び出し側で）
+/// blob 以外の object_key（旧形式の <drop_id>/audio.<ext>）は何もしない
+async fn release_blob_ref(conn: &mut sqlx::SqliteConnection, object_key: &str) -> Result<bool, sqlx::Error> {
+    if !is_blob_key(object_key) {
+        return Ok(false);
+    }
+    sqlx::query("UPDATE audio_blobs SET ref_count = ref_count - 1 WHERE object_key = ?")
+        .bind(object_key)
+        .execute(&mut *conn)
+        .await?;
+    let deleted = sqlx::query("DELETE FROM audio_blobs WHERE object_key = ? AND ref_count <= 0")
+        .bind(object_key)
+        .execute(&mut *conn)
+        .await?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+/// 参照されなくなった blob ファイルを削除（ベストエフォート）
+async fn remove_blob_file(state: &AppState, object_key: &str) {
+    let path = PathBuf::from(&state.base_data_dir).join("drops").join(object_key);
+    match fs::remove_file(&path).await {
+        Ok(()) => info!("Removed unreferenced audio blob: {}", object_key),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove audio blob {:?}: {}", path, e),
+    }
+}
+
+fn is_blob_key(object_key: &str) -> bool {
+    object_key.starts_with(&format!("{}/", BLOB_DIR))
+}
+
+/// レシート署名鍵（未設定時は 503）
+fn receipt_signing_key(state: &AppState) -> Result<SigningKey, ApiError> {
+    state
+        .config
+        .receipt_signing_key
+        .as_ref()
+        .map(SigningKey::from_bytes)
+        .ok_or_else(|| {
+            ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Receipt signing is not configured".to_string())
+        })
+}
+
+/// 配信できなかったダウンロード分の回数を戻す（ベストエフォート）
+async fn refund_download_count(state: &AppState, claim_id: &str) {
+    let _ = sqlx::query("UPDATE drop_claims SET download_count = download_count - 1 WHERE claim_id = ?")
+        .bind(claim_id)
+        .execute(&state.db)
+        .await;
+}
+
+/// ファイルを開き、サイズと合わせて返す
+async fn open_with_len(path: &std::path::Path) -> std::io::Result<(fs::File, u64)> {
+    let file = fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    Ok((file, len))
+}
+
+fn compute_sha256(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use axum::http::Method;
+
+    use crate::test_support::{drop_form, MultipartForm, OwnerKey, TestApp, TestResponse, ADMIN_TOKEN};
+
+    /// Vendor の owner
+    fn owner() -> OwnerKey {
+        OwnerKey::new(1)
+    }
+
+    /// どの Vendor も所有していない鍵
+    fn stranger() -> OwnerKey {
+        OwnerKey::new(2)
+    }
+
+    /// 管理APIを有効にしたアプリと、owner() が所有する Vendor
+    async fn admin_app() -> (TestApp, String) {
+        let app = TestApp::with_config(|c| {
+            c.admin_token = Some(ADMIN_TOKEN.to_string());
+            c.rate_limit_burst = 0;
+        })
+        .await;
+        let vendor = app.create_vendor(Some(&owner().address())).await;
+        (app, vendor)
+    }
+
+    /// Claim して claim_id（ダウンロードトークン）を返す
+    /// start_at が未来（SCHEDULED）の Drop を作成
+    async fn create_scheduled_drop(app: &TestApp, vendor: &str) -> String {
+        let start_at = chrono::Utc::now().timestamp() + 600;
+        let form = drop_form(vendor, 10).text("start_at", &start_at.to_string());
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        res.json()["drop"]["drop_id"].as_str().unwrap().to_string()
+    }
+
+    fn audio_form(data: &[u8]) -> MultipartForm {
+        MultipartForm::new().file("audio", "fixed.mp3", "audio/mpeg", data)
+    }
+
+    async fn claim_id(app: &TestApp, drop_id: &str, user_id: &str) -> String {
+        let res = app.claim(drop_id, user_id).await;
+        assert_eq!(res.status, 200, "claim: {}", res.text());
+        res.json()["claim_id"].as_str().unwrap().to_string()
+    }
+
+    /// Drop を終了済み（ended_at は ended_secs_ago 秒前）にする
+    async fn end_drop(app: &TestApp, drop_id: &str, ended_secs_ago: i64) {
+        let ended_at = chrono::Utc::now().timestamp() - ended_secs_ago;
+        sqlx::query("UPDATE drops SET status = ?, ended_at = ? WHERE drop_id = ?")
+            .bind(drop_status::ENDED)
+            .bind(ended_at)
+            .bind(drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+    }
+
+    async fn drop_status_of(app: &TestApp, drop_id: &str) -> i32 {
+        let (status,): (i32,) = sqlx::query_as("SELECT status FROM drops WHERE drop_id = ?")
+            .bind(drop_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        status
+    }
+
+    /// 一覧レスポンスの drop_id（返却順）
+    fn drop_ids(body: &serde_json::Value) -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["drop_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn download(app: &TestApp, drop_id: &str, token: &str) -> TestResponse {
+        app.get(&format!("/api/drops/{}/download?token={}", drop_id, token)).await
+    }
+
+    #[tokio::test]
+    async fn claim_records_tx_digest() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let digest = bs58::encode([3u8; 32]).into_string();
+
+        let res = app
+            .post_json(
+                &format!("/api/drops/{}/claim", drop_id),
+                json!({ "user_id": "alice", "tx_digest": digest }),
+            )
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["tx_digest"], digest.as_str());
+
+        let claim_id = body["claim_id"].as_str().unwrap();
+        let status = app.get(&format!("/api/drops/{}/claims/{}", drop_id, claim_id)).await.json();
+        assert_eq!(status["claim"]["tx_digest"], digest.as_str());
+
+        // 省略時は null
+        let body = app.claim(&drop_id, "bob").await.json();
+        assert!(body["tx_digest"].is_null());
+    }
+
+    #[tokio::test]
+    async fn claim_rejects_malformed_tx_digest() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        for digest in ["not-base58-0OIl", &bs58::encode([3u8; 16]).into_string()] {
+            let res = app
+                .post_json(
+                    &format!("/api/drops/{}/claim", drop_id),
+                    json!({ "user_id": "alice", "tx_digest": digest }),
+                )
+                .await;
+            assert_eq!(res.status, 400, "{}: {}", digest, res.text());
+        }
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(drop["drop"]["claimed_count"], 0);
+    }
+
+    #[tokio::test]
+    async fn downloads_are_recorded_in_drop_stats() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let alice = claim_id(&app, &drop_id, "alice").await;
+        let bob = claim_id(&app, &drop_id, "bob").await;
+        let _carol = claim_id(&app, &drop_id, "carol").await;
+
+        let first = download(&app, &drop_id, &alice).await;
+        assert_eq!(first.status, 200, "{}", first.text());
+        assert_eq!(download(&app, &drop_id, &alice).await.status, 200);
+        assert_eq!(download(&app, &drop_id, &bob).await.status, 200);
+
+        let stats = app.get(&format!("/api/drops/{}/stats", drop_id)).await.json();
+        assert_eq!(stats["claim_count"], 3);
+        assert_eq!(stats["download_count"], 3);
+        assert_eq!(stats["unique_downloaders"], 2);
+        assert_eq!(stats["total_bytes_sent"], 3 * first.body.len() as i64);
+
+        assert_eq!(app.get("/api/drops/DROP_MISSING/stats").await.status, 404);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_claims_never_exceed_max_claims() {
+        // 同一 IP からの同時リクエストをレート制限で落とさない
+        let app = std::sync::Arc::new(TestApp::with_config(|c| c.rate_limit_burst = 0).await);
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+
+        let tasks: Vec<_> = (0..20)
+            .map(|i| {
+                let app = app.clone();
+                let drop_id = drop_id.clone();
+                tokio::spawn(async move { app.claim(&drop_id, &format!("user-{}", i)).await.status })
+            })
+            .collect();
+        let mut ok = 0;
+        for task in tasks {
+            let status = task.await.unwrap();
+            match status.as_u16() {
+                200 => ok += 1,
+                400 => {}
+                other => panic!("unexpected status {}", other),
+            }
+        }
+        assert_eq!(ok, 5);
+
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(drop["drop"]["claimed_count"], 5);
+        assert_eq!(drop["drop"]["remaining_claims"], 0);
+        let (claims,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drop_claims WHERE drop_id = ?")
+            .bind(&drop_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(claims, 5);
+    }
+
+    #[tokio::test]
+    async fn duplicate_claim_does_not_consume_a_slot() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 2).await;
+
+        assert_eq!(app.claim(&drop_id, "alice").await.status, 200);
+        assert_eq!(app.claim(&drop_id, "alice").await.status, 400);
+        assert_eq!(app.claim(&drop_id, "bob").await.status, 200);
+        assert_eq!(app.claim(&drop_id, "carol").await.status, 400);
+
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(drop["drop"]["claimed_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn purge_processes_at_most_max_per_tick() {
+        let app = TestApp::with_config(|c| {
+            c.purge_max_per_tick = 3;
+            c.purge_concurrency = 2;
+        })
+        .await;
+        let vendor = app.create_vendor(None).await;
+        let mut drop_ids = Vec::new();
+        for _ in 0..5 {
+            let drop_id = app.create_drop(&vendor, 1).await;
+            end_drop(&app, &drop_id, 3600).await;
+            drop_ids.push(drop_id);
+        }
+        // 猶予期間内の Drop は対象外
+        let recent = app.create_drop(&vendor, 1).await;
+        end_drop(&app, &recent, 10).await;
+
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 3);
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 2);
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 0);
+
+        for drop_id in &drop_ids {
+            assert_eq!(drop_status_of(&app, drop_id).await, drop_status::PURGED);
+            assert!(!app.data_path(format!("drops/{}", drop_id)).exists());
+        }
+        assert_eq!(drop_status_of(&app, &recent).await, drop_status::ENDED);
+    }
+
+    #[tokio::test]
+    async fn vendor_drop_list_filters_by_created_range() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let mut ids = Vec::new();
+        for created_at in [1_000, 2_000, 3_000] {
+            let drop_id = app.create_drop(&vendor, 1).await;
+            sqlx::query("UPDATE drops SET created_at = ? WHERE drop_id = ?")
+                .bind(created_at)
+                .bind(&drop_id)
+                .execute(&app.state.db)
+                .await
+                .unwrap();
+            ids.push(drop_id);
+        }
+        let list = |query: &str| format!("/api/vendors/{}/drops?{}", vendor, query);
+
+        assert_eq!(drop_ids(&app.get(&list("created_after=2000")).await.json()), [ids[2].clone(), ids[1].clone()]);
+        assert_eq!(drop_ids(&app.get(&list("created_before=2000")).await.json()), [ids[1].clone(), ids[0].clone()]);
+        assert_eq!(
+            drop_ids(&app.get(&list("created_after=1500&created_before=2500")).await.json()),
+            [ids[1].clone()]
+        );
+        assert_eq!(app.get(&list("created_after=3000&created_before=1000")).await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn purge_deletes_claims_and_tolerates_missing_dir() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+        claim_id(&app, &drop_id, "alice").await;
+        claim_id(&app, &drop_id, "bob").await;
+        end_drop(&app, &drop_id, 3600).await;
+        // ディレクトリだけ先に消えている状態
+        std::fs::remove_dir_all(app.data_path(format!("drops/{}", drop_id))).unwrap();
+
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 1);
+        assert_eq!(drop_status_of(&app, &drop_id).await, drop_status::PURGED);
+        let (claims,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drop_claims WHERE drop_id = ?")
+            .bind(&drop_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(claims, 0);
+    }
+
+    async fn fetch_drop(app: &TestApp, drop_id: &str) -> Drop {
+        sqlx::query_as("SELECT * FROM drops WHERE drop_id = ?")
+            .bind(drop_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn purge_claims_the_drop_before_deleting_files() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let now = chrono::Utc::now().timestamp();
+
+        // 同じ Drop を並行にパージしても確保できるのは1回だけ
+        let raced = app.create_drop(&vendor, 1).await;
+        end_drop(&app, &raced, 3600).await;
+        let drop = fetch_drop(&app, &raced).await;
+        let (a, b) = tokio::join!(purge_drop(&app.state, &drop, now), purge_drop(&app.state, &drop, now));
+        assert_eq!([a.unwrap(), b.unwrap()].iter().filter(|p| **p).count(), 1);
+        assert_eq!(drop_status_of(&app, &raced).await, drop_status::PURGED);
+        assert!(!app.data_path(format!("drops/{}", raced)).exists());
+
+        // 読み取り後に再開された Drop はファイルも claim も残す
+        let reopened = app.create_drop(&vendor, 1).await;
+        claim_id(&app, &reopened, "alice").await;
+        end_drop(&app, &reopened, 3600).await;
+        let drop = fetch_drop(&app, &reopened).await;
+        sqlx::query("UPDATE drops SET status = ? WHERE drop_id = ?")
+            .bind(drop_status::ACTIVE)
+            .bind(&reopened)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        assert!(!purge_drop(&app.state, &drop, now).await.unwrap());
+        assert_eq!(drop_status_of(&app, &reopened).await, drop_status::ACTIVE);
+        assert!(app.data_path(format!("drops/{}", reopened)).exists());
+        let (claims,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drop_claims WHERE drop_id = ?")
+            .bind(&reopened)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(claims, 1);
+    }
+
+    #[tokio::test]
+    async fn create_drop_with_same_idempotency_key_returns_existing_drop() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let create = |key: &'static str| {
+            let form = drop_form(&vendor, 3).text("idempotency_key", key);
+            app.send_form(Method::POST, "/api/drops", form, &[])
+        };
+
+        let first = create("retry-1").await.json();
+        let second = create("retry-1").await.json();
+        assert_eq!(first["drop"]["drop_id"], second["drop"]["drop_id"]);
+        let other = create("retry-2").await.json();
+        assert_ne!(first["drop"]["drop_id"], other["drop"]["drop_id"]);
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops WHERE vendor_stable_id = ?")
+            .bind(&vendor)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_is_scoped_per_vendor() {
+        let app = TestApp::new().await;
+        let vendor_a = app.create_vendor(None).await;
+        let vendor_b = app.create_vendor(None).await;
+
+        let a = app
+            .send_form(Method::POST, "/api/drops", drop_form(&vendor_a, 1).text("idempotency_key", "k"), &[])
+            .await
+            .json();
+        let b = app
+            .send_form(Method::POST, "/api/drops", drop_form(&vendor_b, 1).text("idempotency_key", "k"), &[])
+            .await
+            .json();
+        assert_ne!(a["drop"]["drop_id"], b["drop"]["drop_id"]);
+        assert_eq!(b["drop"]["vendor_stable_id"], vendor_b.as_str());
+    }
+
+    #[tokio::test]
+    async fn create_drop_checks_owner_when_enforced() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        // アドレスの大文字小文字は区別しない
+        let vendor = app.create_vendor(Some(&owner().address().to_uppercase())).await;
+        let unsigned = [("x-owner-address", owner().address())];
+        let cases = [
+            (vec![], 401),
+            (unsigned.to_vec(), 401),
+            (app.owner_headers(&stranger()).await.0, 403),
+            (app.owner_headers(&owner()).await.0, 200),
+        ];
+        for (headers, expected) in cases {
+            let pairs: Vec<(&str, &str)> = headers.iter().map(|(n, v)| (*n, v.as_str())).collect();
+            let res = app.send_form(Method::POST, "/api/drops", drop_form(&vendor, 1), &pairs).await;
+            assert_eq!(res.status, expected, "{:?}: {}", headers, res.text());
+        }
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops")
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn claim_list_is_paginated_for_owner_and_admin() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        for i in 0..5 {
+            claim_id(&app, &drop_id, &format!("user-{}", i)).await;
+        }
+        let uri = |query: &str| format!("/api/drops/{}/claims?{}", drop_id, query);
+
+        let res = app.get_with(&uri("limit=2"), &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let page = res.json();
+        assert_eq!(page["meta"]["total"], 5);
+        assert_eq!(page["meta"]["limit"], 2);
+        assert_eq!(page["data"].as_array().unwrap().len(), 2);
+        // 移行期間中は旧フィールドも返す
+        assert_eq!(page["drop_id"], drop_id.as_str());
+        assert_eq!(page["claims"], page["data"]);
+
+        let last = app.get_with(&uri("limit=2&offset=4"), &[("x-admin-token", ADMIN_TOKEN)]).await.json();
+        assert_eq!(last["data"].as_array().unwrap().len(), 1);
+        assert_eq!(last["meta"]["offset"], 4);
+    }
+
+    #[tokio::test]
+    async fn claim_list_requires_owner_or_admin() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let uri = format!("/api/drops/{}/claims", drop_id);
+
+        assert_eq!(app.get(&uri).await.status, 401);
+        assert_eq!(app.get_with(&uri, &app.owner_headers(&stranger()).await.pairs()).await.status, 403);
+        assert_eq!(app.get_with(&uri, &[("x-admin-token", "wrong")]).await.status, 401);
+        assert_eq!(app.get_with("/api/drops/DROP_MISSING/claims", &[("x-admin-token", ADMIN_TOKEN)]).await.status, 404);
+    }
+
+    #[tokio::test]
+    async fn audio_can_be_replaced_before_start() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = create_scheduled_drop(&app, &vendor).await;
+        assert_eq!(drop_status_of(&app, &drop_id).await, drop_status::SCHEDULED);
+        sqlx::query("UPDATE drops SET updated_at = updated_at - 100 WHERE drop_id = ?")
+            .bind(&drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let before = app.get(&format!("/api/drops/{}", drop_id)).await.json()["drop"].clone();
+
+        let new_audio = b"ID3 the corrected master recording";
+        let uri = format!("/api/drops/{}/audio", drop_id);
+        let res = app.send_form(Method::PUT, &uri, audio_form(new_audio), &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let drop = &res.json()["drop"];
+        assert_eq!(drop["drop_id"], drop_id.as_str());
+        assert_eq!(drop["audio_sha256"], hex::encode(Sha256::digest(new_audio)));
+        assert_eq!(drop["audio_size_bytes"], new_audio.len());
+        assert_eq!(drop["audio_mime"], "audio/mpeg");
+        assert!(drop["updated_at"].as_i64() > before["updated_at"].as_i64());
+        assert_eq!(drop["start_at"], before["start_at"]);
+
+        // 他人は差し替えられない
+        let res = app.send_form(Method::PUT, &uri, audio_form(b"ID3 x"), &app.owner_headers(&stranger()).await.pairs()).await;
+        assert_eq!(res.status, 403);
+        let res = app.send_form(Method::PUT, &uri, audio_form(b"ID3 x"), &[]).await;
+        assert_eq!(res.status, 401);
+    }
+
+    #[tokio::test]
+    async fn audio_replace_is_rejected_after_start() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let uri = format!("/api/drops/{}/audio", drop_id);
+        let res = app.send_form(Method::PUT, &uri, audio_form(b"ID3 late"), &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 409, "{}", res.text());
+
+        end_drop(&app, &drop_id, 10).await;
+        let res = app.send_form(Method::PUT, &uri, audio_form(b"ID3 late"), &[("x-admin-token", ADMIN_TOKEN)]).await;
+        assert_eq!(res.status, 409);
+    }
+
+    async fn set_end_at(app: &TestApp, drop_id: &str, end_at: i64) {
+        sqlx::query("UPDATE drops SET end_at = ? WHERE drop_id = ?")
+            .bind(end_at)
+            .bind(drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn drop_feed_orders_active_drops_across_vendors_by_end_at() {
+        let app = TestApp::new().await;
+        let vendor_a = app.create_vendor(None).await;
+        let vendor_b = app.create_vendor(None).await;
+        let now = chrono::Utc::now().timestamp();
+
+        let late = app.create_drop(&vendor_a, 5).await;
+        let soon = app.create_drop(&vendor_b, 5).await;
+        let middle = app.create_drop(&vendor_a, 5).await;
+        set_end_at(&app, &late, now + 3000).await;
+        set_end_at(&app, &soon, now + 1000).await;
+        set_end_at(&app, &middle, now + 2000).await;
+        claim_id(&app, &soon, "user-1").await;
+
+        // 未開始・パージ済みは含まない
+        create_scheduled_drop(&app, &vendor_b).await;
+        let purged = app.create_drop(&vendor_b, 5).await;
+        sqlx::query("UPDATE drops SET status = ? WHERE drop_id = ?")
+            .bind(drop_status::PURGED)
+            .bind(&purged)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+
+        let body = app.get("/api/drops").await.json();
+        assert_eq!(drop_ids(&body), vec![soon.clone(), middle.clone(), late.clone()]);
+        assert_eq!(body["meta"]["total"], 3);
+        let first = &body["data"][0];
+        assert_eq!(first["remaining_claims"], 4);
+        let remaining = first["remaining_seconds"].as_i64().unwrap();
+        assert!((990..=1000).contains(&remaining), "{}", remaining);
+
+        let page = app.get("/api/drops?limit=1&offset=1").await.json();
+        assert_eq!(drop_ids(&page), vec![middle]);
+        assert_eq!(page["meta"]["total"], 3);
+
+        let purged_only = app.get(&format!("/api/drops?status={}", drop_status::PURGED)).await.json();
+        assert!(drop_ids(&purged_only).is_empty());
+    }
+
+    #[tokio::test]
+    async fn claim_download_limit_rejects_extra_downloads() {
+        let (app, vendor) = admin_app().await;
+        let form = drop_form(&vendor, 10).text("max_downloads_per_claim", "2");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let drop_id = res.json()["drop"]["drop_id"].as_str().unwrap().to_string();
+
+        let token = claim_id(&app, &drop_id, "user-1").await;
+        assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+        assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+        let third = download(&app, &drop_id, &token).await;
+        assert_eq!(third.status, 429, "{}", third.text());
+        assert_eq!(third.json()["success"], false);
+
+        // 上限は Claim ごと
+        let other = claim_id(&app, &drop_id, "user-2").await;
+        assert_eq!(download(&app, &drop_id, &other).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn downloads_are_unlimited_by_default_and_limit_must_be_positive() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+        for _ in 0..4 {
+            assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+        }
+
+        let form = drop_form(&vendor, 10).text("max_downloads_per_claim", "0");
+        assert_eq!(app.send_form(Method::POST, "/api/drops", form, &[]).await.status, 400);
+    }
+
+    fn error_fields(res: &TestResponse) -> Vec<String> {
+        res.json()["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn create_drop_reports_every_missing_field() {
+        let app = TestApp::new().await;
+        let res = app.send_form(Method::POST, "/api/drops", MultipartForm::new(), &[]).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(
+            error_fields(&res),
+            vec!["vendor_stable_id", "artist_name", "title", "end_at", "max_claims", "audio"]
+        );
+        assert_eq!(res.json()["success"], false);
+
+        let form = MultipartForm::new().text("title", "Only Title").text("max_claims", "3");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(error_fields(&res), vec!["vendor_stable_id", "artist_name", "end_at", "audio"]);
+    }
+
+    /// ffmpeg の代わりに引数を出力ファイル（最後の引数）へ書き出すスクリプトを使うアプリ
+    async fn app_with_fake_ffmpeg(script_body: &str) -> TestApp {
+        let script_body = script_body.to_string();
+        TestApp::with_config(move |c| {
+            use std::os::unix::fs::PermissionsExt;
+            let script = std::path::PathBuf::from(&c.base_data_dir).join("fake-ffmpeg.sh");
+            std::fs::write(&script, format!("#!/bin/sh\n{}\n", script_body)).unwrap();
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+            c.ffmpeg_path = script.to_string_lossy().into_owned();
+            c.rate_limit_burst = 0;
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn preview_is_generated_on_create_and_served_without_token() {
+        let app = app_with_fake_ffmpeg(r#"for last; do :; done; printf 'preview %s' "$*" > "$last""#).await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+        assert!(app.data_path(format!("drops/{}/preview.mp3", drop_id)).exists());
+
+        let res = app.get(&format!("/api/drops/{}/preview", drop_id)).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.header("content-type"), Some("audio/mpeg"));
+        let args = res.text();
+        assert!(args.starts_with("preview "), "{}", args);
+        assert!(args.contains("-t 30"), "{}", args);
+    }
+
+    #[tokio::test]
+    async fn preview_is_404_when_generation_fails_or_ffmpeg_is_missing() {
+        let app = app_with_fake_ffmpeg("echo 'unsupported codec' >&2; exit 1").await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+        assert!(!app.data_path(format!("drops/{}/preview.mp3", drop_id)).exists());
+        assert_eq!(app.get(&format!("/api/drops/{}/preview", drop_id)).await.status, 404);
+
+        // 既定のテスト設定では ffmpeg が存在しない
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+        assert_eq!(app.get(&format!("/api/drops/{}/preview", drop_id)).await.status, 404);
+        assert_eq!(app.get("/api/drops/DROP_MISSING/preview").await.status, 404);
+    }
+
+    async fn try_create_drop(app: &TestApp, vendor: &str) -> TestResponse {
+        app.send_form(Method::POST, "/api/drops", drop_form(vendor, 5), &[]).await
+    }
+
+    #[tokio::test]
+    async fn active_drop_quota_rejects_the_next_drop() {
+        let app = TestApp::with_config(|c| c.max_active_drops_per_vendor = 2).await;
+        let vendor = app.create_vendor(None).await;
+        let first = app.create_drop(&vendor, 5).await;
+        app.create_drop(&vendor, 5).await;
+
+        let res = try_create_drop(&app, &vendor).await;
+        assert_eq!(res.status, 409);
+        assert!(res.json()["error"].as_str().unwrap().contains("quota"));
+
+        // 終了した Drop は数えない
+        end_drop(&app, &first, 10).await;
+        assert_eq!(try_create_drop(&app, &vendor).await.status, 200);
+
+        // 他の Vendor には影響しない
+        let other = app.create_vendor(None).await;
+        assert_eq!(try_create_drop(&app, &other).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn concurrent_creates_cannot_exceed_the_active_drop_quota() {
+        let app = TestApp::with_config(|c| c.max_active_drops_per_vendor = 1).await;
+        let vendor = app.create_vendor(None).await;
+        let (a, b, c) = tokio::join!(
+            try_create_drop(&app, &vendor),
+            try_create_drop(&app, &vendor),
+            try_create_drop(&app, &vendor)
+        );
+        let mut statuses = [a.status, b.status, c.status];
+        statuses.sort();
+        assert_eq!(statuses, [200, 409, 409]);
+
+        let (active,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops WHERE vendor_stable_id = ?")
+            .bind(&vendor)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(active, 1);
+    }
+
+    #[tokio::test]
+    async fn vendor_override_takes_precedence_over_default_quota() {
+        let app = TestApp::with_config(|c| c.max_active_drops_per_vendor = 1).await;
+        let vendor = app.create_vendor(None).await;
+        sqlx::query("UPDATE vendors SET max_active_drops = 3 WHERE stable_id = ?")
+            .bind(&vendor)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(try_create_drop(&app, &vendor).await.status, 200);
+        }
+        assert_eq!(try_create_drop(&app, &vendor).await.status, 409);
+
+        // 0 は無制限
+        sqlx::query("UPDATE vendors SET max_active_drops = 0 WHERE stable_id = ?")
+            .bind(&vendor)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(try_create_drop(&app, &vendor).await.status, 200);
+    }
+
+    async fn audio_path_of(app: &TestApp, drop_id: &str) -> std::path::PathBuf {
+        let (key,): (String,) = sqlx::query_as("SELECT audio_object_key FROM drops WHERE drop_id = ?")
+            .bind(drop_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        app.data_path(std::path::Path::new("drops").join(key))
+    }
+
+    #[tokio::test]
+    async fn download_verifies_sha256_when_enabled() {
+        let app = TestApp::with_config(|c| {
+            c.verify_download_sha256 = true;
+            c.rate_limit_burst = 0;
+        })
+        .await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+
+        // ファイルが改ざんされていれば、最後のチャンクを送る前にボディを打ち切る
+        std::fs::write(audio_path_of(&app, &drop_id).await, b"tampered").unwrap();
+        let uri = format!("/api/drops/{}/download?token={}", drop_id, token);
+        let res = app.send_streaming(TestApp::request(Method::GET, &uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(res.status(), 200);
+        let err = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap_err();
+        assert!(err.to_string().contains("integrity"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn download_skips_sha256_check_by_default() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        std::fs::write(audio_path_of(&app, &drop_id).await, b"tampered").unwrap();
+        let res = download(&app, &drop_id, &token).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(&res.body[..], b"tampered");
+    }
+
+    #[tokio::test]
+    async fn missing_audio_is_404_and_refunds_the_download() {
+        let (app, vendor) = admin_app().await;
+        let form = drop_form(&vendor, 10).text("max_downloads_per_claim", "2");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        let drop_id = res.json()["drop"]["drop_id"].as_str().unwrap().to_string();
+        let token = claim_id(&app, &drop_id, "user-1").await;
+        assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+
+        let path = audio_path_of(&app, &drop_id).await;
+        let moved = path.with_extension("moved");
+        std::fs::rename(&path, &moved).unwrap();
+        for _ in 0..3 {
+            let res = download(&app, &drop_id, &token).await;
+            assert_eq!(res.status, 404);
+            assert_eq!(res.json()["success"], false);
+        }
+
+        // 失敗分は数えないので、戻せばあと 1 回ダウンロードできる
+        std::fs::rename(&moved, &path).unwrap();
+        assert_eq!(download(&app, &drop_id, &token).await.status, 200);
+        assert_eq!(download(&app, &drop_id, &token).await.status, 429);
+    }
+
+    #[tokio::test]
+    async fn purged_drop_download_is_410_even_without_claims() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        // purge と同様に Claim も削除されている状態
+        sqlx::query("UPDATE drops SET status = ? WHERE drop_id = ?")
+            .bind(drop_status::PURGED)
+            .bind(&drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM drop_claims WHERE drop_id = ?")
+            .bind(&drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let res = download(&app, &drop_id, &token).await;
+        assert_eq!(res.status, 410, "{}", res.text());
+
+        assert_eq!(download(&app, "no-such-drop", &token).await.status, 404);
+    }
+
+    #[tokio::test]
+    async fn claim_receipt_signature_verifies_against_public_key() {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let app = TestApp::with_config(|c| c.receipt_signing_key = Some([7u8; 32])).await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        let uri = format!("/api/drops/{}/claims/{}/receipt", drop_id, token);
+        assert_eq!(app.get(&uri).await.status, 401);
+        assert_eq!(app.get(&format!("{}?token=other", uri)).await.status, 401);
+
+        let res = app.get(&format!("{}?token={}", uri, token)).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["receipt"]["claim_id"], token.as_str());
+        assert_eq!(body["receipt"]["user_id"], "user-1");
+        let payload: serde_json::Value = serde_json::from_str(body["payload"].as_str().unwrap()).unwrap();
+        assert_eq!(payload, body["receipt"]);
+
+        let public_key = app.get("/api/receipts/public-key").await.json()["public_key"].clone();
+        assert_eq!(public_key, body["public_key"]);
+
+        let decode = |v: &serde_json::Value| {
+            base64::engine::general_purpose::STANDARD.decode(v.as_str().unwrap()).unwrap()
+        };
+        let key = VerifyingKey::from_bytes(&decode(&public_key).try_into().unwrap()).unwrap();
+        let signature = Signature::from_slice(&decode(&body["signature"])).unwrap();
+        let signed = body["payload"].as_str().unwrap();
+        assert!(key.verify(signed.as_bytes(), &signature).is_ok());
+
+        // payload を改ざんすると検証に失敗する
+        let tampered = signed.replace("user-1", "user-2");
+        assert!(key.verify(tampered.as_bytes(), &signature).is_err());
+    }
+
+    #[tokio::test]
+    async fn claim_receipt_is_unavailable_without_signing_key() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        let uri = format!("/api/drops/{}/claims/{}/receipt?token={}", drop_id, token, token);
+        assert_eq!(app.get(&uri).await.status, 503);
+        assert_eq!(app.get("/api/receipts/public-key").await.status, 503);
+    }
+
+    async fn blob_ref_count(app: &TestApp, object_key: &str) -> Option<i64> {
+        sqlx::query_as::<_, (i64,)>("SELECT ref_count FROM audio_blobs WHERE object_key = ?")
+            .bind(object_key)
+            .fetch_optional(&app.state.db)
+            .await
+            .unwrap()
+            .map(|(count,)| count)
+    }
+
+    #[tokio::test]
+    async fn identical_audio_shares_one_blob_until_last_drop_is_purged() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let first = app.create_drop(&vendor, 10).await;
+        let second = app.create_drop(&vendor, 10).await;
+
+        let blob_path = audio_path_of(&app, &first).await;
+        assert_eq!(blob_path, audio_path_of(&app, &second).await);
+        let blobs: Vec<_> = std::fs::read_dir(app.data_path("drops/_blobs")).unwrap().collect();
+        assert_eq!(blobs.len(), 1);
+        let object_key = blob_path.strip_prefix(app.data_path("drops")).unwrap().to_str().unwrap().to_string();
+        assert_eq!(blob_ref_count(&app, &object_key).await, Some(2));
+
+        // 共有中の blob はパージで消さない
+        end_drop(&app, &first, 3600).await;
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 1);
+        assert!(blob_path.exists());
+        assert_eq!(blob_ref_count(&app, &object_key).await, Some(1));
+
+        end_drop(&app, &second, 3600).await;
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 1);
+        assert!(!blob_path.exists());
+        assert_eq!(blob_ref_count(&app, &object_key).await, None);
+    }
+
+    #[tokio::test]
+    async fn oversized_text_field_is_rejected_before_writing_audio() {
+        use crate::extract::{MAX_TEXT_FIELDS_TOTAL_BYTES, MAX_TEXT_FIELD_BYTES};
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+
+        let form = MultipartForm::new()
+            .text("description", &"x".repeat(MAX_TEXT_FIELD_BYTES + 1))
+            .file("audio", "track.mp3", "audio/mpeg", b"ID3 test audio bytes");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("'description'"));
+
+        // 1つずつは上限内でも合計が超えれば拒否
+        let chunk = "x".repeat(MAX_TEXT_FIELD_BYTES);
+        let mut form = drop_form(&vendor, 10);
+        for _ in 0..=MAX_TEXT_FIELDS_TOTAL_BYTES / MAX_TEXT_FIELD_BYTES {
+            form = form.text("description", &chunk);
+        }
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("in total"));
+
+        assert!(!app.data_path("drops/_blobs").exists());
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops").fetch_one(&app.state.db).await.unwrap();
+        assert_eq!(count, 0);
+    }
+
+    /// start_at / end_at を指定して Drop 作成を試みる（後から送ったフィールドが優先される）
+    async fn try_create_drop_window(app: &TestApp, vendor: &str, start_at: Option<i64>, end_at: i64) -> TestResponse {
+        let mut form = drop_form(vendor, 5).text("end_at", &end_at.to_string());
+        if let Some(start_at) = start_at {
+            form = form.text("start_at", &start_at.to_string());
+        }
+        app.send_form(Method::POST, "/api/drops", form, &[]).await
+    }
+
+    #[tokio::test]
+    async fn create_drop_rejects_invalid_windows() {
+        let app = TestApp::with_config(|c| c.max_drop_window_secs = 7 * 24 * 3600).await;
+        let vendor = app.create_vendor(None).await;
+        let now = chrono::Utc::now().timestamp();
+
+        let res = try_create_drop_window(&app, &vendor, None, now - 60).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("future"));
+        assert_eq!(error_fields(&res), ["end_at"]);
+
+        let res = try_create_drop_window(&app, &vendor, Some(now + 7200), now + 3600).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("after start_at"));
+        assert_eq!(error_fields(&res), ["start_at", "end_at"]);
+
+        let res = try_create_drop_window(&app, &vendor, Some(now), now + 8 * 24 * 3600).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("too long"));
+
+        // 上限ちょうどは許可
+        let res = try_create_drop_window(&app, &vendor, Some(now + 60), now + 60 + 7 * 24 * 3600).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    #[tokio::test]
+    async fn zero_max_window_disables_the_length_check() {
+        let app = TestApp::with_config(|c| c.max_drop_window_secs = 0).await;
+        let vendor = app.create_vendor(None).await;
+        let end_at = chrono::Utc::now().timestamp() + 10 * 365 * 24 * 3600;
+        assert_eq!(try_create_drop_window(&app, &vendor, None, end_at).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn large_audio_is_streamed_in_chunks() {
+        use futures_util::StreamExt;
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let audio: Vec<u8> = (0..8 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let end_at = chrono::Utc::now().timestamp() + 3600;
+        let form = MultipartForm::new()
+            .text("vendor_stable_id", &vendor)
+            .text("artist_name", "Test Artist")
+            .text("title", "Large Drop")
+            .text("end_at", &end_at.to_string())
+            .text("max_claims", "10")
+            .file("audio", "large.mp3", "audio/mpeg", &audio);
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let drop_id = res.json()["drop"]["drop_id"].as_str().unwrap().to_string();
+        let token = claim_id(&app, &drop_id, "user-1").await;
+
+        let req = TestApp::request(Method::GET, &format!("/api/drops/{}/download?token={}", drop_id, token))
+            .body(Body::empty())
+            .unwrap();
+        let res = app.send_streaming(req).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-length"], audio.len().to_string().as_str());
+        assert_eq!(res.headers()["content-type"], "audio/mpeg");
+        assert!(res.headers()["content-disposition"].to_str().unwrap().starts_with("attachment;"));
+
+        // ファイル全体を1つのバッファにせず、小さなチャンクで送る
+        let mut stream = res.into_body().into_data_stream();
+        let mut received = Vec::with_capacity(audio.len());
+        let mut chunks = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= 64 * 1024, "chunk of {} bytes", chunk.len());
+            received.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        assert!(chunks > 1);
+        assert!(received == audio, "downloaded bytes differ");
+    }
+
+    #[tokio::test]
+    async fn purge_respects_grace_period_boundary() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let grace = 3600;
+        let over = app.create_drop(&vendor, 10).await;
+        let under = app.create_drop(&vendor, 10).await;
+        end_drop(&app, &over, grace + 5).await;
+        end_drop(&app, &under, grace - 5).await;
+
+        assert_eq!(purge_ended_drops(&app.state, grace).await.unwrap(), 1);
+        assert_eq!(drop_status_of(&app, &over).await, drop_status::PURGED);
+        assert_eq!(drop_status_of(&app, &under).await, drop_status::ENDED);
+        assert!(app.data_path(format!("drops/{}", under)).exists());
+    }
+
+    #[tokio::test]
+    async fn purge_dry_run_leaves_drops_and_files() {
+        let app = TestApp::with_config(|c| c.purge_dry_run = true).await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let token = claim_id(&app, &drop_id, "user-1").await;
+        end_drop(&app, &drop_id, 3600).await;
+
+        assert_eq!(purge_ended_drops(&app.state, 60).await.unwrap(), 0);
+        assert_eq!(drop_status_of(&app, &drop_id).await, drop_status::ENDED);
+        assert!(app.data_path(format!("drops/{}", drop_id)).exists());
+        assert!(audio_path_of(&app, &drop_id).await.exists());
+        let (claims,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drop_claims WHERE claim_id = ?")
+            .bind(&token)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(claims, 1);
+    }
+
+    async fn create_drop_with_cover(app: &TestApp, vendor: &str, filename: &str, data: &[u8]) -> serde_json::Value {
+        let form = drop_form(vendor, 10).file("cover", filename, "image/png", data);
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        res.json()["drop"].clone()
+    }
+
+    #[tokio::test]
+    async fn cover_upload_produces_webp_and_blurhash() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop = create_drop_with_cover(&app, &vendor, "cover.png", &crate::test_support::png_bytes(2048, 64)).await;
+        let drop_id = drop["drop_id"].as_str().unwrap();
+
+        let covers = &drop["covers"];
+        assert!(covers["original"].as_str().unwrap().ends_with(&format!("{}/cover.png", drop_id)));
+        assert!(!covers["blurhash"].as_str().unwrap().is_empty());
+        assert!(covers["webp"].as_str().unwrap().ends_with(&format!("{}/{}", drop_id, COVER_WEBP_FILE)));
+
+        // WebP 派生は最大幅まで縮小して保存される
+        let webp = std::fs::read(app.data_path(format!("drops/{}/{}", drop_id, COVER_WEBP_FILE))).unwrap();
+        assert_eq!(image::guess_format(&webp).unwrap(), image::ImageFormat::WebP);
+        let img = image::load_from_memory(&webp).unwrap();
+        assert_eq!(img.width(), images::COVER_WEBP_MAX_WIDTH);
+
+        // GET でも同じ派生を返す
+        let fetched = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(fetched["drop"]["covers"], *covers);
+    }
+
+    #[tokio::test]
+    async fn undecodable_cover_keeps_original_without_derivatives() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop = create_drop_with_cover(&app, &vendor, "cover.png", b"not an image").await;
+
+        let covers = &drop["covers"];
+        assert!(covers["original"].is_string());
+        assert!(covers["webp"].is_null());
+        assert!(covers["blurhash"].is_null());
+
+
+        // カバー未指定なら covers は null
+        let plain = app.create_drop(&vendor, 10).await;
+        assert!(app.get(&format!("/api/drops/{}", plain)).await.json()["drop"]["covers"].is_null());
+    }
+
+    async fn claimable_reason(app: &TestApp, drop_id: &str, user_id: &str) -> (bool, String) {
+        let res = app.get(&format!("/api/drops/{}/claimable?user_id={}", drop_id, user_id)).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        (body["claimable"].as_bool().unwrap(), body["reason"].as_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn claimable_reports_each_reason_without_claiming() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+
+        let drop_id = app.create_drop(&vendor, 2).await;
+        assert_eq!(claimable_reason(&app, &drop_id, "user-1").await, (true, "ok".to_string()));
+        // 確認だけでは claim されない
+        let res = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(res["drop"]["claimed_count"], 0);
+        claim_id(&app, &drop_id, "user-1").await;
+        assert_eq!(claimable_reason(&app, &drop_id, "user-1").await, (false, "already_claimed".to_string()));
+
+        claim_id(&app, &drop_id, "user-2").await;
+        assert_eq!(claimable_reason(&app, &drop_id, "user-3").await, (false, "sold_out".to_string()));
+
+        let scheduled = create_scheduled_drop(&app, &vendor).await;
+        assert_eq!(claimable_reason(&app, &scheduled, "user-1").await, (false, "not_started".to_string()));
+
+        let expired = app.create_drop(&vendor, 2).await;
+        set_end_at(&app, &expired, chrono::Utc::now().timestamp() - 1).await;
+        assert_eq!(claimable_reason(&app, &expired, "user-1").await, (false, "expired".to_string()));
+
+        let ended = app.create_drop(&vendor, 2).await;
+        end_drop(&app, &ended, 10).await;
+        assert_eq!(claimable_reason(&app, &ended, "user-1").await, (false, "expired".to_string()));
+
+
+        assert_eq!(app.get("/api/drops/DROP_MISSING/claimable").await.status, 404);
+    }
+
+    #[tokio::test]
+    async fn availability_batches_active_ended_and_missing_drops() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let active = app.create_drop(&vendor, 3).await;
+        claim_id(&app, &active, "user-1").await;
+        let ended = app.create_drop(&vendor, 3).await;
+        end_drop(&app, &ended, 10).await;
+        let expired = app.create_drop(&vendor, 3).await;
+        set_end_at(&app, &expired, chrono::Utc::now().timestamp() - 1).await;
+        let scheduled = create_scheduled_drop(&app, &vendor).await;
+
+        let body = json!({ "drop_ids": [&active, &ended, &expired, &scheduled, &active, "DROP_MISSING"] });
+        let res = app.post_json("/api/drops/availability", body).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        let drops = &body["drops"];
+        assert_eq!(drops.as_object().unwrap().len(), 4);
+
+        assert_eq!(drops[&active]["remaining_claims"], 2);
+        assert_eq!(drops[&active]["status"], drop_status::ACTIVE);
+        let seconds_left = drops[&active]["seconds_left"].as_i64().unwrap();
+        assert!((3590..=3600).contains(&seconds_left), "{}", seconds_left);
+
+        assert_eq!(drops[&ended]["status"], drop_status::ENDED);
+        // end_at を過ぎた ACTIVE は ENDED として返す
+        assert_eq!(drops[&expired]["status"], drop_status::ENDED);
+        assert_eq!(drops[&expired]["seconds_left"], 0);
+        assert_eq!(drops[&scheduled]["status"], drop_status::SCHEDULED);
+        assert_eq!(body["missing"], json!(["DROP_MISSING"]));
+    }
+
+    #[tokio::test]
+    async fn availability_rejects_oversized_batches() {
+        let app = TestApp::new().await;
+        let ids: Vec<String> = (0..=MAX_AVAILABILITY_BATCH).map(|i| format!("DROP_{}", i)).collect();
+        let res = app.post_json("/api/drops/availability", json!({ "drop_ids": ids })).await;
+        assert_eq!(res.status, 400);
+
+        let res = app.post_json("/api/drops/availability", json!({ "drop_ids": [] })).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.json()["drops"], json!({}));
+    }
+
+    async fn try_create_drop_for_artist(app: &TestApp, vendor: &str, artist: &str) -> TestResponse {
+        let form = drop_form(vendor, 5).text("artist_stable_id", artist);
+        app.send_form(Method::POST, "/api/drops", form, &[]).await
+    }
+
+    #[tokio::test]
+    async fn drop_artist_must_exist_and_be_alive() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+
+        let res = try_create_drop_for_artist(&app, &vendor, "ARTIST_MISSING").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(error_fields(&res), ["artist_stable_id"]);
+
+        let artist = app.create_artist(None).await;
+        let res = try_create_drop_for_artist(&app, &vendor, &artist).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["drop"]["artist_stable_id"], artist.as_str());
+
+        let uri = format!("/api/account/artists/{}", artist);
+        assert_eq!(app.send_json(Method::DELETE, &uri, None, &[]).await.status, 200);
+        assert_eq!(try_create_drop_for_artist(&app, &vendor, &artist).await.status, 400);
+
+        // 空文字は未指定扱い
+        assert_eq!(try_create_drop_for_artist(&app, &vendor, "").await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn drop_artist_owner_must_match_vendor_owner_when_enforced() {
+        let app = TestApp::with_config(|c| c.enforce_artist_owner = true).await;
+        let vendor = app.create_vendor(Some("0xAAA")).await;
+        let own = app.create_artist(Some("0xaaa")).await;
+        let other = app.create_artist(Some("0xBBB")).await;
+        let unowned = app.create_artist(None).await;
+
+        assert_eq!(try_create_drop_for_artist(&app, &vendor, &own).await.status, 200);
+        assert_eq!(try_create_drop_for_artist(&app, &vendor, &other).await.status, 403);
+        assert_eq!(try_create_drop_for_artist(&app, &vendor, &unowned).await.status, 200);
+    }
+
+    /// 無音の 16bit モノラル PCM WAV
+    fn wav_bytes(seconds: u32, sample_rate: u32) -> Vec<u8> {
+        let data_len = seconds * sample_rate * 2;
+        let mut out = Vec::with_capacity(44 + data_len as usize);
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        out.extend_from_slice(&1u16.to_le_bytes()); // mono
+        out.extend_from_slice(&sample_rate.to_le_bytes());
+        out.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        out.resize(44 + data_len as usize, 0);
+        out
+    }
+
+    #[tokio::test]
+    async fn audio_metadata_is_extracted_on_create_and_replace() {
+        let (app, vendor) = admin_app().await;
+        // ダミーの音声は解析できないが、Drop の作成自体は成功する
+        let drop_id = create_scheduled_drop(&app, &vendor).await;
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json()["drop"].clone();
+        assert!(drop["audio_duration_ms"].is_null());
+        assert!(drop["audio_bitrate_kbps"].is_null());
+        assert!(drop["audio_sample_rate_hz"].is_null());
+
+        // 拡張子ではなく中身で判定する
+        let form = MultipartForm::new().file("audio", "master.mp3", "audio/mpeg", &wav_bytes(3, 44_100));
+        let uri = format!("/api/drops/{}/audio", drop_id);
+        let res = app.send_form(Method::PUT, &uri, form, &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json()["drop"].clone();
+        assert_eq!(drop["audio_duration_ms"], 3000);
+        assert_eq!(drop["audio_sample_rate_hz"], 44_100);
+        let bitrate = drop["audio_bitrate_kbps"].as_i64().unwrap();
+        assert!((700..=710).contains(&bitrate), "{}", bitrate);
+    }
+
+    #[tokio::test]
+    async fn create_drop_rejects_audio_digest_mismatch_before_writing() {
+        use base64::Engine;
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let b64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+
+        let wrong = format!("sha-256={}", b64(&Sha256::digest(b"other audio")));
+        let res = app.send_form(Method::POST, "/api/drops", drop_form(&vendor, 10), &[("digest", &wrong)]).await;
+        assert_eq!(res.status, 422);
+        let (drops,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops").fetch_one(&app.state.db).await.unwrap();
+        assert_eq!(drops, 0);
+        assert!(!app.data_path("drops").exists() || std::fs::read_dir(app.data_path("drops")).unwrap().next().is_none());
+
+        // drop_form の音声と一致するハッシュなら作成できる
+        let right = format!("sha-256={}", b64(&Sha256::digest(b"ID3 test audio bytes")));
+        let res = app.send_form(Method::POST, "/api/drops", drop_form(&vendor, 10), &[("digest", &right)]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    async fn extend(app: &TestApp, drop_id: &str, end_at: i64, owner: Option<OwnerKey>) -> TestResponse {
+        let headers = match owner {
+            Some(owner) => app.owner_headers(&owner).await.0,
+            None => Vec::new(),
+        };
+        let headers: Vec<(&str, &str)> = headers.iter().map(|(n, v)| (*n, v.as_str())).collect();
+        let uri = format!("/api/drops/{}/extend", drop_id);
+        app.send_json(Method::POST, &uri, Some(json!({ "end_at": end_at })), &headers).await
+    }
+
+    #[tokio::test]
+    async fn extend_moves_end_at_forward_for_the_owner_only() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let end_at = app.get(&format!("/api/drops/{}", drop_id)).await.json()["drop"]["end_at"].as_i64().unwrap();
+
+        assert_eq!(extend(&app, &drop_id, end_at + 600, None).await.status, 401);
+        assert_eq!(extend(&app, &drop_id, end_at + 600, Some(stranger())).await.status, 403);
+
+        let res = extend(&app, &drop_id, end_at + 600, Some(owner())).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["drop"]["end_at"], end_at + 600);
+
+        let res = extend(&app, &drop_id, end_at, Some(owner())).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], json!(["end_at"]));
+
+        let res = app
+            .send_json(
+                Method::POST,
+                &format!("/api/drops/{}/extend", drop_id),
+                Some(json!({ "end_at": end_at + 1200 })),
+                &[("x-admin-token", ADMIN_TOKEN)],
+            )
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    #[tokio::test]
+    async fn extend_reopens_only_recently_auto_expired_drops() {
+        let app = TestApp::with_config(|c| c.drop_reactivate_grace_secs = 600).await;
+        let vendor = app.create_vendor(Some(&owner().address())).await;
+        let now = chrono::Utc::now().timestamp();
+
+        // 期限切れジョブで 30 秒前に終了（ended_at >= end_at）
+        let expired = app.create_drop(&vendor, 10).await;
+        set_end_at(&app, &expired, now - 60).await;
+        end_drop(&app, &expired, 30).await;
+        let res = extend(&app, &expired, now + 3600, Some(owner())).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(drop_status_of(&app, &expired).await, drop_status::ACTIVE);
+        assert!(res.json()["drop"]["ended_at"].is_null());
+
+        // 手動終了（ended_at < end_at）は再開しない
+        let manual = app.create_drop(&vendor, 10).await;
+        end_drop(&app, &manual, 30).await;
+        assert_eq!(extend(&app, &manual, now + 7200, Some(owner())).await.status, 409);
+
+        // 猶予を過ぎた自動終了も再開しない
+        let old = app.create_drop(&vendor, 10).await;
+        set_end_at(&app, &old, now - 4000).await;
+        end_drop(&app, &old, 3600).await;
+        assert_eq!(extend(&app, &old, now + 3600, Some(owner())).await.status, 409);
+        assert_eq!(drop_status_of(&app, &old).await, drop_status::ENDED);
+    }
+
+    #[tokio::test]
+    async fn private_drop_requires_matching_passcode() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let form = drop_form(&vendor, 10).text("claim_passcode", "open-sesame");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let drop = res.json()["drop"].clone();
+        assert_eq!(drop["passcode_required"], true);
+        assert!(!res.text().contains("open-sesame"));
+        assert!(drop.get("claim_passcode_hash").is_none());
+        let drop_id = drop["drop_id"].as_str().unwrap();
+
+        let uri = format!("/api/drops/{}/claim", drop_id);
+        assert_eq!(app.claim(drop_id, "user-1").await.status, 403);
+        let res = app.post_json(&uri, json!({ "user_id": "user-1", "passcode": "wrong" })).await;
+        assert_eq!(res.status, 403);
+        let res = app.post_json(&uri, json!({ "user_id": "user-1", "passcode": "open-sesame" })).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        // パスコードなしの Drop は従来どおり
+        let public = app.create_drop(&vendor, 10).await;
+        let detail = app.get(&format!("/api/drops/{}", public)).await.json();
+        assert_eq!(detail["drop"]["passcode_required"], false);
+        assert_eq!(app.claim(&public, "user-1").await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn revoked_claim_can_no_longer_download() {
+        let (app, vendor) = admin_app().await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let leaked = claim_id(&app, &drop_id, "user-1").await;
+        let other = claim_id(&app, &drop_id, "user-2").await;
+        assert_eq!(download(&app, &drop_id, &leaked).await.status, 200);
+
+        let revoke = |claim: String, headers: Vec<(&'static str, String)>| {
+            let app = &app;
+            let uri = format!("/api/drops/{}/claims/{}/revoke", drop_id, claim);
+            async move {
+                let headers: Vec<(&str, &str)> = headers.iter().map(|(n, v)| (*n, v.as_str())).collect();
+                app.send_json(Method::POST, &uri, None, &headers).await
+            }
+        };
+        assert_eq!(revoke(leaked.clone(), vec![]).await.status, 401);
+        assert_eq!(revoke(leaked.clone(), app.owner_headers(&stranger()).await.0).await.status, 403);
+        let res = revoke(leaked.clone(), app.owner_headers(&owner()).await.0).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        assert_eq!(download(&app, &drop_id, &leaked).await.status, 403);
+        assert_eq!(download(&app, &drop_id, &other).await.status, 200);
+
+        // 冪等で、スロットは消費されたまま
+        assert_eq!(revoke(leaked, vec![("x-admin-token", ADMIN_TOKEN.to_string())]).await.status, 200);
+        assert_eq!(revoke("no-such-claim".to_string(), app.owner_headers(&owner()).await.0).await.status, 404);
+        let drop = app.get(&format!("/api/drops/{}", drop_id)).await.json();
+        assert_eq!(drop["drop"]["claimed_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn text_fields_may_follow_audio_without_fields_first() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let end_at = (chrono::Utc::now().timestamp() + 3600).to_string();
+        let form = MultipartForm::new()
+            .text("title", "Late Fields")
+            .file("audio", "track.mp3", "audio/mpeg", b"ID3 test audio bytes")
+            .text("vendor_stable_id", &vendor)
+            .text("artist_name", "Test Artist")
+            .text("end_at", &end_at)
+            .text("max_claims", "5");
+        let res = app.send_form(Method::POST, "/api/drops", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["drop"]["title"], "Late Fields");
+    }
+
+    #[tokio::test]
+    async fn fields_first_rejects_missing_text_before_reading_audio() {
+        use axum::body::{Body, Bytes};
+        use futures_util::StreamExt;
+
+        let app = TestApp::new().await;
+        let form = MultipartForm::new()
+            .text("title", "No Vendor")
+            .text("max_claims", "5")
+            .file("audio", "track.mp3", "audio/mpeg", &[0u8; 64 * 1024]);
+        let content_type = form.content_type();
+        let bytes = form.finish();
+        // 音声の途中から先が届かないボディ（音声を読み進めていれば応答しない）
+        let first: Result<Bytes, std::io::Error> = Ok(Bytes::copy_from_slice(&bytes[..bytes.len() / 2]));
+        let stalled = futures_util::stream::iter([first]).chain(futures_util::stream::pending());
+        let req = TestApp::request(Method::POST, "/api/drops?fields_first=true")
+            .header("content-type", content_type)
+            .body(Body::from_stream(stalled))
+            .unwrap();
+        let res = tokio::time::timeout(std::time::Duration::from_secs(5), app.send(req))
+            .await
+            .expect("create_drop waited for the audio body");
+        assert_eq!(res.status, 400, "{}", res.text());
+        assert_eq!(error_fields(&res), vec!["vendor_stable_id", "artist_name", "end_at"]);
+    }
+
+    #[tokio::test]
+    async fn claim_download_url_uses_asset_base_url() {
+        let app = TestApp::with_config(|c| {
+            c.vps_base_url = "https://media.example.com/nft/files".to_string();
+            c.asset_base_url = "https://api.example.com/v2".to_string();
+        })
+        .await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        let body = app.claim(&drop_id, "user-1").await.json();
+        let expected = format!("https://api.example.com/v2/api/drops/{}/download?token=", drop_id);
+        assert!(body["download_url"].as_str().unwrap().starts_with(&expected), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn head_drop_reports_existence_without_body() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        let req = TestApp::request(Method::HEAD, &format!("/api/drops/{}", drop_id)).body(Body::empty()).unwrap();
+        let res = app.send(req).await;
+        assert_eq!(res.status, 200);
+        assert!(res.body.is_empty());
+        assert!(res.header("etag").is_some());
+        assert!(res.header("last-modified").is_some());
+
+        let req = TestApp::request(Method::HEAD, "/api/drops/DROP_MISSING").body(Body::empty()).unwrap();
+        let res = app.send(req).await;
+        assert_eq!(res.status, 404);
+        assert!(res.body.is_empty());
+    }
+
+    /// 指定サイズの音声で予約 Drop を作成（中身は Drop ごとに変える）
+    async fn try_create_drop_with_audio(app: &TestApp, vendor: &str, len: usize, seed: u8) -> TestResponse {
+        let end_at = chrono::Utc::now().timestamp() + 3600;
+        let start_at = chrono::Utc::now().timestamp() + 600;
+        let form = MultipartForm::new()
+            .text("vendor_stable_id", vendor)
+            .text("artist_name", "Test Artist")
+            .text("title", "Sized Drop")
+            .text("start_at", &start_at.to_string())
+            .text("end_at", &end_at.to_string())
+            .text("max_claims", "5")
+            .file("audio", "track.mp3", "audio/mpeg", &vec![seed; len]);
+        app.send_form(Method::POST, "/api/drops", form, &[]).await
+    }
+
+    #[tokio::test]
+    async fn drop_byte_quota_counts_unpurged_audio() {
+        let app = TestApp::with_config(|c| c.max_drop_bytes_per_vendor = 2500).await;
+        let vendor = app.create_vendor(Some(&owner().address())).await;
+        let first = try_create_drop_with_audio(&app, &vendor, 1000, 1).await;
+        assert_eq!(first.status, 200, "{}", first.text());
+        let first = first.json()["drop"]["drop_id"].as_str().unwrap().to_string();
+        assert_eq!(try_create_drop_with_audio(&app, &vendor, 1000, 2).await.status, 200);
+
+        let res = try_create_drop_with_audio(&app, &vendor, 1000, 3).await;
+        assert_eq!(res.status, 413);
+        assert!(res.json()["error"].as_str().unwrap().contains("500 bytes remaining"), "{}", res.text());
+        let last = try_create_drop_with_audio(&app, &vendor, 500, 4).await;
+        assert_eq!(last.status, 200);
+        let last = last.json()["drop"]["drop_id"].as_str().unwrap().to_string();
+
+        // パージした Drop の分は空く
+        sqlx::query("UPDATE drops SET status = ? WHERE drop_id = ?")
+            .bind(drop_status::PURGED)
+            .bind(&first)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(try_create_drop_with_audio(&app, &vendor, 1000, 5).await.status, 200);
+
+        // 差し替えは旧音声（500 バイト）の分を空けて判定する
+        let uri = format!("/api/drops/{}/audio", last);
+        let res = app.send_form(Method::PUT, &uri, audio_form(&[6u8; 501]), &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 413);
+        let res = app.send_form(Method::PUT, &uri, audio_form(&[6u8; 500]), &app.owner_headers(&owner()).await.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    #[tokio::test]
+    async fn concurrent_creates_cannot_exceed_the_drop_byte_quota() {
+        let app = TestApp::with_config(|c| c.max_drop_bytes_per_vendor = 1500).await;
+        let vendor = app.create_vendor(None).await;
+        let (a, b, c) = tokio::join!(
+            try_create_drop_with_audio(&app, &vendor, 1000, 1),
+            try_create_drop_with_audio(&app, &vendor, 1000, 2),
+            try_create_drop_with_audio(&app, &vendor, 1000, 3)
+        );
+        let mut statuses = [a.status, b.status, c.status];
+        statuses.sort();
+        assert_eq!(statuses, [200, 413, 413]);
+
+        let (used,): (i64,) = sqlx::query_as("SELECT COALESCE(SUM(audio_size_bytes), 0) FROM drops WHERE vendor_stable_id = ?")
+            .bind(&vendor)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(used, 1000);
+    }
 }
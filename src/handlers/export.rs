@@ -0,0 +1,288 @@
+//! Export API Handlers
+//! /api/export エンドポイント - 更新日時順の差分エクスポート（インデクサの増分同期向け）
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+
+use crate::error::ApiError;
+use crate::handlers::{listings, vendors};
+use crate::models::{DropResponse, Drop, Listing, ListingResponse, Vendor, VendorResponse};
+use crate::AppState;
+
+/// 1ページのデフォルト件数
+const DEFAULT_LIMIT: i64 = 500;
+/// 1ページの最大件数
+const MAX_LIMIT: i64 = 1000;
+
+// ========================================
+// Response Types
+// ========================================
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ExportItems {
+    Vendors(Vec<VendorResponse>),
+    Listings(Vec<ListingResponse>),
+    Drops(Vec<DropResponse>),
+}
+
+#[derive(Serialize)]
+pub struct ExportResponse {
+    pub success: bool,
+    pub kind: String,
+    pub items: ExportItems,
+    pub total: usize,
+    /// 次ページ（または次回の増分同期）の起点。0件の場合はリクエストの起点をそのまま返す
+    pub next_cursor: Option<String>,
+    /// limit 件ちょうど返した場合は true（続きがある可能性）
+    pub has_more: bool,
+}
+
+// ========================================
+// Query Parameters
+// ========================================
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// vendors / listings / drops
+    pub kind: String,
+    /// この時刻（ms）より後に更新されたものだけを返す（cursor 指定時は無視）
+    pub since_ms: Option<i64>,
+    /// 前回レスポンスの next_cursor
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// エクスポート位置（更新時刻 ms + 主キー、同時刻の行を取りこぼさないため）
+/// since_ms 指定時は key が空（その時刻ちょうどの行は含めない）
+#[derive(Debug, Clone)]
+struct ExportCursor {
+    updated_at_ms: i64,
+    key: String,
+}
+
+impl ExportCursor {
+    fn encode(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(format!("{}:{}", self.updated_at_ms, self.key))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let (ms, key) = text.split_once(':')?;
+        Some(Self {
+            updated_at_ms: ms.parse().ok()?,
+            key: key.to_string(),
+        })
+    }
+}
+
+// ========================================
+// Handlers
+// ========================================
+
+/// GET /api/export?kind=...&since_ms=...&cursor=... - 更新日時順の差分エクスポート（削除済みも含む）
+pub async fn export(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Json<ExportResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let start = match query.cursor.as_deref() {
+        Some(c) => Some(ExportCursor::decode(c).ok_or_else(|| {
+            ApiError::BadRequest("Invalid cursor".to_string())
+        })?),
+        None => query.since_ms.map(|ms| ExportCursor { updated_at_ms: ms, key: String::new() }),
+    };
+
+    // (テーブル, 主キー, 更新時刻 ms の式)
+    let (table, key_column, updated_expr) = match query.kind.as_str() {
+        "vendors" => ("vendors", "stable_id", "COALESCE(updated_at_ms, created_at_ms, 0)"),
+        "listings" => ("listings", "listing_id", "COALESCE(updated_at_ms, created_at_ms, 0)"),
+        // drops は Unix 秒
+        "drops" => ("drops", "drop_id", "(updated_at * 1000)"),
+        other => {
+            return Err(ApiError::BadRequest(format!("Invalid kind: {}", other)));
+        }
+    };
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT *, ");
+    builder.push(updated_expr).push(" AS export_updated_at_ms FROM ").push(table);
+    match &start {
+        // since_ms のみ: 指定時刻より後
+        Some(start) if start.key.is_empty() => {
+            builder.push(" WHERE ").push(updated_expr).push(" > ").push_bind(start.updated_at_ms);
+        }
+        // cursor: 同時刻の行は主キーで続きから
+        Some(start) => {
+            builder
+                .push(" WHERE (")
+                .push(updated_expr)
+                .push(" > ")
+                .push_bind(start.updated_at_ms)
+                .push(" OR (")
+                .push(updated_expr)
+                .push(" = ")
+                .push_bind(start.updated_at_ms)
+                .push(" AND ")
+                .push(key_column)
+                .push(" > ")
+                .push_bind(start.key.clone())
+                .push("))");
+        }
+        None => {}
+    }
+    builder
+        .push(" ORDER BY export_updated_at_ms ASC, ")
+        .push(key_column)
+        .push(" ASC LIMIT ")
+        .push_bind(limit);
+
+    let rows = builder
+        .build()
+        .fetch_all(&state.db)
+        .await?;
+
+    let mut last: Option<ExportCursor> = None;
+    let items = match query.kind.as_str() {
+        "vendors" => {
+            let mut responses = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let v: Vendor = decode_row(row)?;
+                last = Some(row_cursor(row, &v.stable_id)?);
+                let profile = vendors::load_vendor_profile(&state.base_data_dir, &v.stable_id).await.ok();
+                responses.push(vendors::vendor_to_response(&v, profile));
+            }
+            ExportItems::Vendors(responses)
+        }
+        "listings" => {
+            let mut responses = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let l: Listing = decode_row(row)?;
+                last = Some(row_cursor(row, &l.listing_id)?);
+                responses.push(listings::listing_to_response(&l));
+            }
+            ExportItems::Listings(responses)
+        }
+        _ => {
+            let mut responses = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let d: Drop = decode_row(row)?;
+                last = Some(row_cursor(row, &d.drop_id)?);
+                responses.push(DropResponse::from_drop(&d, &state.vps_base_url));
+            }
+            ExportItems::Drops(responses)
+        }
+    };
+
+    let total = rows.len();
+    Ok(Json(ExportResponse {
+        success: true,
+        kind: query.kind,
+        items,
+        total,
+        next_cursor: last.or(start).map(|c| c.encode()),
+        has_more: total as i64 == limit,
+    }))
+}
+
+// ========================================
+// Helper Functions
+// ========================================
+
+fn decode_row<T>(row: &sqlx::sqlite::SqliteRow) -> Result<T, ApiError>
+where
+    T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow>,
+{
+    Ok(T::from_row(row)?)
+}
+
+fn row_cursor(row: &sqlx::sqlite::SqliteRow, key: &str) -> Result<ExportCursor, ApiError> {
+    let updated_at_ms: i64 = row
+        .try_get("export_updated_at_ms")?;
+    Ok(ExportCursor { updated_at_ms, key: key.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::test_support::TestApp;
+
+    fn export_ids(body: &Value, key: &str) -> Vec<String> {
+        body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i[key].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn set_listing_updated_at(app: &TestApp, listing_id: &str, updated_at_ms: i64) {
+        sqlx::query("UPDATE listings SET updated_at_ms = ? WHERE listing_id = ?")
+            .bind(updated_at_ms)
+            .bind(listing_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn export_returns_rows_after_cursor_in_update_order() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        for (id, updated_at_ms) in [("L-1", 1000), ("L-2", 2000), ("L-3", 2000), ("L-4", 3000)] {
+            app.create_listing(&vendor, id, 100, 1).await;
+            set_listing_updated_at(&app, id, updated_at_ms).await;
+        }
+
+        let all = app.get("/api/export?kind=listings&since_ms=1500").await.json();
+        assert_eq!(export_ids(&all, "listing_id"), vec!["L-2", "L-3", "L-4"]);
+
+        // 同時刻の行も取りこぼさずにページングできる
+        let page = app.get("/api/export?kind=listings&since_ms=1500&limit=1").await.json();
+        assert_eq!(export_ids(&page, "listing_id"), vec!["L-2"]);
+        assert_eq!(page["has_more"], true);
+        let cursor = page["next_cursor"].as_str().unwrap().to_string();
+        let page = app.get(&format!("/api/export?kind=listings&cursor={}&limit=5", cursor)).await.json();
+        assert_eq!(export_ids(&page, "listing_id"), vec!["L-3", "L-4"]);
+        assert_eq!(page["has_more"], false);
+
+        // 末尾の cursor からは更新された行だけ
+        let cursor = page["next_cursor"].as_str().unwrap().to_string();
+        let empty = app.get(&format!("/api/export?kind=listings&cursor={}", cursor)).await.json();
+        assert!(export_ids(&empty, "listing_id").is_empty());
+        assert_eq!(empty["next_cursor"], cursor.as_str());
+
+        let res = app.put_json("/api/listings/L-1", json!({ "price": 200 })).await;
+        assert_eq!(res.status, 200);
+        let changed = app.get(&format!("/api/export?kind=listings&cursor={}", cursor)).await.json();
+        assert_eq!(export_ids(&changed, "listing_id"), vec!["L-1"]);
+    }
+
+    #[tokio::test]
+    async fn export_supports_vendors_and_drops_and_rejects_bad_input() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 5).await;
+
+        let vendors = app.get("/api/export?kind=vendors&since_ms=0").await.json();
+        assert!(export_ids(&vendors, "stable_id").contains(&vendor));
+        let drops = app.get("/api/export?kind=drops&since_ms=0").await.json();
+        assert_eq!(export_ids(&drops, "drop_id"), vec![drop_id]);
+
+        let future = chrono::Utc::now().timestamp_millis() + 60_000;
+        let none = app.get(&format!("/api/export?kind=drops&since_ms={}", future)).await.json();
+        assert!(export_ids(&none, "drop_id").is_empty());
+
+        assert_eq!(app.get("/api/export?kind=artists").await.status, 400);
+        assert_eq!(app.get("/api/export?kind=vendors&cursor=garbage!").await.status, 400);
+    }
+}
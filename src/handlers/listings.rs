@@ -2,56 +2,108 @@
 //! /api/listings エンドポイント
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use futures_util::{stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use sqlx::Acquire;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tracing::{info, warn};
+use uuid::Uuid;
 
+use crate::error::{ApiError, ErrorResponse};
+use crate::response::{exists_response, ApiResponse, ListMeta};
 use crate::models::{
-    CreateListingRequest, Listing, ListingResponse, UpdateListingRequest,
+    BatchCreateListingsRequest, BatchListingResponse, BatchListingStatusRequest, CreateListingRequest, Listing, ListingPriceChange, ListingResponse, UpdateListingRequest, status,
+    Reservation, ReservationResponse, ReserveListingRequest, reservation_status, tombstone_kind, item_type, ms_to_iso,
 };
+use crate::handlers::{tombstones, vendors};
+use crate::webhook;
+use crate::extract::ApiJson;
 use crate::AppState;
 
+/// NDJSON ストリームで DB 読み出しとクライアント送信の間に溜める行数
+const LISTING_STREAM_BUFFER: usize = 64;
+
 // ========================================
 // Response Types
 // ========================================
 
-#[derive(Serialize)]
-pub struct ListingListResponse {
-    pub success: bool,
-    pub listings: Vec<ListingResponse>,
-    pub total: usize,
-}
+/// 一覧レスポンス（`data` + `meta`、移行期間中は旧フィールド `listings` / `total` も返す）
+pub type ListingListResponse = ApiResponse<Vec<ListingResponse>>;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ListingDetailResponse {
     pub success: bool,
     pub listing: Option<ListingResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ListingCreateResponse {
     pub success: bool,
     pub listing_id: String,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
+/// 集計クエリの結果行（SUM/MIN/MAX は対象0件で NULL）
+#[derive(sqlx::FromRow)]
+struct ListingSummaryRow {
+    total: i64,
+    active: Option<i64>,
+    sold_out: Option<i64>,
+    cancelled: Option<i64>,
+    supply_total: Option<i64>,
+    supply_remaining: Option<i64>,
+    min_price: Option<i64>,
+    max_price: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListingStatusCounts {
+    pub active: i64,
+    pub sold_out: i64,
+    pub cancelled: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListingSummaryResponse {
     pub success: bool,
-    pub error: String,
+    pub vendor_stable_id: String,
+    pub total: i64,
+    pub counts: ListingStatusCounts,
+    pub supply_total: i64,
+    pub supply_remaining: i64,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PriceHistoryResponse {
+    pub success: bool,
+    pub listing_id: String,
+    pub history: Vec<ListingPriceChange>,
 }
 
 // ========================================
 // Query Parameters
 // ========================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListListingsQuery {
     pub vendor_stable_id: Option<String>,
     pub status: Option<i32>,
+    /// title / artist の部分一致検索（大文字小文字を区別しない）
+    pub q: Option<String>,
+    /// 並び順: price_asc / price_desc / newest（デフォルト）
+    pub order: Option<String>,
+    /// 環境（devnet / mainnet 等）。未指定時は DEFAULT_ENV、"all" で全環境
+    pub env: Option<String>,
 }
 
 // ========================================
@@ -59,135 +111,238 @@ pub struct ListListingsQuery {
 // ========================================
 
 /// GET /api/listings - Listing一覧取得
+#[utoipa::path(
+    get,
+    path = "/api/listings",
+    tag = "listings",
+    params(ListListingsQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<ListingResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_listings(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListListingsQuery>,
-) -> Result<Json<ListingListResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let listings: Vec<Listing> = if let Some(vendor_id) = &query.vendor_stable_id {
-        sqlx::query_as(
-            "SELECT * FROM listings WHERE vendor_stable_id = ? AND is_alive = 1 ORDER BY created_at_ms DESC"
-        )
-        .bind(vendor_id)
-        .fetch_all(&state.db)
-        .await
-    } else {
-        sqlx::query_as(
-            "SELECT * FROM listings WHERE is_alive = 1 ORDER BY created_at_ms DESC"
-        )
+) -> Result<Json<ListingListResponse>, ApiError> {
+    let mut builder = build_list_listings_query(&state, &query)?;
+    let listings: Vec<Listing> = builder
+        .build_query_as()
         .fetch_all(&state.db)
-        .await
-    }
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+        .await?;
 
     let responses: Vec<ListingResponse> = listings
         .iter()
-        .filter(|l| query.status.map_or(true, |s| l.status == s))
         .map(listing_to_response)
         .collect();
 
-    let total = responses.len();
-    Ok(Json(ListingListResponse {
-        success: true,
-        listings: responses,
-        total,
-    }))
+    let meta = ListMeta::total(responses.len());
+    Ok(Json(
+        ApiResponse::list(responses, meta).with_legacy_fields(state.config.legacy_list_fields, "listings"),
+    ))
+}
+
+/// GET /api/listings/stream - Listing一覧を NDJSON でストリーミング
+/// 1 行 1 Listing（ListingResponse）。フィルタ・並び順は GET /api/listings と同じ
+#[utoipa::path(
+    get,
+    path = "/api/listings/stream",
+    tag = "listings",
+    params(ListListingsQuery),
+    responses(
+        (status = 200, description = "成功（application/x-ndjson、1 行 1 ListingResponse）", body = ListingResponse, content_type = "application/x-ndjson"),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn stream_listings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListListingsQuery>,
+) -> Result<Response, ApiError> {
+    // 不正な order 等はストリーム開始前に 400 で返す
+    let mut builder = build_list_listings_query(&state, &query)?;
+    let db = state.db.clone();
+    let (tx, rx) = mpsc::channel::<Result<String, sqlx::Error>>(LISTING_STREAM_BUFFER);
+
+    // 行を読みながら送る（クライアント切断で送信に失敗したら打ち切る）
+    tokio::spawn(async move {
+        let mut rows = builder.build_query_as::<Listing>().fetch(&db);
+        let mut sent = 0usize;
+        while let Some(row) = rows.next().await {
+            let line = row.map(|listing| {
+                let mut line = serde_json::to_string(&listing_to_response(&listing))
+                    .unwrap_or_else(|_| "{}".to_string());
+                line.push('\n');
+                line
+            });
+            let failed = line.is_err();
+            if let Err(e) = &line {
+                // 途中のエラーはボディを途中で打ち切って伝える（200 の後なのでステータスは変えられない）
+                warn!("Listing stream aborted after {} rows: {}", sent, e);
+            }
+            if tx.send(line).await.is_err() || failed {
+                return;
+            }
+            sent += 1;
+        }
+    });
+
+    let stream = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|line| (line, rx))
+    });
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .map_err(|e| ApiError::internal(format!("Failed to build response: {}", e)))
+}
+
+/// GET /api/listings と /api/listings/stream 共通のクエリ組み立て
+fn build_list_listings_query(
+    state: &AppState,
+    query: &ListListingsQuery,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Sqlite>, ApiError> {
+    let order_by = match query.order.as_deref() {
+        // featured を先頭に（featured 内は featured_rank 昇順、順位なしはその後ろ）
+        None | Some("newest") => "featured DESC, featured_rank IS NULL, featured_rank ASC, created_at_ms DESC",
+        Some("price_asc") => "price ASC, created_at_ms DESC",
+        Some("price_desc") => "price DESC, created_at_ms DESC",
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!("Invalid order: {}", other)));
+        }
+    };
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM listings WHERE is_alive = 1");
+    if let Some(vendor_id) = &query.vendor_stable_id {
+        builder.push(" AND vendor_stable_id = ").push_bind(vendor_id.clone());
+    }
+    if let Some(s) = query.status {
+        builder.push(" AND status = ").push_bind(s);
+    }
+    if let Some(env) = state.config.env_filter(query.env.as_deref()) {
+        builder.push(" AND env = ").push_bind(env.to_string());
+    }
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        // SQLite の LIKE は ASCII の大文字小文字を区別しない
+        let pattern = format!("%{}%", escape_like(q));
+        builder
+            .push(" AND (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR artist LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+    builder.push(" ORDER BY ").push(order_by);
+    Ok(builder)
 }
 
 /// GET /api/listings/:listing_id - Listing詳細取得
+#[utoipa::path(
+    get,
+    path = "/api/listings/{listing_id}",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ListingDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_listing(
     State(state): State<Arc<AppState>>,
     Path(listing_id): Path<String>,
-) -> Result<Json<ListingDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ListingDetailResponse>, ApiError> {
     let listing: Option<Listing> = sqlx::query_as(
         "SELECT * FROM listings WHERE listing_id = ?"
     )
     .bind(&listing_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     match listing {
         Some(l) => Ok(Json(ListingDetailResponse {
             success: true,
             listing: Some(listing_to_response(&l)),
         })),
-        None => Err(error_response(StatusCode::NOT_FOUND, "Listing not found".to_string())),
+        None => Err(ApiError::NotFound("Listing not found".to_string())),
     }
 }
 
+/// HEAD /api/listings/:listing_id - Listing の存在確認（200 / 404、ボディなし）
+#[utoipa::path(
+    head,
+    path = "/api/listings/{listing_id}",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    responses(
+        (status = 200, description = "存在する（ETag / Last-Modified 付き）"),
+        (status = 404, description = "存在しない"),
+    )
+)]
+pub async fn head_listing(
+    State(state): State<Arc<AppState>>,
+    Path(listing_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let timestamps: Option<(Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT updated_at_ms, created_at_ms FROM listings WHERE listing_id = ?"
+    )
+    .bind(&listing_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(match timestamps {
+        Some((updated_at_ms, created_at_ms)) => exists_response(&listing_id, updated_at_ms.or(created_at_ms)),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
+
 /// POST /api/listings - Listing作成
+#[utoipa::path(
+    post,
+    path = "/api/listings",
+    tag = "listings",
+    request_body = CreateListingRequest,
+    responses(
+        (status = 200, description = "成功", body = ListingCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn create_listing(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateListingRequest>,
-) -> Result<Json<ListingCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<CreateListingRequest>,
+) -> Result<Json<ListingCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
+    if let Some(url) = &req.webhook_url {
+        webhook::validate_url(url, &state.config.webhook_allowed_hosts).map_err(ApiError::BadRequest)?;
+    }
+    validate_featured_rank(req.featured_rank)
+        .map_err(|message| ApiError::invalid_fields(message, &["featured_rank"]))?;
+
     // Vendor存在チェック
     let vendor_exists: Option<(i32,)> = sqlx::query_as(
         "SELECT 1 FROM vendors WHERE stable_id = ? AND is_alive = 1"
     )
     .bind(&req.vendor_stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     if vendor_exists.is_none() {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             format!("Vendor not found: {}", req.vendor_stable_id),
         ));
     }
 
+    // オーナー検証（設定で有効な場合のみ）
+    vendors::verify_vendor_owner(&state, &headers, &req.vendor_stable_id).await?;
+
     // DBに挿入
-    sqlx::query(r#"
-        INSERT INTO listings (
-            listing_id, vendor_stable_id, vendor_object_id, seller,
-            item_type, item_id, price, currency,
-            supply_total, supply_remaining, status,
-            env, created_at_ms, updated_at_ms, is_alive,
-            inventory_id, manifest_id, title, artist, cover_url
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, 'devnet', ?, ?, 1, ?, ?, ?, ?, ?)
-        ON CONFLICT(listing_id) DO UPDATE SET
-            vendor_object_id = COALESCE(excluded.vendor_object_id, listings.vendor_object_id),
-            seller = COALESCE(excluded.seller, listings.seller),
-            price = excluded.price,
-            supply_remaining = excluded.supply_remaining,
-            updated_at_ms = excluded.updated_at_ms,
-            is_alive = 1,
-            inventory_id = COALESCE(excluded.inventory_id, listings.inventory_id),
-            manifest_id = COALESCE(excluded.manifest_id, listings.manifest_id),
-            title = COALESCE(excluded.title, listings.title),
-            artist = COALESCE(excluded.artist, listings.artist),
-            cover_url = COALESCE(excluded.cover_url, listings.cover_url)
-    "#)
-    .bind(&req.listing_id)
-    .bind(&req.vendor_stable_id)
-    .bind(&req.vendor_object_id)
-    .bind(&req.seller)
-    .bind(req.item_type)
-    .bind(&req.item_id)
-    .bind(req.price)
-    .bind(&req.currency)
-    .bind(req.supply_total)
-    .bind(req.supply_total) // supply_remaining = supply_total initially
-    .bind(now_ms)
-    .bind(now_ms)
-    .bind(&req.inventory_id)
-    .bind(&req.manifest_id)
-    .bind(&req.title)
-    .bind(&req.artist)
-    .bind(&req.cover_url)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    let inserted = insert_listing(&state.db, &req, now_ms).await?;
+    if !inserted {
+        return Err(ApiError::Conflict(listing_owned_by_other_vendor(&req.listing_id)));
+    }
 
     info!("Listing created: listing_id={}, vendor={}", req.listing_id, req.vendor_stable_id);
 
@@ -198,11 +353,23 @@ pub async fn create_listing(
 }
 
 /// PUT /api/listings/:listing_id - Listing更新
+#[utoipa::path(
+    put,
+    path = "/api/listings/{listing_id}",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    request_body = UpdateListingRequest,
+    responses(
+        (status = 200, description = "成功", body = ListingCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn update_listing(
     State(state): State<Arc<AppState>>,
     Path(listing_id): Path<String>,
-    Json(req): Json<UpdateListingRequest>,
-) -> Result<Json<ListingCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<UpdateListingRequest>,
+) -> Result<Json<ListingCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // 既存チェック
@@ -211,15 +378,36 @@ pub async fn update_listing(
     )
     .bind(&listing_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
-    if existing.is_none() {
-        return Err(error_response(StatusCode::NOT_FOUND, "Listing not found".to_string()));
+    let existing = existing
+        .ok_or_else(|| ApiError::NotFound("Listing not found".to_string()))?;
+
+    // 在庫は [0, supply_total] の範囲のみ
+    if let Some(remaining) = req.supply_remaining {
+        validate_supply_remaining(remaining, existing.supply_total)
+            .map_err(ApiError::Unprocessable)?;
+    }
+
+    // ステータス遷移チェック（ACTIVE へは在庫が必要）
+    // status 未指定で在庫だけ変わる場合は ACTIVE ⇄ SOLD_OUT を自動で切り替える
+    let supply_remaining = req.supply_remaining.unwrap_or(existing.supply_remaining);
+    let new_status = req.status.or_else(|| {
+        req.supply_remaining.and_then(|_| auto_supply_status(existing.status, supply_remaining))
+    });
+    if let Some(to) = new_status {
+        validate_status_transition(existing.status, to, supply_remaining)
+            .map_err(ApiError::Unprocessable)?;
     }
 
+    if let Some(url) = req.webhook_url.as_deref().filter(|u| !u.is_empty()) {
+        webhook::validate_url(url, &state.config.webhook_allowed_hosts).map_err(ApiError::BadRequest)?;
+    }
+    validate_featured_rank(req.featured_rank)
+        .map_err(|message| ApiError::invalid_fields(message, &["featured_rank"]))?;
+
+    let mut tx = state.db.begin().await?;
+
     // DB更新
     sqlx::query(r#"
         UPDATE listings SET
@@ -227,20 +415,42 @@ pub async fn update_listing(
             price = COALESCE(?, price),
             supply_remaining = COALESCE(?, supply_remaining),
             status = COALESCE(?, status),
+            webhook_url = CASE WHEN ? IS NULL THEN webhook_url ELSE NULLIF(?, '') END,
+            sold_out_notified_at_ms = CASE WHEN COALESCE(?, supply_remaining) > 0 THEN NULL ELSE sold_out_notified_at_ms END,
+            featured = COALESCE(?, featured),
+            featured_rank = CASE WHEN COALESCE(?, featured) = 0 THEN NULL ELSE COALESCE(?, featured_rank) END,
             updated_at_ms = ?
         WHERE listing_id = ?
     "#)
     .bind(&req.seller)
     .bind(req.price)
     .bind(req.supply_remaining)
-    .bind(req.status)
+    .bind(new_status)
+    .bind(&req.webhook_url)
+    .bind(&req.webhook_url)
+    .bind(req.supply_remaining)
+    .bind(req.featured)
+    .bind(req.featured)
+    .bind(req.featured_rank)
     .bind(now_ms)
     .bind(&listing_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .execute(&mut *tx)
+    .await?;
+
+    // 価格が変わった場合は履歴を記録
+    if let Some(new_price) = req.price.filter(|p| *p != existing.price) {
+        sqlx::query(
+            "INSERT INTO listing_price_history (listing_id, old_price, new_price, changed_at_ms) VALUES (?, ?, ?, ?)"
+        )
+        .bind(&listing_id)
+        .bind(existing.price)
+        .bind(new_price)
+        .bind(now_ms)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
 
     info!("Listing updated: listing_id={}", listing_id);
 
@@ -250,28 +460,96 @@ pub async fn update_listing(
     }))
 }
 
+/// GET /api/listings/:listing_id/price-history - 価格変更履歴（古い順）
+#[utoipa::path(
+    get,
+    path = "/api/listings/{listing_id}/price-history",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = PriceHistoryResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_price_history(
+    State(state): State<Arc<AppState>>,
+    Path(listing_id): Path<String>,
+) -> Result<Json<PriceHistoryResponse>, ApiError> {
+    let listing_exists: Option<(i32,)> = sqlx::query_as(
+        "SELECT 1 FROM listings WHERE listing_id = ?"
+    )
+    .bind(&listing_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if listing_exists.is_none() {
+        return Err(ApiError::NotFound("Listing not found".to_string()));
+    }
+
+    let history: Vec<ListingPriceChange> = sqlx::query_as(
+        "SELECT * FROM listing_price_history WHERE listing_id = ? ORDER BY changed_at_ms ASC, id ASC"
+    )
+    .bind(&listing_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(PriceHistoryResponse {
+        success: true,
+        listing_id,
+        history,
+    }))
+}
+
 /// DELETE /api/listings/:listing_id - Listing削除（論理削除）
+#[utoipa::path(
+    delete,
+    path = "/api/listings/{listing_id}",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ListingCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn delete_listing(
     State(state): State<Arc<AppState>>,
     Path(listing_id): Path<String>,
-) -> Result<Json<ListingCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ListingCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
-    let result = sqlx::query(
+    let mut tx = state.db.begin().await?;
+
+    let listing: Listing = sqlx::query_as("SELECT * FROM listings WHERE listing_id = ?")
+        .bind(&listing_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Listing not found".to_string()))?;
+
+    sqlx::query(
         "UPDATE listings SET is_alive = 0, updated_at_ms = ? WHERE listing_id = ?"
     )
     .bind(now_ms)
     .bind(&listing_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .execute(&mut *tx)
+    .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(error_response(StatusCode::NOT_FOUND, "Listing not found".to_string()));
+    // 初回の削除のみ Tombstone を記録
+    if listing.is_alive == 1 {
+        tombstones::record_tombstone(
+            &mut *tx,
+            tombstone_kind::LISTING,
+            Some(&listing.vendor_stable_id),
+            &listing_id,
+            &listing.env,
+            now_ms,
+        )
+        .await?;
     }
 
+    tx.commit().await?;
+
     info!("Listing deleted: listing_id={}", listing_id);
 
     Ok(Json(ListingCreateResponse {
@@ -280,35 +558,1409 @@ pub async fn delete_listing(
     }))
 }
 
+/// GET /api/vendors/:stable_id/listings/summary - Vendor別Listing集計
+/// 行をロードせず、1回の集計クエリでステータス別件数・在庫・価格帯を返す
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{stable_id}/listings/summary",
+    tag = "listings",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ListingSummaryResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_vendor_listings_summary(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Json<ListingSummaryResponse>, ApiError> {
+    // Vendor存在チェック
+    let vendor_exists: Option<(i32,)> = sqlx::query_as(
+        "SELECT 1 FROM vendors WHERE stable_id = ?"
+    )
+    .bind(&stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if vendor_exists.is_none() {
+        return Err(ApiError::NotFound("Vendor not found".to_string()));
+    }
+
+    let row: ListingSummaryRow = sqlx::query_as(r#"
+        SELECT
+            COUNT(*) AS total,
+            SUM(CASE WHEN status = ? THEN 1 ELSE 0 END) AS active,
+            SUM(CASE WHEN status = ? THEN 1 ELSE 0 END) AS sold_out,
+            SUM(CASE WHEN status = ? THEN 1 ELSE 0 END) AS cancelled,
+            SUM(supply_total) AS supply_total,
+            SUM(supply_remaining) AS supply_remaining,
+            MIN(price) AS min_price,
+            MAX(price) AS max_price
+        FROM listings
+        WHERE vendor_stable_id = ? AND is_alive = 1
+    "#)
+    .bind(status::ACTIVE)
+    .bind(status::SOLD_OUT)
+    .bind(status::CANCELLED)
+    .bind(&stable_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(ListingSummaryResponse {
+        success: true,
+        vendor_stable_id: stable_id,
+        total: row.total,
+        counts: ListingStatusCounts {
+            active: row.active.unwrap_or(0),
+            sold_out: row.sold_out.unwrap_or(0),
+            cancelled: row.cancelled.unwrap_or(0),
+        },
+        supply_total: row.supply_total.unwrap_or(0),
+        supply_remaining: row.supply_remaining.unwrap_or(0),
+        min_price: row.min_price,
+        max_price: row.max_price,
+    }))
+}
+
+/// 一括作成の上限件数
+const MAX_BATCH_CREATE_ITEMS: usize = 500;
+
+/// POST /api/listings/batch - Listing一括作成
+///
+/// 1トランザクションで挿入し、listing_id ごとの成否を返す。
+/// fail_fast が false の場合は失敗した項目のみスキップ（SAVEPOINT でロールバック）。
+/// 件数が MAX_BATCH_CREATE_ITEMS を超える場合は 422。
+#[utoipa::path(
+    post,
+    path = "/api/listings/batch",
+    tag = "listings",
+    request_body = BatchCreateListingsRequest,
+    responses(
+        (status = 200, description = "成功", body = BatchListingResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn batch_create_listings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<BatchCreateListingsRequest>,
+) -> Result<Json<BatchListingResponse>, ApiError> {
+    if req.listings.is_empty() {
+        return Err(ApiError::BadRequest("listings must not be empty".to_string()));
+    }
+    if req.listings.len() > MAX_BATCH_CREATE_ITEMS {
+        return Err(ApiError::Unprocessable(format!(
+            "listings must be at most {} items",
+            MAX_BATCH_CREATE_ITEMS
+        )));
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let mut results = HashMap::new();
+    let mut errors = HashMap::new();
+    // Vendor ごとの検証結果キャッシュ
+    let mut vendor_checks: HashMap<String, Result<(), String>> = HashMap::new();
+
+    // challenge は1回限りなので、署名は Vendor ごとではなくリクエストにつき1回だけ検証する
+    let caller = if state.config.enforce_vendor_owner {
+        Some(vendors::verify_owner_signature(&state, &headers).await.map_err(|e| e.to_string()))
+    } else {
+        None
+    };
+
+    let mut tx = state.db.begin().await?;
+
+    for item in &req.listings {
+        let vendor_check = match vendor_checks.get(&item.vendor_stable_id) {
+            Some(check) => check.clone(),
+            None => {
+                let check = check_batch_vendor(&state, caller.as_ref(), &item.vendor_stable_id).await;
+                vendor_checks.insert(item.vendor_stable_id.clone(), check.clone());
+                check
+            }
+        };
+
+        let outcome = match validate_listing(item, &state.config.webhook_allowed_hosts).and(vendor_check) {
+            Err(message) => Err(message),
+            Ok(()) => {
+                let mut savepoint = tx.begin().await?;
+                match insert_listing(&mut *savepoint, item, now_ms).await {
+                    Ok(true) => savepoint.commit().await.map_err(|e| format!("DB error: {}", e)),
+                    Ok(false) => Err(listing_owned_by_other_vendor(&item.listing_id)),
+                    Err(e) => {
+                        let _ = savepoint.rollback().await;
+                        Err(format!("DB error: {}", e))
+                    }
+                }
+            }
+        };
+
+        match outcome {
+            Ok(()) => {
+                results.insert(item.listing_id.clone(), true);
+            }
+            Err(message) => {
+                results.insert(item.listing_id.clone(), false);
+                errors.insert(item.listing_id.clone(), message);
+                if req.fail_fast {
+                    // 全件ロールバック
+                    let _ = tx.rollback().await;
+                    for r in results.values_mut() {
+                        *r = false;
+                    }
+                    warn!("Listing batch aborted (fail_fast): {} item(s)", req.listings.len());
+                    return Ok(Json(BatchListingResponse {
+                        success: false,
+                        results,
+                        errors,
+                    }));
+                }
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    let created = results.values().filter(|ok| **ok).count();
+    info!("Listing batch created: {}/{} item(s)", created, req.listings.len());
+
+    Ok(Json(BatchListingResponse {
+        success: true,
+        results,
+        errors,
+    }))
+}
+
+/// ステータス一括変更の上限件数
+const MAX_BATCH_STATUS_ITEMS: usize = 500;
+
+/// POST /api/vendors/:stable_id/listings/batch_status - Vendor の Listing のステータス一括変更
+///
+/// 1トランザクションで更新し、listing_id ごとの成否を返す。
+/// 他 Vendor の Listing・存在しない Listing・許可されない遷移はその項目のみ失敗（errors に理由）。
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{stable_id}/listings/batch_status",
+    tag = "listings",
+    params(("stable_id" = String, Path)),
+    request_body = BatchListingStatusRequest,
+    responses(
+        (status = 200, description = "成功", body = BatchListingResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn batch_update_listing_status(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<BatchListingStatusRequest>,
+) -> Result<Json<BatchListingResponse>, ApiError> {
+    if req.listing_ids.is_empty() {
+        return Err(ApiError::invalid_fields("listing_ids must not be empty", &["listing_ids"]));
+    }
+    if req.listing_ids.len() > MAX_BATCH_STATUS_ITEMS {
+        return Err(ApiError::invalid_fields(
+            format!("listing_ids must be at most {} items", MAX_BATCH_STATUS_ITEMS),
+            &["listing_ids"],
+        ));
+    }
+    if !is_known_status(req.status) {
+        return Err(ApiError::invalid_fields(format!("Unknown status: {}", req.status), &["status"]));
+    }
+
+    let vendor_exists: Option<(i32,)> = sqlx::query_as(
+        "SELECT 1 FROM vendors WHERE stable_id = ? AND is_alive = 1"
+    )
+    .bind(&stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+    if vendor_exists.is_none() {
+        return Err(ApiError::NotFound("Vendor not found".to_string()));
+    }
+    vendors::verify_vendor_owner(&state, &headers, &stable_id).await?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let mut results = HashMap::new();
+    let mut errors = HashMap::new();
+
+    let mut tx = state.db.begin().await?;
+
+    for listing_id in &req.listing_ids {
+        if results.contains_key(listing_id) {
+            continue;
+        }
+
+        let current: Option<(String, i32, i64)> = sqlx::query_as(
+            "SELECT vendor_stable_id, status, supply_remaining FROM listings WHERE listing_id = ? AND is_alive = 1"
+        )
+        .bind(listing_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let outcome = match current {
+            None => Err("Listing not found".to_string()),
+            Some((vendor_stable_id, _, _)) if vendor_stable_id != stable_id => {
+                Err(format!("Listing does not belong to vendor {}", stable_id))
+            }
+            Some((_, from, supply_remaining)) => validate_status_transition(from, req.status, supply_remaining),
+        };
+
+        match outcome {
+            Ok(()) => {
+                sqlx::query("UPDATE listings SET status = ?, updated_at_ms = ? WHERE listing_id = ?")
+                    .bind(req.status)
+                    .bind(now_ms)
+                    .bind(listing_id)
+                    .execute(&mut *tx)
+                    .await?;
+                results.insert(listing_id.clone(), true);
+            }
+            Err(message) => {
+                results.insert(listing_id.clone(), false);
+                errors.insert(listing_id.clone(), message);
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    let updated = results.values().filter(|ok| **ok).count();
+    info!(
+        "Listing batch status: vendor={}, status={}, updated {}/{} item(s)",
+        stable_id,
+        req.status,
+        updated,
+        results.len()
+    );
+
+    Ok(Json(BatchListingResponse {
+        success: true,
+        results,
+        errors,
+    }))
+}
+
+/// POST /api/listings/:listing_id/reserve - 購入手続き中の在庫確保
+#[utoipa::path(
+    post,
+    path = "/api/listings/{listing_id}/reserve",
+    tag = "listings",
+    params(("listing_id" = String, Path)),
+    request_body = ReserveListingRequest,
+    responses(
+        (status = 200, description = "成功", body = ReservationResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn reserve_listing(
+    State(state): State<Arc<AppState>>,
+    Path(listing_id): Path<String>,
+    ApiJson(req): ApiJson<ReserveListingRequest>,
+) -> Result<Json<ReservationResponse>, ApiError> {
+    if req.qty < 1 {
+        return Err(ApiError::BadRequest("qty must be >= 1".to_string()));
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let ttl_secs = req
+        .ttl_secs
+        .filter(|t| *t > 0)
+        .map_or(state.config.reservation_ttl_secs, |t| t.min(state.config.reservation_ttl_secs));
+
+    let exists: Option<(String,)> = sqlx::query_as(
+        "SELECT listing_id FROM listings WHERE listing_id = ? AND is_alive = 1"
+    )
+    .bind(&listing_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if exists.is_none() {
+        return Err(ApiError::NotFound("Listing not found".to_string()));
+    }
+
+    let reservation = Reservation {
+        reservation_id: Uuid::new_v4().to_string(),
+        listing_id: listing_id.clone(),
+        buyer: req.buyer,
+        qty: req.qty,
+        status: reservation_status::HELD,
+        created_at_ms: now_ms,
+        expires_at_ms: now_ms + ttl_secs * 1000,
+        consumed_at_ms: None,
+        released_at_ms: None,
+    };
+
+    // 在庫の条件付き減算と Reservation 挿入を1トランザクションで行う
+    let mut tx = state.db.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE listings SET supply_remaining = supply_remaining - ?, updated_at_ms = ? WHERE listing_id = ? AND is_alive = 1 AND status = ? AND supply_remaining >= ?"
+    )
+    .bind(req.qty)
+    .bind(now_ms)
+    .bind(&listing_id)
+    .bind(status::ACTIVE)
+    .bind(req.qty)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict("Insufficient supply".to_string()));
+    }
+
+    sqlx::query(
+        "INSERT INTO reservations (reservation_id, listing_id, buyer, qty, status, created_at_ms, expires_at_ms) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&reservation.reservation_id)
+    .bind(&reservation.listing_id)
+    .bind(&reservation.buyer)
+    .bind(reservation.qty)
+    .bind(reservation.status)
+    .bind(reservation.created_at_ms)
+    .bind(reservation.expires_at_ms)
+    .execute(&mut *tx)
+    .await?;
+
+    // 在庫がちょうど 0 になった場合は通知済みマークを付ける（マークできた1件だけが通知する）
+    let sold_out = mark_sold_out_notified(&mut *tx, &listing_id, now_ms)
+        .await?;
+
+    tx.commit().await?;
+
+    if let Some(listing) = sold_out {
+        if let Some(url) = listing.webhook_url.clone() {
+            info!("Listing sold out, sending webhook: listing_id={}", listing.listing_id);
+            webhook::spawn_delivery(
+                &state.config,
+                url,
+                webhook::EVENT_LISTING_SOLD_OUT,
+                webhook::SoldOutPayload {
+                    event: webhook::EVENT_LISTING_SOLD_OUT,
+                    listing_id: listing.listing_id,
+                    vendor_stable_id: listing.vendor_stable_id,
+                    supply_total: listing.supply_total,
+                    sold_out_at_ms: now_ms,
+                },
+            );
+        }
+    }
+
+    info!(
+        "Listing reserved: listing_id={}, reservation_id={}, qty={}",
+        listing_id, reservation.reservation_id, reservation.qty
+    );
+
+    Ok(Json(ReservationResponse {
+        success: true,
+        reservation,
+    }))
+}
+
+/// POST /api/reservations/:reservation_id/consume - 購入完了（確保した在庫を確定）
+#[utoipa::path(
+    post,
+    path = "/api/reservations/{reservation_id}/consume",
+    tag = "listings",
+    params(("reservation_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ReservationResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn consume_reservation(
+    State(state): State<Arc<AppState>>,
+    Path(reservation_id): Path<String>,
+) -> Result<Json<ReservationResponse>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let result = sqlx::query(
+        "UPDATE reservations SET status = ?, consumed_at_ms = ? WHERE reservation_id = ? AND status = ? AND expires_at_ms > ?"
+    )
+    .bind(reservation_status::CONSUMED)
+    .bind(now_ms)
+    .bind(&reservation_id)
+    .bind(reservation_status::HELD)
+    .bind(now_ms)
+    .execute(&state.db)
+    .await?;
+
+    let reservation: Option<Reservation> = sqlx::query_as(
+        "SELECT * FROM reservations WHERE reservation_id = ?"
+    )
+    .bind(&reservation_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let reservation = reservation.ok_or_else(|| {
+        ApiError::NotFound("Reservation not found".to_string())
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict(
+            "Reservation is expired or no longer held".to_string(),
+        ));
+    }
+
+    info!("Reservation consumed: reservation_id={}", reservation_id);
+
+    Ok(Json(ReservationResponse {
+        success: true,
+        reservation,
+    }))
+}
+
+/// 期限切れの Reservation を解放し、在庫を Listing に戻す（バックグラウンドジョブ用）
+pub async fn release_expired_reservations(state: &Arc<AppState>) -> anyhow::Result<usize> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let expired: Vec<Reservation> = sqlx::query_as(
+        "SELECT * FROM reservations WHERE status = ? AND expires_at_ms <= ?"
+    )
+    .bind(reservation_status::HELD)
+    .bind(now_ms)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut count = 0;
+    for reservation in expired {
+        let mut tx = state.db.begin().await?;
+
+        // consume と競合した場合は何もしない
+        let result = sqlx::query(
+            "UPDATE reservations SET status = ?, released_at_ms = ? WHERE reservation_id = ? AND status = ?"
+        )
+        .bind(reservation_status::RELEASED)
+        .bind(now_ms)
+        .bind(&reservation.reservation_id)
+        .bind(reservation_status::HELD)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            continue;
+        }
+
+        sqlx::query(
+            "UPDATE listings SET supply_remaining = supply_remaining + ?, sold_out_notified_at_ms = NULL, updated_at_ms = ? WHERE listing_id = ?"
+        )
+        .bind(reservation.qty)
+        .bind(now_ms)
+        .bind(&reservation.listing_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        count += 1;
+    }
+
+    if count > 0 {
+        info!("Released {} expired reservation(s)", count);
+    }
+
+    Ok(count)
+}
+
 // ========================================
 // Helper Functions
 // ========================================
 
-fn listing_to_response(l: &Listing) -> ListingResponse {
+pub(crate) fn listing_to_response(l: &Listing) -> ListingResponse {
     ListingResponse {
         listing_id: l.listing_id.clone(),
         vendor_stable_id: l.vendor_stable_id.clone(),
         vendor_object_id: l.vendor_object_id.clone(),
         seller: l.seller.clone(),
         item_type: l.item_type,
+        item_type_label: item_type::label(l.item_type),
         item_id: l.item_id.clone(),
         price: l.price,
         currency: l.currency.clone(),
         supply_total: l.supply_total,
         supply_remaining: l.supply_remaining,
         status: l.status,
+        status_label: status::label(l.status),
         created_at_ms: l.created_at_ms,
         updated_at_ms: l.updated_at_ms,
+        created_at_iso: l.created_at_ms.and_then(ms_to_iso),
+        updated_at_iso: l.updated_at_ms.and_then(ms_to_iso),
         is_alive: l.is_alive == 1,
         inventory_id: l.inventory_id.clone(),
         manifest_id: l.manifest_id.clone(),
         title: l.title.clone(),
         artist: l.artist.clone(),
         cover_url: l.cover_url.clone(),
+        featured: l.featured == 1,
+        featured_rank: l.featured_rank,
+    }
+}
+
+/// Listing 作成リクエストの値検証
+fn validate_listing(req: &CreateListingRequest, webhook_allowed_hosts: &[String]) -> Result<(), String> {
+    if req.listing_id.trim().is_empty() {
+        return Err("listing_id is required".to_string());
+    }
+    if req.price < 0 {
+        return Err("price must be >= 0".to_string());
+    }
+    if req.supply_total < 1 {
+        return Err("supply_total must be >= 1".to_string());
+    }
+    if let Some(url) = &req.webhook_url {
+        webhook::validate_url(url, webhook_allowed_hosts)?;
+    }
+    validate_featured_rank(req.featured_rank)?;
+    Ok(())
+}
+
+/// featured_rank は 0 以上
+fn validate_featured_rank(featured_rank: Option<i64>) -> Result<(), String> {
+    match featured_rank {
+        Some(rank) if rank < 0 => Err(format!("featured_rank must be >= 0 (got {})", rank)),
+        _ => Ok(()),
+    }
+}
+
+/// 在庫更新値の検証（0 以上 supply_total 以下）
+fn validate_supply_remaining(supply_remaining: i64, supply_total: i64) -> Result<(), String> {
+    if supply_remaining < 0 {
+        return Err(format!("supply_remaining must be >= 0 (got {})", supply_remaining));
+    }
+    if supply_remaining > supply_total {
+        return Err(format!(
+            "supply_remaining must be <= supply_total ({}) (got {})",
+            supply_total, supply_remaining
+        ));
+    }
+    Ok(())
+}
+
+/// 在庫の変化に伴う自動ステータス（ACTIVE で在庫 0 → SOLD_OUT、SOLD_OUT で在庫あり → ACTIVE）
+/// それ以外（SUSPENDED 等）は変更しない
+fn auto_supply_status(current: i32, supply_remaining: i64) -> Option<i32> {
+    match current {
+        status::ACTIVE if supply_remaining == 0 => Some(status::SOLD_OUT),
+        status::SOLD_OUT if supply_remaining > 0 => Some(status::ACTIVE),
+        _ => None,
+    }
+}
+
+/// 一括作成時の Vendor 存在・オーナー検証
+async fn check_batch_vendor(
+    state: &AppState,
+    caller: Option<&Result<String, String>>,
+    vendor_stable_id: &str,
+) -> Result<(), String> {
+    let vendor_exists: Option<(i32,)> = sqlx::query_as(
+        "SELECT 1 FROM vendors WHERE stable_id = ? AND is_alive = 1"
+    )
+    .bind(vendor_stable_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| format!("DB error: {}", e))?;
+
+    if vendor_exists.is_none() {
+        return Err(format!("Vendor not found: {}", vendor_stable_id));
+    }
+
+    match caller {
+        None => Ok(()),
+        Some(Err(message)) => Err(message.clone()),
+        Some(Ok(caller)) => vendors::ensure_vendor_owner(state, caller, vendor_stable_id)
+            .await
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// 定義済みの Listing ステータスか
+fn is_known_status(value: i32) -> bool {
+    matches!(value, status::ACTIVE | status::SUSPENDED | status::DELETED | status::SOLD_OUT | status::CANCELLED)
+}
+
+/// Listing のステータス遷移を検証（同一ステータスへの更新は許可）
+///
+/// ACTIVE    → SUSPENDED / SOLD_OUT / CANCELLED / DELETED
+/// SUSPENDED → ACTIVE / CANCELLED / DELETED
+/// SOLD_OUT  → ACTIVE（再入荷）/ CANCELLED / DELETED
+/// CANCELLED → DELETED
+/// DELETED   → （終端）
+fn validate_status_transition(from: i32, to: i32, supply_remaining: i64) -> Result<(), String> {
+    if !is_known_status(to) {
+        return Err(format!("Unknown status: {}", to));
+    }
+    if from == to {
+        return Ok(());
+    }
+
+    let allowed = match from {
+        status::ACTIVE => matches!(to, status::SUSPENDED | status::SOLD_OUT | status::CANCELLED | status::DELETED),
+        status::SUSPENDED => matches!(to, status::ACTIVE | status::CANCELLED | status::DELETED),
+        status::SOLD_OUT => matches!(to, status::ACTIVE | status::CANCELLED | status::DELETED),
+        status::CANCELLED => to == status::DELETED,
+        _ => false,
+    };
+    if !allowed {
+        return Err(format!("Invalid status transition: {} -> {}", from, to));
+    }
+
+    if to == status::ACTIVE && supply_remaining <= 0 {
+        return Err("Cannot activate a listing with no remaining supply".to_string());
     }
+
+    Ok(())
+}
+
+/// Listing を挿入（listing_id が同じ Vendor の既存 Listing なら更新）
+/// 別の Vendor の Listing と listing_id が衝突した場合は何もせず false を返す
+async fn insert_listing<'e, E>(executor: E, req: &CreateListingRequest, now_ms: i64) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let result = sqlx::query(r#"
+        INSERT INTO listings (
+            listing_id, vendor_stable_id, vendor_object_id, seller,
+            item_type, item_id, price, currency,
+            supply_total, supply_remaining, status,
+            env, created_at_ms, updated_at_ms, is_alive,
+            inventory_id, manifest_id, title, artist, cover_url, webhook_url,
+            featured, featured_rank
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, 'devnet', ?, ?, 1, ?, ?, ?, ?, ?, ?, COALESCE(?, 0), ?)
+        ON CONFLICT(listing_id) DO UPDATE SET
+            vendor_object_id = COALESCE(excluded.vendor_object_id, listings.vendor_object_id),
+            seller = COALESCE(excluded.seller, listings.seller),
+            price = excluded.price,
+            supply_remaining = excluded.supply_remaining,
+            updated_at_ms = excluded.updated_at_ms,
+            is_alive = 1,
+            inventory_id = COALESCE(excluded.inventory_id, listings.inventory_id),
+            manifest_id = COALESCE(excluded.manifest_id, listings.manifest_id),
+            title = COALESCE(excluded.title, listings.title),
+            artist = COALESCE(excluded.artist, listings.artist),
+            cover_url = COALESCE(excluded.cover_url, listings.cover_url),
+            webhook_url = COALESCE(excluded.webhook_url, listings.webhook_url),
+            sold_out_notified_at_ms = NULL,
+            -- featured 未指定の再作成では変更しない（SET 内の listings.* は更新前の値）
+            featured = CASE WHEN ? IS NULL THEN listings.featured ELSE excluded.featured END,
+            featured_rank = CASE WHEN COALESCE(?, listings.featured) = 0 THEN NULL
+                ELSE COALESCE(excluded.featured_rank, listings.featured_rank) END
+        -- 別の Vendor の Listing は上書きしない
+        WHERE listings.vendor_stable_id = excluded.vendor_stable_id
+    "#)
+    .bind(&req.listing_id)
+    .bind(&req.vendor_stable_id)
+    .bind(&req.vendor_object_id)
+    .bind(&req.seller)
+    .bind(req.item_type)
+    .bind(&req.item_id)
+    .bind(req.price)
+    .bind(&req.currency)
+    .bind(req.supply_total)
+    .bind(req.supply_total) // supply_remaining = supply_total initially
+    .bind(now_ms)
+    .bind(now_ms)
+    .bind(&req.inventory_id)
+    .bind(&req.manifest_id)
+    .bind(&req.title)
+    .bind(&req.artist)
+    .bind(&req.cover_url)
+    .bind(&req.webhook_url)
+    .bind(req.featured)
+    // featured でない Listing には順位を持たせない
+    .bind(req.featured_rank.filter(|_| req.featured == Some(true)))
+    .bind(req.featured)
+    .bind(req.featured)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// listing_id が別の Vendor の Listing と衝突した場合のエラーメッセージ
+fn listing_owned_by_other_vendor(listing_id: &str) -> String {
+    format!("Listing {} belongs to another vendor", listing_id)
+}
+
+/// 在庫が 0 の Listing に売り切れ通知済みマークを付け、マークできた場合は Listing を返す
+/// Webhook 未設定・既に通知済み・在庫が残っている場合は None
+async fn mark_sold_out_notified<'e, E>(executor: E, listing_id: &str, now_ms: i64) -> Result<Option<Listing>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_as(
+        "UPDATE listings SET sold_out_notified_at_ms = ? WHERE listing_id = ? AND supply_remaining = 0 AND webhook_url IS NOT NULL AND sold_out_notified_at_ms IS NULL RETURNING *"
+    )
+    .bind(now_ms)
+    .bind(listing_id)
+    .fetch_optional(executor)
+    .await
 }
 
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    warn!("API Error: {}", message);
-    (status, Json(ErrorResponse { success: false, error: message }))
+/// LIKE パターン用にワイルドカード（% _）とエスケープ文字をエスケープ
+fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::models::status;
+    use crate::test_support::{listing_body, OwnerKey, TestApp};
+
+    /// 一覧レスポンスの listing_id（返却順）
+    fn listing_ids(body: &serde_json::Value) -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| l["listing_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn listing_exists(app: &TestApp, listing_id: &str) -> bool {
+        app.get(&format!("/api/listings/{}", listing_id)).await.status == 200
+    }
+
+    async fn supply_remaining(app: &TestApp, listing_id: &str) -> i64 {
+        app.get(&format!("/api/listings/{}", listing_id)).await.json()["listing"]["supply_remaining"]
+            .as_i64()
+            .unwrap()
+    }
+
+    /// title / artist 付きの Listing を作成
+    async fn create_titled_listing(app: &TestApp, vendor: &str, listing_id: &str, price: i64, title: &str, artist: &str) {
+        let mut body = listing_body(vendor, listing_id, price, 1);
+        body["title"] = json!(title);
+        body["artist"] = json!(artist);
+        let res = app.post_json("/api/listings", body).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+    }
+
+    #[tokio::test]
+    async fn vendor_listings_summary_aggregates_by_status() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-active", 100, 5).await;
+        app.create_listing(&vendor, "L-sold", 300, 2).await;
+        app.create_listing(&vendor, "L-cancel", 50, 1).await;
+        let res = app.put_json("/api/listings/L-sold", json!({ "supply_remaining": 0 })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+        let res = app.put_json("/api/listings/L-cancel", json!({ "status": status::CANCELLED })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+
+        let res = app.get(&format!("/api/vendors/{}/listings/summary", vendor)).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["counts"], json!({ "active": 1, "sold_out": 1, "cancelled": 1 }));
+        assert_eq!(body["supply_total"], 8);
+        assert_eq!(body["supply_remaining"], 6);
+        assert_eq!(body["min_price"], 50);
+        assert_eq!(body["max_price"], 300);
+    }
+
+    #[tokio::test]
+    async fn vendor_listings_summary_empty_and_unknown_vendor() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+
+        let body = app.get(&format!("/api/vendors/{}/listings/summary", vendor)).await.json();
+        assert_eq!(body["total"], 0);
+        assert_eq!(body["supply_total"], 0);
+        assert!(body["min_price"].is_null());
+
+        let res = app.get("/api/vendors/VENDOR_MISSING/listings/summary").await;
+        assert_eq!(res.status, 404);
+    }
+
+    #[tokio::test]
+    async fn list_listings_searches_title_and_artist_case_insensitively() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        create_titled_listing(&app, &vendor, "L-1", 100, "Midnight Drive", "Neon Cats").await;
+        create_titled_listing(&app, &vendor, "L-2", 200, "Morning", "midnight club").await;
+        create_titled_listing(&app, &vendor, "L-3", 300, "100% Pure", "Someone").await;
+
+        let mut found = listing_ids(&app.get("/api/listings?q=MIDNIGHT").await.json());
+        found.sort();
+        assert_eq!(found, ["L-1", "L-2"]);
+
+        // LIKE のワイルドカードは文字として扱う
+        assert_eq!(listing_ids(&app.get("/api/listings?q=100%25").await.json()), ["L-3"]);
+        assert!(listing_ids(&app.get("/api/listings?q=_").await.json()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_listings_orders_by_price() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        for (id, price) in [("L-mid", 200), ("L-low", 100), ("L-high", 300)] {
+            app.create_listing(&vendor, id, price, 1).await;
+        }
+
+        assert_eq!(listing_ids(&app.get("/api/listings?order=price_asc").await.json()), ["L-low", "L-mid", "L-high"]);
+        assert_eq!(listing_ids(&app.get("/api/listings?order=price_desc").await.json()), ["L-high", "L-mid", "L-low"]);
+        assert_eq!(app.get("/api/listings?order=cheapest").await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn reservations_hold_supply_and_expired_ones_are_released() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 3).await;
+
+        let res = app.post_json("/api/listings/L-1/reserve", json!({ "qty": 2 })).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let held = res.json()["reservation"]["reservation_id"].as_str().unwrap().to_string();
+        assert_eq!(supply_remaining(&app, "L-1").await, 1);
+        assert_eq!(app.post_json("/api/listings/L-1/reserve", json!({ "qty": 2 })).await.status, 409);
+
+        // 期限切れにして解放ジョブを実行
+        sqlx::query("UPDATE reservations SET expires_at_ms = 0 WHERE reservation_id = ?")
+            .bind(&held)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(release_expired_reservations(&app.state).await.unwrap(), 1);
+        assert_eq!(supply_remaining(&app, "L-1").await, 3);
+        assert_eq!(app.post_json(&format!("/api/reservations/{}/consume", held), json!({})).await.status, 409);
+    }
+
+    #[tokio::test]
+    async fn consumed_reservation_keeps_supply() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 3).await;
+
+        let res = app.post_json("/api/listings/L-1/reserve", json!({ "qty": 1, "ttl_secs": 60 })).await;
+        let reservation = res.json()["reservation"].clone();
+        let id = reservation["reservation_id"].as_str().unwrap();
+        assert!(reservation["expires_at_ms"].as_i64().unwrap() - reservation["created_at_ms"].as_i64().unwrap() <= 60_000);
+
+        let res = app.post_json(&format!("/api/reservations/{}/consume", id), json!({})).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["reservation"]["status"], reservation_status::CONSUMED);
+        assert_eq!(release_expired_reservations(&app.state).await.unwrap(), 0);
+        assert_eq!(supply_remaining(&app, "L-1").await, 2);
+
+        assert_eq!(app.post_json("/api/reservations/missing/consume", json!({})).await.status, 404);
+        assert_eq!(app.post_json("/api/listings/L-1/reserve", json!({ "qty": 0 })).await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn create_listing_checks_owner_when_enforced() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        let (owner, stranger) = (OwnerKey::new(1), OwnerKey::new(2));
+        let vendor = app.create_vendor(Some(&owner.address())).await;
+        let create = |id: &str, headers: Vec<(&'static str, String)>| {
+            let (app, body) = (&app, listing_body(&vendor, id, 100, 1));
+            async move {
+                let headers: Vec<(&str, &str)> = headers.iter().map(|(n, v)| (*n, v.as_str())).collect();
+                app.send_json(Method::POST, "/api/listings", Some(body), &headers).await
+            }
+        };
+
+        assert_eq!(create("L-1", vec![]).await.status, 401);
+        // 署名のない X-Owner-Address は受け付けない
+        assert_eq!(create("L-1", vec![("x-owner-address", owner.address())]).await.status, 401);
+        assert_eq!(create("L-1", app.owner_headers(&stranger).await.0).await.status, 403);
+        assert_eq!(create("L-1", app.owner_headers(&owner).await.0).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn recreating_another_vendors_listing_is_rejected() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        let (victim_key, attacker_key) = (OwnerKey::new(1), OwnerKey::new(2));
+        let victim = app.create_vendor(Some(&victim_key.address())).await;
+        let attacker = app.create_vendor(Some(&attacker_key.address())).await;
+        let res = app
+            .send_json(Method::POST, "/api/listings", Some(listing_body(&victim, "L-1", 100, 5)), &app.owner_headers(&victim_key).await.pairs())
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        // 自分の Vendor の owner として、他人の listing_id を再作成しても上書きされない
+        let hijack = listing_body(&attacker, "L-1", 1, 5);
+        let res = app
+            .send_json(Method::POST, "/api/listings", Some(hijack.clone()), &app.owner_headers(&attacker_key).await.pairs())
+            .await;
+        assert_eq!(res.status, 409, "{}", res.text());
+        let res = app
+            .send_json(Method::POST, "/api/listings/batch", Some(json!({ "listings": [hijack] })), &app.owner_headers(&attacker_key).await.pairs())
+            .await
+            .json();
+        assert_eq!(res["results"]["L-1"], false);
+        assert!(res["errors"]["L-1"].as_str().unwrap().contains("another vendor"));
+
+        let listing = app.get("/api/listings/L-1").await.json();
+        assert_eq!(listing["listing"]["vendor_stable_id"], victim.as_str());
+        assert_eq!(listing["listing"]["price"], 100);
+
+        // 同じ Vendor の再作成は従来どおり更新する
+        let res = app
+            .send_json(Method::POST, "/api/listings", Some(listing_body(&victim, "L-1", 150, 5)), &app.owner_headers(&victim_key).await.pairs())
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(app.get("/api/listings/L-1").await.json()["listing"]["price"], 150);
+    }
+
+    #[tokio::test]
+    async fn create_listing_skips_owner_check_by_default() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(Some("0xabc")).await;
+        let res = app
+            .send_json(Method::POST, "/api/listings", Some(listing_body(&vendor, "L-1", 100, 1)), &[("x-owner-address", "0xdef")])
+            .await;
+        assert_eq!(res.status, 200);
+    }
+
+    #[tokio::test]
+    async fn batch_create_skips_invalid_items_by_default() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let body = json!({
+            "listings": [
+                listing_body(&vendor, "L-ok", 100, 1),
+                listing_body(&vendor, "L-bad", -1, 1),
+                listing_body("VENDOR_MISSING", "L-orphan", 100, 1),
+            ],
+        });
+
+        let res = app.post_json("/api/listings/batch", body).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["success"], true);
+        assert_eq!(body["results"], json!({ "L-ok": true, "L-bad": false, "L-orphan": false }));
+        assert!(body["errors"]["L-bad"].as_str().unwrap().contains("price"));
+        assert!(body["errors"].get("L-ok").is_none());
+        assert!(listing_exists(&app, "L-ok").await);
+        assert!(!listing_exists(&app, "L-bad").await);
+    }
+
+    #[tokio::test]
+    async fn batch_create_fail_fast_rolls_back_everything() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let body = json!({
+            "fail_fast": true,
+            "listings": [
+                listing_body(&vendor, "L-1", 100, 1),
+                listing_body(&vendor, "L-2", 100, 0),
+                listing_body(&vendor, "L-3", 100, 1),
+            ],
+        });
+
+        let body = app.post_json("/api/listings/batch", body).await.json();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["results"]["L-1"], false);
+        assert!(body["errors"]["L-2"].is_string());
+        assert!(!listing_exists(&app, "L-1").await);
+        assert!(!listing_exists(&app, "L-3").await);
+
+        let res = app.post_json("/api/listings/batch", json!({ "listings": [] })).await;
+        assert_eq!(res.status, 400);
+    }
+
+    #[tokio::test]
+    async fn batch_create_rejects_too_many_items() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let items: Vec<Value> = (0..=super::MAX_BATCH_CREATE_ITEMS)
+            .map(|i| listing_body(&vendor, &format!("L-{}", i), 100, 1))
+            .collect();
+
+        let res = app.post_json("/api/listings/batch", json!({ "listings": items })).await;
+        assert_eq!(res.status, 422, "{}", res.text());
+        assert!(!listing_exists(&app, "L-0").await);
+    }
+
+    #[tokio::test]
+    async fn batch_create_checks_owner_once_per_request_when_enforced() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        let (owner, stranger) = (OwnerKey::new(1), OwnerKey::new(2));
+        let first = app.create_vendor(Some(&owner.address())).await;
+        let second = app.create_vendor(Some(&owner.address())).await;
+        let foreign = app.create_vendor(Some(&stranger.address())).await;
+        let body = json!({
+            "listings": [
+                listing_body(&first, "L-1", 100, 1),
+                listing_body(&second, "L-2", 100, 1),
+                listing_body(&foreign, "L-3", 100, 1),
+            ],
+        });
+
+        // 1つの challenge で複数の Vendor を検証できる
+        let headers = app.owner_headers(&owner).await;
+        let res = app.send_json(Method::POST, "/api/listings/batch", Some(body.clone()), &headers.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["results"], json!({ "L-1": true, "L-2": true, "L-3": false }));
+
+        // 使用済みの challenge では全件失敗する
+        let res = app.send_json(Method::POST, "/api/listings/batch", Some(body), &headers.pairs()).await;
+        assert_eq!(res.json()["results"], json!({ "L-1": false, "L-2": false, "L-3": false }));
+    }
+
+    #[tokio::test]
+    async fn list_listings_filters_by_env() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-devnet", 100, 1).await;
+        app.create_listing(&vendor, "L-mainnet", 100, 1).await;
+        sqlx::query("UPDATE listings SET env = 'mainnet' WHERE listing_id = 'L-mainnet'")
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+
+        assert_eq!(listing_ids(&app.get("/api/listings?env=devnet").await.json()), vec!["L-devnet"]);
+        assert_eq!(listing_ids(&app.get("/api/listings?env=mainnet").await.json()), vec!["L-mainnet"]);
+        assert_eq!(app.get("/api/listings?env=all").await.json()["data"].as_array().unwrap().len(), 2);
+
+        let app_default = TestApp::with_config(|c| c.default_env = Some("devnet".to_string())).await;
+        let vendor = app_default.create_vendor(None).await;
+        app_default.create_listing(&vendor, "L-devnet", 100, 1).await;
+        app_default.create_listing(&vendor, "L-mainnet", 100, 1).await;
+        sqlx::query("UPDATE listings SET env = 'mainnet' WHERE listing_id = 'L-mainnet'")
+            .execute(&app_default.state.db)
+            .await
+            .unwrap();
+        assert_eq!(listing_ids(&app_default.get("/api/listings").await.json()), vec!["L-devnet"]);
+    }
+
+    /// from の状態（在庫 supply）から PUT で to へ遷移させ、ステータスコードを返す
+    async fn try_transition(app: &TestApp, vendor: &str, from: i32, supply: i64, to: i32) -> u16 {
+        let listing_id = format!("L-{}-{}-{}", from, supply, to);
+        app.create_listing(vendor, &listing_id, 100, 5).await;
+        sqlx::query("UPDATE listings SET status = ?, supply_remaining = ? WHERE listing_id = ?")
+            .bind(from)
+            .bind(supply)
+            .bind(&listing_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let res = app.put_json(&format!("/api/listings/{}", listing_id), json!({ "status": to })).await;
+        res.status.as_u16()
+    }
+
+    #[tokio::test]
+    async fn update_listing_enforces_status_transitions() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let cases = [
+            // 許可される遷移
+            (status::ACTIVE, 5, status::SUSPENDED, 200),
+            (status::SUSPENDED, 5, status::ACTIVE, 200),
+            (status::ACTIVE, 5, status::CANCELLED, 200),
+            (status::SOLD_OUT, 3, status::ACTIVE, 200),
+            (status::CANCELLED, 5, status::DELETED, 200),
+            (status::ACTIVE, 5, status::ACTIVE, 200),
+            // 不正な遷移
+            (status::CANCELLED, 5, status::ACTIVE, 422),
+            (status::SOLD_OUT, 0, status::ACTIVE, 422),
+            (status::SUSPENDED, 0, status::ACTIVE, 422),
+            (status::DELETED, 5, status::ACTIVE, 422),
+            (status::ACTIVE, 5, 99, 422),
+        ];
+        for (from, supply, to, expected) in cases {
+            assert_eq!(
+                try_transition(&app, &vendor, from, supply, to).await,
+                expected,
+                "{} (supply {}) -> {}",
+                from,
+                supply,
+                to
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_transition_leaves_listing_unchanged() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        assert_eq!(try_transition(&app, &vendor, status::CANCELLED, 5, status::ACTIVE).await, 422);
+        let listing = app.get(&format!("/api/listings/L-{}-5-{}", status::CANCELLED, status::ACTIVE)).await.json();
+        assert_eq!(listing["listing"]["status"], status::CANCELLED);
+    }
+
+    #[tokio::test]
+    async fn price_changes_are_recorded_in_order() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-priced", 100, 5).await;
+
+        for price in [150, 150, 120] {
+            let res = app.put_json("/api/listings/L-priced", json!({ "price": price })).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+        }
+        // 価格以外の更新は履歴に残らない
+        app.put_json("/api/listings/L-priced", json!({ "supply_remaining": 4 })).await;
+
+        let body = app.get("/api/listings/L-priced/price-history").await.json();
+        let changes: Vec<(i64, i64)> = body["history"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| (h["old_price"].as_i64().unwrap(), h["new_price"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(changes, vec![(100, 150), (150, 120)]);
+
+        assert_eq!(app.get("/api/listings/L-missing/price-history").await.status, 404);
+    }
+
+    #[tokio::test]
+    async fn listing_labels_match_integer_codes() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+
+        for (code, label) in [(item_type::NFT, "nft"), (item_type::FILE_DROP, "file_drop"), (item_type::EDITION, "edition")] {
+            let listing_id = format!("L_TYPE_{}", code);
+            let mut body = listing_body(&vendor, &listing_id, 100, 1);
+            body["item_type"] = json!(code);
+            assert_eq!(app.post_json("/api/listings", body).await.status, 200);
+
+            let listing = &app.get(&format!("/api/listings/{}", listing_id)).await.json()["listing"];
+            assert_eq!(listing["item_type"], code);
+            assert_eq!(listing["item_type_label"], label);
+        }
+
+        app.create_listing(&vendor, "L_STATUS", 100, 1).await;
+        for (code, label) in [
+            (status::ACTIVE, "active"),
+            (status::SUSPENDED, "suspended"),
+            (status::SOLD_OUT, "sold_out"),
+            (status::CANCELLED, "cancelled"),
+            (status::DELETED, "deleted"),
+        ] {
+            sqlx::query("UPDATE listings SET status = ? WHERE listing_id = 'L_STATUS'")
+                .bind(code)
+                .execute(&app.state.db)
+                .await
+                .unwrap();
+            let res = app.get("/api/listings/L_STATUS").await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            let listing = &res.json()["listing"];
+            assert_eq!(listing["status"], code);
+            assert_eq!(listing["status_label"], label);
+        }
+
+        assert_eq!(status::label(99), "unknown");
+        assert_eq!(item_type::label(99), "unknown");
+    }
+
+    async fn listing_status(app: &TestApp, listing_id: &str) -> i64 {
+        app.get(&format!("/api/listings/{}", listing_id)).await.json()["listing"]["status"]
+            .as_i64()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn supply_remaining_must_stay_within_supply_total() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 5).await;
+
+        for invalid in [6, -1] {
+            let res = app.put_json("/api/listings/L-1", json!({ "supply_remaining": invalid })).await;
+            assert_eq!(res.status, 422, "{}", invalid);
+            assert!(res.json()["error"].as_str().unwrap().contains("supply_remaining"));
+        }
+        assert_eq!(supply_remaining(&app, "L-1").await, 5);
+
+        // 境界値は受け付ける
+        for valid in [5, 0] {
+            let res = app.put_json("/api/listings/L-1", json!({ "supply_remaining": valid })).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            assert_eq!(supply_remaining(&app, "L-1").await, valid);
+        }
+    }
+
+    #[tokio::test]
+    async fn supply_changes_toggle_sold_out_automatically() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 3).await;
+
+        app.put_json("/api/listings/L-1", json!({ "supply_remaining": 0 })).await;
+        assert_eq!(listing_status(&app, "L-1").await, status::SOLD_OUT as i64);
+
+        app.put_json("/api/listings/L-1", json!({ "supply_remaining": 2 })).await;
+        assert_eq!(listing_status(&app, "L-1").await, status::ACTIVE as i64);
+
+        // 手動で止めた Listing は在庫を戻しても ACTIVE にならない
+        app.put_json("/api/listings/L-1", json!({ "status": status::SUSPENDED })).await;
+        app.put_json("/api/listings/L-1", json!({ "supply_remaining": 3 })).await;
+        assert_eq!(listing_status(&app, "L-1").await, status::SUSPENDED as i64);
+
+        // 明示した status は自動切り替えより優先され、在庫 0 での ACTIVE は拒否
+        let res = app.put_json("/api/listings/L-1", json!({ "supply_remaining": 0, "status": status::ACTIVE })).await;
+        assert_eq!(res.status, 422);
+    }
+
+    /// NDJSON の各行を個別にパースする
+    fn ndjson_lines(text: &str) -> Vec<serde_json::Value> {
+        text.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn listing_stream_emits_one_listing_per_line_with_same_filters() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let other = app.create_vendor(None).await;
+        for (i, price) in [300, 100, 200].into_iter().enumerate() {
+            app.create_listing(&vendor, &format!("L-{}", i), price, 1).await;
+        }
+        app.create_listing(&other, "L-other", 50, 1).await;
+
+        let res = app.get("/api/listings/stream").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("content-type"), Some("application/x-ndjson"));
+        assert!(res.text().ends_with('\n'));
+        assert_eq!(ndjson_lines(&res.text()).len(), 4);
+
+        let query = format!("vendor_stable_id={}&order=price_asc", vendor);
+        let lines = ndjson_lines(&app.get(&format!("/api/listings/stream?{}", query)).await.text());
+        let streamed: Vec<&str> = lines.iter().map(|l| l["listing_id"].as_str().unwrap()).collect();
+        assert_eq!(streamed, ["L-1", "L-2", "L-0"]);
+        assert_eq!(lines[0]["price"], 100);
+
+        let listed = app.get(&format!("/api/listings?{}", query)).await.json();
+        assert_eq!(listing_ids(&listed), streamed);
+    }
+
+    #[tokio::test]
+    async fn listing_stream_rejects_invalid_order_before_streaming() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/listings/stream?order=bogus").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["success"], false);
+
+        let res = app.get("/api/listings/stream").await;
+        assert_eq!(res.status, 200);
+        assert!(res.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn featured_listings_come_first_in_newest_order() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        // a〜e の順に作成（作成日時は 1 ms ずつ新しくする）
+        for (i, (listing_id, featured, rank)) in [
+            ("a", true, Some(2)),
+            ("b", true, None),
+            ("c", false, None),
+            ("d", true, Some(1)),
+            ("e", false, None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut body = listing_body(&vendor, listing_id, 100 + i as i64, 1);
+            body["featured"] = json!(featured);
+            body["featured_rank"] = json!(rank);
+            let res = app.post_json("/api/listings", body).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            sqlx::query("UPDATE listings SET created_at_ms = ? WHERE listing_id = ?")
+                .bind(1_000 + i as i64)
+                .bind(listing_id)
+                .execute(&app.state.db)
+                .await
+                .unwrap();
+        }
+        assert_eq!(listing_ids(&app.get("/api/listings").await.json()), ["d", "a", "b", "e", "c"]);
+
+        // featured を外すと順位も解除される
+        let res = app.put_json("/api/listings/d", json!({ "featured": false })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+        assert_eq!(res.json()["listing"]["featured_rank"], serde_json::Value::Null);
+        let res = app.put_json("/api/listings/c", json!({ "featured": true, "featured_rank": 0 })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+        assert_eq!(listing_ids(&app.get("/api/listings").await.json()), ["c", "a", "b", "e", "d"]);
+
+        // 価格順は featured を考慮しない
+        let ids = listing_ids(&app.get("/api/listings?order=price_asc").await.json());
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+
+        let res = app.put_json("/api/listings/a", json!({ "featured_rank": -1 })).await;
+        assert_eq!(res.status, 400);
+    }
+
+    async fn head(app: &TestApp, uri: &str) -> crate::test_support::TestResponse {
+        app.send(TestApp::request(Method::HEAD, uri).body(axum::body::Body::empty()).unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn head_listing_reports_existence_and_changes_etag_on_update() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 5).await;
+
+        let res = head(&app, "/api/listings/L-1").await;
+        assert_eq!(res.status, 200);
+        assert!(res.body.is_empty());
+        assert!(res.header("last-modified").unwrap().ends_with(" GMT"));
+        let etag = res.header("etag").unwrap().to_string();
+
+        // 同じ ms 内の更新で ETag が変わらないことを避ける
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let res = app.put_json("/api/listings/L-1", json!({ "price": 150 })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+        let res = head(&app, "/api/listings/L-1").await;
+        assert_ne!(res.header("etag").unwrap(), etag);
+
+        let res = head(&app, "/api/listings/L-missing").await;
+        assert_eq!(res.status, 404);
+        assert!(res.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_status_reports_per_item_failures() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        let (owner_key, other_key) = (OwnerKey::new(1), OwnerKey::new(2));
+        let vendor = app.create_vendor(Some(&owner_key.address())).await;
+        let other = app.create_vendor(Some(&other_key.address())).await;
+        for (vendor, key, listing_id) in [(&vendor, &owner_key, "l1"), (&vendor, &owner_key, "l2"), (&other, &other_key, "l3")] {
+            let body = listing_body(vendor, listing_id, 100, 1);
+            let res = app.send_json(Method::POST, "/api/listings", Some(body), &app.owner_headers(key).await.pairs()).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+        }
+        let uri = format!("/api/vendors/{}/listings/batch_status", vendor);
+        let batch = |ids: serde_json::Value, status: i32| json!({ "listing_ids": ids, "status": status });
+
+        let body = batch(json!(["l1", "l2", "l3", "nope", "l1"]), status::SUSPENDED);
+        let res = app.send_json(Method::POST, &uri, Some(body), &app.owner_headers(&owner_key).await.pairs()).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let res = res.json();
+        assert_eq!(res["results"]["l2"], true);
+        assert_eq!(res["results"]["l3"], false);
+        assert_eq!(res["results"]["nope"], false);
+        assert!(res["errors"]["l3"].is_string());
+        assert_eq!(listing_status(&app, "l1").await, i64::from(status::SUSPENDED));
+        assert_eq!(listing_status(&app, "l2").await, i64::from(status::SUSPENDED));
+        assert_eq!(listing_status(&app, "l3").await, i64::from(status::ACTIVE));
+
+        // 不正な遷移は項目ごとに失敗する
+        let body = batch(json!(["l1"]), status::SOLD_OUT);
+        let res = app.send_json(Method::POST, &uri, Some(body), &app.owner_headers(&owner_key).await.pairs()).await.json();
+        assert_eq!(res["results"]["l1"], false);
+        assert_eq!(listing_status(&app, "l1").await, i64::from(status::SUSPENDED));
+
+        let res = app.send_json(Method::POST, &uri, Some(batch(json!(["l1"]), 9)), &app.owner_headers(&owner_key).await.pairs()).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], json!(["status"]));
+        let res = app.send_json(Method::POST, &uri, Some(batch(json!([]), status::ACTIVE)), &app.owner_headers(&owner_key).await.pairs()).await;
+        assert_eq!(res.status, 400);
+        let body = batch(json!(["l1"]), status::ACTIVE);
+        let res = app.send_json(Method::POST, &uri, Some(body), &app.owner_headers(&other_key).await.pairs()).await;
+        assert_eq!(res.status, 403);
+    }
 }
@@ -7,3 +7,8 @@ pub mod drops;
 pub mod camera;
 pub mod devices;
 pub mod transfers;
+pub mod admin;
+pub mod tombstones;
+pub mod uploads;
+pub mod export;
+pub mod assets;
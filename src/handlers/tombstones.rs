@@ -0,0 +1,170 @@
+//! Tombstones API Handlers
+//! /api/tombstones エンドポイント - 削除・パージされたオブジェクトの記録（インデクサ向け）
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::error::ApiError;
+use crate::models::Tombstone;
+use crate::response::{ApiResponse, ListMeta};
+use crate::AppState;
+
+// ========================================
+// Response Types
+// ========================================
+
+/// 一覧レスポンス（`meta.next_cursor` は次の `since`、移行期間中は旧フィールド `tombstones` / `total` も返す）
+pub type TombstoneListResponse = ApiResponse<Vec<Tombstone>>;
+
+// ========================================
+// Query Parameters
+// ========================================
+
+#[derive(Debug, Deserialize)]
+pub struct ListTombstonesQuery {
+    /// この時刻（ms）より後に記録されたものだけを返す
+    pub since: Option<i64>,
+    /// vendor / listing / drop / artist
+    pub kind: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// ========================================
+// Handlers
+// ========================================
+
+/// GET /api/tombstones?since=...&kind=... - Tombstone 一覧（古い順）
+pub async fn list_tombstones(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTombstonesQuery>,
+) -> Result<Json<TombstoneListResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(500).clamp(1, 1000);
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM tombstones WHERE 1 = 1");
+    if let Some(since) = query.since {
+        builder.push(" AND observed_dead_at_ms > ").push_bind(since);
+    }
+    if let Some(kind) = &query.kind {
+        builder.push(" AND kind = ").push_bind(kind);
+    }
+    builder
+        .push(" ORDER BY observed_dead_at_ms ASC, id ASC LIMIT ")
+        .push_bind(limit);
+
+    let tombstones: Vec<Tombstone> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await?;
+
+    // limit 件ちょうど返した場合のみ続きがあり得る
+    let next_cursor = (tombstones.len() as i64 == limit)
+        .then(|| tombstones.last().map(|t| t.observed_dead_at_ms.to_string()))
+        .flatten();
+    let meta = ListMeta {
+        limit: Some(limit),
+        next_cursor,
+        ..ListMeta::total(tombstones.len())
+    };
+    Ok(Json(
+        ApiResponse::list(tombstones, meta).with_legacy_fields(state.config.legacy_list_fields, "tombstones"),
+    ))
+}
+
+// ========================================
+// Helper Functions
+// ========================================
+
+/// Tombstone を記録（削除・パージ処理のトランザクション内で呼ぶ）
+pub async fn record_tombstone<'e, E>(
+    executor: E,
+    kind: &str,
+    stable_id: Option<&str>,
+    object_id: &str,
+    env: &str,
+    observed_dead_at_ms: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(r#"
+        INSERT INTO tombstones (kind, stable_id, object_id, env, observed_dead_at_ms)
+        VALUES (?, ?, ?, ?, ?)
+    "#)
+    .bind(kind)
+    .bind(stable_id)
+    .bind(object_id)
+    .bind(env)
+    .bind(observed_dead_at_ms)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::Value;
+
+    use crate::handlers::drops::purge_ended_drops;
+    use crate::models::drop_status;
+    use crate::test_support::TestApp;
+
+    fn tombstones(body: &Value) -> Vec<(String, String)> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| (t["kind"].as_str().unwrap().to_string(), t["object_id"].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn delisting_vendor_records_a_single_tombstone() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let uri = format!("/api/vendors/{}", vendor);
+
+        for _ in 0..2 {
+            let res = app.send_json(Method::DELETE, &uri, None, &[]).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+        }
+
+        let body = app.get("/api/tombstones?kind=vendor").await.json();
+        assert_eq!(tombstones(&body), vec![("vendor".to_string(), vendor.clone())]);
+        assert_eq!(body["data"][0]["stable_id"], vendor.as_str());
+        assert_eq!(body["data"][0]["env"], "devnet");
+    }
+
+    #[tokio::test]
+    async fn listing_delete_and_drop_purge_record_tombstones() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-gone", 100, 1).await;
+        let res = app.send_json(Method::DELETE, "/api/listings/L-gone", None, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        let drop_id = app.create_drop(&vendor, 5).await;
+        sqlx::query("UPDATE drops SET status = ?, ended_at = ? WHERE drop_id = ?")
+            .bind(drop_status::ENDED)
+            .bind(chrono::Utc::now().timestamp() - 60)
+            .bind(&drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(purge_ended_drops(&app.state, 0).await.unwrap(), 1);
+
+        // パージは秒精度で記録されるため順序は問わない
+        let mut all = tombstones(&app.get("/api/tombstones").await.json());
+        all.sort();
+        assert_eq!(all, vec![("drop".to_string(), drop_id.clone()), ("listing".to_string(), "L-gone".to_string())]);
+        assert_eq!(tombstones(&app.get("/api/tombstones?kind=drop").await.json()).len(), 1);
+
+        // since は記録時刻より後のものだけ
+        let future = chrono::Utc::now().timestamp_millis() + 1000;
+        assert!(tombstones(&app.get(&format!("/api/tombstones?since={}", future)).await.json()).is_empty());
+    }
+}
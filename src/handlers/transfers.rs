@@ -13,7 +13,6 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
-use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs;
@@ -21,47 +20,40 @@ use tokio::io::AsyncWriteExt;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use crate::error::ApiError;
 use crate::models::{
     CreateTransferRequest, Transfer, TransferResponse,
     UpdateTransferStatusRequest, transfer_status,
 };
+use crate::storage;
+use crate::extract::ApiJson;
 use crate::AppState;
 
 /// 期限: 3日（ミリ秒）
 const TRANSFER_EXPIRY_MS: i64 = 3 * 24 * 60 * 60 * 1000;
 
-#[derive(Serialize)]
-pub struct ErrorResp {
-    success: bool,
-    error: String,
-}
-
-fn err(status: StatusCode, msg: String) -> (StatusCode, Json<ErrorResp>) {
-    (status, Json(ErrorResp { success: false, error: msg }))
-}
-
 /// POST /api/transfers - 暗号化アルバムデータ + メタデータをアップロード
 pub async fn create_transfer(
     State(state): State<Arc<AppState>>,
     mut multipart: Multipart,
-) -> Result<Json<TransferResponse>, (StatusCode, Json<ErrorResp>)> {
+) -> Result<Json<TransferResponse>, ApiError> {
     let mut file_data: Option<Vec<u8>> = None;
     let mut metadata_json: Option<String> = None;
 
     while let Some(field) = multipart.next_field().await.map_err(|e| {
-        err(StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
         match name.as_str() {
             "file" => {
                 let bytes = field.bytes().await.map_err(|e| {
-                    err(StatusCode::BAD_REQUEST, format!("File read error: {}", e))
+                    ApiError::new(e.status(), format!("File read error: {}", e))
                 })?;
                 file_data = Some(bytes.to_vec());
             }
             "metadata" => {
                 let text = field.text().await.map_err(|e| {
-                    err(StatusCode::BAD_REQUEST, format!("Metadata read error: {}", e))
+                    ApiError::new(e.status(), format!("Metadata read error: {}", e))
                 })?;
                 metadata_json = Some(text);
             }
@@ -70,14 +62,14 @@ pub async fn create_transfer(
     }
 
     let file_data = file_data.ok_or_else(|| {
-        err(StatusCode::BAD_REQUEST, "No file uploaded".into())
+        ApiError::BadRequest("No file uploaded".into())
     })?;
     let metadata_json = metadata_json.ok_or_else(|| {
-        err(StatusCode::BAD_REQUEST, "No metadata provided".into())
+        ApiError::BadRequest("No metadata provided".into())
     })?;
 
     let req: CreateTransferRequest = serde_json::from_str(&metadata_json).map_err(|e| {
-        err(StatusCode::BAD_REQUEST, format!("Invalid metadata JSON: {}", e))
+        ApiError::BadRequest(format!("Invalid metadata JSON: {}", e))
     })?;
 
     // Transfer ID 生成
@@ -96,16 +88,16 @@ pub async fn create_transfer(
         .join("transfers")
         .join(&transfer_id);
     fs::create_dir_all(&transfer_dir).await.map_err(|e| {
-        err(StatusCode::INTERNAL_SERVER_ERROR, format!("Dir create error: {}", e))
+        ApiError::internal(format!("Dir create error: {}", e))
     })?;
 
     let data_filename = "album.enc";
     let data_path = transfer_dir.join(data_filename);
     let mut file = fs::File::create(&data_path).await.map_err(|e| {
-        err(StatusCode::INTERNAL_SERVER_ERROR, format!("File create error: {}", e))
+        ApiError::internal(format!("File create error: {}", e))
     })?;
     file.write_all(&file_data).await.map_err(|e| {
-        err(StatusCode::INTERNAL_SERVER_ERROR, format!("File write error: {}", e))
+        ApiError::internal(format!("File write error: {}", e))
     })?;
 
     let data_object_key = format!("{}/{}", transfer_id, data_filename);
@@ -141,20 +133,10 @@ pub async fn create_transfer(
     .bind(now_ms)
     .bind(expires_at_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| {
-        err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
-    // caddy ownership (Linux only)
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        let _ = Command::new("chown")
-            .arg("-R").arg("caddy:caddy")
-            .arg(&transfer_dir)
-            .output();
-    }
+    // 所有権を変更（UPLOAD_CHOWN、ベストエフォート）
+    storage::apply_upload_owner(state.config.upload_chown.as_deref(), &transfer_dir, true).await;
 
     info!("Transfer created: {} ({} bytes)", transfer_id, data_size);
 
@@ -182,15 +164,14 @@ pub async fn create_transfer(
 pub async fn get_transfer(
     State(state): State<Arc<AppState>>,
     Path(transfer_id): Path<String>,
-) -> Result<Json<TransferResponse>, (StatusCode, Json<ErrorResp>)> {
+) -> Result<Json<TransferResponse>, ApiError> {
     let transfer: Transfer = sqlx::query_as(
         "SELECT * FROM transfers WHERE transfer_id = ?"
     )
     .bind(&transfer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?
-    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Transfer not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Transfer not found".into()))?;
 
     let download_url = if transfer.status == transfer_status::PENDING {
         Some(format!("{}/transfers/{}", state.vps_base_url, transfer.data_object_key))
@@ -222,18 +203,17 @@ pub async fn get_transfer(
 pub async fn download_transfer(
     State(state): State<Arc<AppState>>,
     Path(transfer_id): Path<String>,
-) -> Result<axum::response::Response, (StatusCode, Json<ErrorResp>)> {
+) -> Result<axum::response::Response, ApiError> {
     let transfer: Transfer = sqlx::query_as(
         "SELECT * FROM transfers WHERE transfer_id = ?"
     )
     .bind(&transfer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?
-    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Transfer not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Transfer not found".into()))?;
 
     if transfer.status != transfer_status::PENDING {
-        return Err(err(StatusCode::GONE, "Transfer is no longer available".into()));
+        return Err(ApiError::new(StatusCode::GONE, "Transfer is no longer available"));
     }
 
     let file_path = PathBuf::from(&state.base_data_dir)
@@ -241,11 +221,11 @@ pub async fn download_transfer(
         .join(&transfer.data_object_key);
 
     if !file_path.exists() {
-        return Err(err(StatusCode::NOT_FOUND, "Transfer file not found on disk".into()));
+        return Err(ApiError::NotFound("Transfer file not found on disk".into()));
     }
 
     let body = fs::read(&file_path).await.map_err(|e| {
-        err(StatusCode::INTERNAL_SERVER_ERROR, format!("File read error: {}", e))
+        ApiError::internal(format!("File read error: {}", e))
     })?;
 
     Ok(axum::response::Response::builder()
@@ -261,23 +241,22 @@ pub async fn download_transfer(
 pub async fn claim_transfer(
     State(state): State<Arc<AppState>>,
     Path(transfer_id): Path<String>,
-    Json(req): Json<UpdateTransferStatusRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResp>)> {
+    ApiJson(req): ApiJson<UpdateTransferStatusRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let transfer: Transfer = sqlx::query_as(
         "SELECT * FROM transfers WHERE transfer_id = ?"
     )
     .bind(&transfer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?
-    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Transfer not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Transfer not found".into()))?;
 
     // 権限チェック: 受信者のみ
     if req.peer_id != transfer.recipient_peer_id {
-        return Err(err(StatusCode::FORBIDDEN, "Only recipient can claim".into()));
+        return Err(ApiError::Forbidden("Only recipient can claim".into()));
     }
     if transfer.status != transfer_status::PENDING {
-        return Err(err(StatusCode::CONFLICT, format!("Transfer status is {}, not pending", transfer.status)));
+        return Err(ApiError::Conflict(format!("Transfer status is {}, not pending", transfer.status)));
     }
 
     let now_ms = chrono::Utc::now().timestamp_millis();
@@ -288,8 +267,7 @@ pub async fn claim_transfer(
         .bind(now_ms)
         .bind(&transfer_id)
         .execute(&state.db)
-        .await
-        .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
     // VPSファイル削除
     delete_transfer_files(&state.base_data_dir, &transfer_id).await;
@@ -307,23 +285,22 @@ pub async fn claim_transfer(
 pub async fn cancel_transfer(
     State(state): State<Arc<AppState>>,
     Path(transfer_id): Path<String>,
-    Json(req): Json<UpdateTransferStatusRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResp>)> {
+    ApiJson(req): ApiJson<UpdateTransferStatusRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let transfer: Transfer = sqlx::query_as(
         "SELECT * FROM transfers WHERE transfer_id = ?"
     )
     .bind(&transfer_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?
-    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Transfer not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Transfer not found".into()))?;
 
     // 権限チェック: 送信者のみ
     if req.peer_id != transfer.sender_peer_id {
-        return Err(err(StatusCode::FORBIDDEN, "Only sender can cancel".into()));
+        return Err(ApiError::Forbidden("Only sender can cancel".into()));
     }
     if transfer.status != transfer_status::PENDING {
-        return Err(err(StatusCode::CONFLICT, format!("Transfer status is {}, not pending", transfer.status)));
+        return Err(ApiError::Conflict(format!("Transfer status is {}, not pending", transfer.status)));
     }
 
     let now_ms = chrono::Utc::now().timestamp_millis();
@@ -333,8 +310,7 @@ pub async fn cancel_transfer(
         .bind(now_ms)
         .bind(&transfer_id)
         .execute(&state.db)
-        .await
-        .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
     // VPSファイル削除
     delete_transfer_files(&state.base_data_dir, &transfer_id).await;
@@ -352,15 +328,14 @@ pub async fn cancel_transfer(
 pub async fn list_pending_transfers(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResp>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let transfers: Vec<Transfer> = sqlx::query_as(
         "SELECT * FROM transfers WHERE recipient_peer_id = ? AND status = ? ORDER BY created_at_ms DESC"
     )
     .bind(&peer_id)
     .bind(transfer_status::PENDING)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let responses: Vec<TransferResponse> = transfers.into_iter().map(|t| {
         let download_url = Some(format!("{}/transfers/{}", state.vps_base_url, t.data_object_key));
@@ -0,0 +1,698 @@
+//! Resumable Upload Handlers
+//! /api/uploads エンドポイント - 大容量音声の分割・再開可能アップロード（TUS 風）
+//!
+//! フロー:
+//!   1. POST  /api/uploads - セッション作成（total_size を宣言）→ upload_id
+//!   2. PATCH /api/uploads/:upload_id - `Upload-Offset` ヘッダの位置からバイト列を追記
+//!   3. GET   /api/uploads/:upload_id - 現在の offset を取得（切断後の再開用）
+//!   4. POST  /api/uploads/:upload_id/finalize - SHA256 を検証して完了
+//!   5. POST  /api/drops に `upload_id` を渡すと、audio の代わりに完了済みファイルを使用
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::{ApiError, ErrorResponse};
+use crate::models::{CreateUploadRequest, FinalizeUploadRequest, UploadSession, upload_status};
+use crate::extract::ApiJson;
+use crate::AppState;
+
+/// 現在の受信済みバイト数を返すヘッダ
+const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+
+// ========================================
+// Response Types
+// ========================================
+
+#[derive(Serialize, ToSchema)]
+pub struct UploadSessionResponse {
+    pub success: bool,
+    pub upload: UploadSession,
+}
+
+// ========================================
+// Handlers
+// ========================================
+
+/// POST /api/uploads - アップロードセッション作成
+#[utoipa::path(
+    post,
+    path = "/api/uploads",
+    tag = "uploads",
+    request_body = CreateUploadRequest,
+    responses(
+        (status = 200, description = "成功", body = UploadSessionResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    ApiJson(req): ApiJson<CreateUploadRequest>,
+) -> Result<Json<UploadSessionResponse>, ApiError> {
+    if req.total_size <= 0 {
+        return Err(ApiError::BadRequest("total_size must be positive".to_string()));
+    }
+    if req.total_size as u64 > state.config.max_audio_bytes as u64 {
+        return Err(ApiError::PayloadTooLarge(
+            format!("Upload too large: at most {} bytes", state.config.max_audio_bytes),
+        ));
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let upload_id = Uuid::new_v4().simple().to_string();
+    let expires_at_ms = now_ms + state.config.upload_session_ttl_secs * 1000;
+
+    // 空のファイルを用意
+    let path = upload_file_path(&state.base_data_dir, &upload_id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| {
+            ApiError::internal(format!("Failed to create dir: {}", e))
+        })?;
+    }
+    fs::File::create(&path).await.map_err(|e| {
+        ApiError::internal(format!("Failed to create upload file: {}", e))
+    })?;
+
+    sqlx::query(r#"
+        INSERT INTO upload_sessions (
+            upload_id, total_size, offset_bytes, filename, content_type,
+            status, created_at_ms, updated_at_ms, expires_at_ms
+        ) VALUES (?, ?, 0, ?, ?, ?, ?, ?, ?)
+    "#)
+    .bind(&upload_id)
+    .bind(req.total_size)
+    .bind(&req.filename)
+    .bind(&req.content_type)
+    .bind(upload_status::UPLOADING)
+    .bind(now_ms)
+    .bind(now_ms)
+    .bind(expires_at_ms)
+    .execute(&state.db)
+    .await?;
+
+    info!("Upload session created: upload_id={}, total_size={}", upload_id, req.total_size);
+
+    let upload = fetch_session(&state, &upload_id).await?;
+    Ok(Json(UploadSessionResponse { success: true, upload }))
+}
+
+/// GET /api/uploads/:upload_id - セッション状態（`Upload-Offset` ヘッダ付き）
+#[utoipa::path(
+    get,
+    path = "/api/uploads/{upload_id}",
+    tag = "uploads",
+    params(("upload_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功（Upload-Offset ヘッダに受信済みバイト数）", body = UploadSessionResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_upload(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let upload = fetch_session(&state, &upload_id).await?;
+    Ok(with_offset_header(upload.offset_bytes, Json(UploadSessionResponse { success: true, upload })))
+}
+
+/// PATCH /api/uploads/:upload_id - `Upload-Offset` の位置からボディを追記
+///
+/// offset がサーバ側と一致しない場合は 409（`Upload-Offset` ヘッダで現在値を返す）。
+/// 途中で切断された場合も、受信済みの分は offset として保存され次回再開できる。
+#[utoipa::path(
+    patch,
+    path = "/api/uploads/{upload_id}",
+    tag = "uploads",
+    params(
+        ("upload_id" = String, Path),
+        ("Upload-Offset" = i64, Header, description = "追記を始めるバイト位置（サーバ側の offset と一致させる）"),
+    ),
+    request_body(content_type = "application/octet-stream", description = "追記するバイト列"),
+    responses(
+        (status = 200, description = "成功（Upload-Offset ヘッダに新しい offset）", body = UploadSessionResponse),
+        (status = 409, description = "offset 不一致（Upload-Offset ヘッダに現在値）", body = ErrorResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn patch_upload(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, ApiError> {
+    let upload = fetch_session(&state, &upload_id).await?;
+
+    if upload.status != upload_status::UPLOADING {
+        return Err(ApiError::Conflict("Upload is already finalized".to_string()));
+    }
+
+    let offset = headers
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .ok_or_else(|| {
+            ApiError::BadRequest("Upload-Offset header is required".to_string())
+        })?;
+
+    if offset != upload.offset_bytes {
+        let mut response = ApiError::Conflict(
+            format!("Upload-Offset mismatch: expected {}", upload.offset_bytes),
+        )
+        .into_response();
+        response.headers_mut().insert(UPLOAD_OFFSET_HEADER, HeaderValue::from(upload.offset_bytes));
+        return Ok(response);
+    }
+
+    // 前回の切断で offset 以降に書かれた中途半端なデータは捨てる
+    let path = upload_file_path(&state.base_data_dir, &upload_id);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .await
+        .map_err(|e| {
+            ApiError::internal(format!("Failed to open upload file: {}", e))
+        })?;
+    file.set_len(offset as u64).await.map_err(|e| {
+        ApiError::internal(format!("Failed to truncate upload file: {}", e))
+    })?;
+    file.seek(std::io::SeekFrom::Start(offset as u64)).await.map_err(|e| {
+        ApiError::internal(format!("Failed to seek upload file: {}", e))
+    })?;
+
+    // ボディをストリームで追記（total_size を超える分は拒否）
+    let mut body = body;
+    let mut written: i64 = 0;
+    let mut failure: Option<ApiError> = None;
+    while let Some(frame) = std::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx)).await {
+        let data = match frame {
+            Ok(frame) => match frame.into_data() {
+                Ok(data) => data,
+                Err(_) => continue,
+            },
+            Err(e) => {
+                failure = Some(ApiError::BadRequest(format!("Upload interrupted: {}", e)));
+                break;
+            }
+        };
+        if offset + written + data.len() as i64 > upload.total_size {
+            failure = Some(ApiError::PayloadTooLarge(format!(
+                "Upload exceeds declared total_size ({} bytes)",
+                upload.total_size
+            )));
+            break;
+        }
+        if let Err(e) = file.write_all(&data).await {
+            failure = Some(ApiError::internal(format!("Failed to write upload file: {}", e)));
+            break;
+        }
+        written += data.len() as i64;
+    }
+
+    // 書けた分までを offset として保存（途中切断でも再開可能）
+    if let Err(e) = file.sync_data().await {
+        return Err(ApiError::internal(format!("Failed to sync upload file: {}", e)));
+    }
+    let new_offset = offset + written;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let result = sqlx::query(
+        "UPDATE upload_sessions SET offset_bytes = ?, updated_at_ms = ? WHERE upload_id = ? AND offset_bytes = ? AND status = ?"
+    )
+    .bind(new_offset)
+    .bind(now_ms)
+    .bind(&upload_id)
+    .bind(offset)
+    .bind(upload_status::UPLOADING)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict("Upload was modified concurrently".to_string()));
+    }
+
+    if let Some(failure) = failure {
+        warn!("Upload chunk failed: upload_id={}, offset={}, error={}", upload_id, new_offset, failure);
+        let mut response = failure.into_response();
+        response.headers_mut().insert(UPLOAD_OFFSET_HEADER, HeaderValue::from(new_offset));
+        return Ok(response);
+    }
+
+    info!("Upload chunk received: upload_id={}, offset={}/{}", upload_id, new_offset, upload.total_size);
+
+    let upload = fetch_session(&state, &upload_id).await?;
+    Ok(with_offset_header(new_offset, Json(UploadSessionResponse { success: true, upload })))
+}
+
+/// POST /api/uploads/:upload_id/finalize - 全バイト受信済みか確認し SHA256 を検証
+///
+/// SHA256 が一致しない場合は 422 を返し、受信データを破棄して offset を 0 に戻す。
+#[utoipa::path(
+    post,
+    path = "/api/uploads/{upload_id}/finalize",
+    tag = "uploads",
+    params(("upload_id" = String, Path)),
+    request_body = FinalizeUploadRequest,
+    responses(
+        (status = 200, description = "成功", body = UploadSessionResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn finalize_upload(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+    ApiJson(req): ApiJson<FinalizeUploadRequest>,
+) -> Result<Json<UploadSessionResponse>, ApiError> {
+    let upload = fetch_session(&state, &upload_id).await?;
+
+    if upload.status == upload_status::COMPLETED {
+        return Ok(Json(UploadSessionResponse { success: true, upload }));
+    }
+    if upload.status != upload_status::UPLOADING {
+        return Err(ApiError::Conflict("Upload is no longer available".to_string()));
+    }
+    if upload.offset_bytes != upload.total_size {
+        return Err(ApiError::Conflict(
+            format!("Upload incomplete: {}/{} bytes", upload.offset_bytes, upload.total_size),
+        ));
+    }
+
+    let path = upload_file_path(&state.base_data_dir, &upload_id);
+    let actual = compute_file_sha256(path.clone()).await.map_err(|e| {
+        ApiError::internal(format!("Failed to hash upload file: {}", e))
+    })?;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    if !actual.eq_ignore_ascii_case(req.sha256.trim()) {
+        // 破損データは残さず、最初からやり直してもらう
+        if let Ok(file) = fs::OpenOptions::new().write(true).open(&path).await {
+            let _ = file.set_len(0).await;
+        }
+        sqlx::query("UPDATE upload_sessions SET offset_bytes = 0, updated_at_ms = ? WHERE upload_id = ?")
+            .bind(now_ms)
+            .bind(&upload_id)
+            .execute(&state.db)
+            .await?;
+        return Err(ApiError::Unprocessable(
+            format!("SHA256 mismatch: received data hashes to {}", actual),
+        ));
+    }
+
+    sqlx::query("UPDATE upload_sessions SET status = ?, sha256 = ?, updated_at_ms = ? WHERE upload_id = ? AND status = ?")
+        .bind(upload_status::COMPLETED)
+        .bind(&actual)
+        .bind(now_ms)
+        .bind(&upload_id)
+        .bind(upload_status::UPLOADING)
+        .execute(&state.db)
+        .await?;
+
+    info!("Upload finalized: upload_id={}, size={}, sha256={}", upload_id, upload.total_size, &actual[..16]);
+
+    let upload = fetch_session(&state, &upload_id).await?;
+    Ok(Json(UploadSessionResponse { success: true, upload }))
+}
+
+// ========================================
+// Drop 作成への受け渡し
+// ========================================
+
+/// 完了済み（未使用）のアップロードを取得する（ファイルは読み込まない）
+pub async fn fetch_completed_upload(
+    state: &Arc<AppState>,
+    upload_id: &str,
+) -> Result<UploadSession, ApiError> {
+    let upload: UploadSession = sqlx::query_as("SELECT * FROM upload_sessions WHERE upload_id = ?")
+        .bind(upload_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest(format!("Upload not found: {}", upload_id)))?;
+
+    if upload.status != upload_status::COMPLETED {
+        return Err(upload_not_available(upload_id));
+    }
+    Ok(upload)
+}
+
+/// 完了済みアップロードを CONSUMED にして確保し、ファイルを dest に移す
+///
+/// 同じ upload_id の同時リクエストは状態遷移（COMPLETED → CONSUMED）で1つだけが確保できる。
+/// 戻り値は移したファイルの (SHA256, サイズ)（ディスク上のファイルから計算）
+pub async fn take_completed_upload(
+    state: &Arc<AppState>,
+    upload_id: &str,
+    dest: &std::path::Path,
+) -> Result<(String, i64), ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let claimed = sqlx::query("UPDATE upload_sessions SET status = ?, updated_at_ms = ? WHERE upload_id = ? AND status = ?")
+        .bind(upload_status::CONSUMED)
+        .bind(now_ms)
+        .bind(upload_id)
+        .bind(upload_status::COMPLETED)
+        .execute(&state.db)
+        .await?;
+    if claimed.rows_affected() == 0 {
+        return Err(upload_not_available(upload_id));
+    }
+
+    if let Err(e) = fs::rename(upload_file_path(&state.base_data_dir, upload_id), dest).await {
+        // 移せなかった場合は再利用できるよう COMPLETED に戻す
+        let _ = sqlx::query("UPDATE upload_sessions SET status = ? WHERE upload_id = ?")
+            .bind(upload_status::COMPLETED)
+            .bind(upload_id)
+            .execute(&state.db)
+            .await;
+        return Err(ApiError::internal(format!("Failed to move upload: {}", e)));
+    }
+    let sha256 = compute_file_sha256(dest.to_path_buf())
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to hash upload: {}", e)))?;
+    let size = fs::metadata(dest)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to stat upload: {}", e)))?
+        .len() as i64;
+    Ok((sha256, size))
+}
+
+fn upload_not_available(upload_id: &str) -> ApiError {
+    ApiError::Conflict(format!("Upload is not finalized or already used: {}", upload_id))
+}
+
+// ========================================
+// Background Job
+// ========================================
+
+/// 期限切れの未使用セッションを EXPIRED にしてファイルを削除
+pub async fn expire_upload_sessions(state: &Arc<AppState>) -> anyhow::Result<usize> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let expired: Vec<(String,)> = sqlx::query_as(
+        "SELECT upload_id FROM upload_sessions WHERE status IN (?, ?) AND expires_at_ms <= ?"
+    )
+    .bind(upload_status::UPLOADING)
+    .bind(upload_status::COMPLETED)
+    .bind(now_ms)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut count = 0;
+    for (upload_id,) in &expired {
+        if expire_session(state, upload_id, now_ms).await? {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// 1件のセッションを EXPIRED にし、更新できた場合のみファイルを削除
+/// 一覧取得後に Drop 作成で使われた（CONSUMED になった）セッションのファイルは消さない
+async fn expire_session(state: &Arc<AppState>, upload_id: &str, now_ms: i64) -> anyhow::Result<bool> {
+    let result = sqlx::query(
+        "UPDATE upload_sessions SET status = ?, updated_at_ms = ? WHERE upload_id = ? AND status IN (?, ?)"
+    )
+    .bind(upload_status::EXPIRED)
+    .bind(now_ms)
+    .bind(upload_id)
+    .bind(upload_status::UPLOADING)
+    .bind(upload_status::COMPLETED)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    delete_upload_file(&state.base_data_dir, upload_id).await;
+    info!("Upload session expired: {}", upload_id);
+    Ok(true)
+}
+
+// ========================================
+// Helper Functions
+// ========================================
+
+async fn fetch_session(
+    state: &Arc<AppState>,
+    upload_id: &str,
+) -> Result<UploadSession, ApiError> {
+    sqlx::query_as("SELECT * FROM upload_sessions WHERE upload_id = ?")
+        .bind(upload_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Upload not found".to_string()))
+}
+
+pub fn upload_file_path(base_data_dir: &str, upload_id: &str) -> PathBuf {
+    PathBuf::from(base_data_dir).join("uploads").join(format!("{}.part", upload_id))
+}
+
+/// アップロードファイルを削除（ベストエフォート）
+async fn delete_upload_file(base_data_dir: &str, upload_id: &str) {
+    let path = upload_file_path(base_data_dir, upload_id);
+    if let Err(e) = fs::remove_file(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to delete upload file {:?}: {}", path, e);
+        }
+    }
+}
+
+/// ファイル全体の SHA256（大容量のためブロッキングスレッドで読み込み）
+async fn compute_file_sha256(path: PathBuf) -> std::io::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+fn with_offset_header(offset: i64, body: impl IntoResponse) -> Response {
+    let mut response = body.into_response();
+    response.headers_mut().insert(UPLOAD_OFFSET_HEADER, HeaderValue::from(offset));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use axum::body::{Body, Bytes};
+    use axum::http::Method;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    use crate::models::upload_status;
+    use crate::test_support::{MultipartForm, TestApp, TestResponse};
+
+    fn audio_bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn create_session(app: &TestApp, total_size: usize) -> String {
+        let res = app
+            .post_json("/api/uploads", json!({ "total_size": total_size, "filename": "master.mp3", "content_type": "audio/mpeg" }))
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        res.json()["upload"]["upload_id"].as_str().unwrap().to_string()
+    }
+
+    async fn patch(app: &TestApp, upload_id: &str, offset: usize, body: Body) -> TestResponse {
+        let req = TestApp::request(Method::PATCH, &format!("/api/uploads/{}", upload_id))
+            .header("upload-offset", offset.to_string())
+            .header("content-type", "application/offset+octet-stream")
+            .body(body)
+            .unwrap();
+        app.send(req).await
+    }
+
+    /// 先頭 `sent` バイトを送ったところで接続が切れるボディ
+    fn interrupted_body(data: &[u8], sent: usize) -> Body {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+            Ok(Bytes::copy_from_slice(&data[..sent])),
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "client disconnected")),
+        ];
+        Body::from_stream(futures_util::stream::iter(chunks))
+    }
+
+    /// audio の代わりに upload_id を渡す Drop 作成フォーム
+    fn drop_from_upload(vendor: &str, upload_id: &str) -> MultipartForm {
+        let end_at = chrono::Utc::now().timestamp() + 3600;
+        MultipartForm::new()
+            .text("vendor_stable_id", vendor)
+            .text("artist_name", "Test Artist")
+            .text("title", "Resumed")
+            .text("end_at", &end_at.to_string())
+            .text("max_claims", "5")
+            .text("upload_id", upload_id)
+    }
+
+    fn offset_of(res: &TestResponse) -> usize {
+        res.header("upload-offset").unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn three_chunk_upload_resumes_after_interruption() {
+        let app = TestApp::new().await;
+        let data = audio_bytes(3000);
+        let upload_id = create_session(&app, data.len()).await;
+
+        // 1つ目
+        let res = patch(&app, &upload_id, 0, Body::from(data[..1000].to_vec())).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(offset_of(&res), 1000);
+
+        // 2つ目は 500 バイト送ったところで切断
+        let res = patch(&app, &upload_id, 1000, interrupted_body(&data[1000..2000], 500)).await;
+        assert_eq!(res.status, 400, "{}", res.text());
+        assert_eq!(offset_of(&res), 1500);
+
+        // 再開位置を問い合わせる
+        let res = app.get(&format!("/api/uploads/{}", upload_id)).await;
+        assert_eq!(offset_of(&res), 1500);
+        assert_eq!(res.json()["upload"]["offset_bytes"], 1500);
+
+        // 古い offset での再送は 409 と現在位置
+        let res = patch(&app, &upload_id, 1000, Body::from(data[1000..2000].to_vec())).await;
+        assert_eq!(res.status, 409);
+        assert_eq!(offset_of(&res), 1500);
+
+        // 2つ目の残りと 3つ目
+        let res = patch(&app, &upload_id, 1500, Body::from(data[1500..2000].to_vec())).await;
+        assert_eq!(offset_of(&res), 2000);
+        let res = patch(&app, &upload_id, 2000, Body::from(data[2000..].to_vec())).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(offset_of(&res), 3000);
+
+        let sha256 = hex::encode(Sha256::digest(&data));
+        let res = app
+            .post_json(&format!("/api/uploads/{}/finalize", upload_id), json!({ "sha256": sha256 }))
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["upload"]["status"], upload_status::COMPLETED);
+
+        // audio の代わりに upload_id で Drop を作成
+        let vendor = app.create_vendor(None).await;
+        let res = app.send_form(Method::POST, "/api/drops", drop_from_upload(&vendor, &upload_id), &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["drop"]["audio_sha256"], sha256.as_str());
+        assert_eq!(res.json()["drop"]["audio_size_bytes"], 3000);
+
+        // ファイルはコピーせずに移している
+        assert!(!app.data_path(format!("uploads/{}.part", upload_id)).exists());
+
+        // 使用済みのアップロードは再利用できない
+        let session = app.get(&format!("/api/uploads/{}", upload_id)).await.json();
+        assert_eq!(session["upload"]["status"], upload_status::CONSUMED);
+        let res = app.send_form(Method::POST, "/api/drops", drop_from_upload(&vendor, &upload_id), &[]).await;
+        assert_eq!(res.status, 409, "{}", res.text());
+    }
+
+    #[tokio::test]
+    async fn finalize_rejects_incomplete_or_corrupted_uploads() {
+        let app = TestApp::new().await;
+        let data = audio_bytes(100);
+        let upload_id = create_session(&app, data.len()).await;
+        let finalize = format!("/api/uploads/{}/finalize", upload_id);
+        let sha256 = hex::encode(Sha256::digest(&data));
+
+        patch(&app, &upload_id, 0, Body::from(data[..50].to_vec())).await;
+        assert_eq!(app.post_json(&finalize, json!({ "sha256": sha256 })).await.status, 409);
+
+        // 宣言サイズを超える分は 413
+        let res = patch(&app, &upload_id, 50, Body::from(vec![0u8; 100])).await;
+        assert_eq!(res.status, 413);
+
+        let mut corrupted = data.clone();
+        corrupted[75] ^= 0xff;
+        patch(&app, &upload_id, 50, Body::from(corrupted[50..].to_vec())).await;
+        assert_eq!(app.post_json(&finalize, json!({ "sha256": sha256 })).await.status, 422);
+        // やり直しのため offset は 0 に戻る
+        let res = app.get(&format!("/api/uploads/{}", upload_id)).await;
+        assert_eq!(offset_of(&res), 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_drops_cannot_share_one_upload() {
+        let app = TestApp::with_config(|c| c.rate_limit_burst = 0).await;
+        let data = audio_bytes(500);
+        let upload_id = create_session(&app, data.len()).await;
+        patch(&app, &upload_id, 0, Body::from(data.clone())).await;
+        let sha256 = hex::encode(Sha256::digest(&data));
+        let res = app
+            .post_json(&format!("/api/uploads/{}/finalize", upload_id), json!({ "sha256": sha256 }))
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        let vendor = app.create_vendor(None).await;
+        // Digest はディスク上のファイルと照合し、不一致ならアップロードは未使用のまま
+        let wrong = format!("sha-256={}", base64::engine::general_purpose::STANDARD.encode(Sha256::digest(b"other")));
+        let res = app
+            .send_form(Method::POST, "/api/drops", drop_from_upload(&vendor, &upload_id), &[("digest", &wrong)])
+            .await;
+        assert_eq!(res.status, 422, "{}", res.text());
+
+        let create = || app.send_form(Method::POST, "/api/drops", drop_from_upload(&vendor, &upload_id), &[]);
+        let (a, b) = tokio::join!(create(), create());
+        let mut statuses = [a.status.as_u16(), b.status.as_u16()];
+        statuses.sort();
+        assert_eq!(statuses, [200, 409], "{} / {}", a.text(), b.text());
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM drops")
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    async fn session_status(app: &TestApp, upload_id: &str) -> i32 {
+        app.get(&format!("/api/uploads/{}", upload_id)).await.json()["upload"]["status"]
+            .as_i64()
+            .unwrap() as i32
+    }
+
+    #[tokio::test]
+    async fn expiry_skips_sessions_consumed_after_listing() {
+        let app = TestApp::new().await;
+        let stale = create_session(&app, 10).await;
+        let consumed = create_session(&app, 10).await;
+        sqlx::query("UPDATE upload_sessions SET expires_at_ms = 0")
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let part = |id: &str| super::upload_file_path(&app.state.base_data_dir, id);
+
+        // 一覧取得後に Drop 作成で使われたセッションは EXPIRED にせず、ファイルも残す
+        sqlx::query("UPDATE upload_sessions SET status = ? WHERE upload_id = ?")
+            .bind(upload_status::CONSUMED)
+            .bind(&consumed)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        assert!(!super::expire_session(&app.state, &consumed, now_ms).await.unwrap());
+        assert_eq!(session_status(&app, &consumed).await, upload_status::CONSUMED);
+        assert!(part(&consumed).exists());
+
+        assert_eq!(super::expire_upload_sessions(&app.state).await.unwrap(), 1);
+        assert_eq!(session_status(&app, &stale).await, upload_status::EXPIRED);
+        assert!(!part(&stale).exists());
+        assert!(part(&consumed).exists());
+    }
+}
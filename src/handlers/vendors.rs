@@ -2,11 +2,12 @@
 //! /api/vendors エンドポイント
 
 use axum::{
-    extract::{Path, State, Multipart},
-    http::StatusCode,
+    extract::{Path, Query, State, Multipart},
+    http::HeaderMap,
     response::Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs;
@@ -14,44 +15,79 @@ use tokio::io::AsyncWriteExt;
 use tracing::{info, warn};
 use sha2::{Sha256, Digest};
 use base32;
+use base64::Engine;
+use blake2::{digest::consts::U32, Blake2b};
+use ed25519_dalek::{Signature, VerifyingKey};
 use rand::Rng;
 
+use crate::error::{ApiError, ErrorResponse};
+use crate::response::{ApiResponse, ListMeta};
 use crate::models::{
-    CreateVendorRequest, UpdateVendorRequest, Vendor, VendorProfile, VendorResponse,
-    AddFollowerRequest, FollowerResponse, SubscriberListResponse, CountResponse,
+    CreateVendorRequest, UpdateVendorRequest, Vendor, VendorProfile, VendorProfileVersion, VendorResponse,
+    AddFollowerRequest, FollowerResponse, SubscriberListResponse, CountResponse, ms_to_iso,
 };
+use crate::handlers::{admin, tombstones};
+use crate::images;
+use crate::models::{shop_type, tombstone_kind};
+use crate::signed_url;
+use crate::storage;
+use crate::extract::ApiJson;
 use crate::AppState;
 
+/// profile.json の最大サイズ（シリアライズ後、バイト）
+const MAX_PROFILE_BYTES: usize = 64 * 1024;
+/// profile（extra を含む）のネストの最大深さ
+const MAX_PROFILE_DEPTH: usize = 8;
+
 // ========================================
 // Response Types
 // ========================================
 
-#[derive(Serialize)]
-pub struct VendorListResponse {
-    pub success: bool,
-    pub vendors: Vec<VendorResponse>,
-    pub total: usize,
-}
+/// 一覧レスポンス（`data` + `meta`、移行期間中は旧フィールド `vendors` / `total` も返す）
+pub type VendorListResponse = ApiResponse<Vec<VendorResponse>>;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct VendorDetailResponse {
     pub success: bool,
     pub vendor: Option<VendorResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct VendorCreateResponse {
     pub success: bool,
     pub stable_id: String,
-    pub peer_id: String,
-    pub manifest_url: String,
-    pub manifest_sha256: String,
+    /// 未設定の項目は空文字ではなく null
+    pub peer_id: Option<String>,
+    pub manifest_url: Option<String>,
+    pub manifest_sha256: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
+#[derive(Serialize, ToSchema)]
+pub struct ProfileHistoryResponse {
     pub success: bool,
-    pub error: String,
+    pub stable_id: String,
+    pub current_seq: i64,
+    pub versions: Vec<VendorProfileVersion>,
+}
+
+// ========================================
+// Query Parameters
+// ========================================
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListVendorsQuery {
+    /// 環境（devnet / mainnet 等）。未指定時は DEFAULT_ENV、"all" で全環境
+    pub env: Option<String>,
+    /// 0=in_app, 1=external_web（未指定時は全種別）
+    pub shop_type: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VendorsByOwnerQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 // ========================================
@@ -59,17 +95,38 @@ pub struct ErrorResponse {
 // ========================================
 
 /// GET /api/vendors - Vendor一覧取得
+#[utoipa::path(
+    get,
+    path = "/api/vendors",
+    tag = "vendors",
+    params(ListVendorsQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<VendorResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_vendors(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<VendorListResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let vendors: Vec<Vendor> = sqlx::query_as(
-        "SELECT * FROM vendors WHERE is_alive = 1 ORDER BY created_at_ms DESC"
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    Query(query): Query<ListVendorsQuery>,
+) -> Result<Json<VendorListResponse>, ApiError> {
+    if let Some(code) = query.shop_type {
+        validate_shop_type(code)?;
+    }
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM vendors WHERE is_alive = 1");
+    if let Some(env) = state.config.env_filter(query.env.as_deref()) {
+        builder.push(" AND env = ").push_bind(env);
+    }
+    if let Some(code) = query.shop_type {
+        builder.push(" AND shop_type = ").push_bind(code);
+    }
+    builder.push(" ORDER BY created_at_ms DESC");
+
+    let vendors: Vec<Vendor> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await?;
 
     let mut responses = Vec::new();
     for v in &vendors {
@@ -77,28 +134,34 @@ pub async fn list_vendors(
         responses.push(vendor_to_response(v, profile));
     }
 
-    let total = responses.len();
-    Ok(Json(VendorListResponse {
-        success: true,
-        vendors: responses,
-        total,
-    }))
+    let meta = ListMeta::total(responses.len());
+    Ok(Json(
+        ApiResponse::list(responses, meta).with_legacy_fields(state.config.legacy_list_fields, "vendors"),
+    ))
 }
 
 /// GET /api/vendors/:stable_id - Vendor詳細取得
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{stable_id}",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = VendorDetailResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_vendor(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<VendorDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<VendorDetailResponse>, ApiError> {
     let vendor: Option<Vendor> = sqlx::query_as(
         "SELECT * FROM vendors WHERE stable_id = ?"
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     match vendor {
         Some(v) => {
@@ -108,24 +171,32 @@ pub async fn get_vendor(
                 vendor: Some(vendor_to_response(&v, profile)),
             }))
         }
-        None => Err(error_response(StatusCode::NOT_FOUND, "Vendor not found".to_string())),
+        None => Err(ApiError::NotFound("Vendor not found".to_string())),
     }
 }
 
 /// GET /api/vendors/by-peer/:peer_id - peer_idでVendor検索（複数返却）
+#[utoipa::path(
+    get,
+    path = "/api/vendors/by-peer/{peer_id}",
+    tag = "vendors",
+    params(("peer_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<VendorResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_vendor_by_peer(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
-) -> Result<Json<VendorListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<VendorListResponse>, ApiError> {
     let vendors: Vec<Vendor> = sqlx::query_as(
         "SELECT * FROM vendors WHERE peer_id = ? AND is_alive = 1 ORDER BY created_at_ms DESC"
     )
     .bind(&peer_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     let mut responses = Vec::new();
     for v in &vendors {
@@ -133,22 +204,93 @@ pub async fn get_vendor_by_peer(
         responses.push(vendor_to_response(v, profile));
     }
 
-    let total = responses.len();
-    Ok(Json(VendorListResponse {
-        success: true,
-        vendors: responses,
+    let meta = ListMeta::total(responses.len());
+    Ok(Json(
+        ApiResponse::list(responses, meta).with_legacy_fields(state.config.legacy_list_fields, "vendors"),
+    ))
+}
+
+/// GET /api/vendors/by-owner/:owner - owner アドレスで Vendor 検索（生存中のみ、ページング付き）
+/// アドレスは大文字小文字を区別しない（X-Owner-Address の照合と同じ）
+#[utoipa::path(
+    get,
+    path = "/api/vendors/by-owner/{owner}",
+    tag = "vendors",
+    params(("owner" = String, Path), VendorsByOwnerQuery),
+    responses(
+        (status = 200, description = "成功", body = ApiResponse<Vec<VendorResponse>>),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_vendors_by_owner(
+    State(state): State<Arc<AppState>>,
+    Path(owner): Path<String>,
+    Query(query): Query<VendorsByOwnerQuery>,
+) -> Result<Json<VendorListResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (total,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM vendors WHERE owner = ? COLLATE NOCASE AND is_alive = 1"
+    )
+    .bind(&owner)
+    .fetch_one(&state.db)
+    .await?;
+
+    let vendors: Vec<Vendor> = sqlx::query_as(
+        "SELECT * FROM vendors WHERE owner = ? COLLATE NOCASE AND is_alive = 1 ORDER BY created_at_ms DESC, stable_id ASC LIMIT ? OFFSET ?"
+    )
+    .bind(&owner)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut responses = Vec::new();
+    for v in &vendors {
+        let profile = load_vendor_profile(&state.base_data_dir, &v.stable_id).await.ok();
+        responses.push(vendor_to_response(v, profile));
+    }
+
+    let meta = ListMeta {
         total,
-    }))
+        limit: Some(limit),
+        offset: Some(offset),
+        ..ListMeta::default()
+    };
+    let legacy = state.config.legacy_list_fields;
+    Ok(Json(
+        ApiResponse::list(responses, meta)
+            .with_legacy_fields(legacy, "vendors")
+            .with_legacy_value(legacy, "owner", owner),
+    ))
 }
 
 /// POST /api/vendors - Vendor作成
-/// 同一peer_idで複数ベンダーを作成可能
+/// 同一peer_idで複数ベンダーを作成可能（peer_id による既存 Vendor の再利用はせず、毎回新しい stable_id を発行する）
+/// デリスト済み（is_alive = 0）の Vendor も作成を妨げない。409 になるのは stable_id を明示して既存と重複した場合のみ
+#[utoipa::path(
+    post,
+    path = "/api/vendors",
+    tag = "vendors",
+    request_body = CreateVendorRequest,
+    responses(
+        (status = 200, description = "成功", body = VendorCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn create_vendor(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateVendorRequest>,
-) -> Result<Json<VendorCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<CreateVendorRequest>,
+) -> Result<Json<VendorCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
+    validate_vendor_profile(&req.profile)
+        .map_err(ApiError::BadRequest)?;
+    validate_shop_type(req.shop_type)?;
+
     // stable_id が指定されている場合は重複チェック
     if let Some(ref specified_id) = req.stable_id {
         let existing: Option<Vendor> = sqlx::query_as(
@@ -156,14 +298,10 @@ pub async fn create_vendor(
         )
         .bind(specified_id)
         .fetch_optional(&state.db)
-        .await
-        .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-        })?;
+        .await?;
 
         if existing.is_some() {
-            return Err(error_response(
-                StatusCode::CONFLICT,
+            return Err(ApiError::Conflict(
                 format!("Vendor with stable_id '{}' already exists", specified_id)
             ));
         }
@@ -185,28 +323,32 @@ pub async fn create_vendor(
         .join("vendors")
         .join(&stable_id);
     fs::create_dir_all(&vendor_dir).await.map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+        ApiError::internal(format!("Failed to create dir: {}", e))
     })?;
 
-    // profile.json を保存
+    // profile.json を保存（初版は profile_seq = 1）
+    let profile_seq = 1;
     let (manifest_url, manifest_sha256) = save_vendor_profile(
         &state.base_data_dir,
         &state.vps_base_url,
         &stable_id,
+        profile_seq,
         &req.profile,
     )
     .await
     .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save profile: {}", e))
+        ApiError::internal(format!("Failed to save profile: {}", e))
     })?;
 
+    let mut tx = state.db.begin().await?;
+
     // DBに挿入
     sqlx::query(r#"
         INSERT INTO vendors (
             stable_id, peer_id, peer_id_sha256, latest_object_id, owner, mode, shop_type, backend,
             manifest_url, manifest_sha256, profile_seq,
             status, env, created_at_ms, updated_at_ms, is_alive
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1, 0, ?, ?, ?, 1)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, 1)
     "#)
     .bind(&stable_id)
     .bind(&req.peer_id)
@@ -218,32 +360,48 @@ pub async fn create_vendor(
     .bind(req.backend)
     .bind(&manifest_url)
     .bind(&manifest_sha256)
+    .bind(profile_seq)
     .bind(&req.env)
     .bind(now_ms)
     .bind(now_ms)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .execute(&mut *tx)
+    .await?;
+
+    record_profile_version(&mut *tx, &state.vps_base_url, &stable_id, profile_seq, &manifest_sha256, now_ms)
+        .await?;
+
+    tx.commit().await?;
 
     info!("Vendor created: stable_id={}, peer_id={}", stable_id, req.peer_id);
 
     Ok(Json(VendorCreateResponse {
         success: true,
         stable_id,
-        peer_id: req.peer_id,
-        manifest_url,
-        manifest_sha256,
+        peer_id: Some(req.peer_id),
+        manifest_url: Some(signed_url::sign(&state.config, &manifest_url)),
+        manifest_sha256: Some(manifest_sha256),
     }))
 }
 
 /// PUT /api/vendors/:stable_id - Vendor更新
+#[utoipa::path(
+    put,
+    path = "/api/vendors/{stable_id}",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    request_body = UpdateVendorRequest,
+    responses(
+        (status = 200, description = "成功", body = VendorCreateResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn update_vendor(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-    Json(req): Json<UpdateVendorRequest>,
-) -> Result<Json<VendorCreateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<UpdateVendorRequest>,
+) -> Result<Json<VendorCreateResponse>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // 既存チェック
@@ -252,31 +410,48 @@ pub async fn update_vendor(
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     let v = match existing {
         Some(v) => v,
-        None => return Err(error_response(StatusCode::NOT_FOUND, "Vendor not found".to_string())),
+        None => return Err(ApiError::NotFound("Vendor not found".to_string())),
     };
+    if let Some(code) = req.shop_type {
+        validate_shop_type(code)?;
+    }
 
+    // owner の変更は管理者トークン、または現 owner の署名がある場合のみ（未設定からの設定は管理者のみ）
+    if let Some(new_owner) = &req.owner {
+        let unchanged = v.owner.as_deref().is_some_and(|o| o.eq_ignore_ascii_case(new_owner));
+        if !unchanged && !admin::is_admin(&state, &headers) {
+            check_vendor_owner(&state, &headers, &stable_id).await?;
+        }
+    }
+
+    // profile_seq はプロフィールを保存したときだけ進める
+    let profile_seq = if req.profile.is_some() { v.profile_seq + 1 } else { v.profile_seq };
     let (manifest_url, manifest_sha256) = if let Some(profile) = &req.profile {
+        validate_vendor_profile(profile)
+            .map_err(ApiError::BadRequest)?;
         save_vendor_profile(
             &state.base_data_dir,
             &state.vps_base_url,
             &stable_id,
+            profile_seq,
             profile,
         )
         .await
+        .map(|(url, sha256)| (Some(url), Some(sha256)))
         .map_err(|e| {
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save profile: {}", e))
+            ApiError::internal(format!("Failed to save profile: {}", e))
         })?
     } else {
-        (v.manifest_url.clone().unwrap_or_default(), v.manifest_sha256.clone().unwrap_or_default())
+        // プロフィール未指定時は現在の値のまま（未設定は NULL のまま）
+        (v.manifest_url.clone(), v.manifest_sha256.clone())
     };
 
+    let mut tx = state.db.begin().await?;
+
     // DB更新
     sqlx::query(r#"
         UPDATE vendors SET
@@ -284,9 +459,10 @@ pub async fn update_vendor(
             owner = COALESCE(?, owner),
             manifest_url = ?,
             manifest_sha256 = ?,
-            profile_seq = profile_seq + 1,
+            profile_seq = ?,
             status = COALESCE(?, status),
             backend = COALESCE(?, backend),
+            shop_type = COALESCE(?, shop_type),
             updated_at_ms = ?
         WHERE stable_id = ?
     "#)
@@ -294,32 +470,50 @@ pub async fn update_vendor(
     .bind(&req.owner)
     .bind(&manifest_url)
     .bind(&manifest_sha256)
+    .bind(profile_seq)
     .bind(req.status)
     .bind(req.backend)
+    .bind(req.shop_type)
     .bind(now_ms)
     .bind(&stable_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .execute(&mut *tx)
+    .await?;
+
+    // プロフィールを書き換えた場合のみ履歴に追加
+    if let (Some(_), Some(sha256)) = (&req.profile, &manifest_sha256) {
+        record_profile_version(&mut *tx, &state.vps_base_url, &stable_id, profile_seq, sha256, now_ms)
+            .await?;
+    }
+
+    tx.commit().await?;
 
     info!("Vendor updated: stable_id={}", stable_id);
 
     Ok(Json(VendorCreateResponse {
         success: true,
         stable_id,
-        peer_id: v.peer_id.unwrap_or_default(),
-        manifest_url,
+        peer_id: v.peer_id,
+        manifest_url: signed_url::sign_opt(&state.config, manifest_url),
         manifest_sha256,
     }))
 }
 
 /// DELETE /api/vendors/:stable_id - Vendorをデリスト（論理削除）
+#[utoipa::path(
+    delete,
+    path = "/api/vendors/{stable_id}",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn delist_vendor(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // 既存チェック
@@ -328,24 +522,35 @@ pub async fn delist_vendor(
     )
     .bind(&stable_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-    })?;
+    .await?;
 
     match existing {
         Some(v) => {
+            let mut tx = state.db.begin().await?;
+
             // is_alive を 0 に設定（論理削除）
             sqlx::query(
                 "UPDATE vendors SET is_alive = 0, updated_at_ms = ? WHERE stable_id = ?"
             )
             .bind(now_ms)
             .bind(&stable_id)
-            .execute(&state.db)
-            .await
-            .map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
-            })?;
+            .execute(&mut *tx)
+            .await?;
+
+            // 初回の delist のみ Tombstone を記録（object_id 未登録時は stable_id）
+            if v.is_alive == 1 {
+                tombstones::record_tombstone(
+                    &mut *tx,
+                    tombstone_kind::VENDOR,
+                    Some(&stable_id),
+                    v.latest_object_id.as_deref().unwrap_or(&stable_id),
+                    &v.env,
+                    now_ms,
+                )
+                .await?;
+            }
+
+            tx.commit().await?;
 
             info!("Vendor delisted: stable_id={}, peer_id={:?}", stable_id, v.peer_id);
 
@@ -355,27 +560,184 @@ pub async fn delist_vendor(
                 "message": "Vendor delisted successfully"
             })))
         }
-        None => Err(error_response(StatusCode::NOT_FOUND, "Vendor not found".to_string())),
+        None => Err(ApiError::NotFound("Vendor not found".to_string())),
     }
 }
 
+/// POST /api/vendors/:stable_id/regenerate - profile.json を再生成して URL/SHA256 を更新
+/// ディスク上のファイルが外部で編集・破損した場合の修復用。
+/// 読み込めない場合は DB とアイコンファイルから最小限のプロフィールを再構築する
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{stable_id}/regenerate",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn regenerate_vendor_profile(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let vendor: Option<Vendor> = sqlx::query_as(
+        "SELECT * FROM vendors WHERE stable_id = ?"
+    )
+    .bind(&stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some(vendor) = vendor else {
+        return Err(ApiError::NotFound("Vendor not found".to_string()));
+    };
+    let profile_seq = vendor.profile_seq + 1;
+
+    let (profile, profile_source) = match load_vendor_profile(&state.base_data_dir, &stable_id).await {
+        Ok(profile) => (profile, "file"),
+        Err(e) => {
+            warn!("Vendor profile unreadable, rebuilding from DB: stable_id={}, error={}", stable_id, e);
+            let dir = PathBuf::from(&state.base_data_dir)
+                .join("account")
+                .join("vendors")
+                .join(&stable_id);
+            let (icon, thumb) = storage::find_icon_files(&dir).await;
+            let icon_url_for = |name: String| {
+                format!("{}/account/vendors/{}/{}", state.vps_base_url, stable_id, name)
+            };
+            let profile = VendorProfile {
+                name: stable_id.clone(),
+                description: None,
+                icon_url: icon.map(icon_url_for),
+                icon_thumb_url: thumb.map(icon_url_for),
+                address: None,
+                fee_rate: None,
+                extra: serde_json::Value::Object(Default::default()),
+            };
+            (profile, "db")
+        }
+    };
+
+    let (manifest_url, manifest_sha256) = save_vendor_profile(
+        &state.base_data_dir,
+        &state.vps_base_url,
+        &stable_id,
+        profile_seq,
+        &profile,
+    )
+    .await
+    .map_err(|e| {
+        ApiError::internal(format!("Failed to save profile: {}", e))
+    })?;
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query(r#"
+        UPDATE vendors SET
+            manifest_url = ?,
+            manifest_sha256 = ?,
+            profile_seq = ?,
+            updated_at_ms = ?
+        WHERE stable_id = ?
+    "#)
+    .bind(&manifest_url)
+    .bind(&manifest_sha256)
+    .bind(profile_seq)
+    .bind(now_ms)
+    .bind(&stable_id)
+    .execute(&mut *tx)
+    .await?;
+
+    record_profile_version(&mut *tx, &state.vps_base_url, &stable_id, profile_seq, &manifest_sha256, now_ms)
+        .await?;
+
+    tx.commit().await?;
+
+    info!("Vendor profile regenerated: stable_id={}, source={}", stable_id, profile_source);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "stable_id": stable_id,
+        "manifest_url": signed_url::sign(&state.config, &manifest_url),
+        "manifest_sha256": manifest_sha256,
+        "profile_source": profile_source
+    })))
+}
+
+/// GET /api/vendors/:stable_id/profile/history - profile.json の過去バージョン一覧（古い順）
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{stable_id}/profile/history",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = ProfileHistoryResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
+pub async fn get_profile_history(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Json<ProfileHistoryResponse>, ApiError> {
+    let current: Option<(i64,)> = sqlx::query_as(
+        "SELECT profile_seq FROM vendors WHERE stable_id = ?"
+    )
+    .bind(&stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some((current_seq,)) = current else {
+        return Err(ApiError::NotFound("Vendor not found".to_string()));
+    };
+
+    let versions: Vec<VendorProfileVersion> = sqlx::query_as(
+        "SELECT * FROM vendor_profile_history WHERE stable_id = ? ORDER BY profile_seq ASC"
+    )
+    .bind(&stable_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ProfileHistoryResponse {
+        success: true,
+        stable_id,
+        current_seq,
+        versions,
+    }))
+}
+
 /// POST /api/vendors/:stable_id/icon - アイコンアップロード
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{stable_id}/icon",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    request_body(content_type = "multipart/form-data", description = "file（または icon）: 画像ファイル"),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn upload_vendor_icon(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
     mut multipart: Multipart,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // ファイルを取得
     while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error_response(StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
+        ApiError::new(e.status(), format!("Multipart error: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
         if name == "file" || name == "icon" {
             let filename = field.file_name().unwrap_or("icon.webp").to_string();
-            let ext = filename.split('.').last().unwrap_or("webp");
+            let ext = filename.split('.').next_back().unwrap_or("webp");
 
             let data = field.bytes().await.map_err(|e| {
-                error_response(StatusCode::BAD_REQUEST, format!("File read error: {}", e))
+                ApiError::new(e.status(), format!("File read error: {}", e))
             })?;
 
             // 保存先ディレクトリ
@@ -384,51 +746,163 @@ pub async fn upload_vendor_icon(
                 .join("vendors")
                 .join(&stable_id);
             fs::create_dir_all(&dir).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create dir: {}", e))
+                ApiError::internal(format!("Failed to create dir: {}", e))
             })?;
 
             // ファイル保存
             let icon_filename = format!("icon.{}", ext);
             let path = dir.join(&icon_filename);
             let mut file = fs::File::create(&path).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create file: {}", e))
+                ApiError::internal(format!("Failed to create file: {}", e))
             })?;
             file.write_all(&data).await.map_err(|e| {
-                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write file: {}", e))
+                ApiError::internal(format!("Failed to write file: {}", e))
             })?;
 
+            // サムネイル生成（正方形 WebP、失敗してもオリジナルは保存済み）
+            let thumb_filename = "icon_thumb.webp";
+            let thumb_generated =
+                images::generate_icon_thumbnail(data.to_vec(), dir.join(thumb_filename)).await;
+
             let icon_url = format!("{}/account/vendors/{}/{}", state.vps_base_url, stable_id, icon_filename);
-            info!("Icon uploaded: {}", icon_url);
-
-            // profile.json の icon_url を更新
-            let profile_path = dir.join("profile.json");
-            if profile_path.exists() {
-                if let Ok(content) = fs::read_to_string(&profile_path).await {
-                    if let Ok(mut profile) = serde_json::from_str::<VendorProfile>(&content) {
-                        profile.icon_url = Some(icon_url.clone());
-                        if let Ok(updated_json) = serde_json::to_string_pretty(&profile) {
-                            let _ = fs::write(&profile_path, updated_json).await;
-                            info!("Profile updated with icon_url: {}", icon_url);
-                        }
-                    }
-                }
-            }
+            let icon_thumb_url = thumb_generated.then(|| {
+                format!("{}/account/vendors/{}/{}", state.vps_base_url, stable_id, thumb_filename)
+            });
+            info!("Icon uploaded: {} (thumb: {:?})", icon_url, icon_thumb_url);
+
+            // profile.json の icon_url を更新し、DB の manifest・profile_seq・updated_at_ms も合わせる
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let manifest_sha256 =
+                bump_vendor_profile_for_icon(&state, &stable_id, &icon_url, icon_thumb_url.as_deref(), now_ms).await?;
+
+            // 所有権を変更（UPLOAD_CHOWN、ベストエフォート）
+            storage::apply_upload_owner(state.config.upload_chown.as_deref(), &dir, true).await;
 
             return Ok(Json(serde_json::json!({
                 "success": true,
-                "icon_url": icon_url,
+                "icon_url": signed_url::sign(&state.config, &icon_url),
+                "icon_thumb_url": signed_url::sign_opt(&state.config, icon_thumb_url),
+                "manifest_sha256": manifest_sha256,
+                "updated_at_ms": now_ms,
                 "path": path.to_string_lossy()
             })));
         }
     }
 
-    Err(error_response(StatusCode::BAD_REQUEST, "No file provided".to_string()))
+    Err(ApiError::BadRequest("No file provided".to_string()))
 }
 
 // ========================================
 // Helper Functions
 // ========================================
 
+/// 呼び出し元が Vendor の owner か検証（ENFORCE_VENDOR_OWNER 有効時のみ）
+pub async fn verify_vendor_owner(
+    state: &AppState,
+    headers: &HeaderMap,
+    vendor_stable_id: &str,
+) -> Result<(), ApiError> {
+    if !state.config.enforce_vendor_owner {
+        return Ok(());
+    }
+    check_vendor_owner(state, headers, vendor_stable_id).await
+}
+
+/// 署名で証明された呼び出し元のアドレスと vendors.owner を比較する（設定に関わらず常に検証）
+pub async fn check_vendor_owner(
+    state: &AppState,
+    headers: &HeaderMap,
+    vendor_stable_id: &str,
+) -> Result<(), ApiError> {
+    let caller = verify_owner_signature(state, headers).await?;
+    ensure_vendor_owner(state, &caller, vendor_stable_id).await
+}
+
+/// 署名検証済みのアドレスが vendors.owner と一致するか
+pub async fn ensure_vendor_owner(
+    state: &AppState,
+    caller: &str,
+    vendor_stable_id: &str,
+) -> Result<(), ApiError> {
+    let owner: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT owner FROM vendors WHERE stable_id = ? AND is_alive = 1"
+    )
+    .bind(vendor_stable_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match owner.and_then(|(o,)| o) {
+        Some(owner) if owner.eq_ignore_ascii_case(caller) => Ok(()),
+        _ => Err(ApiError::Forbidden(format!(
+            "Caller is not the owner of vendor {}",
+            vendor_stable_id
+        ))),
+    }
+}
+
+/// X-Owner-* ヘッダの署名を検証し、証明されたアドレスを返す
+///
+/// 1. X-Owner-Public-Key から Sui アドレスを導出し、X-Owner-Address と一致確認
+/// 2. `"{challenge}\n{address}"`（address は小文字）の Ed25519 署名を検証（verify_strict）
+/// 3. challenge（GET /api/devices/auth/challenge で発行）を消費（再利用防止、1リクエストにつき1回だけ呼ぶ）
+pub async fn verify_owner_signature(state: &AppState, headers: &HeaderMap) -> Result<String, ApiError> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let (Some(address), Some(public_key), Some(challenge), Some(signature)) = (
+        header("x-owner-address"),
+        header("x-owner-public-key"),
+        header("x-owner-challenge"),
+        header("x-owner-signature"),
+    ) else {
+        return Err(ApiError::Unauthorized(
+            "X-Owner-Address, X-Owner-Public-Key, X-Owner-Challenge and X-Owner-Signature headers required".to_string(),
+        ));
+    };
+
+    let public_key: [u8; 32] = base64::engine::general_purpose::STANDARD
+        .decode(public_key)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| ApiError::Unauthorized("X-Owner-Public-Key must be a base64 Ed25519 public key".to_string()))?;
+    let verifying_key = VerifyingKey::from_bytes(&public_key)
+        .map_err(|_| ApiError::Unauthorized("Invalid X-Owner-Public-Key".to_string()))?;
+    if !sui_address_from_pubkey(&public_key).eq_ignore_ascii_case(address) {
+        return Err(ApiError::Unauthorized(
+            "X-Owner-Address does not match X-Owner-Public-Key".to_string(),
+        ));
+    }
+
+    let signature: [u8; 64] = base64::engine::general_purpose::STANDARD
+        .decode(signature)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| ApiError::Unauthorized("X-Owner-Signature must be a base64 Ed25519 signature".to_string()))?;
+    let message = format!("{}\n{}", challenge, address.to_ascii_lowercase());
+    verifying_key
+        .verify_strict(message.as_bytes(), &Signature::from_bytes(&signature))
+        .map_err(|_| ApiError::Unauthorized("Owner signature verification failed".to_string()))?;
+
+    // 署名が正しいことを確認してから challenge を消費する
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    match state.challenges.write().await.remove(challenge) {
+        Some((_, expires_at_ms)) if expires_at_ms >= now_ms => Ok(address.to_string()),
+        _ => Err(ApiError::Unauthorized("Unknown or expired challenge".to_string())),
+    }
+}
+
+/// Ed25519 公開鍵から Sui アドレスを導出（0x + hex(blake2b256(0x00 || pubkey))）
+pub fn sui_address_from_pubkey(public_key: &[u8; 32]) -> String {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([0x00]); // Ed25519 の署名スキームフラグ
+    hasher.update(public_key);
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
 /// stable_id を生成（PREFIX_XXXXXXXX形式）
 fn generate_stable_id(prefix: &str) -> String {
     let random_bytes: [u8; 5] = rand::thread_rng().gen();
@@ -436,11 +910,119 @@ fn generate_stable_id(prefix: &str) -> String {
     format!("{}_{}", prefix, &encoded[..8])
 }
 
+/// VendorProfile のサイズ・ネスト深さを検証（保存前に呼ぶ）
+/// extra は任意 JSON のため、巨大なデータや深いネストを拒否する
+fn validate_vendor_profile(profile: &VendorProfile) -> Result<(), String> {
+    let value = serde_json::to_value(profile).map_err(|e| format!("Invalid profile: {}", e))?;
+
+    let depth = json_depth(&value);
+    if depth > MAX_PROFILE_DEPTH {
+        return Err(format!(
+            "Profile is nested too deeply: depth {} exceeds limit {}",
+            depth, MAX_PROFILE_DEPTH
+        ));
+    }
+
+    let size = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Invalid profile: {}", e))?
+        .len();
+    if size > MAX_PROFILE_BYTES {
+        return Err(format!(
+            "Profile too large: {} bytes exceeds limit {} bytes",
+            size, MAX_PROFILE_BYTES
+        ));
+    }
+
+    Ok(())
+}
+
+/// JSON 値のネスト深さ（スカラーは 0、空でないオブジェクト/配列ごとに +1）
+fn json_depth(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Object(map) => 1 + map.values().map(json_depth).max().unwrap_or(0),
+        serde_json::Value::Array(items) => 1 + items.iter().map(json_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// アイコン更新を profile.json と DB に反映し、新しい manifest_sha256 を返す
+/// profile.json が無い・読めない場合は updated_at_ms のみ進めて None
+async fn bump_vendor_profile_for_icon(
+    state: &AppState,
+    stable_id: &str,
+    icon_url: &str,
+    icon_thumb_url: Option<&str>,
+    now_ms: i64,
+) -> Result<Option<String>, ApiError> {
+    let vendor: Option<Vendor> = sqlx::query_as("SELECT * FROM vendors WHERE stable_id = ?")
+        .bind(stable_id)
+        .fetch_optional(&state.db)
+        .await?;
+    let profile = load_vendor_profile(&state.base_data_dir, stable_id).await;
+
+    let (vendor, mut profile) = match (vendor, profile) {
+        (Some(vendor), Ok(profile)) => (vendor, profile),
+        (Some(_), Err(e)) => {
+            warn!("Vendor profile unreadable, icon not recorded in profile: stable_id={}, error={}", stable_id, e);
+            sqlx::query("UPDATE vendors SET updated_at_ms = ? WHERE stable_id = ?")
+                .bind(now_ms)
+                .bind(stable_id)
+                .execute(&state.db)
+                .await?;
+            return Ok(None);
+        }
+        (None, _) => return Ok(None),
+    };
+
+    profile.icon_url = Some(icon_url.to_string());
+    profile.icon_thumb_url = icon_thumb_url.map(str::to_string);
+    let profile_seq = vendor.profile_seq + 1;
+    let (manifest_url, manifest_sha256) = save_vendor_profile(
+        &state.base_data_dir,
+        &state.vps_base_url,
+        stable_id,
+        profile_seq,
+        &profile,
+    )
+    .await
+    .map_err(|e| {
+        ApiError::internal(format!("Failed to save profile: {}", e))
+    })?;
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query(r#"
+        UPDATE vendors SET
+            manifest_url = ?,
+            manifest_sha256 = ?,
+            profile_seq = ?,
+            updated_at_ms = ?
+        WHERE stable_id = ?
+    "#)
+    .bind(&manifest_url)
+    .bind(&manifest_sha256)
+    .bind(profile_seq)
+    .bind(now_ms)
+    .bind(stable_id)
+    .execute(&mut *tx)
+    .await?;
+
+    record_profile_version(&mut *tx, &state.vps_base_url, stable_id, profile_seq, &manifest_sha256, now_ms)
+        .await?;
+
+    tx.commit().await?;
+
+    info!("Profile updated with icon_url: {} (seq: {})", icon_url, profile_seq);
+    Ok(Some(manifest_sha256))
+}
+
 /// VendorProfile を保存して URL と SHA256 を返す
+/// profile.json（最新）に加えて、不変の profile.v<seq>.json も書き出す
 async fn save_vendor_profile(
     base_dir: &str,
     base_url: &str,
     stable_id: &str,
+    profile_seq: i64,
     profile: &VendorProfile,
 ) -> anyhow::Result<(String, String)> {
     let dir = PathBuf::from(base_dir)
@@ -456,10 +1038,9 @@ async fn save_vendor_profile(
     hasher.update(json.as_bytes());
     let sha256 = hex::encode(hasher.finalize());
 
-    // ファイル保存
-    let path = dir.join("profile.json");
-    let mut file = fs::File::create(&path).await?;
-    file.write_all(json.as_bytes()).await?;
+    // ファイル保存（バージョン付きを先に書き、最新を差し替える）
+    storage::write_atomic(&dir.join(versioned_profile_name(profile_seq)), json.as_bytes()).await?;
+    storage::write_atomic(&dir.join("profile.json"), json.as_bytes()).await?;
 
     let url = format!("{}/account/vendors/{}/profile.json", base_url, stable_id);
 
@@ -468,8 +1049,42 @@ async fn save_vendor_profile(
     Ok((url, sha256))
 }
 
+/// バージョン付き profile のファイル名
+fn versioned_profile_name(profile_seq: i64) -> String {
+    format!("profile.v{}.json", profile_seq)
+}
+
+/// profile の履歴を vendor_profile_history に記録
+async fn record_profile_version<'e, E>(
+    executor: E,
+    base_url: &str,
+    stable_id: &str,
+    profile_seq: i64,
+    sha256: &str,
+    now_ms: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let url = format!(
+        "{}/account/vendors/{}/{}",
+        base_url, stable_id, versioned_profile_name(profile_seq)
+    );
+    sqlx::query(
+        "INSERT OR REPLACE INTO vendor_profile_history (stable_id, profile_seq, sha256, url, created_at_ms) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(stable_id)
+    .bind(profile_seq)
+    .bind(sha256)
+    .bind(url)
+    .bind(now_ms)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
 /// VendorProfile をファイルから読み込む
-async fn load_vendor_profile(base_dir: &str, stable_id: &str) -> anyhow::Result<VendorProfile> {
+pub(crate) async fn load_vendor_profile(base_dir: &str, stable_id: &str) -> anyhow::Result<VendorProfile> {
     let path = PathBuf::from(base_dir)
         .join("account")
         .join("vendors")
@@ -481,8 +1096,20 @@ async fn load_vendor_profile(base_dir: &str, stable_id: &str) -> anyhow::Result<
     Ok(profile)
 }
 
+/// shop_type は既知のコードのみ（0=in_app, 1=external_web）
+fn validate_shop_type(code: i32) -> Result<(), ApiError> {
+    if shop_type::is_valid(code) {
+        Ok(())
+    } else {
+        Err(ApiError::invalid_fields(
+            format!("shop_type must be 0 (in_app) or 1 (external_web) (got {})", code),
+            &["shop_type"],
+        ))
+    }
+}
+
 /// Vendor を VendorResponse に変換
-fn vendor_to_response(v: &Vendor, profile: Option<VendorProfile>) -> VendorResponse {
+pub(crate) fn vendor_to_response(v: &Vendor, profile: Option<VendorProfile>) -> VendorResponse {
     VendorResponse {
         stable_id: v.stable_id.clone(),
         peer_id: v.peer_id.clone(),
@@ -490,12 +1117,15 @@ fn vendor_to_response(v: &Vendor, profile: Option<VendorProfile>) -> VendorRespo
         owner: v.owner.clone(),
         mode: v.mode,
         shop_type: v.shop_type,
+        shop_type_label: shop_type::label(v.shop_type),
         backend: v.backend,
         profile,
         profile_seq: v.profile_seq,
         status: v.status,
         created_at_ms: v.created_at_ms,
         updated_at_ms: v.updated_at_ms,
+        created_at_iso: v.created_at_ms.and_then(ms_to_iso),
+        updated_at_iso: v.updated_at_ms.and_then(ms_to_iso),
         is_alive: v.is_alive == 1,
     }
 }
@@ -505,11 +1135,23 @@ fn vendor_to_response(v: &Vendor, profile: Option<VendorProfile>) -> VendorRespo
 // ========================================
 
 /// POST /api/vendors/:stable_id/subscribers - サブスクライバー登録
+#[utoipa::path(
+    post,
+    path = "/api/vendors/{stable_id}/subscribers",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    request_body = AddFollowerRequest,
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn add_subscriber(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-    Json(req): Json<AddFollowerRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<AddFollowerRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     // peer_profiles を UPSERT（初回は display_name=NULL のまま登録）
@@ -521,8 +1163,7 @@ pub async fn add_subscriber(
     .bind(&req.peer_id)
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     // vendor_subscribers に UPSERT
     sqlx::query(r#"
@@ -534,8 +1175,7 @@ pub async fn add_subscriber(
     .bind(&req.peer_id)
     .bind(now_ms)
     .execute(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     info!("Subscriber added: vendor={}, peer={}", stable_id, &req.peer_id[..20.min(req.peer_id.len())]);
 
@@ -543,16 +1183,26 @@ pub async fn add_subscriber(
 }
 
 /// DELETE /api/vendors/:stable_id/subscribers/:peer_id - サブスクライバー削除
+#[utoipa::path(
+    delete,
+    path = "/api/vendors/{stable_id}/subscribers/{peer_id}",
+    tag = "vendors",
+    params(("stable_id" = String, Path), ("peer_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = Object),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn remove_subscriber(
     State(state): State<Arc<AppState>>,
     Path((stable_id, peer_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     sqlx::query("DELETE FROM vendor_subscribers WHERE vendor_stable_id = ? AND peer_id = ?")
         .bind(&stable_id)
         .bind(&peer_id)
         .execute(&state.db)
-        .await
-        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+        .await?;
 
     info!("Subscriber removed: vendor={}, peer={}", stable_id, &peer_id[..20.min(peer_id.len())]);
 
@@ -560,10 +1210,21 @@ pub async fn remove_subscriber(
 }
 
 /// GET /api/vendors/:stable_id/subscribers - サブスクライバー一覧（peer_id 非公開）
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{stable_id}/subscribers",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = SubscriberListResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn list_subscribers(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<SubscriberListResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<SubscriberListResponse>, ApiError> {
     let rows: Vec<(Option<String>, Option<String>, i64)> = sqlx::query_as(
         r#"
         SELECT pp.display_name, pp.pfp_url, vs.subscribed_at_ms
@@ -575,8 +1236,7 @@ pub async fn list_subscribers(
     )
     .bind(&stable_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     let subscribers: Vec<FollowerResponse> = rows.into_iter().map(|(name, pfp, ts)| {
         FollowerResponse { display_name: name, pfp_url: pfp, followed_at_ms: ts }
@@ -586,23 +1246,506 @@ pub async fn list_subscribers(
 }
 
 /// GET /api/vendors/:stable_id/subscriber-count - サブスクライバー数（パブリック）
+#[utoipa::path(
+    get,
+    path = "/api/vendors/{stable_id}/subscriber-count",
+    tag = "vendors",
+    params(("stable_id" = String, Path)),
+    responses(
+        (status = 200, description = "成功", body = CountResponse),
+        (status = "4XX", description = "リクエストエラー", body = ErrorResponse),
+        (status = "5XX", description = "サーバエラー", body = ErrorResponse),
+    )
+)]
 pub async fn get_subscriber_count(
     State(state): State<Arc<AppState>>,
     Path(stable_id): Path<String>,
-) -> Result<Json<CountResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<CountResponse>, ApiError> {
     let (count,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM vendor_subscribers WHERE vendor_stable_id = ?"
     )
     .bind(&stable_id)
     .fetch_one(&state.db)
-    .await
-    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)))?;
+    .await?;
 
     Ok(Json(CountResponse { success: true, count }))
 }
 
-/// エラーレスポンス生成
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    warn!("API Error: {}", message);
-    (status, Json(ErrorResponse { success: false, error: message }))
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+    use sha2::Digest;
+
+    use crate::test_support::{
+        png_bytes, vendor_body, MultipartForm, OwnerHeaders, OwnerKey, TestApp, TestResponse, ADMIN_TOKEN,
+    };
+
+    /// depth 段にネストした JSON（最内は文字列）
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(json!("leaf"), |inner, _| json!({ "a": inner }))
+    }
+
+    #[tokio::test]
+    async fn vendor_icon_upload_generates_square_webp_thumbnail() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(400, 300));
+
+        let res = app.send_form(Method::POST, &format!("/api/vendors/{}/icon", vendor), form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert!(body["icon_thumb_url"].as_str().unwrap().ends_with("/icon_thumb.webp"));
+
+        let thumb = app.data_path(format!("account/vendors/{}/icon_thumb.webp", vendor));
+        let img = image::open(&thumb).unwrap();
+        assert_eq!((img.width(), img.height()), (256, 256));
+        assert_eq!(image::ImageFormat::from_path(&thumb).unwrap(), image::ImageFormat::WebP);
+
+        let vendor_json = app.get(&format!("/api/vendors/{}", vendor)).await.json();
+        assert_eq!(vendor_json["vendor"]["profile"]["icon_thumb_url"], body["icon_thumb_url"]);
+    }
+
+    #[tokio::test]
+    async fn undecodable_icon_drops_stale_thumbnail() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let uri = format!("/api/vendors/{}/icon", vendor);
+        let png = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(64, 64));
+        assert_eq!(app.send_form(Method::POST, &uri, png, &[]).await.status, 200);
+
+        let garbage = MultipartForm::new().file("file", "icon.png", "image/png", b"not an image");
+        let res = app.send_form(Method::POST, &uri, garbage, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert!(res.json()["icon_thumb_url"].is_null());
+        assert!(!app.data_path(format!("account/vendors/{}/icon_thumb.webp", vendor)).exists());
+    }
+
+    #[tokio::test]
+    async fn oversized_profile_is_rejected() {
+        let app = TestApp::new().await;
+        let mut body = vendor_body(None);
+        body["profile"]["extra"] = json!({ "blob": "x".repeat(70 * 1024) });
+        let res = app.post_json("/api/vendors", body).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("Profile too large"));
+    }
+
+    #[tokio::test]
+    async fn deeply_nested_profile_is_rejected() {
+        let app = TestApp::new().await;
+        // profile 自体で 1 段、extra は 7 段まで
+        let mut body = vendor_body(None);
+        body["profile"]["extra"] = nested(7);
+        assert_eq!(app.post_json("/api/vendors", body).await.status, 200);
+
+        let mut body = vendor_body(None);
+        body["profile"]["extra"] = nested(8);
+        let res = app.post_json("/api/vendors", body).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().contains("nested too deeply"));
+
+        // 更新時も同様に拒否し、profile.json は書き換えない
+        let vendor = app.create_vendor(None).await;
+        let profile_path = app.data_path(format!("account/vendors/{}/profile.json", vendor));
+        let before = std::fs::read(&profile_path).unwrap();
+        let res = app
+            .put_json(
+                &format!("/api/vendors/{}", vendor),
+                json!({ "profile": { "name": "Nested", "extra": nested(20) } }),
+            )
+            .await;
+        assert_eq!(res.status, 400);
+        assert_eq!(std::fs::read(&profile_path).unwrap(), before);
+    }
+
+    async fn create_vendor_in(app: &TestApp, env: &str) -> String {
+        let mut body = vendor_body(None);
+        body["env"] = json!(env);
+        let res = app.post_json("/api/vendors", body).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        res.json()["stable_id"].as_str().unwrap().to_string()
+    }
+
+    fn vendor_ids(body: &Value) -> Vec<String> {
+        let mut ids: Vec<String> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["stable_id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn list_vendors_filters_by_env() {
+        let app = TestApp::new().await;
+        let devnet = create_vendor_in(&app, "devnet").await;
+        let mainnet = create_vendor_in(&app, "mainnet").await;
+
+        assert_eq!(vendor_ids(&app.get("/api/vendors?env=devnet").await.json()), vec![devnet.clone()]);
+        assert_eq!(vendor_ids(&app.get("/api/vendors?env=mainnet").await.json()), vec![mainnet.clone()]);
+        let mut both = vec![devnet, mainnet];
+        both.sort();
+        assert_eq!(vendor_ids(&app.get("/api/vendors").await.json()), both);
+        assert_eq!(vendor_ids(&app.get("/api/vendors?env=all").await.json()), both);
+    }
+
+    #[tokio::test]
+    async fn list_vendors_defaults_to_configured_env() {
+        let app = TestApp::with_config(|c| c.default_env = Some("mainnet".to_string())).await;
+        create_vendor_in(&app, "devnet").await;
+        let mainnet = create_vendor_in(&app, "mainnet").await;
+
+        assert_eq!(vendor_ids(&app.get("/api/vendors").await.json()), vec![mainnet]);
+        assert_eq!(app.get("/api/vendors?env=all").await.json()["data"].as_array().unwrap().len(), 2);
+    }
+
+    async fn owner_check(app: &TestApp, vendor: &str, headers: &OwnerHeaders) -> Result<(), StatusCode> {
+        let mut map = axum::http::HeaderMap::new();
+        for (name, value) in &headers.0 {
+            map.insert(*name, value.parse().unwrap());
+        }
+        super::verify_vendor_owner(&app.state, &map, vendor).await.map_err(|e| e.status())
+    }
+
+    #[tokio::test]
+    async fn owner_check_requires_a_fresh_signature_from_the_owner_key() {
+        let app = TestApp::with_config(|c| c.enforce_vendor_owner = true).await;
+        let (owner, stranger) = (OwnerKey::new(1), OwnerKey::new(2));
+        let vendor = app.create_vendor(Some(&owner.address())).await;
+        let unauthorized = Err(StatusCode::UNAUTHORIZED);
+
+        // 公開の owner アドレスを写しただけでは通らない
+        let copied = OwnerHeaders(vec![("x-owner-address", owner.address())]);
+        assert_eq!(owner_check(&app, &vendor, &copied).await, unauthorized);
+
+        // 他人の鍵の署名にアドレスだけ差し替えても通らない
+        let mut forged = app.owner_headers(&stranger).await;
+        forged.0[0].1 = owner.address();
+        assert_eq!(owner_check(&app, &vendor, &forged).await, unauthorized);
+
+        // 発行していない challenge は通らない
+        assert_eq!(owner_check(&app, &vendor, &owner.sign("not-issued")).await, unauthorized);
+
+        let headers = app.owner_headers(&owner).await;
+        assert_eq!(owner_check(&app, &vendor, &headers).await, Ok(()));
+        // challenge は1回限り
+        assert_eq!(owner_check(&app, &vendor, &headers).await, unauthorized);
+
+        let res = owner_check(&app, &vendor, &app.owner_headers(&stranger).await).await;
+        assert_eq!(res, Err(StatusCode::FORBIDDEN));
+    }
+
+    async fn vendor_owner(app: &TestApp, vendor: &str) -> Option<String> {
+        app.get(&format!("/api/vendors/{}", vendor)).await.json()["vendor"]["owner"]
+            .as_str()
+            .map(str::to_string)
+    }
+
+    #[tokio::test]
+    async fn update_changes_owner_only_for_owner_or_admin() {
+        let app = TestApp::with_config(|c| c.admin_token = Some(ADMIN_TOKEN.to_string())).await;
+        let (owner, stranger, next) = (OwnerKey::new(1), OwnerKey::new(2), OwnerKey::new(3));
+        let vendor = app.create_vendor(Some(&owner.address())).await;
+        let uri = format!("/api/vendors/{}", vendor);
+
+        // 署名なし・他人の署名では owner を書き換えられない
+        let res = app.put_json(&uri, json!({ "owner": stranger.address() })).await;
+        assert_eq!(res.status, 401, "{}", res.text());
+        let headers = app.owner_headers(&stranger).await;
+        let res = app
+            .send_json(Method::PUT, &uri, Some(json!({ "owner": stranger.address() })), &headers.pairs())
+            .await;
+        assert_eq!(res.status, 403, "{}", res.text());
+        assert_eq!(vendor_owner(&app, &vendor).await, Some(owner.address()));
+
+        // 同じ値の再送は署名なしでも通る
+        let res = app.put_json(&uri, json!({ "owner": owner.address().to_uppercase() })).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        // 現 owner の署名なら移譲できる
+        let headers = app.owner_headers(&owner).await;
+        let res = app
+            .send_json(Method::PUT, &uri, Some(json!({ "owner": next.address() })), &headers.pairs())
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(vendor_owner(&app, &vendor).await, Some(next.address()));
+
+        // 管理者トークンでも変更できる
+        let res = app
+            .send_json(Method::PUT, &uri, Some(json!({ "owner": owner.address() })), &[("x-admin-token", ADMIN_TOKEN)])
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(vendor_owner(&app, &vendor).await, Some(owner.address()));
+    }
+
+    #[tokio::test]
+    async fn regenerate_repairs_edited_vendor_profile() {
+        use sha2::{Digest, Sha256};
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let path = app.data_path(format!("account/vendors/{}/profile.json", vendor));
+        let mut edited: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        edited["name"] = json!("Edited Out Of Band");
+        std::fs::write(&path, serde_json::to_vec(&edited).unwrap()).unwrap();
+
+        let res = app.post_json(&format!("/api/vendors/{}/regenerate", vendor), json!({})).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["profile_source"], "file");
+
+        let (manifest_sha256,): (String,) = sqlx::query_as("SELECT manifest_sha256 FROM vendors WHERE stable_id = ?")
+            .bind(&vendor)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        let on_disk = std::fs::read(&path).unwrap();
+        assert_eq!(hex::encode(Sha256::digest(&on_disk)), manifest_sha256);
+        let regenerated: Value = serde_json::from_slice(&on_disk).unwrap();
+        assert_eq!(regenerated["name"], "Edited Out Of Band");
+    }
+
+    #[tokio::test]
+    async fn profile_updates_keep_every_version() {
+        use sha2::{Digest, Sha256};
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        for name in ["Second Name", "Third Name"] {
+            let res = app
+                .put_json(&format!("/api/vendors/{}", vendor), json!({ "profile": { "name": name } }))
+                .await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            // プロフィールを含まない更新では profile_seq は進まない
+            let res = app.put_json(&format!("/api/vendors/{}", vendor), json!({ "shop_type": 1 })).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+        }
+
+        let body = app.get(&format!("/api/vendors/{}/profile/history", vendor)).await.json();
+        let versions = body["versions"].as_array().unwrap();
+        let seqs: Vec<i64> = versions.iter().map(|v| v["profile_seq"].as_i64().unwrap()).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+        assert_eq!(body["current_seq"], 3);
+        // 一時ファイルは残らない
+        let dir = app.data_path(format!("account/vendors/{}", vendor));
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["profile.json", "profile.v1.json", "profile.v2.json", "profile.v3.json"]);
+
+        // 各バージョンのファイルが残っており、記録した sha256 と一致する
+        for (version, name) in versions.iter().zip(["Test Shop", "Second Name", "Third Name"]) {
+            let url = version["url"].as_str().unwrap();
+            let relative = url.strip_prefix("http://test.local/nft/").unwrap();
+            let data = std::fs::read(app.data_path(relative)).unwrap();
+            assert_eq!(hex::encode(Sha256::digest(&data)), version["sha256"].as_str().unwrap());
+            let profile: Value = serde_json::from_slice(&data).unwrap();
+            assert_eq!(profile["name"], name);
+        }
+
+        assert_eq!(app.get("/api/vendors/VENDOR_MISSING/profile/history").await.status, 404);
+    }
+
+    fn owner_vendor_ids(body: &Value) -> Vec<String> {
+        let mut ids: Vec<String> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["stable_id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn vendors_by_owner_isolates_owners_and_pages() {
+        let app = TestApp::new().await;
+        let mut owned = vec![
+            app.create_vendor(Some("0xAAA")).await,
+            app.create_vendor(Some("0xAAA")).await,
+        ];
+        owned.sort();
+        let other = app.create_vendor(Some("0xBBB")).await;
+        let delisted = app.create_vendor(Some("0xAAA")).await;
+        sqlx::query("UPDATE vendors SET is_alive = 0 WHERE stable_id = ?")
+            .bind(&delisted)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+
+        // 大文字小文字を区別しない
+        let body = app.get("/api/vendors/by-owner/0xaaa").await.json();
+        assert_eq!(body["meta"]["total"], 2);
+        assert_eq!(owner_vendor_ids(&body), owned);
+        // 移行期間中は旧フィールドも返す
+        assert_eq!(body["owner"], "0xaaa");
+        assert_eq!(body["vendors"], body["data"]);
+
+        let body = app.get("/api/vendors/by-owner/0xBBB").await.json();
+        assert_eq!(owner_vendor_ids(&body), vec![other]);
+
+        let first = app.get("/api/vendors/by-owner/0xAAA?limit=1").await.json();
+        let second = app.get("/api/vendors/by-owner/0xAAA?limit=1&offset=1").await.json();
+        assert_eq!(first["meta"]["total"], 2);
+        let mut paged = [owner_vendor_ids(&first), owner_vendor_ids(&second)].concat();
+        paged.sort();
+        assert_eq!(paged, owned);
+
+        assert_eq!(app.get("/api/vendors/by-owner/0xCCC").await.json()["meta"]["total"], 0);
+
+        let index: Option<(String,)> =
+            sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'index' AND name = 'idx_vendors_owner'")
+                .fetch_optional(&app.state.db)
+                .await
+                .unwrap();
+        assert!(index.is_some());
+    }
+
+    async fn create_vendor_with(app: &TestApp, body: Value) -> TestResponse {
+        app.post_json("/api/vendors", body).await
+    }
+
+    #[tokio::test]
+    async fn create_vendor_issues_new_stable_id_for_same_peer() {
+        let app = TestApp::new().await;
+        let body = vendor_body(None);
+
+        let first = create_vendor_with(&app, body.clone()).await;
+        assert_eq!(first.status, 200, "{}", first.text());
+        let first = first.json()["stable_id"].as_str().unwrap().to_string();
+        let second = create_vendor_with(&app, body.clone()).await.json()["stable_id"].as_str().unwrap().to_string();
+        assert_ne!(first, second);
+
+        // デリスト済みの Vendor があっても作成できる
+        let uri = format!("/api/vendors/{}", second);
+        assert_eq!(app.send_json(Method::DELETE, &uri, None, &[]).await.status, 200);
+        let third = create_vendor_with(&app, body.clone()).await;
+        assert_eq!(third.status, 200, "{}", third.text());
+        assert_ne!(third.json()["stable_id"], second.as_str());
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM vendors WHERE peer_id = ?")
+            .bind(body["peer_id"].as_str().unwrap())
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[tokio::test]
+    async fn explicit_duplicate_stable_id_is_409() {
+        let app = TestApp::new().await;
+        let existing = app.create_vendor(None).await;
+
+        let mut body = vendor_body(None);
+        body["stable_id"] = json!(existing);
+        let res = create_vendor_with(&app, body).await;
+        assert_eq!(res.status, 409);
+        assert_eq!(res.json()["success"], false);
+    }
+
+    async fn icon_state(app: &TestApp, stable_id: &str) -> (i64, i64, String, String) {
+        sqlx::query_as("SELECT updated_at_ms, profile_seq, manifest_sha256, manifest_url FROM vendors WHERE stable_id = ?")
+            .bind(stable_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn icon_upload_bumps_updated_at_seq_and_sha256() {
+        let app = TestApp::new().await;
+        let stable_id = app.create_vendor(None).await;
+        let (updated_before, seq_before, sha_before, _) = icon_state(&app, &stable_id).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(8, 8));
+        let res = app.send_form(Method::POST, &format!("/api/vendors/{}/icon", stable_id), form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+
+        let (updated_after, seq_after, sha_after, url) = icon_state(&app, &stable_id).await;
+        assert!(updated_after > updated_before);
+        assert_eq!(body["updated_at_ms"], updated_after);
+        assert_eq!(seq_after, seq_before + 1);
+        assert_ne!(sha_after, sha_before);
+        assert_eq!(body["manifest_sha256"], sha_after.as_str());
+
+        // 保存された JSON のハッシュと一致し、icon_url が反映されている
+        let relative = url.strip_prefix(&format!("{}/", app.state.vps_base_url)).unwrap();
+        let data = std::fs::read(app.data_path(relative)).unwrap();
+        assert_eq!(hex::encode(sha2::Sha256::digest(&data)), sha_after);
+        let profile: Value = serde_json::from_slice(&data).unwrap();
+        assert!(profile["icon_url"].as_str().unwrap().ends_with("/icon.png"));
+    }
+
+    /// shop_type を指定して Vendor を作成
+    async fn create_vendor_with_shop_type(app: &TestApp, shop_type: i32) -> TestResponse {
+        let mut body = vendor_body(None);
+        body["shop_type"] = json!(shop_type);
+        app.post_json("/api/vendors", body).await
+    }
+
+    #[tokio::test]
+    async fn list_vendors_filters_by_shop_type() {
+        let app = TestApp::new().await;
+        let in_app = create_vendor_with_shop_type(&app, 0).await.json()["stable_id"].as_str().unwrap().to_string();
+        let mut external = Vec::new();
+        for _ in 0..2 {
+            let res = create_vendor_with_shop_type(&app, 1).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            external.push(res.json()["stable_id"].as_str().unwrap().to_string());
+        }
+        external.sort();
+
+        assert_eq!(vendor_ids(&app.get("/api/vendors").await.json()).len(), 3);
+        assert_eq!(vendor_ids(&app.get("/api/vendors?shop_type=0").await.json()), vec![in_app.clone()]);
+        assert_eq!(vendor_ids(&app.get("/api/vendors?shop_type=1").await.json()), external);
+
+        let res = app.get("/api/vendors?shop_type=9").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], json!(["shop_type"]));
+        let res = create_vendor_with_shop_type(&app, 7).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], json!(["shop_type"]));
+
+        // 更新で変更でき、ラベルも追従する
+        let uri = format!("/api/vendors/{}", in_app);
+        assert_eq!(app.get(&uri).await.json()["vendor"]["shop_type_label"], "in_app");
+        let res = app.put_json(&uri, json!({ "shop_type": 1 })).await;
+        assert!(res.status.is_success(), "{}", res.text());
+        let vendor = app.get(&uri).await.json()["vendor"].clone();
+        assert_eq!(vendor["shop_type"], 1);
+        assert_eq!(vendor["shop_type_label"], "external_web");
+        assert_eq!(app.put_json(&uri, json!({ "shop_type": 5 })).await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn update_without_profile_keeps_unset_manifest_null() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        sqlx::query("UPDATE vendors SET manifest_url = NULL, manifest_sha256 = NULL WHERE stable_id = ?")
+            .bind(&vendor)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+
+        let res = app.put_json(&format!("/api/vendors/{}", vendor), json!({ "shop_type": 1 })).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert!(body["manifest_url"].is_null());
+        assert!(body["manifest_sha256"].is_null());
+        assert!(body["peer_id"].as_str().unwrap().starts_with("peer-"));
+
+        let (url, sha256): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT manifest_url, manifest_sha256 FROM vendors WHERE stable_id = ?")
+                .bind(&vendor)
+                .fetch_one(&app.state.db)
+                .await
+                .unwrap();
+        assert_eq!((url, sha256), (None, None));
+    }
 }
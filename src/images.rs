@@ -0,0 +1,156 @@
+//! Image Processing
+//! アップロード画像のサムネイル生成
+
+use std::path::Path;
+
+/// アイコンサムネイルの一辺（px）
+pub const ICON_THUMB_SIZE: u32 = 256;
+
+/// 正方形の WebP サムネイルを生成（PFP 用、中央でクロップ）
+/// 同期処理なので spawn_blocking 内で呼ぶこと
+pub fn write_square_webp_thumbnail(data: &[u8], path: &Path, size: u32) -> anyhow::Result<()> {
+    let img = image::load_from_memory(data)?;
+    let thumb = img.resize_to_fill(size, size, image::imageops::FilterType::Lanczos3);
+    thumb.save_with_format(path, image::ImageFormat::WebP)?;
+    Ok(())
+}
+
+/// アイコンのサムネイルを生成（ベストエフォート）
+/// 成功時は true、デコード失敗等の場合は古いサムネイルを削除して false
+pub async fn generate_icon_thumbnail(data: Vec<u8>, path: std::path::PathBuf) -> bool {
+    let result = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || write_square_webp_thumbnail(&data, &path, ICON_THUMB_SIZE)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            tracing::info!("Icon thumbnail generated: {:?}", path);
+            true
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Icon thumbnail generation failed: {:?} ({})", path, e);
+            let _ = tokio::fs::remove_file(&path).await;
+            false
+        }
+        Err(e) => {
+            tracing::warn!("Icon thumbnail task failed: {:?} ({})", path, e);
+            let _ = tokio::fs::remove_file(&path).await;
+            false
+        }
+    }
+}
+
+/// カバーのリサイズで許可する最大幅（px）
+pub const MAX_COVER_WIDTH: u32 = 2048;
+
+/// 指定幅（アスペクト比維持）の JPEG に縮小（元画像より大きくはしない）
+/// 同期処理なので spawn_blocking 内で呼ぶこと
+pub fn resize_to_width_jpeg(data: &[u8], width: u32) -> anyhow::Result<Vec<u8>> {
+    let img = image::load_from_memory(data)?;
+    let width = width.min(img.width());
+    let resized = img.resize(width, u32::MAX, image::imageops::FilterType::Lanczos3);
+    let mut out = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(resized.to_rgb8()).write_to(&mut out, image::ImageFormat::Jpeg)?;
+    Ok(out.into_inner())
+}
+
+/// リサイズ済みカバーを取得（キャッシュが元画像より新しければ再利用、なければ生成して保存）
+pub async fn cached_resized_cover(src: &Path, cache_path: &Path, width: u32) -> anyhow::Result<Vec<u8>> {
+    let src_modified = tokio::fs::metadata(src).await?.modified()?;
+    if let Ok(meta) = tokio::fs::metadata(cache_path).await {
+        if meta.modified().is_ok_and(|m| m >= src_modified) {
+            return Ok(tokio::fs::read(cache_path).await?);
+        }
+    }
+
+    let data = tokio::fs::read(src).await?;
+    let resized = tokio::task::spawn_blocking(move || resize_to_width_jpeg(&data, width)).await??;
+
+    if let Some(parent) = cache_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if let Err(e) = crate::storage::write_atomic(cache_path, &resized).await {
+        // キャッシュ保存の失敗は配信に影響させない
+        tracing::warn!("Cover cache write failed: {:?} ({})", cache_path, e);
+    } else {
+        tracing::info!("Cover resized: {:?} -> {:?} (w={})", src, cache_path, width);
+    }
+    Ok(resized)
+}
+
+/// カバーの WebP 派生の最大幅（px）（image の WebP エンコーダは可逆のみのため縮小して保存）
+pub const COVER_WEBP_MAX_WIDTH: u32 = 1024;
+/// blurhash の成分数（横 x 縦）
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+/// blurhash 計算前に縮小するサイズ（px、計算量を抑えるため）
+const BLURHASH_SAMPLE_SIZE: u32 = 64;
+
+/// カバー派生の生成結果（いずれもベストエフォート）
+#[derive(Debug, Default)]
+pub struct CoverDerivatives {
+    /// WebP 派生を保存できたか
+    pub webp: bool,
+    pub blurhash: Option<String>,
+}
+
+/// カバーの WebP 派生（最大幅 COVER_WEBP_MAX_WIDTH、アスペクト比維持）と blurhash を生成
+/// 同期処理なので spawn_blocking 内で呼ぶこと
+fn build_cover_derivatives(data: &[u8], webp_path: &Path) -> anyhow::Result<CoverDerivatives> {
+    let img = image::load_from_memory(data)?;
+
+    let webp = {
+        let width = COVER_WEBP_MAX_WIDTH.min(img.width());
+        let resized = img.resize(width, u32::MAX, image::imageops::FilterType::Lanczos3);
+        match image::DynamicImage::ImageRgba8(resized.to_rgba8()).save_with_format(webp_path, image::ImageFormat::WebP) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Cover webp generation failed: {:?} ({})", webp_path, e);
+                false
+            }
+        }
+    };
+
+    let sample = img
+        .thumbnail(BLURHASH_SAMPLE_SIZE, BLURHASH_SAMPLE_SIZE)
+        .to_rgba8();
+    let (cx, cy) = BLURHASH_COMPONENTS;
+    let blurhash = match blurhash::encode(cx, cy, sample.width(), sample.height(), sample.as_raw()) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            tracing::warn!("Cover blurhash generation failed: {}", e);
+            None
+        }
+    };
+
+    Ok(CoverDerivatives { webp, blurhash })
+}
+
+/// カバーの派生（WebP + blurhash）を生成（ベストエフォート）
+/// デコード失敗等で WebP を作れなかった場合は古い派生を削除する
+pub async fn generate_cover_derivatives(data: Vec<u8>, webp_path: std::path::PathBuf) -> CoverDerivatives {
+    let result = tokio::task::spawn_blocking({
+        let webp_path = webp_path.clone();
+        move || build_cover_derivatives(&data, &webp_path)
+    })
+    .await;
+
+    let derivatives = match result {
+        Ok(Ok(derivatives)) => derivatives,
+        Ok(Err(e)) => {
+            tracing::warn!("Cover derivatives skipped: {:?} ({})", webp_path, e);
+            CoverDerivatives::default()
+        }
+        Err(e) => {
+            tracing::warn!("Cover derivatives task failed: {:?} ({})", webp_path, e);
+            CoverDerivatives::default()
+        }
+    };
+    if derivatives.webp {
+        tracing::info!("Cover webp generated: {:?}", webp_path);
+    } else {
+        let _ = tokio::fs::remove_file(&webp_path).await;
+    }
+    derivatives
+}
@@ -0,0 +1,215 @@
+//! Outbound Mail
+//! Drop Claim 時のダウンロードリンク通知（SMTP、バックグラウンド送信・失敗はログのみ）
+
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::{Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+/// メールアドレスの形式チェック
+pub fn validate_email(email: &str) -> Result<(), String> {
+    email
+        .parse::<Address>()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid email: {}", email))
+}
+
+/// 保存用の値（STORE_CLAIM_EMAIL_PLAINTEXT 無効時は小文字化した値の SHA256 hex）
+pub fn stored_email(config: &AppConfig, email: &str) -> String {
+    if config.store_claim_email_plaintext {
+        email.to_string()
+    } else {
+        hex::encode(Sha256::digest(email.to_lowercase().as_bytes()))
+    }
+}
+
+/// Claim したダウンロードリンクのメール
+pub struct ClaimEmail {
+    pub claim_id: String,
+    pub to: String,
+    pub drop_title: String,
+    pub artist_name: String,
+    pub download_url: String,
+    pub expires_at: i64,
+}
+
+impl ClaimEmail {
+    fn subject(&self) -> String {
+        format!("ダウンロードリンク: {} / {}", self.artist_name, self.drop_title)
+    }
+
+    fn body(&self) -> String {
+        let expires = chrono::DateTime::from_timestamp(self.expires_at, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| self.expires_at.to_string());
+        format!(
+            "{} / {} を受け取りました。\n\n以下のリンクからダウンロードできます（{} まで有効）。\n{}\n",
+            self.artist_name, self.drop_title, expires, self.download_url
+        )
+    }
+}
+
+/// メールをバックグラウンドで送信（呼び出し元は待たない）
+/// SMTP_URL・MAIL_FROM のどちらかが未設定、または From 不正の場合は false（キューに入れない）
+pub fn spawn_claim_email(config: &AppConfig, email: ClaimEmail) -> bool {
+    let (Some(smtp_url), Some(from)) = (config.smtp_url.clone(), config.mail_from.as_deref()) else {
+        return false;
+    };
+    let from: Mailbox = match from.parse() {
+        Ok(from) => from,
+        Err(e) => {
+            warn!("MAIL_FROM is invalid; claim email not sent: {}", e);
+            return false;
+        }
+    };
+
+    tokio::spawn(async move {
+        // ログにはアドレスを出さず claim_id で追う
+        let claim_id = email.claim_id.clone();
+        match send(&smtp_url, from, email).await {
+            Ok(()) => info!("Claim email sent: claim_id={}", claim_id),
+            Err(e) => warn!("Claim email failed: claim_id={}, error={:#}", claim_id, e),
+        }
+    });
+    true
+}
+
+/// 送信本体（再送はしない。リンクは Claim レスポンスでも返しているため）
+async fn send(smtp_url: &str, from: Mailbox, email: ClaimEmail) -> anyhow::Result<()> {
+    let message = Message::builder()
+        .from(from)
+        .to(email.to.parse()?)
+        .subject(email.subject())
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body())?;
+
+    let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(smtp_url)?.build();
+    transport.send(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use crate::test_support::TestApp;
+
+    /// 1通受け取るごとに SMTP の会話（コマンドと DATA）を送る最小限のモック SMTP サーバ
+    async fn mock_smtp_server() -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("smtp://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = socket.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    let mut transcript = String::new();
+                    let mut in_data = false;
+                    write.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        transcript.push_str(&line);
+                        transcript.push('\n');
+                        let reply: &[u8] = if in_data {
+                            if line != "." {
+                                continue;
+                            }
+                            in_data = false;
+                            b"250 queued\r\n"
+                        } else {
+                            match line.get(..4).map(str::to_ascii_uppercase).as_deref() {
+                                Some("DATA") => {
+                                    in_data = true;
+                                    b"354 go ahead\r\n"
+                                }
+                                Some("QUIT") => {
+                                    let _ = write.write_all(b"221 bye\r\n").await;
+                                    break;
+                                }
+                                _ => b"250 ok\r\n",
+                            }
+                        };
+                        write.write_all(reply).await.unwrap();
+                    }
+                    let _ = tx.send(transcript);
+                });
+            }
+        });
+        (url, rx)
+    }
+
+    async fn claim_with_email(app: &TestApp, drop_id: &str, email: &str) -> crate::test_support::TestResponse {
+        app.post_json(
+            &format!("/api/drops/{}/claim", drop_id),
+            json!({ "user_id": "fan-1", "email": email }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn claim_with_email_sends_download_link() {
+        let (smtp_url, mut received) = mock_smtp_server().await;
+        let app = TestApp::with_config(|c| {
+            c.smtp_url = Some(smtp_url);
+            c.mail_from = Some("Shop <noreply@example.com>".to_string());
+        })
+        .await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        let res = claim_with_email(&app, &drop_id, " Fan@Example.com ").await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let body = res.json();
+        assert_eq!(body["email_queued"], true);
+        let claim_id = body["claim_id"].as_str().unwrap();
+
+        let transcript = tokio::time::timeout(std::time::Duration::from_secs(10), received.recv())
+            .await
+            .expect("email was not delivered")
+            .unwrap();
+        assert!(transcript.contains("RCPT TO:<Fan@Example.com>"), "{}", transcript);
+        // 本文は base64 で送られるので、ヘッダ後の空行から終端 "." までを復号して確認する
+        let encoded: String = transcript
+            .split("\n\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take_while(|l| *l != ".")
+            .collect();
+        let body = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(&format!("token={}", claim_id)), "{}", body);
+
+        // 既定ではアドレスはハッシュで保存する
+        let (stored,): (String,) = sqlx::query_as("SELECT email FROM drop_claims WHERE claim_id = ?")
+            .bind(claim_id)
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        assert_eq!(stored, hex::encode(Sha256::digest(b"fan@example.com")));
+    }
+
+    #[tokio::test]
+    async fn claim_without_smtp_returns_link_in_band_only() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        let res = claim_with_email(&app, &drop_id, "not-an-email").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], json!(["email"]));
+
+        let res = claim_with_email(&app, &drop_id, "fan@example.com").await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(res.json()["email_queued"], false);
+        assert!(res.json()["download_url"].as_str().unwrap().contains("/download?token="));
+    }
+}
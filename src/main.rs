@@ -1,25 +1,48 @@
 use axum::{
-    extract::{DefaultBodyLimit, Multipart, State},
-    http::StatusCode,
-    response::Json,
-    routing::{delete, get, post, put},
-    Router,
+    body::Body,
+    extract::{ConnectInfo, DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post, put, MethodRouter},
+    Router, ServiceExt,
 };
 use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use crate::error::ApiError;
+use crate::extract::{ApiJson, ExpectedDigest, TextFieldBudget};
 use crate::models::UpsertPeerProfileRequest;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
-use tower_http::cors::CorsLayer;
+use tower::Layer;
+use tower_http::normalize_path::{NormalizePath, NormalizePathLayer};
 use tracing::{info, warn};
 
+mod audio;
+mod config;
 mod db;
+mod error;
+mod extract;
 mod models;
 mod handlers;
-
+mod images;
+mod mailer;
+mod middleware;
+mod openapi;
+mod redact;
+mod response;
+mod signed_url;
+mod storage;
+mod transfer;
+mod webhook;
+
+#[cfg(test)]
+mod test_support;
+
+use config::AppConfig;
 use db::DbPool;
 
 // ========================================
@@ -31,29 +54,21 @@ pub struct AppState {
     pub base_data_dir: String,
     pub vps_base_url: String,
     pub db: DbPool,
+    pub config: AppConfig,
     /// Challenge store: challenge_hex → (challenge_hex, expires_at_ms)
     pub challenges: RwLock<HashMap<String, (String, i64)>>,
     /// Token store: token → (peer_id, expires_at_ms)
     pub tokens: RwLock<HashMap<String, (String, i64)>>,
-}
-
-// ========================================
-// レガシー設定（後方互換用）
-// ========================================
-
-#[derive(Clone)]
-struct AppConfig {
-    base_data_dir: PathBuf,
-    vps_base_url: String,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            base_data_dir: PathBuf::from("/data"),
-            vps_base_url: "http://153.121.61.17".to_string(),
-        }
-    }
+    /// カメラ受信の直近キャプチャ（新しい順）
+    pub camera_captures: RwLock<VecDeque<handlers::camera::CameraCapture>>,
+    /// IP 単位のレート制限バケット
+    pub rate_limiter: middleware::RateLimiter,
+    /// 音声 blob の配置・参照カウント更新と Drop 作成時の上限判定を直列化するロック
+    pub blob_lock: tokio::sync::Mutex<()>,
+    /// 読み取り専用（メンテナンス）モード（READ_ONLY で初期化、管理APIで切り替え）
+    pub read_only: AtomicBool,
+    /// GET /api/admin/storage の集計結果（集計時刻と共にキャッシュ）
+    pub storage_usage: tokio::sync::Mutex<Option<(std::time::Instant, handlers::admin::StorageUsage)>>,
 }
 
 // ========================================
@@ -66,6 +81,11 @@ struct HealthResponse {
     service: String,
     version: String,
     db_status: String,
+    /// DB 接続プールの使用状況
+    db_pool: db::PoolStats,
+    disk_free_bytes: Option<u64>,
+    disk_total_bytes: Option<u64>,
+    data_dir_writable: bool,
 }
 
 #[derive(Serialize)]
@@ -76,22 +96,47 @@ struct UploadResponse {
     filename: String,
 }
 
+#[derive(Deserialize)]
+struct DeleteRequest {
+    album_id: String,
+    file_type: String, // "promo" | "albums"
+}
+
 #[derive(Serialize)]
-struct ErrorResponse {
+struct DeleteResponse {
     success: bool,
-    error: String,
+    message: String,
 }
 
+/// 一括削除の1リクエストあたりの上限
+const MAX_BULK_DELETE: usize = 500;
+
 #[derive(Deserialize)]
-struct DeleteRequest {
+struct LegacyFilePath {
+    file_type: String,
     album_id: String,
+    filename: String,
+}
+
+#[derive(Deserialize)]
+struct CoverQuery {
+    /// 出力幅（px）。未指定時は元画像をそのまま返す
+    w: Option<u32>,
+    /// "albums"（デフォルト）| "promo"
+    file_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BulkDeleteRequest {
     file_type: String, // "promo" | "albums"
+    album_ids: Vec<String>,
 }
 
 #[derive(Serialize)]
-struct DeleteResponse {
+struct BulkDeleteResponse {
     success: bool,
-    message: String,
+    /// album_id → 削除できたか（存在しない・不正な album_id は false）
+    results: HashMap<String, bool>,
 }
 
 // ========================================
@@ -101,26 +146,56 @@ struct DeleteResponse {
 /// ヘルスチェック
 async fn health_check(
     State(state): State<Arc<AppState>>,
-) -> Json<HealthResponse> {
+) -> (StatusCode, Json<HealthResponse>) {
     // DB接続チェック
     let db_status = match sqlx::query("SELECT 1").execute(&state.db).await {
         Ok(_) => "connected".to_string(),
         Err(e) => format!("error: {}", e),
     };
 
-    Json(HealthResponse {
-        status: "ok".to_string(),
-        service: "nft-upload-api".to_string(),
-        version: "0.2.0".to_string(),
-        db_status,
-    })
+    // ディスク容量・書き込み可否チェック
+    let disk_free_bytes = fs2::available_space(&state.base_data_dir).ok();
+    let disk_total_bytes = fs2::total_space(&state.base_data_dir).ok();
+    let data_dir_writable = check_data_dir_writable(&state.base_data_dir).await;
+
+    // 書き込み不可の場合は 503（ロードバランサから切り離す）
+    let (status_code, status) = if data_dir_writable {
+        (StatusCode::OK, "ok")
+    } else {
+        warn!("Health check: data dir is not writable: {}", state.base_data_dir);
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    };
+
+    (
+        status_code,
+        Json(HealthResponse {
+            status: status.to_string(),
+            service: "nft-upload-api".to_string(),
+            version: "0.2.0".to_string(),
+            db_status,
+            db_pool: db::PoolStats::of(&state.db),
+            disk_free_bytes,
+            disk_total_bytes,
+            data_dir_writable,
+        }),
+    )
+}
+
+/// データディレクトリに一時ファイルを書き込めるか確認
+async fn check_data_dir_writable(base_data_dir: &str) -> bool {
+    let probe = PathBuf::from(base_data_dir).join(format!(".health_{}", uuid::Uuid::new_v4()));
+    let writable = fs::write(&probe, b"ok").await.is_ok();
+    let _ = fs::remove_file(&probe).await;
+    writable
 }
 
 /// ファイルアップロード（レガシーAPI - 後方互換）
 async fn upload_file(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<UploadResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<UploadResponse>, ApiError> {
+    let timer = transfer::TransferTimer::start("upload_file");
     info!("Multipart parsing started");
 
     let mut file_data: Option<Vec<u8>> = None;
@@ -129,6 +204,8 @@ async fn upload_file(
     let mut file_type: Option<String> = None;
     let mut category: Option<String> = None;
     let mut track_number: Option<String> = None;
+    let mut collision: Option<String> = None;
+    let mut text_budget = TextFieldBudget::new();
 
     // multipart フィールドを解析
     while let Some(field) = multipart
@@ -136,7 +213,7 @@ async fn upload_file(
         .await
         .map_err(|e| {
             warn!("Field read error: {:?}", e);
-            error_response(StatusCode::BAD_REQUEST, format!("Field read error: {:?}", e))
+            ApiError::new(e.status(), format!("Field read error: {:?}", e))
         })?
     {
         let name = field.name().unwrap_or("").to_string();
@@ -152,7 +229,7 @@ async fn upload_file(
                     .await
                     .map_err(|e| {
                         warn!("File bytes read error: {:?}", e);
-                        error_response(StatusCode::BAD_REQUEST, format!("File read error: {:?}", e))
+                        ApiError::new(e.status(), format!("File read error: {:?}", e))
                     })?
                     .to_vec();
 
@@ -160,83 +237,118 @@ async fn upload_file(
                 file_data = Some(bytes);
             }
             "album_id" => {
-                let text = field.text().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("album_id error: {:?}", e))
-                })?;
+                let text = text_budget
+                    .read(field)
+                    .await?;
                 album_id = Some(text);
             }
             "file_type" => {
-                let text = field.text().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("file_type error: {:?}", e))
-                })?;
+                let text = text_budget
+                    .read(field)
+                    .await?;
                 file_type = Some(text);
             }
             "category" => {
-                let text = field.text().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("category error: {:?}", e))
-                })?;
+                let text = text_budget
+                    .read(field)
+                    .await?;
                 category = Some(text);
             }
             "track_number" => {
-                let text = field.text().await.map_err(|e| {
-                    error_response(StatusCode::BAD_REQUEST, format!("track_number error: {:?}", e))
-                })?;
+                let text = text_budget
+                    .read(field)
+                    .await?;
                 track_number = Some(text);
             }
+            "collision" => {
+                let text = text_budget
+                    .read(field)
+                    .await?;
+                collision = Some(text);
+            }
             _ => {
                 warn!("Unknown field: {}", name);
             }
         }
     }
 
-    // 必須パラメータの検証
-    let file_data = file_data.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "No file uploaded".to_string())
-    })?;
-
-    let original_filename = original_filename.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "No filename provided".to_string())
-    })?;
-
-    let album_id = album_id.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "album_id is required".to_string())
-    })?;
-
-    let file_type = file_type.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "file_type is required".to_string())
-    })?;
-
-    let category = category.ok_or_else(|| {
-        error_response(StatusCode::BAD_REQUEST, "category is required".to_string())
-    })?;
+    // 必須パラメータの検証（不足分はまとめて返す）
+    let missing: Vec<&str> = [
+        ("file", file_data.is_none() || original_filename.is_none()),
+        ("album_id", album_id.is_none()),
+        ("file_type", file_type.is_none()),
+        ("category", category.is_none()),
+    ]
+    .into_iter()
+    .filter_map(|(name, is_missing)| is_missing.then_some(name))
+    .collect();
+    let (Some(file_data), Some(original_filename), Some(album_id), Some(file_type), Some(category)) =
+        (file_data, original_filename, album_id, file_type, category)
+    else {
+        return Err(ApiError::missing_fields(&missing));
+    };
 
     // file_type のバリデーション (nft/promo または nft/albums)
     if file_type != "promo" && file_type != "albums" {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             "file_type must be 'promo' or 'albums'".to_string(),
         ));
     }
 
     // category のバリデーション
     if category != "tracks" && category != "cover" && category != "manifest" {
-        return Err(error_response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::BadRequest(
             "category must be 'tracks', 'cover', or 'manifest'".to_string(),
         ));
     }
 
-    // ファイル名の生成
+    let collision = storage::CollisionPolicy::parse(collision.as_deref())
+        .map_err(|message| ApiError::invalid_fields(message, &["collision"]))?;
+
+    // Digest / Content-MD5 ヘッダ指定時はファイルのハッシュを照合（保存前に 422）
+    if let Some(expected) = ExpectedDigest::from_headers(&headers)? {
+        expected.verify(&file_data)?;
+    }
+
+    // カバー画像サイズチェック（リクエスト全体は音声の上限で制限済み）
+    if category == "cover" && file_data.len() > state.config.max_cover_bytes {
+        return Err(ApiError::PayloadTooLarge(
+            format!("Cover too large: at most {} bytes", state.config.max_cover_bytes),
+        ));
+    }
+
+    // 拡張子は category ごとの許可リストで検証（拡張子なしも拒否）
     let extension = original_filename
-        .split('.')
-        .last()
-        .unwrap_or("bin")
-        .to_lowercase();
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+    let allowed = storage::allowed_upload_extensions(&category);
+    if !allowed.contains(&extension.as_str()) {
+        return Err(ApiError::BadRequest(
+            format!(
+                "File extension '{}' is not allowed for {} (allowed: {})",
+                extension,
+                category,
+                allowed.join(", ")
+            ),
+        ));
+    }
+
+    // manifest は常に manifest.json として配信されるため、JSON として読めない内容は保存しない
+    if category == "manifest" {
+        if let Err(e) = serde_json::from_slice::<serde_json::Value>(&file_data) {
+            return Err(ApiError::invalid_fields(
+                format!("Manifest is not valid JSON: {}", e),
+                &["file"],
+            ));
+        }
+    }
+
+    // ファイル名の生成
 
     let filename = if category == "tracks" {
         let track_num = track_number.ok_or_else(|| {
-            error_response(
-                StatusCode::BAD_REQUEST,
+            ApiError::BadRequest(
                 "track_number is required for tracks".to_string(),
             )
         })?;
@@ -248,60 +360,41 @@ async fn upload_file(
     };
 
     // 保存先ディレクトリの構築
-    // albums -> nft/albums, promo -> promo
-    let base_dir = PathBuf::from(&state.base_data_dir);
-    let type_dir = if file_type == "albums" {
-        base_dir.join("nft").join("albums")
-    } else {
-        base_dir.join(&file_type)
-    };
+    let album_dir = legacy_album_dir(&state.base_data_dir, &file_type, &album_id)
+        .map_err(ApiError::BadRequest)?;
     let target_dir = if category == "tracks" {
-        type_dir.join(&album_id).join("tracks")
+        album_dir.join("tracks")
     } else {
-        type_dir.join(&album_id)
+        album_dir
     };
 
     // ディレクトリ作成
     fs::create_dir_all(&target_dir)
         .await
         .map_err(|e| {
-            error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::internal(
                 format!("Failed to create directory: {}", e),
             )
         })?;
 
+    // 同名ファイルの扱い（collision: overwrite / error / version）
+    let Some(filename) = storage::resolve_collision(&target_dir, &filename, collision).await? else {
+        return Err(ApiError::Conflict(format!("File already exists: {}", filename)));
+    };
+
     // ファイル保存
     let target_path = target_dir.join(&filename);
-    let mut file = fs::File::create(&target_path).await.map_err(|e| {
-        error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to create file: {}", e),
-        )
-    })?;
-
-    file.write_all(&file_data).await.map_err(|e| {
-        error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
+    storage::write_atomic(&target_path, &file_data).await.map_err(|e| {
+        ApiError::internal(
             format!("Failed to write file: {}", e),
         )
     })?;
 
     info!("File saved: {:?}", target_path);
+    timer.finish(&format!("{}/{}/{}", file_type, album_id, filename), file_data.len() as u64);
 
-    // 所有権を caddy に変更（ベストエフォート）
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        match Command::new("chown")
-            .arg("caddy:caddy")
-            .arg(&target_path)
-            .output()
-        {
-            Ok(_) => info!("Changed ownership to caddy:caddy"),
-            Err(e) => warn!("Failed to chown (not critical): {}", e),
-        }
-    }
+    // 所有権を変更（UPLOAD_CHOWN、ベストエフォート）
+    storage::apply_upload_owner(state.config.upload_chown.as_deref(), &target_path, false).await;
 
     // URL 生成 (albums -> nft/albums, promo -> promo)
     let url_type_path = if file_type == "albums" { "nft/albums" } else { &file_type };
@@ -328,27 +421,19 @@ async fn upload_file(
 /// ファイル削除（売り切れ時などに使用）
 async fn delete_file(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<DeleteRequest>,
-) -> Result<Json<DeleteResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // albums -> nft/albums, promo -> promo
-    let base_dir = PathBuf::from(&state.base_data_dir);
-    let type_dir = if payload.file_type == "albums" {
-        base_dir.join("nft").join("albums")
-    } else {
-        base_dir.join(&payload.file_type)
-    };
-    let target_dir = type_dir.join(&payload.album_id);
+    ApiJson(payload): ApiJson<DeleteRequest>,
+) -> Result<Json<DeleteResponse>, ApiError> {
+    let target_dir = legacy_album_dir(&state.base_data_dir, &payload.file_type, &payload.album_id)
+        .map_err(ApiError::BadRequest)?;
 
     if !target_dir.exists() {
-        return Err(error_response(
-            StatusCode::NOT_FOUND,
+        return Err(ApiError::NotFound(
             format!("Directory does not exist: {:?}", target_dir),
         ));
     }
 
     fs::remove_dir_all(&target_dir).await.map_err(|e| {
-        error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
+        ApiError::internal(
             format!("Failed to delete directory: {}", e),
         )
     })?;
@@ -361,6 +446,208 @@ async fn delete_file(
     }))
 }
 
+/// 複数アルバムの一括削除（ベンダーの promo ツリー整理などに使用）
+async fn bulk_delete_files(
+    State(state): State<Arc<AppState>>,
+    ApiJson(payload): ApiJson<BulkDeleteRequest>,
+) -> Result<Json<BulkDeleteResponse>, ApiError> {
+    if payload.album_ids.len() > MAX_BULK_DELETE {
+        return Err(ApiError::BadRequest(
+            format!("Too many album_ids: at most {}", MAX_BULK_DELETE),
+        ));
+    }
+    // file_type はリクエスト全体で共通なので先に検証
+    if payload.file_type != "promo" && payload.file_type != "albums" {
+        return Err(ApiError::BadRequest(
+            "file_type must be 'promo' or 'albums'".to_string(),
+        ));
+    }
+
+    let mut results = HashMap::new();
+    for album_id in &payload.album_ids {
+        let deleted = match legacy_album_dir(&state.base_data_dir, &payload.file_type, album_id) {
+            Ok(dir) => match fs::remove_dir_all(&dir).await {
+                Ok(()) => {
+                    info!("Deleted: {:?}", dir);
+                    true
+                }
+                Err(e) => {
+                    warn!("Bulk delete failed: {:?}: {}", dir, e);
+                    false
+                }
+            },
+            Err(msg) => {
+                warn!("Bulk delete rejected: {}", msg);
+                false
+            }
+        };
+        results.insert(album_id.clone(), deleted);
+    }
+
+    info!(
+        "Bulk delete: file_type={}, requested={}, deleted={}",
+        payload.file_type,
+        payload.album_ids.len(),
+        results.values().filter(|d| **d).count()
+    );
+
+    Ok(Json(BulkDeleteResponse {
+        success: true,
+        results,
+    }))
+}
+
+/// GET /api/files/:file_type/:album_id/:filename - レガシーアップロードファイルの配信（アクセスログ付き）
+/// cover / manifest はアルバム直下、トラックは tracks/ 配下から探す
+async fn download_legacy_file(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    headers: HeaderMap,
+    Path(path): Path<LegacyFilePath>,
+) -> Result<Response, ApiError> {
+    let album_dir = legacy_album_dir(&state.base_data_dir, &path.file_type, &path.album_id)
+        .map_err(ApiError::BadRequest)?;
+    if !is_single_path_component(&path.filename) {
+        return Err(ApiError::BadRequest(
+            format!("Invalid filename: {:?}", path.filename),
+        ));
+    }
+
+    let candidates = [album_dir.join(&path.filename), album_dir.join("tracks").join(&path.filename)];
+    let mut data = None;
+    for candidate in &candidates {
+        match fs::read(candidate).await {
+            Ok(bytes) => {
+                data = Some(bytes);
+                break;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(ApiError::internal(
+                    format!("File read error: {}", e),
+                ));
+            }
+        }
+    }
+    let data = data.ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+
+    // アクセスログ（失敗してもダウンロードは継続）
+    let ip_hash = hex::encode(sha2::Sha256::digest(
+        middleware::client_ip(&state.config, &headers, &remote_addr).as_bytes(),
+    ));
+    if let Err(e) = sqlx::query(
+        "INSERT INTO file_downloads (file_type, album_id, filename, bytes_sent, ip_hash, downloaded_at_ms) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&path.file_type)
+    .bind(&path.album_id)
+    .bind(&path.filename)
+    .bind(data.len() as i64)
+    .bind(&ip_hash)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(&state.db)
+    .await
+    {
+        warn!("Failed to record file download: {}/{}/{}: {}", path.file_type, path.album_id, path.filename, e);
+    }
+
+    let ext = path.filename.rsplit('.').next().unwrap_or("");
+    let content_type = storage::sniff_content_type(&data, ext);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, data.len())
+        .body(Body::from(data))
+        .map_err(|e| {
+            ApiError::internal(format!("Response build error: {}", e))
+        })
+}
+
+/// GET /api/covers/:album_id?w=256 - アルバムカバーの縮小版（幅ごとにディスクキャッシュ）
+async fn get_album_cover(
+    State(state): State<Arc<AppState>>,
+    Path(album_id): Path<String>,
+    Query(query): Query<CoverQuery>,
+) -> Result<Response, ApiError> {
+    let file_type = query.file_type.as_deref().unwrap_or("albums");
+    let album_dir = legacy_album_dir(&state.base_data_dir, file_type, &album_id)
+        .map_err(ApiError::BadRequest)?;
+
+    if let Some(w) = query.w {
+        if w == 0 || w > images::MAX_COVER_WIDTH {
+            return Err(ApiError::BadRequest(
+                format!("w must be between 1 and {}", images::MAX_COVER_WIDTH),
+            ));
+        }
+    }
+
+    // カバーは cover.<ext>（アップロード時の拡張子）
+    let mut cover_path = None;
+    if let Ok(mut entries) = fs::read_dir(&album_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with("cover.") {
+                cover_path = Some(entry.path());
+                break;
+            }
+        }
+    }
+    let cover_path = cover_path.ok_or_else(|| ApiError::NotFound("Cover not found".to_string()))?;
+
+    let data = match query.w {
+        Some(w) => {
+            let cache_path = PathBuf::from(&state.base_data_dir)
+                .join("cover_cache")
+                .join(file_type)
+                .join(&album_id)
+                .join(format!("w{}.jpg", w));
+            images::cached_resized_cover(&cover_path, &cache_path, w)
+                .await
+                .map_err(|e| ApiError::internal(format!("Cover resize error: {}", e)))?
+        }
+        None => fs::read(&cover_path)
+            .await
+            .map_err(|e| ApiError::internal(format!("File read error: {}", e)))?,
+    };
+
+    let ext = cover_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let content_type = if query.w.is_some() { "image/jpeg" } else { storage::sniff_content_type(&data, ext) };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, data.len())
+        .header(header::CACHE_CONTROL, "public, max-age=86400")
+        .body(Body::from(data))
+        .map_err(|e| {
+            ApiError::internal(format!("Response build error: {}", e))
+        })
+}
+
+/// レガシーアルバムのディレクトリを解決（albums -> nft/albums, promo -> promo）
+/// file_type は promo / albums のみ、album_id は1階層のディレクトリ名のみ許可（パストラバーサル防止）
+fn legacy_album_dir(base_data_dir: &str, file_type: &str, album_id: &str) -> Result<PathBuf, String> {
+    let base_dir = PathBuf::from(base_data_dir);
+    let type_dir = match file_type {
+        "albums" => base_dir.join("nft").join("albums"),
+        "promo" => base_dir.join("promo"),
+        _ => return Err("file_type must be 'promo' or 'albums'".to_string()),
+    };
+
+    if !is_single_path_component(album_id) {
+        return Err(format!("Invalid album_id: {:?}", album_id));
+    }
+    Ok(type_dir.join(album_id))
+}
+
+/// `..` や区切り文字を含まない1階層の名前か
+fn is_single_path_component(name: &str) -> bool {
+    let mut components = std::path::Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) && !name.contains(['/', '\\'])
+}
+
 // ========================================
 // Peer Profile Handler
 // ========================================
@@ -368,8 +655,8 @@ async fn delete_file(
 /// PUT /api/peer-profile - P2P名/PFP 更新（名前変更時に1回だけ呼ぶ）
 async fn upsert_peer_profile(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<UpsertPeerProfileRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(req): ApiJson<UpsertPeerProfileRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let now_ms = chrono::Utc::now().timestamp_millis();
 
     sqlx::query(r#"
@@ -390,7 +677,7 @@ async fn upsert_peer_profile(
     .await
     .map_err(|e| {
         warn!("peer_profile upsert error: {}", e);
-        error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e))
+        ApiError::from(e)
     })?;
 
     info!("Peer profile upserted: peer_id={}", &req.peer_id[..20.min(req.peer_id.len())]);
@@ -402,14 +689,214 @@ async fn upsert_peer_profile(
 // エラーレスポンスヘルパー
 // ========================================
 
-fn error_response(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
-    (
-        status,
-        Json(ErrorResponse {
-            success: false,
-            error: message,
-        }),
-    )
+/// 未定義パスへのフォールバック（404）
+/// /api 配下で大文字小文字だけが違うパスは正しいパスへ 308、それ以外は 404
+async fn not_found_fallback(uri: axum::http::Uri) -> Response {
+    match middleware::case_redirect(&uri) {
+        Some(redirect) => redirect,
+        None => ApiError::NotFound("not found".to_string()).into_response(),
+    }
+}
+
+/// 定義済みパスへの未対応メソッドのフォールバック（405）
+async fn method_not_allowed_fallback() -> ApiError {
+    ApiError::new(StatusCode::METHOD_NOT_ALLOWED, "method not allowed".to_string())
+}
+
+// ========================================
+// アプリケーション構築
+// ========================================
+
+/// 共有アプリケーション状態を構築
+fn new_app_state(config: AppConfig, db: DbPool) -> Arc<AppState> {
+    let read_only = AtomicBool::new(config.read_only);
+    Arc::new(AppState {
+        base_data_dir: config.base_data_dir.clone(),
+        vps_base_url: config.vps_base_url.clone(),
+        db,
+        config,
+        challenges: RwLock::new(HashMap::new()),
+        tokens: RwLock::new(HashMap::new()),
+        camera_captures: RwLock::new(VecDeque::new()),
+        rate_limiter: middleware::RateLimiter::default(),
+        blob_lock: tokio::sync::Mutex::new(()),
+        read_only,
+        storage_usage: tokio::sync::Mutex::new(None),
+    })
+}
+
+/// 登録するルート（パスとハンドラ）。同じパスを複数回書いた場合はメソッドごとにまとめられる
+/// build_app と、ROUTE_SEGMENTS の網羅テストで共有する
+fn api_routes() -> Vec<(&'static str, MethodRouter<Arc<AppState>>)> {
+    vec![
+        // ヘルスチェック
+        ("/api/health", get(health_check)),
+        ("/api/openapi.json", get(openapi::openapi_json)),
+        // レガシーAPI（後方互換）
+        ("/api/upload", post(upload_file)),
+        ("/api/delete", post(delete_file)),
+        ("/api/delete/bulk", post(bulk_delete_files)),
+        ("/api/files/:file_type/:album_id/:filename", get(download_legacy_file)),
+        ("/api/covers/:album_id", get(get_album_cover)),
+        // Vendors API
+        ("/api/vendors", get(handlers::vendors::list_vendors)),
+        ("/api/vendors", post(handlers::vendors::create_vendor)),
+        ("/api/vendors/:stable_id", get(handlers::vendors::get_vendor)),
+        ("/api/vendors/:stable_id", put(handlers::vendors::update_vendor)),
+        ("/api/vendors/:stable_id", delete(handlers::vendors::delist_vendor)),
+        ("/api/vendors/:stable_id/icon", post(handlers::vendors::upload_vendor_icon)),
+        ("/api/vendors/:stable_id/regenerate", post(handlers::vendors::regenerate_vendor_profile)),
+        ("/api/vendors/:stable_id/profile/history", get(handlers::vendors::get_profile_history)),
+        ("/api/vendors/by-peer/:peer_id", get(handlers::vendors::get_vendor_by_peer)),
+        ("/api/vendors/by-owner/:owner", get(handlers::vendors::get_vendors_by_owner)),
+        // Listings API
+        ("/api/uploads", post(handlers::uploads::create_upload)),
+        ("/api/uploads/:upload_id", get(handlers::uploads::get_upload).patch(handlers::uploads::patch_upload)),
+        ("/api/uploads/:upload_id/finalize", post(handlers::uploads::finalize_upload)),
+        ("/api/listings", get(handlers::listings::list_listings)),
+        ("/api/listings", post(handlers::listings::create_listing)),
+        ("/api/listings/stream", get(handlers::listings::stream_listings)),
+        ("/api/listings/:listing_id", get(handlers::listings::get_listing).head(handlers::listings::head_listing)),
+        ("/api/listings/:listing_id", put(handlers::listings::update_listing)),
+        ("/api/listings/:listing_id", delete(handlers::listings::delete_listing)),
+        ("/api/listings/:listing_id/price-history", get(handlers::listings::get_price_history)),
+        ("/api/vendors/:stable_id/listings/summary", get(handlers::listings::get_vendor_listings_summary)),
+        ("/api/vendors/:stable_id/listings/batch_status", post(handlers::listings::batch_update_listing_status)),
+        ("/api/listings/batch", post(handlers::listings::batch_create_listings)),
+        ("/api/listings/:listing_id/reserve", post(handlers::listings::reserve_listing)),
+        ("/api/reservations/:reservation_id/consume", post(handlers::listings::consume_reservation)),
+        // Artists API (Account)
+        ("/api/account/artists", get(handlers::artists::list_artists)),
+        ("/api/account/artists", post(handlers::artists::create_artist)),
+        ("/api/account/artists/:stable_id", get(handlers::artists::get_artist)),
+        ("/api/account/artists/:stable_id", put(handlers::artists::update_artist)),
+        ("/api/account/artists/:stable_id", delete(handlers::artists::delist_artist)),
+        ("/api/account/artists/:stable_id/icon", post(handlers::artists::upload_artist_icon)),
+        ("/api/account/artists/:stable_id/regenerate", post(handlers::artists::regenerate_artist)),
+        ("/api/account/artists/:stable_id/discography", get(handlers::artists::get_discography)),
+        ("/api/account/artists/:stable_id/discography", post(handlers::artists::add_discography)),
+        ("/api/account/artists/:stable_id/discography/:album_id", delete(handlers::artists::remove_discography)),
+        ("/api/account/artists/by-peer/:peer_id", get(handlers::artists::get_artist_by_peer)),
+        // Artist Followers API
+        ("/api/account/artists/:stable_id/followers", post(handlers::artists::add_follower)),
+        ("/api/account/artists/:stable_id/followers", get(handlers::artists::list_followers)),
+        ("/api/account/artists/:stable_id/followers/:peer_id", delete(handlers::artists::remove_follower)),
+        ("/api/account/artists/:stable_id/follower-count", get(handlers::artists::get_follower_count)),
+        // Vendor Subscribers API
+        ("/api/vendors/:stable_id/subscribers", post(handlers::vendors::add_subscriber)),
+        ("/api/vendors/:stable_id/subscribers", get(handlers::vendors::list_subscribers)),
+        ("/api/vendors/:stable_id/subscribers/:peer_id", delete(handlers::vendors::remove_subscriber)),
+        ("/api/vendors/:stable_id/subscriber-count", get(handlers::vendors::get_subscriber_count)),
+        // Peer Profile API
+        ("/api/peer-profile", put(upsert_peer_profile)),
+        // Drops API
+        ("/api/vendors/:vendor_stable_id/drops", get(handlers::drops::list_drops)),
+        ("/api/vendors/:vendor_stable_id/drops/batch_end", post(handlers::drops::batch_end_drops)),
+        ("/api/vendors/:vendor_stable_id/drops/batch_purge", post(handlers::drops::batch_purge_drops)),
+        ("/api/drops", get(handlers::drops::list_drop_feed).post(handlers::drops::create_drop)),
+        ("/api/drops/availability", post(handlers::drops::get_drops_availability)),
+        ("/api/drops/:drop_id", get(handlers::drops::get_drop).head(handlers::drops::head_drop)),
+        ("/api/drops/:drop_id/audio", put(handlers::drops::replace_drop_audio)),
+        ("/api/drops/:drop_id/extend", post(handlers::drops::extend_drop)),
+        ("/api/drops/:drop_id/preview", get(handlers::drops::get_drop_preview)),
+        ("/api/drops/:drop_id/claim", post(handlers::drops::claim_drop)),
+        ("/api/drops/:drop_id/claimable", get(handlers::drops::get_drop_claimable)),
+        ("/api/drops/:drop_id/claims", get(handlers::drops::list_drop_claims)),
+        ("/api/drops/:drop_id/claims/:claim_id", get(handlers::drops::get_claim_status)),
+        ("/api/drops/:drop_id/claims/:claim_id/revoke", post(handlers::drops::revoke_claim)),
+        ("/api/drops/:drop_id/claims/:claim_id/receipt", get(handlers::drops::get_claim_receipt)),
+        ("/api/receipts/public-key", get(handlers::drops::get_receipt_public_key)),
+        ("/api/drops/:drop_id/download", get(handlers::drops::download_drop)),
+        ("/api/drops/:drop_id/stats", get(handlers::drops::get_drop_stats)),
+        // Devices Auth API (Challenge-Response認証)
+        ("/api/devices/auth/challenge", get(handlers::devices::get_challenge)),
+        ("/api/devices/auth/verify", post(handlers::devices::verify_challenge)),
+        // Devices API (デバイス制限 — 要認証)
+        ("/api/devices/register", post(handlers::devices::register_device)),
+        ("/api/devices/:peer_id", get(handlers::devices::list_devices)),
+        ("/api/devices/:peer_id/:device_type", delete(handlers::devices::unregister_device)),
+        // Transfers API (P2P NFTアルバム転送)
+        ("/api/transfers", post(handlers::transfers::create_transfer)),
+        ("/api/transfers/:transfer_id", get(handlers::transfers::get_transfer)),
+        ("/api/transfers/:transfer_id/download", get(handlers::transfers::download_transfer)),
+        ("/api/transfers/:transfer_id/claim", post(handlers::transfers::claim_transfer)),
+        ("/api/transfers/:transfer_id/cancel", post(handlers::transfers::cancel_transfer)),
+        ("/api/transfers/pending/:peer_id", get(handlers::transfers::list_pending_transfers)),
+        // Tombstones（インデクサ向け）
+        ("/api/tombstones", get(handlers::tombstones::list_tombstones)),
+        ("/api/export", get(handlers::export::export)),
+        // Admin（運用者向け）
+        ("/api/assets/verify", get(handlers::assets::verify_asset)),
+        ("/api/admin/config", get(handlers::admin::get_config)),
+        ("/api/admin/reindex/discography", post(handlers::admin::reindex_discography)),
+        ("/api/admin/drops/orphans", get(handlers::admin::list_orphan_drop_dirs)),
+        ("/api/admin/drops/orphans/reap", post(handlers::admin::reap_orphan_drop_dirs)),
+        ("/api/admin/drops/reconcile", post(handlers::admin::reconcile_all_drop_claims)),
+        ("/api/admin/drops/:drop_id/reconcile", post(handlers::admin::reconcile_drop_claims)),
+        ("/api/admin/storage", get(handlers::admin::get_storage_usage)),
+        ("/api/admin/read-only", get(handlers::admin::get_read_only).put(handlers::admin::set_read_only)),
+        // Camera (モバイルカメラ → デスクトップアプリ転送)
+        ("/camera", get(handlers::camera::camera_page)),
+        ("/api/camera/upload", post(handlers::camera::upload_image)),
+        ("/api/camera/latest", get(handlers::camera::get_latest)),
+        ("/api/camera/latest", delete(handlers::camera::delete_latest)),
+        ("/api/camera/captures", get(handlers::camera::list_captures)),
+    ]
+}
+
+/// ルーター（ミドルウェア・パス正規化込み）を構築
+fn build_app(state: &Arc<AppState>) -> NormalizePath<Router> {
+    let router = api_routes()
+        .into_iter()
+        .fold(Router::new(), |router, (path, route)| router.route(path, route))
+        // 未定義ルート・未対応メソッドも JSON で返す（登録済みルートの後に設定する）
+        .method_not_allowed_fallback(method_not_allowed_fallback)
+        .fallback(not_found_fallback)
+        // ミドルウェア
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::verify_request_signature,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::enforce_body_limit,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit,
+        ))
+        // ボディを読む前・レート制限の前に拒否する
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::reject_writes_when_read_only,
+        ))
+        // 署名検証のボディ読み込みも含めて制限するため最も外側に置く
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::enforce_timeout,
+        ))
+        // 実際の上限は enforce_body_limit がルート別に適用する
+        .layer(DefaultBodyLimit::max(state.config.max_audio_bytes))
+        .layer(middleware::cors_layer(&state.config))
+        .with_state(state.clone());
+    // 末尾スラッシュはルーティング前に除去する（Router::layer はルーティング後に適用されるため外側で包む）
+    NormalizePathLayer::trim_trailing_slash().layer(router)
+}
+
+// ========================================
+// バックグラウンドジョブ
+// ========================================
+
+/// DB・ファイルを書き換えるジョブの1回分を実行する（実行したら true）
+///
+/// 読み取り専用（メンテナンス）モード中はリクエストと同様に書き込みを止め、その回はスキップする
+async fn run_write_job(state: &AppState, name: &str, job: impl std::future::Future<Output = ()>) -> bool {
+    if state.read_only.load(std::sync::atomic::Ordering::Relaxed) {
+        info!("[Job] Skipping {} (read-only mode)", name);
+        return false;
+    }
+    job.await;
+    true
 }
 
 // ========================================
@@ -424,128 +911,80 @@ async fn main() {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "info".into()),
         )
+        .with_writer(redact::RedactingStdout)
         .init();
 
-    // 設定
-    let base_data_dir = "/data".to_string();
-    let vps_base_url = "http://153.121.61.17".to_string();
-    let db_path = "/data/nft_server.db";
+    // 設定（環境変数 → 未設定時はデフォルト）
+    let config = AppConfig::from_env();
+    redact::init(config.log_redaction, &config.log_redact_fields);
+    let base_data_dir = config.base_data_dir.clone();
+    let vps_base_url = config.vps_base_url.clone();
+    let db_path = config.db_path.clone();
 
     // DB初期化
     info!("Initializing database...");
-    let db = db::init_db(db_path).await.expect("Failed to initialize database");
+    let pool_settings = db::PoolSettings {
+        max_connections: config.db_max_connections,
+        min_connections: config.db_min_connections,
+        slow_query_threshold: (config.db_slow_query_ms > 0)
+            .then(|| std::time::Duration::from_millis(config.db_slow_query_ms)),
+    };
+    let db = db::init_db(&db_path, pool_settings).await.expect("Failed to initialize database");
 
     // 公式ショップをシード（VPSリセット後も必ず存在を保証）
     db::seed_official_vendors(&db, &base_data_dir, &vps_base_url)
         .await
         .expect("Failed to seed official vendors");
 
-    // アプリケーション状態
-    let state = Arc::new(AppState {
-        base_data_dir,
-        vps_base_url,
-        db,
-        challenges: RwLock::new(HashMap::new()),
-        tokens: RwLock::new(HashMap::new()),
-    });
+    if config.read_only {
+        warn!("Starting in read-only (maintenance) mode: write requests will return 503");
+    }
 
-    // ルーター構築
-    let app = Router::new()
-        // ヘルスチェック
-        .route("/api/health", get(health_check))
-        // レガシーAPI（後方互換）
-        .route("/api/upload", post(upload_file))
-        .route("/api/delete", post(delete_file))
-        // Vendors API
-        .route("/api/vendors", get(handlers::vendors::list_vendors))
-        .route("/api/vendors", post(handlers::vendors::create_vendor))
-        .route("/api/vendors/:stable_id", get(handlers::vendors::get_vendor))
-        .route("/api/vendors/:stable_id", put(handlers::vendors::update_vendor))
-        .route("/api/vendors/:stable_id", delete(handlers::vendors::delist_vendor))
-        .route("/api/vendors/:stable_id/icon", post(handlers::vendors::upload_vendor_icon))
-        .route("/api/vendors/by-peer/:peer_id", get(handlers::vendors::get_vendor_by_peer))
-        // Listings API
-        .route("/api/listings", get(handlers::listings::list_listings))
-        .route("/api/listings", post(handlers::listings::create_listing))
-        .route("/api/listings/:listing_id", get(handlers::listings::get_listing))
-        .route("/api/listings/:listing_id", put(handlers::listings::update_listing))
-        .route("/api/listings/:listing_id", delete(handlers::listings::delete_listing))
-        // Artists API (Account)
-        .route("/api/account/artists", get(handlers::artists::list_artists))
-        .route("/api/account/artists", post(handlers::artists::create_artist))
-        .route("/api/account/artists/:stable_id", get(handlers::artists::get_artist))
-        .route("/api/account/artists/:stable_id", put(handlers::artists::update_artist))
-        .route("/api/account/artists/:stable_id/icon", post(handlers::artists::upload_artist_icon))
-        .route("/api/account/artists/:stable_id/discography", get(handlers::artists::get_discography))
-        .route("/api/account/artists/:stable_id/discography", post(handlers::artists::add_discography))
-        .route("/api/account/artists/by-peer/:peer_id", get(handlers::artists::get_artist_by_peer))
-        // Artist Followers API
-        .route("/api/account/artists/:stable_id/followers", post(handlers::artists::add_follower))
-        .route("/api/account/artists/:stable_id/followers", get(handlers::artists::list_followers))
-        .route("/api/account/artists/:stable_id/followers/:peer_id", delete(handlers::artists::remove_follower))
-        .route("/api/account/artists/:stable_id/follower-count", get(handlers::artists::get_follower_count))
-        // Vendor Subscribers API
-        .route("/api/vendors/:stable_id/subscribers", post(handlers::vendors::add_subscriber))
-        .route("/api/vendors/:stable_id/subscribers", get(handlers::vendors::list_subscribers))
-        .route("/api/vendors/:stable_id/subscribers/:peer_id", delete(handlers::vendors::remove_subscriber))
-        .route("/api/vendors/:stable_id/subscriber-count", get(handlers::vendors::get_subscriber_count))
-        // Peer Profile API
-        .route("/api/peer-profile", put(upsert_peer_profile))
-        // Drops API
-        .route("/api/vendors/:vendor_stable_id/drops", get(handlers::drops::list_drops))
-        .route("/api/vendors/:vendor_stable_id/drops/batch_end", post(handlers::drops::batch_end_drops))
-        .route("/api/vendors/:vendor_stable_id/drops/batch_purge", post(handlers::drops::batch_purge_drops))
-        .route("/api/drops", post(handlers::drops::create_drop))
-        .route("/api/drops/:drop_id", get(handlers::drops::get_drop))
-        .route("/api/drops/:drop_id/claim", post(handlers::drops::claim_drop))
-        .route("/api/drops/:drop_id/download", get(handlers::drops::download_drop))
-        // Devices Auth API (Challenge-Response認証)
-        .route("/api/devices/auth/challenge", get(handlers::devices::get_challenge))
-        .route("/api/devices/auth/verify", post(handlers::devices::verify_challenge))
-        // Devices API (デバイス制限 — 要認証)
-        .route("/api/devices/register", post(handlers::devices::register_device))
-        .route("/api/devices/:peer_id", get(handlers::devices::list_devices))
-        .route("/api/devices/:peer_id/:device_type", delete(handlers::devices::unregister_device))
-        // Transfers API (P2P NFTアルバム転送)
-        .route("/api/transfers", post(handlers::transfers::create_transfer))
-        .route("/api/transfers/:transfer_id", get(handlers::transfers::get_transfer))
-        .route("/api/transfers/:transfer_id/download", get(handlers::transfers::download_transfer))
-        .route("/api/transfers/:transfer_id/claim", post(handlers::transfers::claim_transfer))
-        .route("/api/transfers/:transfer_id/cancel", post(handlers::transfers::cancel_transfer))
-        .route("/api/transfers/pending/:peer_id", get(handlers::transfers::list_pending_transfers))
-        // Camera (モバイルカメラ → デスクトップアプリ転送)
-        .route("/camera", get(handlers::camera::camera_page))
-        .route("/api/camera/upload", post(handlers::camera::upload_image))
-        .route("/api/camera/latest", get(handlers::camera::get_latest))
-        .route("/api/camera/latest", delete(handlers::camera::delete_latest))
-        // ミドルウェア
-        .layer(DefaultBodyLimit::max(800 * 1024 * 1024)) // 800MB まで許可
-        .layer(CorsLayer::permissive())
-        .with_state(state.clone());
+    // アプリケーション状態・ルーター
+    let state = new_app_state(config, db);
+    let app = build_app(&state);
 
-    let addr = "0.0.0.0:3000";
+    let addr = state.config.listen_addr.clone();
     info!("NFT Upload API Server v0.2.0 listening on {}", addr);
-    info!("Max body size: 800MB");
+    info!(
+        "Max body size: icon={}B, cover={}B, audio={}B, json={}B",
+        state.config.max_icon_bytes,
+        state.config.max_cover_bytes,
+        state.config.max_audio_bytes,
+        state.config.max_json_bytes
+    );
     info!("Database: {}", db_path);
+    if !state.config.signed_routes.is_empty() {
+        info!(
+            "Signature required routes: {:?} ({} trusted key(s))",
+            state.config.signed_routes,
+            state.config.trusted_client_keys.len()
+        );
+    }
 
-    // 期限切れDrops処理のバックグラウンドジョブ（1時間ごと）
+    // 期限切れDrops処理のバックグラウンドジョブ（PURGE_INTERVAL_SECS ごと、デフォルト1時間）
     let state_for_drops = state.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            state_for_drops.config.purge_interval_secs,
+        ));
         loop {
             interval.tick().await;
-            info!("[Job] Running expired drops check...");
+            run_write_job(&state_for_drops, "expired drops check", async {
+                info!("[Job] Running expired drops check...");
 
-            // 期限切れDropsをENDED状態に更新
-            if let Err(e) = handlers::drops::expire_drops(&state_for_drops).await {
-                warn!("[Job] expire_drops error: {:?}", e);
-            }
+                // 期限切れDropsをENDED状態に更新
+                if let Err(e) = handlers::drops::expire_drops(&state_for_drops).await {
+                    warn!("[Job] expire_drops error: {:?}", e);
+                }
 
-            // 7日以上前にENDEDになったDropsをpurge（ファイル削除）
-            // grace_seconds = 7 * 24 * 3600 = 604800 (7日)
-            if let Err(e) = handlers::drops::purge_ended_drops(&state_for_drops, 604800).await {
-                warn!("[Job] purge_ended_drops error: {:?}", e);
-            }
+                // PURGE_GRACE_SECS（デフォルト7日）以上前にENDEDになったDropsをpurge（ファイル削除）
+                let grace_seconds = state_for_drops.config.purge_grace_secs;
+                if let Err(e) = handlers::drops::purge_ended_drops(&state_for_drops, grace_seconds).await {
+                    warn!("[Job] purge_ended_drops error: {:?}", e);
+                }
+            })
+            .await;
         }
     });
 
@@ -555,18 +994,21 @@ async fn main() {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
         loop {
             interval.tick().await;
-            info!("[Job] Running stale devices check...");
-
-            // 7日間heartbeatがないデバイスを無効化
-            let ttl_ms: i64 = 7 * 24 * 3600 * 1000;
-            match handlers::devices::expire_stale_devices(&state_for_devices, ttl_ms).await {
-                Ok(count) => {
-                    if count > 0 {
-                        info!("[Job] Expired {} stale device(s)", count);
+            run_write_job(&state_for_devices, "stale devices check", async {
+                info!("[Job] Running stale devices check...");
+
+                // 7日間heartbeatがないデバイスを無効化
+                let ttl_ms: i64 = 7 * 24 * 3600 * 1000;
+                match handlers::devices::expire_stale_devices(&state_for_devices, ttl_ms).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[Job] Expired {} stale device(s)", count);
+                        }
                     }
+                    Err(e) => warn!("[Job] expire_stale_devices error: {:?}", e),
                 }
-                Err(e) => warn!("[Job] expire_stale_devices error: {:?}", e),
-            }
+            })
+            .await;
         }
     });
 
@@ -576,31 +1018,458 @@ async fn main() {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
         loop {
             interval.tick().await;
-            info!("[Job] Running expired transfers check...");
+            run_write_job(&state_for_transfers, "expired transfers check", async {
+                info!("[Job] Running expired transfers check...");
 
-            // 期限切れ転送をEXPIRED状態に更新 + ファイル削除
-            if let Err(e) = handlers::transfers::expire_transfers(&state_for_transfers).await {
-                warn!("[Job] expire_transfers error: {:?}", e);
-            }
+                // 期限切れ転送をEXPIRED状態に更新 + ファイル削除
+                if let Err(e) = handlers::transfers::expire_transfers(&state_for_transfers).await {
+                    warn!("[Job] expire_transfers error: {:?}", e);
+                }
 
-            // 7日以上前に完了/キャンセル/期限切れになったレコードをパージ
-            let grace_ms: i64 = 7 * 24 * 3600 * 1000;
-            if let Err(e) = handlers::transfers::purge_old_transfers(&state_for_transfers, grace_ms).await {
-                warn!("[Job] purge_old_transfers error: {:?}", e);
-            }
+                // 7日以上前に完了/キャンセル/期限切れになったレコードをパージ
+                let grace_ms: i64 = 7 * 24 * 3600 * 1000;
+                if let Err(e) = handlers::transfers::purge_old_transfers(&state_for_transfers, grace_ms).await {
+                    warn!("[Job] purge_old_transfers error: {:?}", e);
+                }
+            })
+            .await;
+        }
+    });
+
+    // 期限切れの再開可能アップロードの削除（1時間ごと）
+    let state_for_uploads = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            run_write_job(&state_for_uploads, "upload session expiry", async {
+                match handlers::uploads::expire_upload_sessions(&state_for_uploads).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[Job] Expired {} upload sessions", count);
+                        }
+                    }
+                    Err(e) => warn!("[Job] expire_upload_sessions error: {:?}", e),
+                }
+            })
+            .await;
+        }
+    });
+
+    // 期限切れ在庫確保の解放（1分ごと）
+    let state_for_reservations = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            run_write_job(&state_for_reservations, "reservation release", async {
+                if let Err(e) = handlers::listings::release_expired_reservations(&state_for_reservations).await {
+                    warn!("[Job] release_expired_reservations error: {:?}", e);
+                }
+            })
+            .await;
+        }
+    });
+
+    // 古いカメラ一時ファイルの削除（CAMERA_SWEEP_INTERVAL_SECS ごと）
+    let state_for_camera = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            state_for_camera.config.camera_sweep_interval_secs,
+        ));
+        loop {
+            interval.tick().await;
+            run_write_job(&state_for_camera, "camera temp sweep", async {
+                match handlers::camera::sweep_camera_temp(&state_for_camera).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[Job] Removed {} stale camera temp file(s)", count);
+                        }
+                    }
+                    Err(e) => warn!("[Job] sweep_camera_temp error: {:?}", e),
+                }
+            })
+            .await;
         }
     });
 
-    // 期限切れ認証情報クリーンアップ（10分ごと）
+    // 期限切れ認証情報クリーンアップ（10分ごと、メモリ上のみなので読み取り専用モード中も実行）
     let state_for_auth = state.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(600));
         loop {
             interval.tick().await;
             handlers::devices::cleanup_expired_auth(&state_for_auth).await;
+
+            // 回復済みのレート制限バケットを削除
+            state_for_auth.rate_limiter.prune(
+                state_for_auth.config.rate_limit_burst as f64,
+                state_for_auth.config.rate_limit_per_sec,
+            );
         }
     });
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    axum::serve(
+        listener,
+        ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<std::net::SocketAddr>(app),
+    )
+    .await
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use axum::http::Method;
+
+    use crate::test_support::{png_bytes, MultipartForm, TestApp};
+
+    #[tokio::test]
+    async fn write_jobs_skip_ticks_while_read_only() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        sqlx::query("UPDATE drops SET end_at = ? WHERE drop_id = ?")
+            .bind(chrono::Utc::now().timestamp() - 60)
+            .bind(&drop_id)
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+        let status = || async {
+            let (status,): (i32,) = sqlx::query_as("SELECT status FROM drops WHERE drop_id = ?")
+                .bind(&drop_id)
+                .fetch_one(&app.state.db)
+                .await
+                .unwrap();
+            status
+        };
+        let tick = || {
+            super::run_write_job(&app.state, "expired drops check", async {
+                crate::handlers::drops::expire_drops(&app.state).await.unwrap();
+            })
+        };
+
+        app.state.read_only.store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(!tick().await);
+        assert_eq!(status().await, crate::models::drop_status::ACTIVE);
+
+        app.state.read_only.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(tick().await);
+        assert_eq!(status().await, crate::models::drop_status::ENDED);
+    }
+
+    #[tokio::test]
+    async fn health_reports_disk_and_writable_data_dir() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/health").await;
+        assert_eq!(res.status, 200);
+        let body = res.json();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["db_status"], "connected");
+        assert_eq!(body["data_dir_writable"], true);
+        assert!(body["disk_free_bytes"].as_u64().is_some());
+        assert!(body["disk_total_bytes"].as_u64() >= body["disk_free_bytes"].as_u64());
+        // 書き込み確認の一時ファイルは残さない
+        let leftovers = std::fs::read_dir(&app.dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with(".health_"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[tokio::test]
+    async fn health_is_503_when_data_dir_is_not_writable() {
+        // ディレクトリではなくファイルを指す（root で実行してもパーミッションでは防げないため）
+        let app = TestApp::with_config(|c| {
+            let blocker = PathBuf::from(&c.base_data_dir).join("blocker");
+            std::fs::write(&blocker, b"").unwrap();
+            c.base_data_dir = blocker.to_string_lossy().into_owned();
+        })
+        .await;
+        let res = app.get("/api/health").await;
+        assert_eq!(res.status, 503);
+        let body = res.json();
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["data_dir_writable"], false);
+    }
+
+    #[tokio::test]
+    async fn unknown_path_returns_json_404() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/does-not-exist").await;
+        assert_eq!(res.status, 404);
+        let body = res.json();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "not found");
+    }
+
+    #[tokio::test]
+    async fn wrong_method_returns_json_405() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/drops/DROP_X/claim").await;
+        assert_eq!(res.status, 405);
+        let body = res.json();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "method not allowed");
+    }
+
+    #[tokio::test]
+    async fn upload_reports_every_missing_field() {
+        let app = TestApp::new().await;
+        let res = app.send_form(Method::POST, "/api/upload", MultipartForm::new(), &[]).await;
+        assert_eq!(res.status, 400);
+        let body = res.json();
+        assert_eq!(body["fields"], serde_json::json!(["file", "album_id", "file_type", "category"]));
+        assert!(body["error"].as_str().unwrap().starts_with("Missing required fields"));
+    }
+
+    /// レガシーアップロードのフォーム（albums/ALBUM_A）
+    fn upload_form(category: &str, filename: &str, data: &[u8]) -> MultipartForm {
+        MultipartForm::new()
+            .text("album_id", "ALBUM_A")
+            .text("file_type", "albums")
+            .text("category", category)
+            .text("track_number", "01")
+            .file("file", filename, "application/octet-stream", data)
+    }
+
+    #[tokio::test]
+    async fn upload_rejects_disallowed_extensions_per_category() {
+        let app = TestApp::new().await;
+        for (category, filename) in [
+            ("cover", "cover.svg"),
+            ("cover", "cover.php"),
+            ("cover", "cover"),
+            ("tracks", "track.exe"),
+            ("manifest", "manifest.js"),
+        ] {
+            let res = app.send_form(Method::POST, "/api/upload", upload_form(category, filename, b"<x/>"), &[]).await;
+            assert_eq!(res.status, 400, "{} {}", category, filename);
+            assert!(res.json()["error"].as_str().unwrap().contains("not allowed"));
+        }
+        assert!(!app.data_path("nft/albums/ALBUM_A").exists());
+
+        // 拡張子は大文字小文字を区別せず、保存名は小文字
+        let form = upload_form("cover", "Cover.PNG", &png_bytes(4, 4));
+        let res = app.send_form(Method::POST, "/api/upload", form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert!(app.data_path("nft/albums/ALBUM_A/cover.png").exists());
+
+        let form = upload_form("tracks", "song.flac", b"fLaC");
+        assert_eq!(app.send_form(Method::POST, "/api/upload", form, &[]).await.status, 200);
+        assert!(app.data_path("nft/albums/ALBUM_A/tracks/01.flac").exists());
+    }
+
+    #[tokio::test]
+    async fn bulk_delete_reports_per_album_results() {
+        let app = TestApp::new().await;
+        for album in ["ALBUM_A", "ALBUM_B"] {
+            let dir = app.data_path(format!("promo/{}/tracks", album));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("01.mp3"), b"ID3").unwrap();
+        }
+        std::fs::create_dir_all(app.data_path("promo/ALBUM_KEEP")).unwrap();
+
+        let res = app
+            .post_json(
+                "/api/delete/bulk",
+                serde_json::json!({ "file_type": "promo", "album_ids": ["ALBUM_A", "ALBUM_B", "ALBUM_MISSING", ".."] }),
+            )
+            .await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let results = &res.json()["results"];
+        assert_eq!(results["ALBUM_A"], true);
+        assert_eq!(results["ALBUM_B"], true);
+        assert_eq!(results["ALBUM_MISSING"], false);
+        assert_eq!(results[".."], false);
+
+        assert!(!app.data_path("promo/ALBUM_A").exists());
+        assert!(!app.data_path("promo/ALBUM_B").exists());
+        assert!(app.data_path("promo/ALBUM_KEEP").exists());
+
+        let res = app
+            .post_json("/api/delete/bulk", serde_json::json!({ "file_type": "other", "album_ids": ["ALBUM_KEEP"] }))
+            .await;
+        assert_eq!(res.status, 400);
+    }
+
+    async fn file_download_count(app: &TestApp) -> i64 {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM file_downloads")
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+        count
+    }
+
+    #[tokio::test]
+    async fn legacy_files_are_served_with_sniffed_type_and_logged() {
+        let app = TestApp::new().await;
+        let album = app.data_path("nft/albums/ALBUM_A");
+        std::fs::create_dir_all(album.join("tracks")).unwrap();
+        // 拡張子ではなく内容から判定する
+        std::fs::write(album.join("cover.bin"), png_bytes(4, 4)).unwrap();
+        std::fs::write(album.join("tracks/01.mp3"), b"ID3 track").unwrap();
+
+        let res = app.get("/api/files/albums/ALBUM_A/cover.bin").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("content-type"), Some("image/png"));
+
+        let res = app.get("/api/files/albums/ALBUM_A/01.mp3").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_ref(), b"ID3 track");
+        assert_eq!(file_download_count(&app).await, 2);
+    }
+
+    #[tokio::test]
+    async fn legacy_file_rejects_traversal_and_missing_files() {
+        let app = TestApp::new().await;
+        std::fs::create_dir_all(app.data_path("nft/albums/ALBUM_A")).unwrap();
+
+        assert_eq!(app.get("/api/files/albums/ALBUM_A/..%2F..%2F..%2Ftest.db").await.status, 400);
+        assert_eq!(app.get("/api/files/albums/..%2F..%2F/test.db").await.status, 400);
+        assert_eq!(app.get("/api/files/secret/ALBUM_A/cover.png").await.status, 400);
+
+        let res = app.get("/api/files/albums/ALBUM_A/missing.mp3").await;
+        assert_eq!(res.status, 404);
+        assert_eq!(res.json()["success"], false);
+        assert_eq!(file_download_count(&app).await, 0);
+    }
+
+    #[tokio::test]
+    async fn resized_covers_are_cached_per_width_and_reused() {
+        let app = TestApp::new().await;
+        let album = app.data_path("nft/albums/ALBUM_A");
+        std::fs::create_dir_all(&album).unwrap();
+        std::fs::write(album.join("cover.png"), png_bytes(64, 32)).unwrap();
+
+        let cache_dir = app.data_path("cover_cache/albums/ALBUM_A");
+        for w in [16, 32] {
+            let res = app.get(&format!("/api/covers/ALBUM_A?w={}", w)).await;
+            assert_eq!(res.status, 200);
+            assert_eq!(res.header("content-type"), Some("image/jpeg"));
+            let img = image::load_from_memory(&res.body).unwrap();
+            assert_eq!((img.width(), img.height()), (w, w / 2));
+            assert!(cache_dir.join(format!("w{}.jpg", w)).exists());
+        }
+
+        // キャッシュが元画像より新しければ再生成せずそのまま返す
+        std::fs::write(cache_dir.join("w16.jpg"), b"cached").unwrap();
+        let res = app.get("/api/covers/ALBUM_A?w=16").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_ref(), b"cached");
+    }
+
+    #[tokio::test]
+    async fn resized_cover_rejects_out_of_range_width() {
+        let app = TestApp::new().await;
+        let album = app.data_path("nft/albums/ALBUM_A");
+        std::fs::create_dir_all(&album).unwrap();
+        std::fs::write(album.join("cover.png"), png_bytes(8, 8)).unwrap();
+
+        assert_eq!(app.get("/api/covers/ALBUM_A?w=2049").await.status, 400);
+        assert_eq!(app.get("/api/covers/ALBUM_A?w=0").await.status, 400);
+        assert_eq!(app.get("/api/covers/ALBUM_A?w=2048").await.status, 200);
+        assert!(!app.data_path("cover_cache/albums/ALBUM_A/w2049.jpg").exists());
+        assert_eq!(app.get("/api/covers/MISSING?w=16").await.status, 404);
+    }
+
+    #[tokio::test]
+    async fn upload_applies_collision_policy() {
+        let app = TestApp::new().await;
+        let upload = |collision: Option<&str>, data: &'static [u8]| {
+            let mut form = upload_form("tracks", "song.mp3", data);
+            if let Some(collision) = collision {
+                form = form.text("collision", collision);
+            }
+            app.send_form(Method::POST, "/api/upload", form, &[])
+        };
+        let track = |name: &str| std::fs::read(app.data_path(format!("nft/albums/ALBUM_A/tracks/{}", name))).unwrap();
+
+        assert_eq!(upload(None, b"first").await.status, 200);
+        let res = upload(Some("overwrite"), b"second").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.json()["filename"], "01.mp3");
+        assert_eq!(track("01.mp3"), b"second");
+
+        let res = upload(Some("error"), b"third").await;
+        assert_eq!(res.status, 409);
+        assert_eq!(track("01.mp3"), b"second");
+
+        for (expected, data) in [("01.v2.mp3", b"v2"), ("01.v3.mp3", b"v3")] {
+            let res = upload(Some("version"), data).await;
+            assert_eq!(res.status, 200, "{}", res.text());
+            let body = res.json();
+            assert_eq!(body["filename"], expected);
+            assert!(body["url"].as_str().unwrap().ends_with(&format!("/tracks/{}", expected)));
+            assert_eq!(track(expected), data);
+        }
+        assert_eq!(track("01.mp3"), b"second");
+
+        let res = upload(Some("bogus"), b"x").await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], serde_json::json!(["collision"]));
+    }
+
+    #[tokio::test]
+    async fn upload_verifies_digest_and_content_md5() {
+        use base64::Engine;
+        use md5::Md5;
+        use sha2::{Digest, Sha256};
+
+        let app = TestApp::new().await;
+        let data: &[u8] = b"fLaC track bytes";
+        let b64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let sha256 = format!("sha-256={}", b64(&Sha256::digest(data)));
+        let md5 = b64(&Md5::digest(data));
+        let track = app.data_path("nft/albums/ALBUM_A/tracks/01.flac");
+        let app = &app;
+        let upload = |header: &'static str, value: String| {
+            let form = upload_form("tracks", "song.flac", data);
+            async move { app.send_form(Method::POST, "/api/upload", form, &[(header, &value)]).await }
+        };
+
+        let wrong = format!("sha-256={}", b64(&Sha256::digest(b"something else")));
+        let res = upload("digest", wrong).await;
+        assert_eq!(res.status, 422);
+        assert!(!track.exists());
+
+        assert_eq!(upload("digest", format!("SHA-256={}", b64(b"short"))).await.status, 400);
+        assert_eq!(upload("digest", "crc32c=AAAAAA==".to_string()).await.status, 400);
+        assert_eq!(upload("content-md5", b64(b"short")).await.status, 400);
+        assert!(!track.exists());
+
+        let res = upload("digest", sha256).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(std::fs::read(&track).unwrap(), data);
+        assert_eq!(upload("content-md5", md5.clone()).await.status, 200);
+        assert_eq!(upload("digest", format!("crc32c=AAAAAA==, md5={}", md5)).await.status, 200);
+    }
+
+
+    #[tokio::test]
+    async fn upload_rejects_manifest_that_is_not_json() {
+        let app = TestApp::new().await;
+        let form = upload_form("manifest", "manifest.json", b"{ not json");
+        let res = app.send_form(Method::POST, "/api/upload", form, &[]).await;
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json()["fields"], serde_json::json!(["file"]));
+        assert!(!app.data_path("nft/albums/ALBUM_A/manifest.json").exists());
+    }
+
+    #[tokio::test]
+    async fn upload_saves_valid_manifest_and_rejects_empty_one() {
+        let app = TestApp::new().await;
+        let manifest = app.data_path("nft/albums/ALBUM_A/manifest.json");
+        let res = app.send_form(Method::POST, "/api/upload", upload_form("manifest", "manifest.json", b""), &[]).await;
+        assert_eq!(res.status, 400);
+        assert!(res.json()["error"].as_str().unwrap().starts_with("Manifest is not valid JSON"));
+        assert!(!manifest.exists());
+
+        let data = br#"{ "album_id": "ALBUM_A", "tracks": [] }"#;
+        let res = app.send_form(Method::POST, "/api/upload", upload_form("manifest", "manifest.json", data), &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(std::fs::read(&manifest).unwrap(), data);
+    }
 }
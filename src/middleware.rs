@@ -0,0 +1,821 @@
+//! Middleware
+//! ルート横断のリクエスト検証
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, DefaultBodyLimit, MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tower::{Layer, ServiceExt};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::error::ApiError;
+use crate::AppState;
+
+// ========================================
+// 信頼済みクライアント署名検証（Ed25519）
+// ========================================
+
+/// 設定で指定されたルートに対し、リクエスト署名を検証する
+///
+/// ヘッダ:
+///   X-Key-Id    - 登録済みクライアント鍵のID
+///   X-Timestamp - Unix秒（許容ずれ外は 400）
+///   X-Signature - base64 の Ed25519 署名
+///
+/// 署名対象: "{METHOD}\n{PATH_AND_QUERY}\n{sha256_hex(body)}\n{timestamp}"（クエリがあれば `?` 以降も含む）
+pub async fn verify_request_signature(
+    State(state): State<Arc<AppState>>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let method = request.method().as_str().to_string();
+    let route = matched_path.as_ref().map(|p| p.as_str()).unwrap_or("");
+    if !state.config.requires_signature(&method, route) {
+        return Ok(next.run(request).await);
+    }
+
+    // ヘッダは所有値として取り出す（Request の借用を await 越しに保持しない）
+    let (key_id, timestamp, signature_b64) = {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+        };
+        (header("x-key-id"), header("x-timestamp"), header("x-signature"))
+    };
+    let key_id = key_id.ok_or_else(|| {
+        ApiError::Unauthorized("X-Key-Id header required".to_string())
+    })?;
+    let timestamp = timestamp.ok_or_else(|| {
+        ApiError::Unauthorized("X-Timestamp header required".to_string())
+    })?;
+    let signature_b64 = signature_b64.ok_or_else(|| {
+        ApiError::Unauthorized("X-Signature header required".to_string())
+    })?;
+
+    // タイムスタンプ検証（リプレイ対策）
+    let ts: i64 = timestamp.parse().map_err(|_| {
+        ApiError::BadRequest("Invalid X-Timestamp".to_string())
+    })?;
+    let now = chrono::Utc::now().timestamp();
+    if (now - ts).abs() > state.config.signature_max_skew_secs {
+        return Err(ApiError::BadRequest("Stale timestamp".to_string()));
+    }
+
+    // 公開鍵取得
+    let pubkey = state.config.trusted_client_keys.get(&key_id).ok_or_else(|| {
+        ApiError::Unauthorized(format!("Unknown key id: {}", key_id))
+    })?;
+    let verifying_key = VerifyingKey::from_bytes(pubkey).map_err(|e| {
+        ApiError::internal(format!("Invalid registered key: {}", e))
+    })?;
+
+    let sig_array: [u8; 64] = base64::engine::general_purpose::STANDARD
+        .decode(&signature_b64)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| {
+            ApiError::Unauthorized("Invalid X-Signature".to_string())
+        })?;
+    let signature = Signature::from_bytes(&sig_array);
+
+    // ボディをバッファしてハッシュ計算（検証後にハンドラへ戻す）
+    let (parts, body) = request.into_parts();
+    let limit = state.config.body_limit_for(parts.extensions.get::<MatchedPath>().map_or("", |p| p.as_str()));
+    let bytes = axum::body::to_bytes(body, limit).await.map_err(|e| {
+        ApiError::PayloadTooLarge(format!("Body read error: {}", e))
+    })?;
+    let body_hash = hex::encode(Sha256::digest(&bytes));
+
+    let path_and_query = parts.uri.path_and_query().map_or(parts.uri.path(), |pq| pq.as_str());
+    let canonical = format!("{}\n{}\n{}\n{}", method, path_and_query, body_hash, timestamp);
+    verifying_key
+        .verify_strict(canonical.as_bytes(), &signature)
+        .map_err(|_| ApiError::Unauthorized("Signature verification failed".to_string()))?;
+
+    let request = Request::from_parts(parts, Body::from(bytes));
+    Ok(next.run(request).await)
+}
+
+// ========================================
+// ルート別ボディサイズ制限
+// ========================================
+
+/// ルート種別（アイコン / 画像 / 音声 / JSON）ごとにボディサイズを制限する
+///
+/// Content-Length が上限を超える場合はボディを読む前に 413 を返す。
+/// Content-Length が無い場合も extractor 側で上限が適用され、超過時は JSON の 413 になる。
+pub async fn enforce_body_limit(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let limit = state.config.body_limit_for(&route);
+
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > limit as u64) {
+        return Err(ApiError::PayloadTooLarge(
+            format!("Payload too large: {} accepts at most {} bytes", route, limit),
+        ));
+    }
+
+    // 後続の extractor（Json / Multipart 等）にルート別の上限を適用
+    let response = DefaultBodyLimit::max(limit)
+        .layer(next)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|never| match never {});
+
+    // extractor のテキスト 413 を JSON エラーに揃える（ハンドラが返した JSON の 413 はそのまま）
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return Err(ApiError::PayloadTooLarge(
+            format!("Payload too large: {} accepts at most {} bytes", route, limit),
+        ));
+    }
+    Ok(response)
+}
+
+// ========================================
+// ルート別タイムアウト
+// ========================================
+
+/// ルート種別（JSON / アップロード / ダウンロード）ごとのタイムアウトを適用する
+///
+/// ボディ受信を含めてハンドラがレスポンスを返すまでの時間を制限し、超過時は 504 を返す。
+/// レスポンスボディの送信時間は含まない
+pub async fn enforce_timeout(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let timeout = state.config.timeout_for(&route);
+
+    tokio::time::timeout(timeout, next.run(request)).await.map_err(|_| {
+        ApiError::new(
+            StatusCode::GATEWAY_TIMEOUT,
+            format!("Request timed out after {} seconds: {}", timeout.as_secs(), route),
+        )
+    })
+}
+
+// ========================================
+// パスの大文字小文字の正規化
+// ========================================
+
+/// ルート定義（main.rs の api_routes）の固定セグメント。ルートを追加した場合はここにも追加する（漏れはテストで検出）
+/// ID 等のパラメータは大文字を含み得るため、この一覧にあるセグメントだけを小文字に直す
+const ROUTE_SEGMENTS: &[&str] = &[
+    "account", "admin", "api", "artists", "assets", "audio", "auth", "availability", "batch",
+    "batch_end", "batch_purge", "batch_status", "bulk", "by-owner", "by-peer", "camera", "cancel", "captures",
+    "challenge", "claim", "claimable", "claims", "config", "consume", "covers", "delete", "devices",
+    "discography", "download", "drops", "export", "extend", "files", "finalize", "follower-count",
+    "followers", "health", "history", "icon", "latest", "listings", "openapi.json", "orphans",
+    "peer-profile", "pending", "preview", "price-history", "profile", "public-key", "read-only",
+    "reap", "receipt", "receipts", "reconcile", "regenerate", "register", "reindex", "reservations",
+    "reserve", "revoke", "stats", "storage", "stream", "subscriber-count", "subscribers", "summary",
+    "tombstones", "transfers", "upload", "uploads", "vendors", "verify",
+];
+
+/// /api 配下のパスで固定セグメントの大文字小文字だけが違う場合、正しいパスへの 308 を返す
+/// ルートに一致しなかったリクエスト（404 フォールバック）からのみ呼ぶ
+pub fn case_redirect(uri: &axum::http::Uri) -> Option<Response> {
+    let path = uri.path();
+    let segments: Vec<&str> = path.split('/').collect();
+    if !segments.get(1).is_some_and(|s| s.eq_ignore_ascii_case("api")) {
+        return None;
+    }
+
+    let normalized = segments
+        .iter()
+        .map(|segment| {
+            let lower = segment.to_ascii_lowercase();
+            if ROUTE_SEGMENTS.contains(&lower.as_str()) {
+                lower
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    if normalized == path {
+        return None;
+    }
+
+    let location = match uri.query() {
+        Some(query) => format!("{}?{}", normalized, query),
+        None => normalized,
+    };
+    let location = HeaderValue::from_str(&location).ok()?;
+    let mut response = StatusCode::PERMANENT_REDIRECT.into_response();
+    response.headers_mut().insert(header::LOCATION, location);
+    Some(response)
+}
+
+// ========================================
+// 読み取り専用（メンテナンス）モード
+// ========================================
+
+/// 読み取り専用モードでも受け付ける書き込みメソッドのルート
+/// モード切り替え（解除に必要）と、POST だが読み取りのみのルート
+const READ_ONLY_EXEMPT_ROUTES: &[&str] = &["/api/admin/read-only", "/api/drops/availability"];
+
+/// 読み取り専用モード中は書き込み系メソッド（POST / PUT / PATCH / DELETE）を 503 で拒否する
+pub async fn reject_writes_when_read_only(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !state.read_only.load(Ordering::Relaxed) {
+        return Ok(next.run(request).await);
+    }
+
+    let is_write = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str())
+        .unwrap_or("");
+    if !is_write || READ_ONLY_EXEMPT_ROUTES.contains(&route) {
+        return Ok(next.run(request).await);
+    }
+
+    Err(ApiError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Server is in maintenance (read-only) mode",
+    ))
+}
+
+// ========================================
+// IP 単位のレート制限（トークンバケット）
+// ========================================
+
+/// IP・ルートごとのトークンバケット
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    /// 1トークン消費を試みる。不足時は次のトークンまでの待ち時間を返す
+    fn try_acquire(&self, key: (String, String), burst: f64, per_sec: f64) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: burst, updated_at: now });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(burst);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+
+    /// 満タンまで回復したバケットを削除（定期実行用）、残りのバケット数を返す
+    pub fn prune(&self, burst: f64, per_sec: f64) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.retain(|_, b| {
+            b.tokens + now.duration_since(b.updated_at).as_secs_f64() * per_sec < burst
+        });
+        buckets.len()
+    }
+}
+
+/// 設定されたルート（claim / download）をクライアントIP単位で制限し、超過時は 429 + Retry-After
+pub async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    if !state.config.is_rate_limited(&route) {
+        return next.run(request).await;
+    }
+
+    let ip = match &connect_info {
+        Some(ConnectInfo(addr)) => client_ip(&state.config, request.headers(), addr),
+        None => "unknown".to_string(),
+    };
+    let burst = state.config.rate_limit_burst as f64;
+    let per_sec = state.config.rate_limit_per_sec;
+
+    match state.rate_limiter.try_acquire((ip.clone(), route.clone()), burst, per_sec) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            warn!("Rate limit exceeded: ip={}, route={}, retry_after={}s", ip, route, retry_after);
+            let mut response = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many requests, retry after {} seconds", retry_after),
+            )
+            .into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+/// クライアントIP
+/// 接続元が TRUSTED_PROXIES のプロキシの場合のみ X-Forwarded-For を使い、右端から信頼済みプロキシを除いた最初のアドレスを返す
+/// （左側はクライアントが任意に書けるため使わない）。それ以外・解釈できない場合は接続元アドレス
+pub fn client_ip(config: &AppConfig, headers: &HeaderMap, remote_addr: &SocketAddr) -> String {
+    let remote_ip = remote_addr.ip();
+    if !config.trusted_proxies.contains(&remote_ip) {
+        return remote_ip.to_string();
+    }
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect();
+    for hop in hops.iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) if config.trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return ip.to_string(),
+            Err(_) => break,
+        }
+    }
+    remote_ip.to_string()
+}
+
+// ========================================
+// CORS
+// ========================================
+
+/// CORS レイヤーを構築
+/// CORS_ALLOWED_ORIGINS 未設定時は開発用に permissive（警告を出す）
+pub fn cors_layer(config: &AppConfig) -> CorsLayer {
+    if config.cors_allowed_origins.is_empty() {
+        warn!("CORS_ALLOWED_ORIGINS is not set: CORS is permissive (not recommended for production)");
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|o| match HeaderValue::from_str(o) {
+            Ok(v) => Some(v),
+            Err(_) => {
+                warn!("Invalid CORS origin ignored: {}", o);
+                None
+            }
+        })
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static("x-key-id"),
+            HeaderName::from_static("x-timestamp"),
+            HeaderName::from_static("x-signature"),
+            HeaderName::from_static("x-admin-token"),
+            HeaderName::from_static("x-owner-address"),
+            HeaderName::from_static("x-owner-public-key"),
+            HeaderName::from_static("x-owner-challenge"),
+            HeaderName::from_static("x-owner-signature"),
+            // アップロードのチェックサム検証
+            HeaderName::from_static("digest"),
+            HeaderName::from_static("content-md5"),
+            // 再開可能アップロード（PATCH /api/uploads/:upload_id）
+            HeaderName::from_static("upload-offset"),
+            HeaderName::from_static("upload-length"),
+        ])
+        // 再開位置をブラウザのクライアントから読めるようにする
+        .expose_headers([HeaderName::from_static("upload-offset")])
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use base64::Engine;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    use crate::test_support::{png_bytes, vendor_body, MultipartForm, TestApp};
+
+    const KEY_ID: &str = "test-client";
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    async fn signed_app() -> TestApp {
+        TestApp::with_config(|c| {
+            c.signed_routes = vec!["POST /api/vendors".to_string()];
+            c.trusted_client_keys
+                .insert(KEY_ID.to_string(), signing_key().verifying_key().to_bytes());
+        })
+        .await
+    }
+
+    fn sign(key: &SigningKey, method: &str, path: &str, body: &str, timestamp: i64) -> String {
+        let canonical = format!("{}\n{}\n{}\n{}", method, path, hex::encode(Sha256::digest(body)), timestamp);
+        base64::engine::general_purpose::STANDARD.encode(key.sign(canonical.as_bytes()).to_bytes())
+    }
+
+    async fn post_signed(app: &TestApp, body: &serde_json::Value, timestamp: i64, signature: &str) -> u16 {
+        let ts = timestamp.to_string();
+        app.send_json(
+            Method::POST,
+            "/api/vendors",
+            Some(body.clone()),
+            &[("x-key-id", KEY_ID), ("x-timestamp", &ts), ("x-signature", signature)],
+        )
+        .await
+        .status
+        .as_u16()
+    }
+
+    #[tokio::test]
+    async fn signed_route_accepts_valid_signature() {
+        let app = signed_app().await;
+        let body = vendor_body(None);
+        let now = chrono::Utc::now().timestamp();
+        let signature = sign(&signing_key(), "POST", "/api/vendors", &body.to_string(), now);
+
+        assert_eq!(post_signed(&app, &body, now, &signature).await, 200);
+    }
+
+    #[tokio::test]
+    async fn signed_route_rejects_missing_or_bad_signature() {
+        let app = signed_app().await;
+        let body = vendor_body(None);
+        let now = chrono::Utc::now().timestamp();
+
+        // 署名ヘッダなし
+        assert_eq!(app.post_json("/api/vendors", body.clone()).await.status, 401);
+
+        // 別の鍵で署名
+        let other = SigningKey::from_bytes(&[9u8; 32]);
+        let signature = sign(&other, "POST", "/api/vendors", &body.to_string(), now);
+        assert_eq!(post_signed(&app, &body, now, &signature).await, 401);
+
+        // 署名後にボディを改ざん
+        let signature = sign(&signing_key(), "POST", "/api/vendors", &body.to_string(), now);
+        assert_eq!(post_signed(&app, &vendor_body(Some("0xevil")), now, &signature).await, 401);
+    }
+
+    #[tokio::test]
+    async fn signature_covers_the_query_string() {
+        let app = signed_app().await;
+        let body = vendor_body(None);
+        let now = chrono::Utc::now().timestamp();
+        let ts = now.to_string();
+        let post = |uri: &'static str, signature: String| {
+            let (app, body, ts) = (&app, body.clone(), ts.clone());
+            async move {
+                let headers = [("x-key-id", KEY_ID), ("x-timestamp", ts.as_str()), ("x-signature", signature.as_str())];
+                app.send_json(Method::POST, uri, Some(body), &headers).await.status
+            }
+        };
+
+        // パスだけの署名ではクエリを付け足したリクエストは通らない
+        let path_only = sign(&signing_key(), "POST", "/api/vendors", &body.to_string(), now);
+        assert_eq!(post("/api/vendors?replace=true", path_only).await, 401);
+
+        let with_query = sign(&signing_key(), "POST", "/api/vendors?replace=true", &body.to_string(), now);
+        assert_eq!(post("/api/vendors?replace=true", with_query).await, 200);
+    }
+
+    #[tokio::test]
+    async fn signed_route_rejects_stale_timestamp() {
+        let app = signed_app().await;
+        let body = vendor_body(None);
+        let stale = chrono::Utc::now().timestamp() - app.state.config.signature_max_skew_secs - 60;
+        let signature = sign(&signing_key(), "POST", "/api/vendors", &body.to_string(), stale);
+
+        assert_eq!(post_signed(&app, &body, stale, &signature).await, 400);
+    }
+
+    #[tokio::test]
+    async fn unsigned_routes_are_not_checked() {
+        let app = signed_app().await;
+        assert_eq!(app.get("/api/vendors").await.status, 200);
+        let res = app.post_json("/api/listings", json!({})).await;
+        assert_ne!(res.status, 401);
+    }
+
+    async fn cors_app() -> TestApp {
+        TestApp::with_config(|c| c.cors_allowed_origins = vec!["https://app.example".to_string()]).await
+    }
+
+    #[tokio::test]
+    async fn cors_allows_listed_origin() {
+        let app = cors_app().await;
+        let res = app.get_with("/api/vendors", &[("origin", "https://app.example")]).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("access-control-allow-origin"), Some("https://app.example"));
+    }
+
+    #[tokio::test]
+    async fn cors_omits_headers_for_unlisted_origin() {
+        let app = cors_app().await;
+        let res = app.get_with("/api/vendors", &[("origin", "https://evil.example")]).await;
+        assert!(res.header("access-control-allow-origin").is_none());
+
+        let res = app
+            .send_json(
+                Method::OPTIONS,
+                "/api/vendors",
+                None,
+                &[("origin", "https://evil.example"), ("access-control-request-method", "POST")],
+            )
+            .await;
+        assert!(res.header("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_allows_resumable_upload_patch() {
+        let app = cors_app().await;
+        let res = app
+            .send_json(
+                Method::OPTIONS,
+                "/api/uploads/some-upload",
+                None,
+                &[
+                    ("origin", "https://app.example"),
+                    ("access-control-request-method", "PATCH"),
+                    ("access-control-request-headers", "upload-offset,upload-length,content-type"),
+                ],
+            )
+            .await;
+        assert!(res.status.is_success());
+        assert_eq!(res.header("access-control-allow-origin"), Some("https://app.example"));
+        assert!(res.header("access-control-allow-methods").unwrap().contains("PATCH"));
+        let allowed = res.header("access-control-allow-headers").unwrap().to_ascii_lowercase();
+        assert!(allowed.contains("upload-offset") && allowed.contains("upload-length"), "{}", allowed);
+
+        // 実リクエストでは Upload-Offset をクライアントから読めるようにする
+        let res = app.get_with("/api/uploads/some-upload", &[("origin", "https://app.example")]).await;
+        assert_eq!(res.header("access-control-expose-headers"), Some("upload-offset"));
+    }
+
+    #[tokio::test]
+    async fn cors_is_permissive_without_allowlist() {
+        let app = TestApp::new().await;
+        let res = app.get_with("/api/vendors", &[("origin", "https://any.example")]).await;
+        assert_eq!(res.header("access-control-allow-origin"), Some("*"));
+    }
+
+    async fn small_limit_app() -> TestApp {
+        TestApp::with_config(|c| {
+            c.max_icon_bytes = 1024;
+            c.max_json_bytes = 512;
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn oversized_icon_is_rejected_before_writing() {
+        let app = small_limit_app().await;
+        let vendor = app.create_vendor(None).await;
+        let uri = format!("/api/vendors/{}/icon", vendor);
+        let icon_path = app.data_path(format!("account/vendors/{}/icon.png", vendor));
+
+        // Content-Length 申告で超過 → ボディを読まずに拒否
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &vec![0u8; 4096]);
+        let content_type = form.content_type();
+        let body = form.finish();
+        let req = TestApp::request(Method::POST, &uri)
+            .header("content-type", content_type)
+            .header("content-length", body.len().to_string())
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let res = app.send(req).await;
+        assert_eq!(res.status, 413);
+        assert!(res.json()["error"].as_str().unwrap().contains("1024 bytes"));
+
+        // Content-Length なし → 読み込み中に上限超過
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &vec![0u8; 4096]);
+        let res = app.send_form(Method::POST, &uri, form, &[]).await;
+        assert_eq!(res.status, 413, "{}", res.text());
+        assert_eq!(res.json()["success"], false);
+
+        assert!(!icon_path.exists(), "no icon file should be written");
+
+        // 上限内ならアップロードできる
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(4, 4));
+        assert_eq!(app.send_form(Method::POST, &uri, form, &[]).await.status, 200);
+        assert!(icon_path.exists());
+    }
+
+    #[tokio::test]
+    async fn oversized_json_body_returns_json_413() {
+        let app = small_limit_app().await;
+        let mut body = vendor_body(None);
+        body["profile"]["bio"] = json!("x".repeat(2048));
+        let res = app.post_json("/api/vendors", body).await;
+        assert_eq!(res.status, 413);
+        assert_eq!(res.json()["success"], false);
+    }
+
+    async fn rate_limited_app() -> TestApp {
+        TestApp::with_config(|c| {
+            c.rate_limit_burst = 3;
+            c.rate_limit_per_sec = 0.01;
+        })
+        .await
+    }
+
+    /// 接続元アドレス・X-Forwarded-For を指定して Claim
+    async fn claim_from(app: &TestApp, drop_id: &str, user_id: &str, remote: &str, forwarded_for: Option<&str>) -> u16 {
+        let mut builder = TestApp::request(Method::POST, &format!("/api/drops/{}/claim", drop_id))
+            .header("content-type", "application/json");
+        if let Some(xff) = forwarded_for {
+            builder = builder.header("x-forwarded-for", xff);
+        }
+        let mut req = builder
+            .body(axum::body::Body::from(json!({ "user_id": user_id }).to_string()))
+            .unwrap();
+        let addr: std::net::SocketAddr = remote.parse().unwrap();
+        req.extensions_mut().insert(axum::extract::ConnectInfo(addr));
+        app.send(req).await.status.as_u16()
+    }
+
+    #[tokio::test]
+    async fn rapid_claims_from_one_ip_are_throttled() {
+        let app = rate_limited_app().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 100).await;
+
+        for i in 0..3 {
+            assert_eq!(app.claim(&drop_id, &format!("user-{}", i)).await.status, 200);
+        }
+        let res = app.claim(&drop_id, "user-3").await;
+        assert_eq!(res.status, 429);
+        assert!(res.header("retry-after").unwrap().parse::<u64>().unwrap() >= 1);
+        assert_eq!(res.json()["success"], false);
+
+        // ダウンロードはルートごとに別のバケット
+        let res = app.get(&format!("/api/drops/{}/download?token=unknown", drop_id)).await;
+        assert_ne!(res.status, 429);
+
+        // 別 IP は制限されない
+        assert_eq!(claim_from(&app, &drop_id, "user-4", "198.51.100.7:5000", None).await, 200);
+    }
+
+    #[tokio::test]
+    async fn forwarded_for_is_only_trusted_from_proxies() {
+        let app = rate_limited_app().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 100).await;
+
+        // 信頼済みプロキシ（127.0.0.1）経由ならクライアントごとに数える
+        for i in 0..3 {
+            assert_eq!(claim_from(&app, &drop_id, &format!("a-{}", i), "127.0.0.1:9000", Some("203.0.113.1")).await, 200);
+        }
+        assert_eq!(claim_from(&app, &drop_id, "a-3", "127.0.0.1:9000", Some("203.0.113.1")).await, 429);
+        assert_eq!(claim_from(&app, &drop_id, "b-0", "127.0.0.1:9000", Some("203.0.113.2")).await, 200);
+
+        // 直接接続のクライアントが X-Forwarded-For を偽っても回避できない
+        for i in 0..3 {
+            let xff = format!("203.0.113.{}", 10 + i);
+            assert_eq!(claim_from(&app, &drop_id, &format!("c-{}", i), "192.0.2.50:1", Some(&xff)).await, 200);
+        }
+        assert_eq!(claim_from(&app, &drop_id, "c-3", "192.0.2.50:1", Some("203.0.113.99")).await, 429);
+    }
+
+    #[tokio::test]
+    async fn slow_handler_times_out_with_json_504() {
+        use tower::ServiceExt;
+
+        let app = TestApp::with_config(|c| c.request_timeout_secs = 1).await;
+        let slow = axum::Router::new()
+            .route(
+                "/api/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    "done"
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(app.state.clone(), super::enforce_timeout));
+
+        let started = std::time::Instant::now();
+        let req = TestApp::request(Method::GET, "/api/slow").body(axum::body::Body::empty()).unwrap();
+        let res = slow.oneshot(req).await.unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(res.status(), 504);
+
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("/api/slow"));
+    }
+
+    #[test]
+    fn uploads_and_downloads_get_longer_timeouts() {
+        let config = crate::config::AppConfig::default();
+        let secs = |route: &str| config.timeout_for(route).as_secs();
+        assert_eq!(secs("/api/vendors"), config.request_timeout_secs);
+        assert_eq!(secs("/api/drops"), config.upload_timeout_secs);
+        assert_eq!(secs("/api/vendors/:stable_id/icon"), config.upload_timeout_secs);
+        assert_eq!(secs("/api/drops/:drop_id/download"), config.download_timeout_secs);
+        assert!(config.upload_timeout_secs > config.request_timeout_secs);
+    }
+
+    #[tokio::test]
+    async fn trailing_slash_and_mixed_case_paths_reach_the_route() {
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+
+        assert_eq!(app.get("/api/vendors/").await.status, 200);
+        assert_eq!(app.get(&format!("/api/drops/{}/", drop_id)).await.status, 200);
+
+        let res = app.get("/api/Vendors?env=all").await;
+        assert_eq!(res.status, 308);
+        assert_eq!(res.header("location"), Some("/api/vendors?env=all"));
+
+        // ID は大文字を含んでも書き換えない
+        let res = app.get(&format!("/API/Drops/{}", drop_id)).await;
+        assert_eq!(res.status, 308);
+        let location = res.header("location").unwrap().to_string();
+        assert_eq!(location, format!("/api/drops/{}", drop_id));
+        assert_eq!(app.get(&location).await.status, 200);
+
+        let res = app.post_json("/api/Drops/availability", json!({ "drop_ids": [] })).await;
+        assert_eq!(res.status, 308);
+        assert_eq!(res.header("location"), Some("/api/drops/availability"));
+
+        let res = app.get("/api/nothing").await;
+        assert_eq!(res.status, 404);
+        assert_eq!(res.json()["success"], false);
+    }
+
+    /// build_app に登録する /api 配下のルート（api_routes）の固定セグメントが ROUTE_SEGMENTS にすべて含まれる
+    #[test]
+    fn route_segments_cover_every_registered_route() {
+        let routes: Vec<&str> = crate::api_routes()
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| path.starts_with("/api/"))
+            .collect();
+        assert!(!routes.is_empty());
+
+        let missing: Vec<String> = routes
+            .iter()
+            .flat_map(|path| path.split('/').map(move |segment| (path, segment)))
+            .filter(|(_, segment)| !segment.is_empty() && !segment.starts_with(':'))
+            .filter(|(_, segment)| !super::ROUTE_SEGMENTS.contains(segment))
+            .map(|(path, segment)| format!("{} ({})", segment, path))
+            .collect();
+        assert!(missing.is_empty(), "add to ROUTE_SEGMENTS: {:?}", missing);
+    }
+}
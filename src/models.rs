@@ -2,13 +2,30 @@
 //! Vendor, Listing, Receipt などのデータ構造定義
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// ========================================
+// Timestamp（ISO-8601 表記）
+// ========================================
+
+/// Unix ミリ秒 → ISO-8601（UTC、ミリ秒精度）
+pub fn ms_to_iso(ms: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+/// Unix 秒 → ISO-8601（UTC、秒精度）
+pub fn secs_to_iso(secs: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
 
 // ========================================
 // Vendor
 // ========================================
 
 /// Vendor (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Vendor {
     pub stable_id: String,
     pub peer_id: Option<String>,
@@ -27,22 +44,37 @@ pub struct Vendor {
     pub created_at_ms: Option<i64>,
     pub updated_at_ms: Option<i64>,
     pub is_alive: i32,
+    pub max_active_drops: Option<i64>,  // 開催中/予定Drop数の上限（NULL は MAX_ACTIVE_DROPS_PER_VENDOR）
+    pub max_drop_bytes: Option<i64>,  // Drop 音声の合計バイト数の上限（NULL は MAX_DROP_BYTES_PER_VENDOR）
 }
 
 /// Vendor Profile (manifest JSON の中身)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VendorProfile {
     pub name: String,
     pub description: Option<String>,
     pub icon_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_thumb_url: Option<String>,
     pub address: Option<String>,
     pub fee_rate: Option<f64>,
     #[serde(default)]
     pub extra: serde_json::Value,
 }
 
+/// Vendor Profile の過去バージョン (DB row)
+/// 各バージョンは profile.v<seq>.json として保存される
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct VendorProfileVersion {
+    pub stable_id: String,
+    pub profile_seq: i64,
+    pub sha256: String,
+    pub url: String,
+    pub created_at_ms: i64,
+}
+
 /// Vendor 作成リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateVendorRequest {
     pub stable_id: Option<String>,  // 指定しない場合は自動生成
     pub peer_id: String,
@@ -60,17 +92,18 @@ pub struct CreateVendorRequest {
 }
 
 /// Vendor 更新リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateVendorRequest {
     pub object_id: Option<String>,
     pub owner: Option<String>,
     pub profile: Option<VendorProfile>,
     pub status: Option<i32>,
     pub backend: Option<i32>,
+    pub shop_type: Option<i32>,  // 0=in_app, 1=external_web
 }
 
 /// Vendor レスポンス（API返却用）
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VendorResponse {
     pub stable_id: String,
     pub peer_id: Option<String>,
@@ -78,12 +111,17 @@ pub struct VendorResponse {
     pub owner: Option<String>,
     pub mode: i32,
     pub shop_type: i32,
+    /// shop_type の表示用ラベル（in_app / external_web）
+    pub shop_type_label: &'static str,
     pub backend: i32,    // 0=VPS, 1=Sui
     pub profile: Option<VendorProfile>,
     pub profile_seq: i64,
     pub status: i32,
     pub created_at_ms: Option<i64>,
     pub updated_at_ms: Option<i64>,
+    /// created_at_ms / updated_at_ms の ISO-8601 表記
+    pub created_at_iso: Option<String>,
+    pub updated_at_iso: Option<String>,
     pub is_alive: bool,
 }
 
@@ -92,7 +130,7 @@ pub struct VendorResponse {
 // ========================================
 
 /// Listing (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Listing {
     pub listing_id: String,
     pub vendor_stable_id: String,
@@ -117,10 +155,16 @@ pub struct Listing {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub cover_url: Option<String>,
+    // 売り切れ Webhook（通知済みなら送信時刻、在庫が戻るとクリア）
+    pub webhook_url: Option<String>,
+    pub sold_out_notified_at_ms: Option<i64>,
+    // ストアフロントの上位表示（featured_rank は featured の場合のみ値を持つ）
+    pub featured: i32,
+    pub featured_rank: Option<i64>,
 }
 
 /// Listing 作成リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateListingRequest {
     pub listing_id: String,
     pub vendor_stable_id: String,
@@ -141,36 +185,81 @@ pub struct CreateListingRequest {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub cover_url: Option<String>,
+    /// 売り切れ時に通知する URL
+    pub webhook_url: Option<String>,
+    /// 一覧の先頭に表示する（未指定時は false、既存 Listing の再作成では変更しない）
+    pub featured: Option<bool>,
+    /// featured 内の並び順（小さいほど先、未指定は順位付きの後ろ）
+    pub featured_rank: Option<i64>,
 }
 
 fn default_currency() -> String { "SUI".to_string() }
 fn default_supply() -> i64 { 1 }
 
+/// Listing 一括作成リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchCreateListingsRequest {
+    pub listings: Vec<CreateListingRequest>,
+    /// true の場合、1件でも失敗したら全件ロールバック
+    #[serde(default)]
+    pub fail_fast: bool,
+}
+
+/// Listing ステータス一括変更リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchListingStatusRequest {
+    pub listing_ids: Vec<String>,
+    /// 変更後のステータス（listing status）
+    pub status: i32,
+}
+
+/// Listing 一括作成・ステータス一括変更レスポンス（listing_id → 成否）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchListingResponse {
+    pub success: bool,
+    pub results: std::collections::HashMap<String, bool>,
+    #[serde(skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub errors: std::collections::HashMap<String, String>,
+}
+
 /// Listing 更新リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateListingRequest {
     pub seller: Option<String>,
     pub price: Option<i64>,
     pub supply_remaining: Option<i64>,
     pub status: Option<i32>,
+    /// 売り切れ時に通知する URL（空文字で解除）
+    pub webhook_url: Option<String>,
+    /// 一覧の先頭に表示する（false にすると featured_rank も解除）
+    pub featured: Option<bool>,
+    /// featured 内の並び順（小さいほど先）
+    pub featured_rank: Option<i64>,
 }
 
 /// Listing レスポンス（API返却用）
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ListingResponse {
     pub listing_id: String,
     pub vendor_stable_id: String,
     pub vendor_object_id: Option<String>,
     pub seller: Option<String>,
     pub item_type: i32,
+    /// item_type の表示用ラベル（nft / file_drop / edition）
+    pub item_type_label: &'static str,
     pub item_id: Option<String>,
     pub price: i64,
     pub currency: String,
     pub supply_total: i64,
     pub supply_remaining: i64,
     pub status: i32,
+    /// status の表示用ラベル（active / suspended / deleted / sold_out / cancelled）
+    pub status_label: &'static str,
     pub created_at_ms: Option<i64>,
     pub updated_at_ms: Option<i64>,
+    /// created_at_ms / updated_at_ms の ISO-8601 表記
+    pub created_at_iso: Option<String>,
+    pub updated_at_iso: Option<String>,
     pub is_alive: bool,
     // Sui オンチェーン参照
     pub inventory_id: Option<String>,
@@ -179,6 +268,62 @@ pub struct ListingResponse {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub cover_url: Option<String>,
+    pub featured: bool,
+    pub featured_rank: Option<i64>,
+}
+
+/// Listing 価格変更履歴 (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ListingPriceChange {
+    pub id: i64,
+    pub listing_id: String,
+    pub old_price: i64,
+    pub new_price: i64,
+    pub changed_at_ms: i64,
+}
+
+// ========================================
+// Reservation（購入手続き中の在庫確保）
+// ========================================
+
+/// Reservation ステータス
+pub mod reservation_status {
+    pub const HELD: i32 = 0;      // 確保中
+    pub const CONSUMED: i32 = 1;  // 購入完了（在庫は戻さない）
+    pub const RELEASED: i32 = 2;  // 期限切れで解放（在庫を戻す）
+}
+
+/// Reservation (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Reservation {
+    pub reservation_id: String,
+    pub listing_id: String,
+    pub buyer: Option<String>,
+    pub qty: i64,
+    pub status: i32,
+    pub created_at_ms: i64,
+    pub expires_at_ms: i64,
+    pub consumed_at_ms: Option<i64>,
+    pub released_at_ms: Option<i64>,
+}
+
+/// Reservation 作成リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReserveListingRequest {
+    pub buyer: Option<String>,
+    #[serde(default = "default_reserve_qty")]
+    pub qty: i64,
+    /// 確保期間（秒）。省略時・上限超過時はサーバ設定値
+    pub ttl_secs: Option<i64>,
+}
+
+fn default_reserve_qty() -> i64 { 1 }
+
+/// Reservation レスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReservationResponse {
+    pub success: bool,
+    pub reservation: Reservation,
 }
 
 // ========================================
@@ -186,7 +331,8 @@ pub struct ListingResponse {
 // ========================================
 
 /// Receipt (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[allow(dead_code)] // 未使用（互換のため定義を残す）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Receipt {
     pub receipt_id: String,
     pub vendor_stable_id: String,
@@ -202,7 +348,8 @@ pub struct Receipt {
 }
 
 /// Receipt 作成リクエスト
-#[derive(Debug, Deserialize)]
+#[allow(dead_code)] // 未使用（互換のため定義を残す）
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateReceiptRequest {
     pub receipt_id: String,
     pub vendor_stable_id: String,
@@ -217,6 +364,7 @@ pub struct CreateReceiptRequest {
     pub tx_digest: Option<String>,
 }
 
+#[allow(dead_code)] // 未使用（互換のため定義を残す）
 fn default_qty() -> i64 { 1 }
 
 // ========================================
@@ -229,22 +377,85 @@ pub mod status {
     pub const DELETED: i32 = 2;
     pub const SOLD_OUT: i32 = 3;
     pub const CANCELLED: i32 = 4;
+
+    /// ステータスコードの表示用ラベル（未知のコードは "unknown"）
+    pub fn label(code: i32) -> &'static str {
+        match code {
+            ACTIVE => "active",
+            SUSPENDED => "suspended",
+            DELETED => "deleted",
+            SOLD_OUT => "sold_out",
+            CANCELLED => "cancelled",
+            _ => "unknown",
+        }
+    }
 }
 
+#[allow(dead_code)] // DB の値と対応（未使用の値も残す）
 pub mod item_type {
     pub const NFT: i32 = 0;
     pub const FILE_DROP: i32 = 1;
     pub const EDITION: i32 = 2;
+
+    /// item_type コードの表示用ラベル（未知のコードは "unknown"）
+    pub fn label(code: i32) -> &'static str {
+        match code {
+            NFT => "nft",
+            FILE_DROP => "file_drop",
+            EDITION => "edition",
+            _ => "unknown",
+        }
+    }
 }
 
+#[allow(dead_code)] // DB の値と対応（未使用の値も残す）
 pub mod mode {
     pub const TEST_VENDOR: i32 = 0;
     pub const PROD_VENDOR: i32 = 1;
 }
 
+#[allow(dead_code)] // DB の値と対応（未使用の値も残す）
 pub mod shop_type {
     pub const IN_APP: i32 = 0;
     pub const EXTERNAL_WEB: i32 = 1;
+
+    /// shop_type コードの表示用ラベル（未知のコードは "unknown"）
+    pub fn label(code: i32) -> &'static str {
+        match code {
+            IN_APP => "in_app",
+            EXTERNAL_WEB => "external_web",
+            _ => "unknown",
+        }
+    }
+
+    /// 既知のコードか
+    pub fn is_valid(code: i32) -> bool {
+        matches!(code, IN_APP | EXTERNAL_WEB)
+    }
+}
+
+// ========================================
+// Tombstone（死亡オブジェクト記録）
+// ========================================
+
+pub mod tombstone_kind {
+    pub const VENDOR: &str = "vendor";
+    pub const LISTING: &str = "listing";
+    pub const DROP: &str = "drop";
+    pub const ARTIST: &str = "artist";
+}
+
+/// Tombstone (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Tombstone {
+    pub id: i64,
+    pub kind: String,
+    pub stable_id: Option<String>,
+    pub object_id: String,
+    pub env: String,
+    pub run_id: Option<String>,
+    pub observed_dead_at_ms: i64,
+    pub note: Option<String>,
 }
 
 // ========================================
@@ -252,7 +463,7 @@ pub mod shop_type {
 // ========================================
 
 /// Artist (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Artist {
     pub stable_id: String,
     pub peer_id: String,
@@ -273,13 +484,15 @@ pub struct Artist {
 }
 
 /// Artist Profile (profile.json の中身)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ArtistProfile {
     pub version: String,
     pub stable_id: String,
     pub name: String,
     pub bio: Option<String>,
     pub icon_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_thumb_url: Option<String>,
     #[serde(default)]
     pub links: Vec<serde_json::Value>,
     pub p2p: Option<ArtistP2P>,
@@ -287,14 +500,14 @@ pub struct ArtistProfile {
 }
 
 /// Artist P2P info
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ArtistP2P {
     pub peer_id: String,
     pub peer_id_sha256: Option<String>,
 }
 
 /// Artist 作成リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateArtistRequest {
     pub peer_id: String,
     pub name: String,
@@ -307,7 +520,7 @@ pub struct CreateArtistRequest {
 fn default_env() -> String { "devnet".to_string() }
 
 /// Artist 更新リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateArtistRequest {
     pub object_id: Option<String>,
     pub owner: Option<String>,
@@ -317,7 +530,7 @@ pub struct UpdateArtistRequest {
 }
 
 /// Artist レスポンス（API返却用）
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArtistResponse {
     pub stable_id: String,
     pub peer_id: String,
@@ -332,19 +545,23 @@ pub struct ArtistResponse {
     pub status: i32,
     pub created_at_ms: Option<i64>,
     pub updated_at_ms: Option<i64>,
+    /// created_at_ms / updated_at_ms の ISO-8601 表記
+    pub created_at_iso: Option<String>,
+    pub updated_at_iso: Option<String>,
     pub is_alive: bool,
 }
 
 /// Artist 作成レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArtistCreateResponse {
     pub success: bool,
     pub stable_id: String,
     pub peer_id: String,
-    pub profile_url: String,
-    pub profile_sha256: String,
-    pub discography_url: String,
-    pub discography_sha256: String,
+    /// 未設定の項目は空文字ではなく null
+    pub profile_url: Option<String>,
+    pub profile_sha256: Option<String>,
+    pub discography_url: Option<String>,
+    pub discography_sha256: Option<String>,
     pub icon_url: Option<String>,
     pub updated_at_ms: i64,
 }
@@ -354,7 +571,7 @@ pub struct ArtistCreateResponse {
 // ========================================
 
 /// Discography Entry (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct DiscographyEntry {
     pub id: i64,
     pub artist_stable_id: String,
@@ -370,14 +587,14 @@ pub struct DiscographyEntry {
 }
 
 /// Track Preview (discography.json 内の track_preview)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TrackPreview {
     pub i: i32,
     pub title: String,
 }
 
 /// Discography JSON (discography.json の中身)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DiscographyJson {
     pub version: String,
     pub artist_stable_id: String,
@@ -386,7 +603,7 @@ pub struct DiscographyJson {
 }
 
 /// Discography Album Entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DiscographyAlbum {
     pub album_id: String,
     pub edition_id: Option<String>,
@@ -399,7 +616,7 @@ pub struct DiscographyAlbum {
 }
 
 /// Discography 追加リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddDiscographyRequest {
     pub album_id: String,
     pub edition_id: Option<String>,
@@ -429,7 +646,7 @@ pub mod drop_status {
 }
 
 /// Drop (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Drop {
     pub drop_id: String,
     pub vendor_stable_id: String,
@@ -453,10 +670,21 @@ pub struct Drop {
     pub updated_at: i64,    // Unix秒
     pub ended_at: Option<i64>,   // Unix秒
     pub purged_at: Option<i64>,  // Unix秒
+    pub idempotency_key: Option<String>,  // 再送時の重複作成防止
+    pub max_downloads_per_claim: Option<i64>,  // Claimごとのダウンロード上限（NULL は無制限）
+    pub preview_object_key: Option<String>,    // 30秒プレビュー（生成できなかった場合は NULL）
+    pub cover_webp_object_key: Option<String>, // カバーの WebP 派生（生成できなかった場合は NULL）
+    pub cover_blurhash: Option<String>,        // カバーのプレースホルダ（生成できなかった場合は NULL）
+    pub audio_duration_ms: Option<i64>,        // 再生時間（解析できなかった場合は NULL、以下同様）
+    pub audio_bitrate_kbps: Option<i64>,
+    pub audio_sample_rate_hz: Option<i64>,
+    #[serde(skip_serializing)]
+    pub claim_passcode_hash: Option<String>,   // Claim 用パスコードの SHA256（NULL は誰でも Claim 可）
 }
 
 /// Drop 作成リクエスト
-#[derive(Debug, Deserialize)]
+#[allow(dead_code)] // 未使用（互換のため定義を残す）
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDropRequest {
     pub vendor_stable_id: String,
     pub artist_stable_id: Option<String>,
@@ -466,12 +694,26 @@ pub struct CreateDropRequest {
     pub start_at: Option<i64>,  // 省略時は現在時刻
     pub end_at: i64,            // 必須
     pub max_claims: i64,        // 必須
+    pub max_downloads_per_claim: Option<i64>,  // 省略時は無制限
+    pub claim_passcode: Option<String>,        // 指定時は Claim にパスコードが必要
     #[serde(default = "default_env")]
     pub env: String,
 }
 
+/// Drop カバーの派生一覧
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CoverSet {
+    /// アップロードされたオリジナル
+    pub original: String,
+    pub thumb: Option<String>,
+    /// 最大幅 1024px の WebP
+    pub webp: Option<String>,
+    /// 読み込み中のプレースホルダ用 blurhash
+    pub blurhash: Option<String>,
+}
+
 /// Drop レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DropResponse {
     pub drop_id: String,
     pub vendor_stable_id: String,
@@ -481,18 +723,35 @@ pub struct DropResponse {
     pub description: Option<String>,
     pub cover_url: Option<String>,
     pub cover_thumb_url: Option<String>,
+    /// カバーの派生一覧（カバー未設定の場合は null）
+    pub covers: Option<CoverSet>,
     pub audio_mime: String,
     pub audio_size_bytes: i64,
     pub audio_sha256: String,
+    /// 再生時間・ビットレート・サンプルレート（音声を解析できなかった場合は null）
+    pub audio_duration_ms: Option<i64>,
+    pub audio_bitrate_kbps: Option<i64>,
+    pub audio_sample_rate_hz: Option<i64>,
     pub start_at: i64,
     pub end_at: i64,
     pub max_claims: i64,
     pub claimed_count: i64,
     pub remaining_claims: i64,
+    pub max_downloads_per_claim: Option<i64>,
+    /// GET /api/drops/:drop_id/preview で試聴クリップを取得できるか
+    pub has_preview: bool,
+    /// Claim にパスコードが必要か（パスコード自体は返さない）
+    pub passcode_required: bool,
     pub status: i32,
     pub created_at: i64,
     pub updated_at: i64,
     pub ended_at: Option<i64>,
+    /// 各 Unix 秒フィールドの ISO-8601 表記
+    pub start_at_iso: Option<String>,
+    pub end_at_iso: Option<String>,
+    pub created_at_iso: Option<String>,
+    pub updated_at_iso: Option<String>,
+    pub ended_at_iso: Option<String>,
 }
 
 impl DropResponse {
@@ -512,6 +771,15 @@ impl DropResponse {
                 format!("{}/drops/{}_thumb", base_url, key)
             }
         });
+        // 派生は生成できたものだけ（webp / blurhash は NULL になり得る）
+        let covers = cover_url.as_ref().map(|original| CoverSet {
+            original: original.clone(),
+            thumb: cover_thumb_url.clone(),
+            webp: drop.cover_webp_object_key.as_ref().map(|key| {
+                format!("{}/drops/{}", base_url, key)
+            }),
+            blurhash: drop.cover_blurhash.clone(),
+        });
         Self {
             drop_id: drop.drop_id.clone(),
             vendor_stable_id: drop.vendor_stable_id.clone(),
@@ -521,41 +789,61 @@ impl DropResponse {
             description: drop.description.clone(),
             cover_url,
             cover_thumb_url,
+            covers,
             audio_mime: drop.audio_mime.clone(),
             audio_size_bytes: drop.audio_size_bytes,
             audio_sha256: drop.audio_sha256.clone(),
+            audio_duration_ms: drop.audio_duration_ms,
+            audio_bitrate_kbps: drop.audio_bitrate_kbps,
+            audio_sample_rate_hz: drop.audio_sample_rate_hz,
             start_at: drop.start_at,
             end_at: drop.end_at,
             max_claims: drop.max_claims,
             claimed_count: drop.claimed_count,
             remaining_claims: drop.max_claims - drop.claimed_count,
+            max_downloads_per_claim: drop.max_downloads_per_claim,
+            has_preview: drop.preview_object_key.is_some(),
+            passcode_required: drop.claim_passcode_hash.is_some(),
             status: drop.status,
             created_at: drop.created_at,
             updated_at: drop.updated_at,
             ended_at: drop.ended_at,
+            start_at_iso: secs_to_iso(drop.start_at),
+            end_at_iso: secs_to_iso(drop.end_at),
+            created_at_iso: secs_to_iso(drop.created_at),
+            updated_at_iso: secs_to_iso(drop.updated_at),
+            ended_at_iso: drop.ended_at.and_then(secs_to_iso),
         }
     }
 }
 
 /// Drop Claim (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct DropClaim {
     pub claim_id: String,
     pub drop_id: String,
     pub user_id: String,
     pub device_id_hash: Option<String>,
     pub claimed_at: i64,    // Unix秒
+    pub tx_digest: Option<String>,  // 有料/ゲート付きDropの支払いトランザクション
+    pub download_count: i64,
+    pub revoked_at: Option<i64>,    // Unix秒（失効済みの Claim はダウンロード不可、枠は消費したまま）
 }
 
 /// Drop Claim リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ClaimDropRequest {
     pub user_id: String,
     pub device_id_hash: Option<String>,
+    pub tx_digest: Option<String>,
+    /// 指定時は SMTP 設定があればダウンロードリンクをメールでも送る
+    pub email: Option<String>,
+    /// パスコード付き Drop の場合は必須（不一致は 403）
+    pub passcode: Option<String>,
 }
 
 /// Drop Claim レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ClaimDropResponse {
     pub success: bool,
     pub claim_id: String,
@@ -564,16 +852,58 @@ pub struct ClaimDropResponse {
     pub expires_at: i64,
     pub audio_sha256: String,
     pub audio_size_bytes: i64,
+    pub tx_digest: Option<String>,
+    /// ダウンロードリンクのメール送信をキューに入れたか（送信結果は含まない）
+    pub email_queued: bool,
+}
+
+/// Claim レシート（署名対象、フィールド順がそのまま署名ペイロードになる）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClaimReceipt {
+    pub receipt_version: u32,
+    pub claim_id: String,
+    pub drop_id: String,
+    pub vendor_stable_id: String,
+    pub artist_name: String,
+    pub title: String,
+    pub user_id: String,
+    pub claimed_at: i64,    // Unix秒
+    pub audio_sha256: String,
+    pub audio_size_bytes: i64,
+    pub expires_at: i64,    // Drop の end_at（Unix秒）
+    pub tx_digest: Option<String>,
+    pub issued_at: i64,     // Unix秒
+}
+
+/// Claim レシートレスポンス
+/// signature は payload（UTF-8 バイト列）に対する Ed25519 署名
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClaimReceiptResponse {
+    pub success: bool,
+    pub receipt: ClaimReceipt,
+    /// 署名対象の JSON 文字列（receipt をシリアライズしたもの）
+    pub payload: String,
+    /// base64
+    pub signature: String,
+    /// base64（GET /api/receipts/public-key と同じ値）
+    pub public_key: String,
+}
+
+/// Drop 延長リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExtendDropRequest {
+    /// 新しい終了時刻（Unix秒、現在の end_at より後かつ未来）
+    pub end_at: i64,
 }
 
 /// Batch 終了/削除リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct BatchDropRequest {
     pub drop_ids: Vec<String>,
 }
 
 /// Batch レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BatchDropResponse {
     pub success: bool,
     pub results: std::collections::HashMap<String, bool>,
@@ -584,7 +914,7 @@ pub struct BatchDropResponse {
 // ========================================
 
 /// Device (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Device {
     pub device_id: String,
     pub peer_id: String,
@@ -597,7 +927,7 @@ pub struct Device {
 }
 
 /// デバイス登録リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RegisterDeviceRequest {
     pub peer_id: String,
     pub device_id: String,
@@ -607,7 +937,7 @@ pub struct RegisterDeviceRequest {
 }
 
 /// デバイスレスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeviceResponse {
     pub device_id: String,
     pub peer_id: String,
@@ -616,10 +946,12 @@ pub struct DeviceResponse {
     pub platform: String,
     pub registered_at_ms: i64,
     pub last_seen_at_ms: i64,
+    pub registered_at_iso: Option<String>,
+    pub last_seen_at_iso: Option<String>,
 }
 
 /// デバイス一覧レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeviceListResponse {
     pub success: bool,
     pub devices: Vec<DeviceResponse>,
@@ -628,7 +960,7 @@ pub struct DeviceListResponse {
 }
 
 /// デバイス登録レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RegisterDeviceResponse {
     pub success: bool,
     pub device: DeviceResponse,
@@ -641,14 +973,14 @@ pub struct RegisterDeviceResponse {
 // ========================================
 
 /// Challenge レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeviceChallengeResponse {
     pub challenge: String,
     pub expires_at_ms: i64,
 }
 
 /// 署名検証リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DeviceVerifyRequest {
     pub peer_id: String,
     pub challenge: String,
@@ -657,7 +989,7 @@ pub struct DeviceVerifyRequest {
 }
 
 /// 検証成功レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeviceVerifyResponse {
     pub ok: bool,
     pub token: String,
@@ -670,7 +1002,8 @@ pub struct DeviceVerifyResponse {
 // ========================================
 
 /// Peer Profile (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[allow(dead_code)] // 未使用（互換のため定義を残す）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct PeerProfile {
     pub peer_id: String,
     pub display_name: Option<String>,
@@ -680,7 +1013,7 @@ pub struct PeerProfile {
 }
 
 /// Peer Profile 登録/更新リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpsertPeerProfileRequest {
     pub peer_id: String,
     pub display_name: Option<String>,
@@ -689,13 +1022,13 @@ pub struct UpsertPeerProfileRequest {
 }
 
 /// Follower/Subscriber 登録リクエスト（peer_id のみ）
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddFollowerRequest {
     pub peer_id: String,
 }
 
 /// Follower/Subscriber レスポンス（peer_id 非公開、JOIN 結果）
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FollowerResponse {
     pub display_name: Option<String>,
     pub pfp_url: Option<String>,
@@ -703,21 +1036,21 @@ pub struct FollowerResponse {
 }
 
 /// Follower 一覧レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FollowerListResponse {
     pub success: bool,
     pub followers: Vec<FollowerResponse>,
 }
 
 /// Subscriber 一覧レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SubscriberListResponse {
     pub success: bool,
     pub subscribers: Vec<FollowerResponse>,
 }
 
 /// カウントレスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CountResponse {
     pub success: bool,
     pub count: i64,
@@ -736,7 +1069,7 @@ pub mod transfer_status {
 }
 
 /// Transfer (DB row)
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Transfer {
     pub transfer_id: String,
     pub sender_peer_id: String,
@@ -766,7 +1099,7 @@ pub struct Transfer {
 }
 
 /// Transfer 作成リクエスト（ファイルアップロード時のメタデータ）
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTransferRequest {
     pub sender_peer_id: String,
     pub recipient_peer_id: String,
@@ -781,7 +1114,7 @@ pub struct CreateTransferRequest {
 }
 
 /// Transfer レスポンス
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TransferResponse {
     pub transfer_id: String,
     pub sender_peer_id: String,
@@ -802,7 +1135,96 @@ pub struct TransferResponse {
 }
 
 /// Transfer ステータス更新リクエスト
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateTransferStatusRequest {
     pub peer_id: String,  // 操作者のpeer_id（権限チェック用）
 }
+
+// ========================================
+// Upload Session（再開可能アップロード）
+// ========================================
+
+/// Upload Session ステータス
+pub mod upload_status {
+    pub const UPLOADING: i32 = 0;  // 受信中
+    pub const COMPLETED: i32 = 1;  // 全バイト受信 + SHA256検証済み
+    pub const CONSUMED: i32 = 2;   // Drop作成に使用済み
+    pub const EXPIRED: i32 = 3;    // 期限切れ
+}
+
+/// Upload Session (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct UploadSession {
+    pub upload_id: String,
+    pub total_size: i64,
+    pub offset_bytes: i64,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub sha256: Option<String>,
+    pub status: i32,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+/// Upload Session 作成リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUploadRequest {
+    pub total_size: i64,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+/// Upload 完了リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FinalizeUploadRequest {
+    /// ファイル全体の SHA256（hex）
+    pub sha256: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    fn parse_iso(value: &serde_json::Value) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap()).unwrap().to_utc()
+    }
+
+    #[test]
+    fn iso_helpers_round_trip() {
+        let ms = 1_700_000_000_123;
+        let iso = ms_to_iso(ms).unwrap();
+        assert_eq!(iso, "2023-11-14T22:13:20.123Z");
+        assert_eq!(parse_iso(&serde_json::json!(iso)).timestamp_millis(), ms);
+
+        assert_eq!(secs_to_iso(1_700_000_000).as_deref(), Some("2023-11-14T22:13:20Z"));
+        assert!(ms_to_iso(i64::MAX).is_none());
+    }
+
+    #[tokio::test]
+    async fn responses_carry_iso_fields_matching_raw_timestamps() {
+        let app = TestApp::new().await;
+        let vendor_id = app.create_vendor(None).await;
+        let artist_id = app.create_artist(None).await;
+        app.create_listing(&vendor_id, "L-iso", 100, 1).await;
+        let drop_id = app.create_drop(&vendor_id, 5).await;
+
+        let vendor = &app.get(&format!("/api/vendors/{}", vendor_id)).await.json()["vendor"];
+        let artist = &app.get(&format!("/api/account/artists/{}", artist_id)).await.json()["artist"];
+        let listing = &app.get("/api/listings/L-iso").await.json()["listing"];
+        for ms_model in [vendor, artist, listing] {
+            for field in ["created_at", "updated_at"] {
+                let raw = ms_model[format!("{}_ms", field)].as_i64().unwrap();
+                assert_eq!(parse_iso(&ms_model[format!("{}_iso", field)]).timestamp_millis(), raw);
+            }
+        }
+
+        let drop = &app.get(&format!("/api/drops/{}", drop_id)).await.json()["drop"];
+        for field in ["start_at", "end_at", "created_at", "updated_at"] {
+            let raw = drop[field].as_i64().unwrap();
+            assert_eq!(parse_iso(&drop[format!("{}_iso", field)]).timestamp(), raw, "{}", field);
+        }
+        assert!(drop["ended_at_iso"].is_null());
+    }
+}
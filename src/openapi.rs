@@ -0,0 +1,154 @@
+//! OpenAPI Document
+//! /api/openapi.json - Vendors / Listings / Artists / Drops / Uploads API の OpenAPI 3 ドキュメント
+//! スキーマは models・各ハンドラの型から derive しているため、型を変更すれば自動で追従する
+
+use axum::response::Json;
+use utoipa::OpenApi;
+
+use crate::handlers;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "NFT Upload Server API"),
+    paths(
+        handlers::vendors::list_vendors,
+        handlers::vendors::get_vendor,
+        handlers::vendors::get_vendor_by_peer,
+        handlers::vendors::get_vendors_by_owner,
+        handlers::vendors::create_vendor,
+        handlers::vendors::update_vendor,
+        handlers::vendors::delist_vendor,
+        handlers::vendors::regenerate_vendor_profile,
+        handlers::vendors::get_profile_history,
+        handlers::vendors::upload_vendor_icon,
+        handlers::vendors::add_subscriber,
+        handlers::vendors::remove_subscriber,
+        handlers::vendors::list_subscribers,
+        handlers::vendors::get_subscriber_count,
+        handlers::listings::list_listings,
+        handlers::listings::stream_listings,
+        handlers::listings::get_listing,
+        handlers::listings::head_listing,
+        handlers::listings::create_listing,
+        handlers::listings::update_listing,
+        handlers::listings::get_price_history,
+        handlers::listings::delete_listing,
+        handlers::listings::get_vendor_listings_summary,
+        handlers::listings::batch_create_listings,
+        handlers::listings::batch_update_listing_status,
+        handlers::listings::reserve_listing,
+        handlers::listings::consume_reservation,
+        handlers::artists::list_artists,
+        handlers::artists::get_artist,
+        handlers::artists::get_artist_by_peer,
+        handlers::artists::create_artist,
+        handlers::artists::update_artist,
+        handlers::artists::delist_artist,
+        handlers::artists::regenerate_artist,
+        handlers::artists::upload_artist_icon,
+        handlers::artists::add_discography,
+        handlers::artists::remove_discography,
+        handlers::artists::get_discography,
+        handlers::artists::add_follower,
+        handlers::artists::remove_follower,
+        handlers::artists::list_followers,
+        handlers::artists::get_follower_count,
+        handlers::drops::list_drops,
+        handlers::drops::list_drop_feed,
+        handlers::drops::get_drop,
+        handlers::drops::head_drop,
+        handlers::drops::create_drop,
+        handlers::drops::replace_drop_audio,
+        handlers::drops::extend_drop,
+        handlers::drops::get_drop_preview,
+        handlers::drops::claim_drop,
+        handlers::drops::get_drop_claimable,
+        handlers::drops::get_drops_availability,
+        handlers::drops::list_drop_claims,
+        handlers::drops::get_claim_status,
+        handlers::drops::revoke_claim,
+        handlers::drops::get_claim_receipt,
+        handlers::drops::get_receipt_public_key,
+        handlers::drops::download_drop,
+        handlers::drops::get_drop_stats,
+        handlers::drops::batch_end_drops,
+        handlers::drops::batch_purge_drops,
+        handlers::uploads::create_upload,
+        handlers::uploads::get_upload,
+        handlers::uploads::patch_upload,
+        handlers::uploads::finalize_upload,
+    ),
+    tags(
+        (name = "vendors", description = "Vendor（ショップ）"),
+        (name = "listings", description = "Listing（出品）と在庫確保"),
+        (name = "artists", description = "Artist アカウントとディスコグラフィ"),
+        (name = "drops", description = "期限付きファイル配信（Drop）"),
+        (name = "uploads", description = "再開可能な大容量アップロード"),
+    )
+)]
+pub struct ApiDoc;
+
+/// GET /api/openapi.json - OpenAPI ドキュメント
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::test_support::TestApp;
+
+    /// ドキュメント内の $ref をすべて集める
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, v) in map {
+                    match (key.as_str(), v) {
+                        ("$ref", Value::String(r)) => refs.push(r.clone()),
+                        _ => collect_refs(v, refs),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn openapi_document_lists_known_paths() {
+        let app = TestApp::new().await;
+        let res = app.get("/api/openapi.json").await;
+        assert_eq!(res.status, 200);
+        let doc: Value = serde_json::from_slice(&res.body).unwrap();
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+
+        let paths = doc["paths"].as_object().unwrap();
+        for (path, method) in [
+            ("/api/vendors", "post"),
+            ("/api/vendors/{stable_id}", "get"),
+            ("/api/listings", "get"),
+            ("/api/listings/{listing_id}", "put"),
+            ("/api/account/artists", "post"),
+            ("/api/account/artists/{stable_id}/discography", "get"),
+            ("/api/drops", "post"),
+            ("/api/drops/{drop_id}/claim", "post"),
+            ("/api/drops/{drop_id}/download", "get"),
+            ("/api/uploads", "post"),
+            ("/api/uploads/{upload_id}", "patch"),
+            ("/api/uploads/{upload_id}/finalize", "post"),
+        ] {
+            assert!(paths.get(path).and_then(|p| p.get(method)).is_some(), "{} {}", method, path);
+        }
+
+        // 参照しているスキーマがすべて components に定義されている
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        let mut refs = Vec::new();
+        collect_refs(&doc, &mut refs);
+        assert!(!refs.is_empty());
+        for r in refs {
+            let name = r.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "unresolved $ref: {}", r);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Log Redaction
+//! ログ出力前に個人情報・秘密値（メールアドレス・デバイス ID・トークン等）をマスクする
+//!
+//! `key=値` / `key: 値` / `"key":"値"` 形式の値（key は LOG_REDACT_FIELDS）と、
+//! 文中のメールアドレスを伏せ字にする。ログの整形後（tracing の writer）で適用するため、
+//! 各ハンドラの info! / warn! や ApiError のログにもそのまま効く。
+
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// マスク対象のフィールド名（None = 無効）。設定読み込み前は未初期化（マスクしない）
+static RULES: OnceLock<Option<Vec<String>>> = OnceLock::new();
+
+/// LOG_REDACT_FIELDS 未設定時のマスク対象
+/// claim_id はダウンロードの bearer トークンを兼ねるため含める
+pub const DEFAULT_FIELDS: &[&str] = &[
+    "email", "device_id", "device_id_hash", "token", "passcode", "claim_passcode", "claim_id", "secret",
+];
+
+/// マスク規則を設定（起動時に1回、設定の読み込み後）
+pub fn init(enabled: bool, fields: &[String]) {
+    let rules = enabled.then(|| fields.iter().map(|f| f.to_ascii_lowercase()).collect());
+    let _ = RULES.set(rules);
+}
+
+fn fields() -> Option<&'static [String]> {
+    RULES.get().and_then(|rules| rules.as_deref())
+}
+
+/// ログに出す単独の値（キーなしで出す device_id 等）をマスク（無効時はそのまま）
+pub fn value(value: &str) -> String {
+    match fields() {
+        Some(_) => mask(value),
+        None => value.to_string(),
+    }
+}
+
+/// ログ1行分の文字列を fields でマスク（None はそのまま）
+fn redact_with(message: &str, fields: Option<&[String]>) -> String {
+    match fields {
+        Some(fields) => mask_fields(&mask_emails(message), fields),
+        None => message.to_string(),
+    }
+}
+
+/// 伏せ字（長い値のみ先頭 4 文字を残して突き合わせに使えるようにする）
+fn mask(value: &str) -> String {
+    if value.contains('@') {
+        return mask_email(value);
+    }
+    if value.chars().count() >= 16 {
+        format!("{}***", value.chars().take(4).collect::<String>())
+    } else {
+        "***".to_string()
+    }
+}
+
+/// メールアドレスの伏せ字（先頭 1 文字とドメインのみ残す）
+fn mask_email(email: &str) -> String {
+    let (local, domain) = email.rsplit_once('@').unwrap_or((email, ""));
+    let first: String = local.chars().take(1).collect();
+    format!("{}***@{}", first, domain)
+}
+
+fn is_email_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')
+}
+
+fn is_email_domain_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-')
+}
+
+/// 文中のメールアドレスをマスク
+fn mask_emails(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut last = 0;
+    for (at, _) in message.match_indices('@') {
+        if at < last {
+            continue;
+        }
+        let start = message[last..at]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_email_local_char(*c))
+            .map_or(last, |(i, c)| last + i + c.len_utf8());
+        let domain_end = message[at + 1..]
+            .find(|c: char| !is_email_domain_char(c))
+            .map_or(message.len(), |i| at + 1 + i);
+        let domain = message[at + 1..domain_end].trim_end_matches('.');
+        if start == at || domain.starts_with('.') || !domain.contains('.') {
+            continue;
+        }
+        let end = at + 1 + domain.len();
+        out.push_str(&message[last..start]);
+        out.push_str(&mask_email(&message[start..end]));
+        last = end;
+    }
+    out.push_str(&message[last..]);
+    out
+}
+
+/// 設定されたフィールドの値をマスク
+fn mask_fields(message: &str, fields: &[String]) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut i = 0;
+    while let Some(c) = message[i..].chars().next() {
+        let at_word_start = message[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|prev| !(prev.is_alphanumeric() || prev == '_'));
+        if at_word_start {
+            if let Some((start, end)) = fields.iter().find_map(|f| field_value(message, i, f)) {
+                out.push_str(&message[i..start]);
+                out.push_str(&mask(&message[start..end]));
+                i = end;
+                continue;
+            }
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// `field=値` / `field: 値` / `"field":"値"` の値の範囲（バイト位置）
+fn field_value(message: &str, start: usize, field: &str) -> Option<(usize, usize)> {
+    let key = message.get(start..start + field.len())?;
+    if !key.eq_ignore_ascii_case(field) {
+        return None;
+    }
+    let bytes = message.as_bytes();
+    let mut j = start + field.len();
+    if bytes.get(j) == Some(&b'"') {
+        j += 1;
+    }
+    if !matches!(bytes.get(j), Some(b'=') | Some(b':')) {
+        return None;
+    }
+    j += 1;
+    while bytes.get(j) == Some(&b' ') {
+        j += 1;
+    }
+    if bytes.get(j) == Some(&b'"') {
+        j += 1;
+    }
+    let end = message[j..]
+        .find(|c: char| c.is_whitespace() || matches!(c, ',' | '&' | '"' | '\'' | ')' | ']' | '}' | ';'))
+        .map_or(message.len(), |k| j + k);
+    (end > j).then_some((j, end))
+}
+
+/// 標準出力にマスク済みのログを書き出す writer（tracing_subscriber の with_writer 用）
+/// 規則は書き込みごとに init の設定を参照する（ロガーは設定の読み込み前に初期化されるため）
+pub struct RedactingStdout;
+
+impl<'a> MakeWriter<'a> for RedactingStdout {
+    type Writer = RedactingWriter<'static, io::Stdout>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter::new(io::stdout(), fields())
+    }
+}
+
+/// inner に fields でマスクしたログを書き出す（None はそのまま）
+/// fmt レイヤは1イベント分を整形してから write するため、write 単位でマスクする
+pub struct RedactingWriter<'f, W> {
+    inner: W,
+    fields: Option<&'f [String]>,
+}
+
+impl<'f, W: Write> RedactingWriter<'f, W> {
+    pub fn new(inner: W, fields: Option<&'f [String]>) -> Self {
+        Self { inner, fields }
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact_with(&text, self.fields).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_fields() -> Vec<String> {
+        DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn emails_anywhere_in_the_line_are_masked() {
+        assert_eq!(mask_emails("Invalid email: alice@example.com"), "Invalid email: a***@example.com");
+        assert_eq!(
+            mask_emails("to=<Bob.Smith+tag@mail.example.org>, cc bob@b.io."),
+            "to=<B***@mail.example.org>, cc b***@b.io."
+        );
+        // ドメインに "." が無いもの・ローカル部が無いものはメールアドレスとみなさない
+        assert_eq!(mask_emails("user@localhost and @handle"), "user@localhost and @handle");
+    }
+
+    #[test]
+    fn configured_fields_are_masked_in_each_syntax() {
+        let fields = default_fields();
+        assert_eq!(mask_fields("claim ok token=abc123", &fields), "claim ok token=***");
+        assert_eq!(
+            mask_fields(r#"body {"device_id":"0123456789abcdef0123"} done"#, &fields),
+            r#"body {"device_id":"0123***"} done"#
+        );
+        assert_eq!(mask_fields("Passcode: hunter2, user_id=u1", &fields), "Passcode: ***, user_id=u1");
+        // 単語の途中（access_token 等）は対象外
+        assert_eq!(mask_fields("access_token=abc", &fields), "access_token=abc");
+        assert_eq!(mask_fields("tokens are fine", &fields), "tokens are fine");
+    }
+
+    #[test]
+    fn redacting_writer_masks_each_write_only_when_enabled() {
+        let fields = default_fields();
+        let line = "Drop claimed: claim_id=0123456789abcdef0123 email=alice@example.com user_id=u1\n";
+
+        let mut out = Vec::new();
+        let mut writer = RedactingWriter::new(&mut out, Some(fields.as_slice()));
+        assert_eq!(writer.write(line.as_bytes()).unwrap(), line.len());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Drop claimed: claim_id=0123*** email=a***@example.com user_id=u1\n"
+        );
+
+        let mut out = Vec::new();
+        RedactingWriter::new(&mut out, None).write_all(line.as_bytes()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), line);
+    }
+
+    /// テスト用のログ溜め先（RedactingWriter でマスクしてから溜める）
+    #[derive(Clone, Default)]
+    struct CapturedLogs {
+        buf: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+        fields: Vec<String>,
+    }
+
+    struct CapturedBuf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for CapturedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedLogs {
+        type Writer = RedactingWriter<'a, CapturedBuf>;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            RedactingWriter::new(CapturedBuf(self.buf.clone()), Some(&self.fields))
+        }
+    }
+
+    #[tokio::test]
+    async fn claim_id_is_masked_in_claim_and_download_logs() {
+        use axum::http::Method;
+        use crate::test_support::{TestApp, ADMIN_TOKEN};
+
+        let logs = CapturedLogs { fields: default_fields(), ..CapturedLogs::default() };
+        let subscriber = tracing_subscriber::fmt().with_writer(logs.clone()).with_ansi(false).finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = TestApp::with_config(|c| c.admin_token = Some(ADMIN_TOKEN.to_string())).await;
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        let res = app.claim(&drop_id, "user-1").await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        let claim_id = res.json()["claim_id"].as_str().unwrap().to_string();
+        let res = app.get(&format!("/api/drops/{}/download?token={}", drop_id, claim_id)).await;
+        assert_eq!(res.status, 200);
+        let uri = format!("/api/drops/{}/claims/{}/revoke", drop_id, claim_id);
+        let res = app.send_json(Method::POST, &uri, None, &[("x-admin-token", ADMIN_TOKEN)]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+
+        let logs = String::from_utf8(logs.buf.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("Drop claimed") && logs.contains("Claim revoked"), "{}", logs);
+        assert!(!logs.contains(&claim_id), "{}", logs);
+    }
+}
@@ -0,0 +1,163 @@
+//! API Response
+//! 成功レスポンスの共通形 `{success:true, data, meta?}`（エラー時の形は error.rs）
+
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// 成功レスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: T,
+    /// 一覧の件数・ページング情報（一覧以外では省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ListMeta>,
+    /// 非推奨: 移行期間中の旧フィールド（`vendors` / `total` 等）。LEGACY_LIST_FIELDS=false で出力しない
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub legacy: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 一覧のページング情報
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ListMeta {
+    /// 条件に一致する件数（ページングしない一覧では data の件数）
+    pub total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    /// 続きを取得する際に渡すカーソル（続きが無い場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl ListMeta {
+    pub fn total(total: usize) -> Self {
+        Self {
+            total: total as i64,
+            ..Self::default()
+        }
+    }
+}
+
+impl<T: Serialize> ApiResponse<T> {
+    pub fn ok(data: T) -> Self {
+        Self {
+            success: true,
+            data,
+            meta: None,
+            legacy: serde_json::Map::new(),
+        }
+    }
+
+    /// 一覧レスポンス
+    pub fn list(data: T, meta: ListMeta) -> Self {
+        Self {
+            meta: Some(meta),
+            ..Self::ok(data)
+        }
+    }
+
+    /// 旧形式のフィールドを付ける（data を `key` に、meta を total / limit / offset に複製）
+    /// enabled=false（LEGACY_LIST_FIELDS=false）の場合は何もしない
+    pub fn with_legacy_fields(mut self, enabled: bool, key: &str) -> Self {
+        if !enabled {
+            return self;
+        }
+        if let Ok(value) = serde_json::to_value(&self.data) {
+            self.legacy.insert(key.to_string(), value);
+        }
+        if let Some(meta) = &self.meta {
+            self.legacy.insert("total".to_string(), meta.total.into());
+            if let Some(limit) = meta.limit {
+                self.legacy.insert("limit".to_string(), limit.into());
+            }
+            if let Some(offset) = meta.offset {
+                self.legacy.insert("offset".to_string(), offset.into());
+            }
+        }
+        self
+    }
+
+    /// 旧形式のみにあった一覧以外のフィールド（`owner` / `drop_id` 等）を付ける
+    /// enabled=false（LEGACY_LIST_FIELDS=false）の場合は何もしない
+    pub fn with_legacy_value(mut self, enabled: bool, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        if enabled {
+            self.legacy.insert(key.to_string(), value.into());
+        }
+        self
+    }
+}
+
+/// HEAD（存在確認）の 200 レスポンス（ボディなし）
+/// ETag は ID と更新時刻（ms）から作るため、更新されると変わる
+pub fn exists_response(id: &str, updated_at_ms: Option<i64>) -> Response {
+    let hash = Sha256::digest(format!("{}:{}", id, updated_at_ms.unwrap_or(0)).as_bytes());
+    let etag = format!("\"{}\"", &hex::encode(hash)[..16]);
+    let mut response = (StatusCode::OK, [(header::ETAG, etag)]).into_response();
+    let last_modified = updated_at_ms
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .map(|t| t.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .and_then(|v| v.parse().ok());
+    if let Some(last_modified) = last_modified {
+        response.headers_mut().insert(header::LAST_MODIFIED, last_modified);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn list_envelope_copies_legacy_fields_only_when_enabled() {
+        let meta = ListMeta { total: 5, limit: Some(2), offset: Some(0), next_cursor: None };
+        let legacy = ApiResponse::list(vec![1, 2], meta.clone()).with_legacy_fields(true, "items");
+        assert_eq!(
+            serde_json::to_value(&legacy).unwrap(),
+            json!({
+                "success": true,
+                "data": [1, 2],
+                "meta": { "total": 5, "limit": 2, "offset": 0 },
+                "items": [1, 2],
+                "total": 5,
+                "limit": 2,
+                "offset": 0,
+            })
+        );
+
+        let plain = ApiResponse::list(vec![1, 2], meta).with_legacy_fields(false, "items");
+        assert_eq!(
+            serde_json::to_value(&plain).unwrap(),
+            json!({ "success": true, "data": [1, 2], "meta": { "total": 5, "limit": 2, "offset": 0 } })
+        );
+        assert_eq!(serde_json::to_value(ApiResponse::ok("x")).unwrap(), json!({ "success": true, "data": "x" }));
+    }
+
+    #[tokio::test]
+    async fn list_endpoints_use_envelope_with_optional_legacy_fields() {
+        let app = TestApp::new().await;
+        app.create_vendor(None).await;
+        let body = app.get("/api/vendors").await.json();
+        assert_eq!(body["success"], true);
+        assert_eq!(body["meta"]["total"], 1);
+        assert_eq!(body["vendors"], body["data"]);
+        assert_eq!(body["total"], 1);
+
+        let app = TestApp::with_config(|c| c.legacy_list_fields = false).await;
+        let vendor = app.create_vendor(None).await;
+        app.create_listing(&vendor, "L-1", 100, 1).await;
+        let body = app.get("/api/listings").await.json();
+        let mut keys: Vec<&str> = body.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["data", "meta", "success"]);
+        assert_eq!(body["data"][0]["listing_id"], "L-1");
+    }
+}
@@ -0,0 +1,73 @@
+//! Signed Asset URLs
+//! プロフィール JSON・アイコン等の期限付き署名 URL（検証は CDN/エッジから /api/assets/verify を呼ぶ）
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::AppConfig;
+
+/// 署名検証の失敗理由
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    Expired,
+    BadSignature,
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::Expired => write!(f, "Signature expired"),
+            VerifyError::BadSignature => write!(f, "Invalid signature"),
+        }
+    }
+}
+
+/// URL に expires / sig クエリを付与（ASSET_SIGNING_SECRET 未設定時はそのまま返す）
+/// 署名対象は URL のパス部分のみ（ホスト・既存クエリは含めない）
+pub fn sign(config: &AppConfig, url: &str) -> String {
+    let Some(secret) = config.asset_signing_secret.as_deref() else {
+        return url.to_string();
+    };
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let expires = chrono::Utc::now().timestamp() + config.asset_url_ttl_secs;
+    let sig = signature(secret, parsed.path(), expires);
+    let separator = if parsed.query().is_some() { '&' } else { '?' };
+    format!("{}{}expires={}&sig={}", url, separator, expires, sig)
+}
+
+/// Option<String> の URL 用（None はそのまま）
+pub fn sign_opt(config: &AppConfig, url: Option<String>) -> Option<String> {
+    url.map(|u| sign(config, &u))
+}
+
+/// パス・期限・署名を検証
+pub fn verify(secret: &str, path: &str, expires: i64, sig: &str, now: i64) -> Result<(), VerifyError> {
+    let Ok(sig_bytes) = hex::decode(sig) else {
+        return Err(VerifyError::BadSignature);
+    };
+    // 定数時間比較。期限切れより先に改ざんを判定する
+    if mac_for(secret, path, expires).verify_slice(&sig_bytes).is_err() {
+        return Err(VerifyError::BadSignature);
+    }
+    if now >= expires {
+        return Err(VerifyError::Expired);
+    }
+    Ok(())
+}
+
+/// hex(HMAC-SHA256(secret, "<path>\n<expires>"))
+fn signature(secret: &str, path: &str, expires: i64) -> String {
+    hex::encode(mac_for(secret, path, expires).finalize().into_bytes())
+}
+
+fn mac_for(secret: &str, path: &str, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(path.as_bytes());
+    mac.update(b"\n");
+    mac.update(expires.to_string().as_bytes());
+    mac
+}
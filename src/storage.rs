@@ -0,0 +1,280 @@
+//! Storage Helpers
+//! アップロードファイルの保存処理
+
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+#[cfg(target_os = "linux")]
+use tracing::{info, warn};
+
+/// ファイルをアトミックに書き込む
+/// `<path>.<random>.tmp` に書き込んで sync 後に rename するため、
+/// 書き込み途中で失敗しても最終パスに壊れたファイルが残らない
+pub async fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = tmp_path_for(path);
+    let result = write_and_rename(&tmp_path, path, data).await;
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path).await;
+    }
+    result
+}
+
+async fn write_and_rename(tmp_path: &Path, path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(tmp_path).await?;
+    file.write_all(data).await?;
+    file.flush().await?;
+    file.sync_all().await?;
+    drop(file);
+    fs::rename(tmp_path, path).await
+}
+
+/// 一時ファイルのパス（"<final>.<random>.tmp"、同じパスへの同時書き込みでも衝突しない）
+fn tmp_path_for(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+    path.with_file_name(name)
+}
+
+/// アップロードしたファイル・ディレクトリの所有者を変更（UPLOAD_CHOWN、ベストエフォート）
+/// owner が None の場合、および Linux 以外では何もしない
+pub async fn apply_upload_owner(owner: Option<&str>, path: &Path, recursive: bool) {
+    let Some(owner) = owner else {
+        return;
+    };
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut command = tokio::process::Command::new("chown");
+        if recursive {
+            command.arg("-R");
+        }
+        match command.arg(owner).arg(path).output().await {
+            Ok(output) if output.status.success() => {
+                info!("Changed ownership to {}: {:?}", owner, path);
+            }
+            Ok(output) => warn!(
+                "chown {} {:?} failed (not critical): {} {}",
+                owner,
+                path,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("Failed to run chown (not critical): {}", e),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (owner, path, recursive);
+}
+
+/// ディレクトリ内のアイコンファイル名を探す（"icon.<ext>" とサムネイル "icon_thumb.webp"）
+pub async fn find_icon_files(dir: &Path) -> (Option<String>, Option<String>) {
+    let mut icon = None;
+    let mut thumb = None;
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return (None, None);
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == "icon_thumb.webp" {
+            thumb = Some(name);
+        } else if name.starts_with("icon.") && !name.ends_with(".tmp") {
+            icon = Some(name);
+        }
+    }
+    (icon, thumb)
+}
+
+/// 先頭バイトから Content-Type を判定（判別できない場合は拡張子、最後は octet-stream）
+pub fn sniff_content_type(data: &[u8], ext: &str) -> &'static str {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    if starts(b"ID3") || (data.len() >= 2 && data[0] == 0xFF && data[1] & 0xE0 == 0xE0) {
+        "audio/mpeg"
+    } else if starts(b"fLaC") {
+        "audio/flac"
+    } else if starts(b"OggS") {
+        "audio/ogg"
+    } else if starts(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+        "audio/wav"
+    } else if starts(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        "image/webp"
+    } else if data.get(4..8) == Some(b"ftyp") {
+        if ext.eq_ignore_ascii_case("mp4") { "video/mp4" } else { "audio/mp4" }
+    } else if starts(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if starts(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if starts(b"GIF8") {
+        "image/gif"
+    } else {
+        match ext.to_ascii_lowercase().as_str() {
+            "json" => "application/json",
+            "txt" => "text/plain; charset=utf-8",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+/// レガシーアップロードで許可する拡張子（小文字、category ごと）
+/// SVG・HTML 等は配信時にスクリプトとして解釈され得るため受け付けない
+pub const COVER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "wav", "ogg", "aac", "m4a"];
+pub const MANIFEST_EXTENSIONS: &[&str] = &["json"];
+
+/// category（tracks / cover / manifest）で許可される拡張子（不明な category は空）
+pub fn allowed_upload_extensions(category: &str) -> &'static [&'static str] {
+    match category {
+        "tracks" => AUDIO_EXTENSIONS,
+        "cover" => COVER_EXTENSIONS,
+        "manifest" => MANIFEST_EXTENSIONS,
+        _ => &[],
+    }
+}
+
+/// レガシーアップロードで同名ファイルが既にある場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// 上書き（従来の動作）
+    Overwrite,
+    /// 409 で拒否
+    Error,
+    /// "<stem>.v2.<ext>"・"<stem>.v3.<ext>"… の空いている名前で保存
+    Version,
+}
+
+impl CollisionPolicy {
+    /// "overwrite" / "error" / "version"（未指定は Overwrite）
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::trim).filter(|v| !v.is_empty()) {
+            None | Some("overwrite") => Ok(Self::Overwrite),
+            Some("error") => Ok(Self::Error),
+            Some("version") => Ok(Self::Version),
+            Some(other) => Err(format!(
+                "collision must be 'overwrite', 'error', or 'version' (got '{}')",
+                other
+            )),
+        }
+    }
+}
+
+/// Version ポリシーで試す最大の版番号（これを超えたら諦める）
+const MAX_FILE_VERSION: u32 = 1000;
+
+/// 保存するファイル名を決める（Error で既存の場合は None）
+/// 存在確認と書き込みの間は排他していないため、同じ名前への同時アップロードは後勝ちになり得る
+pub async fn resolve_collision(dir: &Path, filename: &str, policy: CollisionPolicy) -> std::io::Result<Option<String>> {
+    if policy == CollisionPolicy::Overwrite || !fs::try_exists(dir.join(filename)).await? {
+        return Ok(Some(filename.to_string()));
+    }
+    if policy == CollisionPolicy::Error {
+        return Ok(None);
+    }
+
+    let (stem, ext) = filename.rsplit_once('.').unwrap_or((filename, ""));
+    for version in 2..=MAX_FILE_VERSION {
+        let candidate = if ext.is_empty() {
+            format!("{}.v{}", stem, version)
+        } else {
+            format!("{}.v{}.{}", stem, version, ext)
+        };
+        if !fs::try_exists(dir.join(&candidate)).await? {
+            return Ok(Some(candidate));
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("Too many versions of {}", filename),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn write_atomic_replaces_file_without_leaving_temp_files() {
+        let dir = TempDir::new();
+        let path = dir.path().join("audio.mp3");
+
+        write_atomic(&path, b"first").await.unwrap();
+        write_atomic(&path, b"second").await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(dir.file_names(), ["audio.mp3"]);
+    }
+
+    #[tokio::test]
+    async fn write_atomic_failure_leaves_no_partial_file() {
+        let dir = TempDir::new();
+        // rename 先がディレクトリのため最後の rename で失敗する
+        let path = dir.path().join("target");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("keep"), b"x").unwrap();
+
+        assert!(write_atomic(&path, b"data").await.is_err());
+        assert_eq!(dir.file_names(), ["target"]);
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn temp_paths_are_unique_siblings() {
+        let path = Path::new("/data/drops/D1/audio.mp3");
+        let (a, b) = (tmp_path_for(path), tmp_path_for(path));
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        assert!(a.to_string_lossy().ends_with(".tmp"));
+    }
+
+    #[cfg(target_os = "linux")]
+    fn uid_of(path: &Path) -> u32 {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata(path).unwrap().uid()
+    }
+
+    /// root で動いているときだけ実際の chown を確認できる
+    #[cfg(target_os = "linux")]
+    fn running_as_root(dir: &Path) -> bool {
+        let probe = dir.join("probe");
+        std::fs::write(&probe, b"").unwrap();
+        uid_of(&probe) == 0
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn upload_owner_is_left_unchanged_when_disabled() {
+        let dir = TempDir::new();
+        let path = dir.path().join("audio.mp3");
+        std::fs::write(&path, b"data").unwrap();
+        let before = uid_of(&path);
+
+        apply_upload_owner(None, &path, false).await;
+        assert_eq!(uid_of(&path), before);
+
+        if running_as_root(dir.path()) {
+            apply_upload_owner(Some("65534:65534"), &path, false).await;
+            assert_eq!(uid_of(&path), 65534);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn upload_owner_applies_to_drop_and_icon_uploads() {
+        use crate::test_support::{png_bytes, MultipartForm, TestApp};
+
+        let app = TestApp::with_config(|c| c.upload_chown = Some("65534:65534".to_string())).await;
+        if !running_as_root(&app.dir) {
+            return;
+        }
+        let vendor = app.create_vendor(None).await;
+        let drop_id = app.create_drop(&vendor, 10).await;
+        assert_eq!(uid_of(&app.data_path(format!("drops/{}", drop_id))), 65534);
+        let blob = std::fs::read_dir(app.data_path("drops/_blobs")).unwrap().next().unwrap().unwrap();
+        assert_eq!(uid_of(&blob.path()), 65534);
+
+        let form = MultipartForm::new().file("file", "icon.png", "image/png", &png_bytes(4, 4));
+        let uri = format!("/api/vendors/{}/icon", vendor);
+        let res = app.send_form(axum::http::Method::POST, &uri, form, &[]).await;
+        assert_eq!(res.status, 200, "{}", res.text());
+        assert_eq!(uid_of(&app.data_path(format!("account/vendors/{}/icon.png", vendor))), 65534);
+    }
+}
@@ -0,0 +1,403 @@
+//! Test Support
+//! ハンドラ・ミドルウェアのテスト用ヘルパー
+//!
+//! 一時ディレクトリ上の DB・データディレクトリで本番と同じルーター（build_app）を組み立て、
+//! `tower::ServiceExt::oneshot` でリクエストを流す。バックグラウンドジョブは起動しないため、
+//! ジョブの処理はテストから直接呼び出す。
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::ConnectInfo,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::Value;
+use tower::ServiceExt;
+use tower_http::normalize_path::NormalizePath;
+
+use crate::config::AppConfig;
+use crate::handlers::vendors::sui_address_from_pubkey;
+use crate::{build_app, db, new_app_state, AppState};
+
+/// テストリクエストの既定の接続元
+pub const CLIENT_ADDR: &str = "192.0.2.1:40000";
+
+/// 管理API用トークン（with_config で admin_token に設定して使う）
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// テスト用のアプリケーション（破棄時に一時ディレクトリを削除）
+pub struct TestApp {
+    pub state: Arc<AppState>,
+    pub dir: PathBuf,
+    app: NormalizePath<Router>,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    /// 既定のテスト設定を `configure` で上書きして起動
+    pub async fn with_config(configure: impl FnOnce(&mut AppConfig)) -> Self {
+        let dir = std::env::temp_dir().join(format!("nft-upload-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create test dir");
+
+        let mut config = AppConfig {
+            base_data_dir: dir.to_string_lossy().into_owned(),
+            vps_base_url: "http://test.local/nft".to_string(),
+            db_path: dir.join("test.db").to_string_lossy().into_owned(),
+            camera_temp_dir: dir.join("camera_temp").to_string_lossy().into_owned(),
+            upload_chown: None,
+            ..AppConfig::default()
+        };
+        configure(&mut config);
+
+        let settings = db::PoolSettings {
+            max_connections: config.db_max_connections,
+            min_connections: config.db_min_connections,
+            slow_query_threshold: None,
+        };
+        let pool = db::init_db(&config.db_path, settings).await.expect("init test db");
+        let state = new_app_state(config, pool);
+        let app = build_app(&state);
+        Self { state, dir, app }
+    }
+
+    /// リクエストビルダー
+    pub fn request(method: Method, uri: &str) -> axum::http::request::Builder {
+        Request::builder().method(method).uri(uri)
+    }
+
+    /// リクエストを送信（ConnectInfo 未設定時は CLIENT_ADDR から）
+    pub async fn send(&self, req: Request<Body>) -> TestResponse {
+        let res = self.send_streaming(req).await;
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .expect("read body");
+        TestResponse { status, headers, body }
+    }
+
+    /// リクエストを送信し、ボディを読まずにレスポンスを返す（ストリーミングの確認用）
+    pub async fn send_streaming(&self, mut req: Request<Body>) -> axum::response::Response {
+        if req.extensions().get::<ConnectInfo<SocketAddr>>().is_none() {
+            let addr: SocketAddr = CLIENT_ADDR.parse().unwrap();
+            req.extensions_mut().insert(ConnectInfo(addr));
+        }
+        self.app.clone().oneshot(req).await.expect("infallible")
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.send(Self::request(Method::GET, uri).body(Body::empty()).unwrap()).await
+    }
+
+    pub async fn get_with(&self, uri: &str, headers: &[(&str, &str)]) -> TestResponse {
+        self.send_json(Method::GET, uri, None, headers).await
+    }
+
+    pub async fn post_json(&self, uri: &str, body: Value) -> TestResponse {
+        self.send_json(Method::POST, uri, Some(body), &[]).await
+    }
+
+    pub async fn put_json(&self, uri: &str, body: Value) -> TestResponse {
+        self.send_json(Method::PUT, uri, Some(body), &[]).await
+    }
+
+    /// JSON ボディ（任意）と追加ヘッダ付きで送信
+    pub async fn send_json(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut builder = Self::request(method, uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let req = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        };
+        self.send(req.unwrap()).await
+    }
+
+    /// multipart/form-data を送信
+    pub async fn send_form(
+        &self,
+        method: Method,
+        uri: &str,
+        form: MultipartForm,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut builder = Self::request(method, uri).header(header::CONTENT_TYPE, form.content_type());
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        self.send(builder.body(Body::from(form.finish())).unwrap()).await
+    }
+
+    /// Vendor を作成して stable_id を返す
+    pub async fn create_vendor(&self, owner: Option<&str>) -> String {
+        let res = self.post_json("/api/vendors", vendor_body(owner)).await;
+        assert_eq!(res.status, StatusCode::OK, "create vendor: {}", res.text());
+        res.json()["stable_id"].as_str().unwrap().to_string()
+    }
+
+    /// Artist を作成して stable_id を返す
+    pub async fn create_artist(&self, owner: Option<&str>) -> String {
+        let res = self.post_json("/api/account/artists", artist_body(owner)).await;
+        assert_eq!(res.status, StatusCode::OK, "create artist: {}", res.text());
+        res.json()["stable_id"].as_str().unwrap().to_string()
+    }
+
+    /// Drop を作成して drop_id を返す
+    pub async fn create_drop(&self, vendor_stable_id: &str, max_claims: i64) -> String {
+        let res = self
+            .send_form(Method::POST, "/api/drops", drop_form(vendor_stable_id, max_claims), &[])
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "create drop: {}", res.text());
+        res.json()["drop"]["drop_id"].as_str().unwrap().to_string()
+    }
+
+    /// Listing を作成（在庫は supply_total のまま ACTIVE）
+    pub async fn create_listing(&self, vendor_stable_id: &str, listing_id: &str, price: i64, supply_total: i64) {
+        let res = self.post_json("/api/listings", listing_body(vendor_stable_id, listing_id, price, supply_total)).await;
+        assert_eq!(res.status, StatusCode::OK, "create listing: {}", res.text());
+    }
+
+    /// Drop を Claim（成功時の JSON は呼び出し側で確認）
+    pub async fn claim(&self, drop_id: &str, user_id: &str) -> TestResponse {
+        self.post_json(
+            &format!("/api/drops/{}/claim", drop_id),
+            serde_json::json!({ "user_id": user_id }),
+        )
+        .await
+    }
+
+    /// `owner` が署名した X-Owner-* ヘッダ（challenge は1回限りなのでリクエストごとに取得する）
+    pub async fn owner_headers(&self, owner: &OwnerKey) -> OwnerHeaders {
+        let challenge = self.get("/api/devices/auth/challenge").await.json()["challenge"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        owner.sign(&challenge)
+    }
+
+    /// データディレクトリ配下のパス
+    pub fn data_path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.dir.join(relative)
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Vendor owner の鍵（seed から決定的に生成）
+pub struct OwnerKey(SigningKey);
+
+impl OwnerKey {
+    pub fn new(seed: u8) -> Self {
+        Self(SigningKey::from_bytes(&[seed; 32]))
+    }
+
+    /// 公開鍵から導出した Sui アドレス
+    pub fn address(&self) -> String {
+        sui_address_from_pubkey(self.0.verifying_key().as_bytes())
+    }
+
+    /// challenge とアドレスに署名したヘッダ
+    pub fn sign(&self, challenge: &str) -> OwnerHeaders {
+        let address = self.address();
+        let signature = self.0.sign(format!("{}\n{}", challenge, address).as_bytes());
+        let b64 = base64::engine::general_purpose::STANDARD;
+        OwnerHeaders(vec![
+            ("x-owner-address", address),
+            ("x-owner-public-key", b64.encode(self.0.verifying_key().as_bytes())),
+            ("x-owner-challenge", challenge.to_string()),
+            ("x-owner-signature", b64.encode(signature.to_bytes())),
+        ])
+    }
+}
+
+/// 署名済みの X-Owner-* ヘッダ
+pub struct OwnerHeaders(pub Vec<(&'static str, String)>);
+
+impl OwnerHeaders {
+    /// send_json / send_form / get_with に渡す形
+    pub fn pairs(&self) -> Vec<(&str, &str)> {
+        self.0.iter().map(|(name, value)| (*name, value.as_str())).collect()
+    }
+}
+
+/// 一時ディレクトリ（破棄時に削除、アプリを起動しない単体テスト用）
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("nft-upload-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// ディレクトリ内のファイル名（ソート済み）
+    pub fn file_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(&self.0)
+            .expect("read temp dir")
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for TempDir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// テスト用 Vendor 作成リクエスト
+pub fn vendor_body(owner: Option<&str>) -> Value {
+    serde_json::json!({
+        "peer_id": format!("peer-{}", uuid::Uuid::new_v4()),
+        "owner": owner,
+        "profile": { "name": "Test Shop" },
+    })
+}
+
+/// テスト用 Artist 作成リクエスト
+pub fn artist_body(owner: Option<&str>) -> Value {
+    serde_json::json!({
+        "peer_id": format!("peer-{}", uuid::Uuid::new_v4()),
+        "name": "Test Artist",
+        "owner": owner,
+    })
+}
+
+/// テスト用 Listing 作成リクエスト
+pub fn listing_body(vendor_stable_id: &str, listing_id: &str, price: i64, supply_total: i64) -> Value {
+    serde_json::json!({
+        "listing_id": listing_id,
+        "vendor_stable_id": vendor_stable_id,
+        "item_type": 0,
+        "price": price,
+        "currency": "SUI",
+        "supply_total": supply_total,
+    })
+}
+
+/// テスト用 Drop 作成フォーム（必須項目のみ、音声は小さなダミー）
+pub fn drop_form(vendor_stable_id: &str, max_claims: i64) -> MultipartForm {
+    let end_at = chrono::Utc::now().timestamp() + 3600;
+    MultipartForm::new()
+        .text("vendor_stable_id", vendor_stable_id)
+        .text("artist_name", "Test Artist")
+        .text("title", "Test Drop")
+        .text("end_at", &end_at.to_string())
+        .text("max_claims", &max_claims.to_string())
+        .file("audio", "track.mp3", "audio/mpeg", b"ID3 test audio bytes")
+}
+
+/// 単色の PNG 画像
+pub fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let img = image::RgbImage::from_pixel(width, height, image::Rgb([200, 40, 90]));
+    let mut out = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut out, image::ImageFormat::Png).expect("encode png");
+    out.into_inner()
+}
+
+/// テストレスポンス（ボディは読み切ったもの）
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("invalid JSON ({}): {}", e, self.text()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
+
+/// multipart/form-data のボディ組み立て
+pub struct MultipartForm {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Default for MultipartForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartForm {
+    pub fn new() -> Self {
+        Self { boundary: format!("test-boundary-{}", uuid::Uuid::new_v4().simple()), body: Vec::new() }
+    }
+
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                self.boundary, name, value
+            )
+            .as_bytes(),
+        );
+        self
+    }
+
+    pub fn file(mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                self.boundary, name, filename, content_type
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        self.body
+    }
+}
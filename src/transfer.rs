@@ -0,0 +1,244 @@
+//! Transfer Logging
+//! アップロード・ダウンロードの転送量と所要時間をログに出す（tracing のフィールドとして集計可能）
+//! ダウンロード中の SHA256 照合（VerifiedStream）もここに置く
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::body::Bytes;
+use futures_util::Stream;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+/// 転送の計測（ハンドラ開始時に作り、完了時に finish）
+pub struct TransferTimer {
+    operation: &'static str,
+    started: Instant,
+}
+
+impl TransferTimer {
+    pub fn start(operation: &'static str) -> Self {
+        Self {
+            operation,
+            started: Instant::now(),
+        }
+    }
+
+    /// bytes・duration_ms・mb_per_sec をフィールドにして info ログを出す
+    pub fn finish(&self, id: &str, bytes: u64) {
+        self.log(id, bytes, true);
+    }
+
+    fn log(&self, id: &str, bytes: u64, completed: bool) {
+        let elapsed = self.started.elapsed();
+        let duration_ms = elapsed.as_millis() as u64;
+        let mb_per_sec = mb_per_sec(bytes, elapsed.as_secs_f64());
+        info!(
+            operation = self.operation,
+            id,
+            bytes,
+            duration_ms,
+            mb_per_sec,
+            completed,
+            "Transfer finished"
+        );
+    }
+}
+
+/// MB/s（1MB = 1,000,000 バイト、小数2桁）
+fn mb_per_sec(bytes: u64, secs: f64) -> f64 {
+    // 極端に短い転送で無限大にならないよう 1ms を下限にする
+    let rate = bytes as f64 / 1_000_000.0 / secs.max(0.001);
+    (rate * 100.0).round() / 100.0
+}
+
+/// レスポンスボディのストリームを包み、送り終えた（または切断された）時点で転送ログを出す
+/// ハンドラはボディを返した時点で終わるため、ダウンロードの所要時間はストリーム側で測る
+pub struct LoggedStream<S> {
+    inner: S,
+    timer: TransferTimer,
+    id: String,
+    expected: u64,
+    sent: u64,
+}
+
+impl<S> LoggedStream<S> {
+    pub fn new(inner: S, timer: TransferTimer, id: String, expected: u64) -> Self {
+        Self {
+            inner,
+            timer,
+            id,
+            expected,
+            sent: 0,
+        }
+    }
+}
+
+impl<S> Stream for LoggedStream<S>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Unpin,
+{
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(chunk))) = &poll {
+            self.sent += chunk.len() as u64;
+        }
+        poll
+    }
+}
+
+impl<S> Drop for LoggedStream<S> {
+    fn drop(&mut self) {
+        // 途中切断は completed=false（bytes は実際に送れた分）
+        self.timer.log(&self.id, self.sent, self.sent >= self.expected);
+    }
+}
+
+/// レスポンスボディのストリームを包み、送りながら SHA256 を計算して終端で expected と照合する
+/// 最後のチャンクは照合が済むまで送らないため、不一致時はクライアントに完全なボディが届かない
+/// （不一致は InvalidData エラーとしてストリームを打ち切る）
+pub struct VerifiedStream<S> {
+    inner: S,
+    hasher: Sha256,
+    expected: String,
+    id: String,
+    pending: Option<Bytes>,
+    finished: bool,
+}
+
+impl<S> VerifiedStream<S> {
+    pub fn new(inner: S, expected: String, id: String) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            expected,
+            id,
+            pending: None,
+            finished: false,
+        }
+    }
+}
+
+impl<S> Stream for VerifiedStream<S>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Unpin,
+{
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.finished {
+            return Poll::Ready(None);
+        }
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.hasher.update(&chunk);
+                    // 1つ前のチャンクを送り、今回の分は保留する
+                    if let Some(previous) = this.pending.replace(chunk) {
+                        return Poll::Ready(Some(Ok(previous)));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    let actual = hex::encode(std::mem::take(&mut this.hasher).finalize());
+                    if actual != this.expected {
+                        error!(
+                            "Download integrity check FAILED: id={}, expected_sha256={}, actual_sha256={}",
+                            this.id, this.expected, actual
+                        );
+                        this.pending = None;
+                        return Poll::Ready(Some(Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "integrity check failed",
+                        ))));
+                    }
+                    return Poll::Ready(this.pending.take().map(Ok));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    fn chunks(sizes: &[usize]) -> impl Stream<Item = std::io::Result<Bytes>> + Unpin {
+        let chunks: Vec<std::io::Result<Bytes>> = sizes.iter().map(|&n| Ok(Bytes::from(vec![7u8; n]))).collect();
+        futures_util::stream::iter(chunks)
+    }
+
+    #[test]
+    fn mb_per_sec_rounds_and_floors_duration() {
+        assert_eq!(mb_per_sec(2_500_000, 2.0), 1.25);
+        assert_eq!(mb_per_sec(1_000_000, 3.0), 0.33);
+        // 0 秒でも 1ms として計算する
+        assert_eq!(mb_per_sec(1_000, 0.0), 1.0);
+        assert_eq!(mb_per_sec(0, 0.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn logged_stream_passes_chunks_through_and_counts_bytes() {
+        let timer = TransferTimer::start("test_download");
+        let mut stream = LoggedStream::new(chunks(&[3, 5, 2]), timer, "drop-1".to_string(), 10);
+        let mut received = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            received.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(received, vec![7u8; 10]);
+        assert_eq!(stream.sent, stream.expected);
+    }
+
+    async fn drain(mut stream: impl Stream<Item = std::io::Result<Bytes>> + Unpin) -> (Vec<u8>, Option<std::io::Error>) {
+        let mut received = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => received.extend_from_slice(&chunk),
+                Err(e) => return (received, Some(e)),
+            }
+        }
+        (received, None)
+    }
+
+    #[tokio::test]
+    async fn verified_stream_passes_matching_content_through() {
+        let expected = hex::encode(Sha256::digest(vec![7u8; 10]));
+        let (received, err) = drain(VerifiedStream::new(chunks(&[3, 5, 2]), expected, "drop-1".to_string())).await;
+        assert_eq!(received, vec![7u8; 10]);
+        assert!(err.is_none());
+
+        // 空ファイルも照合できる
+        let expected = hex::encode(Sha256::digest(b""));
+        let (received, err) = drain(VerifiedStream::new(chunks(&[]), expected, "drop-1".to_string())).await;
+        assert!(received.is_empty() && err.is_none());
+    }
+
+    #[tokio::test]
+    async fn verified_stream_withholds_last_chunk_on_mismatch() {
+        let expected = hex::encode(Sha256::digest(b"original"));
+        let (received, err) = drain(VerifiedStream::new(chunks(&[3, 5, 2]), expected, "drop-1".to_string())).await;
+        assert_eq!(received.len(), 8);
+        assert_eq!(err.unwrap().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn logged_stream_counts_only_sent_bytes_when_dropped_early() {
+        let timer = TransferTimer::start("test_download");
+        let mut stream = LoggedStream::new(chunks(&[4, 4, 4]), timer, "drop-1".to_string(), 12);
+        stream.next().await.unwrap().unwrap();
+        // 途中で破棄した場合は送信済みの分のみ（completed=false で記録される）
+        assert_eq!(stream.sent, 4);
+        assert!(stream.sent < stream.expected);
+    }
+}
@@ -0,0 +1,333 @@
+//! Outbound Webhooks
+//! Listing の売り切れ通知（署名付き JSON を POST、失敗時はバックオフして再送）
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+/// 売り切れ通知のイベント名
+pub const EVENT_LISTING_SOLD_OUT: &str = "listing.sold_out";
+
+/// 再送の初回待ち時間（以降は倍々）
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// 売り切れ通知のペイロード
+#[derive(Debug, Serialize)]
+pub struct SoldOutPayload {
+    pub event: &'static str,
+    pub listing_id: String,
+    pub vendor_stable_id: String,
+    pub supply_total: i64,
+    pub sold_out_at_ms: i64,
+}
+
+/// Webhook URL の形式チェック（http / https のみ許可）
+/// ループバック・プライベート・リンクローカル宛て（SSRF）は allowed_hosts に含まれるホストのみ許可
+pub fn validate_url(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let host = match reqwest::Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => match u.host_str() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase(),
+            None => return Err(format!("Invalid webhook_url: {}", url)),
+        },
+        _ => return Err(format!("Invalid webhook_url: {}", url)),
+    };
+    if is_allowed_host(&host, allowed_hosts) {
+        return Ok(());
+    }
+    let internal = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public_ip(ip),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    };
+    if internal {
+        return Err(format!("webhook_url must not point to a private or loopback address: {}", url));
+    }
+    Ok(())
+}
+
+/// WEBHOOK_ALLOWED_HOSTS に含まれるホストか（大文字小文字は区別しない）
+fn is_allowed_host(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// 外部から到達できるアドレスか（ループバック・プライベート・リンクローカル・CGNAT 等は false）
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// 送信用クライアントを作る
+/// ホスト名は送信前に名前解決して内部アドレスを拒否し、解決したアドレスに固定する（DNS リバインディング対策）
+/// リダイレクトは追わない（外部 URL から内部へ転送されるのを防ぐ）
+async fn build_client(url: &str, timeout: Duration, allowed_hosts: &[String]) -> Result<reqwest::Client, String> {
+    validate_url(url, allowed_hosts)?;
+    let parsed = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    let builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none());
+
+    let host = parsed.host_str().unwrap_or_default();
+    if host.parse::<IpAddr>().is_ok()
+        || host.starts_with('[')
+        || is_allowed_host(host, allowed_hosts)
+    {
+        return builder.build().map_err(|e| e.to_string());
+    }
+
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("DNS lookup failed: {}", e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("DNS lookup returned no addresses: {}", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!("{} resolves to a private or loopback address: {}", host, addr.ip()));
+    }
+    builder.resolve_to_addrs(host, &addrs).build().map_err(|e| e.to_string())
+}
+
+/// 署名ヘッダの値を計算: "sha256=" + hex(HMAC-SHA256(secret, "<timestamp>.<body>"))
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Webhook をバックグラウンドで送信（呼び出し元は待たない）
+pub fn spawn_delivery(config: &AppConfig, url: String, event: &'static str, payload: impl Serialize) {
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Webhook payload serialization failed: event={}, error={}", event, e);
+            return;
+        }
+    };
+    let secret = config.webhook_secret.clone();
+    let timeout = Duration::from_secs(config.webhook_timeout_secs);
+    let max_retries = config.webhook_max_retries;
+    let allowed_hosts = config.webhook_allowed_hosts.clone();
+
+    tokio::spawn(async move {
+        deliver(&url, event, &body, secret.as_deref(), timeout, max_retries, &allowed_hosts).await;
+    });
+}
+
+/// 送信本体（2xx 以外・接続失敗は最大 max_retries 回まで再送）
+async fn deliver(
+    url: &str,
+    event: &str,
+    body: &[u8],
+    secret: Option<&str>,
+    timeout: Duration,
+    max_retries: u32,
+    allowed_hosts: &[String],
+) {
+    let client = match build_client(url, timeout, allowed_hosts).await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Webhook refused: event={}, url={}, error={}", event, url, e);
+            return;
+        }
+    };
+    if secret.is_none() {
+        warn!("WEBHOOK_SECRET is not set; sending unsigned webhook: event={}, url={}", event, url);
+    }
+
+    let mut delay = RETRY_BASE_DELAY;
+    for attempt in 0..=max_retries {
+        if attempt > 0 {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Event", event)
+            .header("X-Webhook-Timestamp", timestamp.to_string())
+            .body(body.to_vec());
+        if let Some(secret) = secret {
+            request = request.header("X-Webhook-Signature", sign(secret, timestamp, body));
+        }
+
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                info!("Webhook delivered: event={}, url={}, attempt={}", event, url, attempt + 1);
+                return;
+            }
+            Ok(resp) => warn!(
+                "Webhook rejected: event={}, url={}, status={}, attempt={}/{}",
+                event, url, resp.status(), attempt + 1, max_retries + 1
+            ),
+            Err(e) => warn!(
+                "Webhook failed: event={}, url={}, error={}, attempt={}/{}",
+                event, url, e, attempt + 1, max_retries + 1
+            ),
+        }
+    }
+
+    warn!("Webhook gave up: event={}, url={}", event, url);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::{listing_body, TestApp};
+
+    const SECRET: &str = "test-webhook-secret";
+
+    /// 受信した (ヘッダ, ボディ) を記録するローカルのモックサーバ
+    /// `statuses` を先頭から順に返し、尽きたら 200
+    async fn mock_server(statuses: Vec<StatusCode>) -> (String, Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: axum::body::Bytes| {
+                let sink = sink.clone();
+                let statuses = statuses.clone();
+                async move {
+                    sink.lock().unwrap().push((headers, body.to_vec()));
+                    let next = statuses.lock().unwrap().next();
+                    next.unwrap_or(StatusCode::OK)
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hook", addr), received)
+    }
+
+    /// 指定件数を受信するまで待つ（最大 5 秒）
+    async fn wait_for(received: &Mutex<Vec<(HeaderMap, Vec<u8>)>>, count: usize) {
+        for _ in 0..100 {
+            if received.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("expected {} webhook deliveries", count);
+    }
+
+    #[tokio::test]
+    async fn sold_out_webhook_fires_exactly_once() {
+        let (url, received) = mock_server(vec![]).await;
+        let app = TestApp::with_config(|c| {
+            c.webhook_secret = Some(SECRET.to_string());
+            c.webhook_allowed_hosts = local_hosts();
+        })
+        .await;
+        let vendor = app.create_vendor(None).await;
+        let mut body = listing_body(&vendor, "L-hook", 100, 2);
+        body["webhook_url"] = json!(url);
+        assert_eq!(app.post_json("/api/listings", body).await.status, 200);
+
+        let reserve = |qty: i64| app.post_json("/api/listings/L-hook/reserve", json!({ "buyer": "0xbuyer", "qty": qty }));
+        assert_eq!(reserve(1).await.status, 200);
+        assert_eq!(reserve(1).await.status, 200);
+        assert_eq!(reserve(1).await.status, 409);
+
+        wait_for(&received, 1).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+
+        let (headers, body) = &received[0];
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event"], EVENT_LISTING_SOLD_OUT);
+        assert_eq!(payload["listing_id"], "L-hook");
+        assert_eq!(payload["supply_total"], 2);
+
+        let timestamp: i64 = headers["x-webhook-timestamp"].to_str().unwrap().parse().unwrap();
+        assert_eq!(headers["x-webhook-signature"].to_str().unwrap(), sign(SECRET, timestamp, body));
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried() {
+        let (url, received) = mock_server(vec![StatusCode::INTERNAL_SERVER_ERROR]).await;
+        deliver(&url, EVENT_LISTING_SOLD_OUT, b"{}", None, Duration::from_secs(5), 1, &local_hosts()).await;
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn internal_targets_are_refused_unless_allowed() {
+        let (url, received) = mock_server(vec![]).await;
+        deliver(&url, EVENT_LISTING_SOLD_OUT, b"{}", None, Duration::from_secs(5), 0, &[]).await;
+        assert!(received.lock().unwrap().is_empty());
+
+        let app = TestApp::new().await;
+        let vendor = app.create_vendor(None).await;
+        let mut body = listing_body(&vendor, "L-ssrf", 100, 2);
+        body["webhook_url"] = json!(url);
+        assert_eq!(app.post_json("/api/listings", body).await.status, 400);
+    }
+
+    #[test]
+    fn webhook_url_must_be_http() {
+        assert!(validate_url("https://hooks.example/sold-out", &[]).is_ok());
+        assert!(validate_url("ftp://hooks.example/", &[]).is_err());
+        assert!(validate_url("not a url", &[]).is_err());
+    }
+
+    #[test]
+    fn webhook_url_must_not_target_internal_addresses() {
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://localhost:8080/hook",
+            "http://10.0.0.5/hook",
+            "http://192.168.1.1/hook",
+            "http://172.16.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(validate_url(url, &[]).is_err(), "{} should be rejected", url);
+        }
+        assert!(validate_url("http://8.8.8.8/hook", &[]).is_ok());
+        assert!(validate_url("http://127.0.0.1:9000/hook", &["127.0.0.1".to_string()]).is_ok());
+        assert!(validate_url("http://Hooks.Internal/hook", &["hooks.internal".to_string()]).is_ok());
+    }
+
+    /// テストのモックサーバ（127.0.0.1）宛てを許可する
+    fn local_hosts() -> Vec<String> {
+        vec!["127.0.0.1".to_string()]
+    }
+}